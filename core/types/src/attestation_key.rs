@@ -14,6 +14,7 @@ pub enum MrSignerKeyHash {
     Sha384([u8; 48]),
 }
 
+/// The algorithm of an attestation key
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
 #[non_exhaustive]
 #[repr(u16)]
@@ -62,14 +63,26 @@ impl_newtype! {
 }
 
 /// The type of quote.  Only valid for EPID quotes.
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 #[non_exhaustive]
 #[repr(u16)]
 pub enum QuoteSignatureKind {
+    /// Quotes from the same platform can not be linked to each other
     UnLinkable,
+    /// Quotes from the same platform, with the same basename, can be linked
+    /// to each other
     Linkable,
 }
 
+impl From<QuoteSignatureKind> for sgx_quote_sign_type_t {
+    fn from(kind: QuoteSignatureKind) -> Self {
+        match kind {
+            QuoteSignatureKind::UnLinkable => sgx_quote_sign_type_t::SGX_UNLINKABLE_SIGNATURE,
+            QuoteSignatureKind::Linkable => sgx_quote_sign_type_t::SGX_LINKABLE_SIGNATURE,
+        }
+    }
+}
+
 impl TryFrom<sgx_quote_sign_type_t> for QuoteSignatureKind {
     type Error = FfiError;
     fn try_from(sign_type: sgx_quote_sign_type_t) -> Result<QuoteSignatureKind, FfiError> {
@@ -276,6 +289,17 @@ mod test {
         );
     }
 
+    #[parameterized(
+    unlinkable = {QuoteSignatureKind::UnLinkable, 0},
+    linkable = {QuoteSignatureKind::Linkable, 1},
+    )]
+    fn signature_type_from_kind(kind: QuoteSignatureKind, raw_value: u32) {
+        assert_eq!(
+            sgx_quote_sign_type_t::from(kind),
+            sgx_quote_sign_type_t(raw_value)
+        );
+    }
+
     #[parameterized(
     epid = {0, Ok(Algorithm::Epid)},
     ecdsa_p384 = {3, Ok(Algorithm::EcdsaP384)},
@@ -3,7 +3,7 @@
 //! SGX Error types
 
 use mc_sgx_core_sys_types::sgx_status_t;
-use mc_sgx_util::{ResultFrom, ResultInto};
+use mc_sgx_util::{Classify, ErrorClass, ResultFrom, ResultInto};
 
 use serde::{Deserialize, Serialize};
 
@@ -379,6 +379,27 @@ impl TryFrom<sgx_status_t> for Error {
 impl ResultFrom<sgx_status_t> for Error {}
 impl ResultInto<Error> for sgx_status_t {}
 
+impl Classify for Error {
+    /// Resource exhaustion, lost enclaves, and unavailable services are
+    /// transient, everything else is permanent.
+    fn class(&self) -> ErrorClass {
+        match self {
+            Error::OutOfMemory
+            | Error::EnclaveLost
+            | Error::OutOfTcs
+            | Error::OutOfEpc
+            | Error::DeviceBusy
+            | Error::ServiceUnavailable
+            | Error::ServiceTimeout
+            | Error::NetworkFailure
+            | Error::AeSessionInvalid
+            | Error::Busy
+            | Error::EnclaveCreateInterrupted => ErrorClass::Transient,
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use yare::parameterized;
@@ -413,6 +434,19 @@ mod test {
         );
     }
 
+    #[parameterized(
+        out_of_epc = { Error::OutOfEpc, ErrorClass::Transient },
+        enclave_lost = { Error::EnclaveLost, ErrorClass::Transient },
+        busy = { Error::Busy, ErrorClass::Transient },
+        network_failure = { Error::NetworkFailure, ErrorClass::Transient },
+        invalid_parameter = { Error::InvalidParameter, ErrorClass::Permanent },
+        invalid_signature = { Error::InvalidSignature, ErrorClass::Permanent },
+        unsupported_att_key_id = { Error::UnsupportedAttKeyId, ErrorClass::Permanent },
+    )]
+    fn error_class(error: Error, expected: ErrorClass) {
+        assert_eq!(error.class(), expected);
+    }
+
     #[test]
     fn unknown_sgx_error_maps_to_unexpected() {
         let unknown = sgx_status_t(0x8000);
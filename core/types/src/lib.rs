@@ -20,7 +20,7 @@ mod svn;
 mod target_info;
 
 pub use crate::{
-    attestation_key::{AttestationKeyId, ExtendedAttestationKeyId},
+    attestation_key::{Algorithm, AttestationKeyId, ExtendedAttestationKeyId, QuoteSignatureKind},
     attributes::{
         AttributeFlags, Attributes, ExtendedFeatureRequestMask, MiscellaneousAttribute,
         MiscellaneousSelect,
@@ -29,7 +29,10 @@ pub use crate::{
     error::{Error, FfiError},
     key_request::{KeyName, KeyPolicy, KeyRequest, KeyRequestBuilder},
     measurement::{MrEnclave, MrSigner},
-    quote::QuoteNonce,
+    quote::{
+        BaseQuote, Basename, EpidGroupId, EpidSignature, PlatformInfo, Quote, QuoteNonce,
+        QuotingEnclaveReportInfo, RawQuote, Version as QuoteVersion, QUOTE_MIN_SIZE,
+    },
     report::{ExtendedProductId, FamilyId, IsvProductId, Report, ReportBody, ReportData},
     svn::{ConfigSvn, CpuSvn, IsvSvn},
     target_info::TargetInfo,
@@ -5,10 +5,14 @@ use crate::{
     attestation_key::QuoteSignatureKind, impl_display_for_bytestruct, impl_newtype,
     impl_newtype_for_bytestruct, report::Report, FfiError, IsvSvn, ReportBody, TargetInfo,
 };
+use core::mem;
 use mc_sgx_core_sys_types::{
     sgx_basename_t, sgx_epid_group_id_t, sgx_platform_info_t, sgx_qe_report_info_t,
-    sgx_quote_nonce_t, sgx_quote_sign_type_t, sgx_update_info_bit_t, SGX_PLATFORM_INFO_SIZE,
+    sgx_quote_nonce_t, sgx_quote_sign_type_t, sgx_report_body_t, sgx_update_info_bit_t,
+    SGX_PLATFORM_INFO_SIZE,
 };
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u16, le_u32};
 use serde::{Deserialize, Serialize};
 
 /// Quoting Enclave Report Info
@@ -106,92 +110,153 @@ impl_newtype_for_bytestruct! {
 }
 impl_display_for_bytestruct!(QuoteNonce);
 
-/// The raw bytes representing a quote.
+/// The size of the quote fields before the report body.
+const QUOTE_HEADER_SIZE: usize = 48;
+
+/// The size of the quote up to, and including, `signature_len`.
+pub const QUOTE_MIN_SIZE: usize = QUOTE_HEADER_SIZE + mem::size_of::<sgx_report_body_t>() + 4;
+
+// Sizes from `se_quote_internal.h` of the SGX SDK
+const WRAPPED_KEY_SIZE: usize = 256;
+const WRAPPED_KEY_HASH_SIZE: usize = 32;
+const SIGNATURE_IV_SIZE: usize = 12;
+const SIGNATURE_MAC_SIZE: usize = 16;
+
+/// The version of a [`Quote`]
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Version(u16);
+
+impl_newtype! {
+    Version, u16;
+}
+
+/// The parsed fields of a quote.
 ///
 /// Should not be used directly instead use [`Quote`].
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct RawQuote<'a> {
     bytes: &'a [u8],
+    version: Version,
+    signature_type: QuoteSignatureKind,
+    epid_group_id: EpidGroupId,
+    qe_svn: IsvSvn,
+    pce_svn: IsvSvn,
+    extended_epid_group_id: EpidGroupId,
+    basename: Basename,
+    report_body: ReportBody,
+    signature: &'a [u8],
 }
 
-#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
-#[repr(transparent)]
-pub struct Version(u16);
+impl<'a> TryFrom<&'a [u8]> for RawQuote<'a> {
+    type Error = FfiError;
+
+    /// Parse the quote in `bytes`.
+    ///
+    /// # Errors
+    /// * [`FfiError::InvalidInputLength`] if `bytes` is not exactly the size
+    ///   of a quote with `signature_len` bytes of signature.
+    /// * [`FfiError::UnknownEnumValue`] if the signature type is not a
+    ///   [`QuoteSignatureKind`].
+    fn try_from(bytes: &'a [u8]) -> Result<Self, FfiError> {
+        let (rest, version) = le_u16(bytes)?;
+        let (rest, sign_type) = le_u16(rest)?;
+        let (rest, epid_group_id) = take(mem::size_of::<sgx_epid_group_id_t>())(rest)?;
+        let (rest, qe_svn) = le_u16(rest)?;
+        let (rest, pce_svn) = le_u16(rest)?;
+        let (rest, extended_epid_group_id) = take(mem::size_of::<sgx_epid_group_id_t>())(rest)?;
+        let (rest, basename) = take(BASENAME_SIZE)(rest)?;
+        let (rest, report_body) = take(mem::size_of::<sgx_report_body_t>())(rest)?;
+        let (rest, signature_len) = le_u32(rest)?;
+        let signature_len =
+            usize::try_from(signature_len).map_err(|_| FfiError::InvalidInputLength)?;
+        if rest.len() != signature_len {
+            return Err(FfiError::InvalidInputLength);
+        }
 
-impl_newtype! {
-    Version, u16;
+        Ok(Self {
+            bytes,
+            version: version.into(),
+            signature_type: sgx_quote_sign_type_t(sign_type.into()).try_into()?,
+            epid_group_id: EpidGroupId::from(*fixed_size::<4>(epid_group_id)?),
+            qe_svn: qe_svn.into(),
+            pce_svn: pce_svn.into(),
+            extended_epid_group_id: EpidGroupId::from(*fixed_size::<4>(extended_epid_group_id)?),
+            basename: Basename::try_from(basename)?,
+            report_body: ReportBody::try_from(report_body)?,
+            signature: rest,
+        })
+    }
 }
 
+/// Common accessors for the different kinds of quotes.
 pub trait BaseQuote {
     /// Provides access to the [`RawQuote`] to perform the common lookup
     /// operations on the basic quote type.
-    fn raw_quote(&self) -> &RawQuote;
+    fn raw_quote(&self) -> &RawQuote<'_>;
 
     /// Version of the quote
     fn version(&self) -> Version {
-        let bytes = self.raw_quote().bytes[..2]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `version`");
-        u16::from_le_bytes(bytes).into()
+        self.raw_quote().version.clone()
     }
 
     /// The signature type
-    fn signature_type(&self) -> Result<QuoteSignatureKind, FfiError> {
-        let bytes = self.raw_quote().bytes[2..4]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `sign_type`");
-        sgx_quote_sign_type_t(u16::from_le_bytes(bytes) as u32).try_into()
+    fn signature_type(&self) -> QuoteSignatureKind {
+        self.raw_quote().signature_type
     }
 
     /// EPID group id
     fn epid_group_id(&self) -> EpidGroupId {
-        let bytes: [u8; 4] = self.raw_quote().bytes[4..8]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `epid_group_id`");
-        bytes.into()
+        self.raw_quote().epid_group_id.clone()
     }
 
     /// Quoting enclave (QE) SVN (Security Version Number)
     fn quoting_enclave_svn(&self) -> IsvSvn {
-        let bytes = self.raw_quote().bytes[8..10]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `qe_svn`");
-        u16::from_le_bytes(bytes).into()
+        self.raw_quote().qe_svn
     }
 
     /// Provisioning certification enclave (PCE) SVN (Security Version Number)
     fn provisioning_certification_enclave_svn(&self) -> IsvSvn {
-        let bytes = self.raw_quote().bytes[10..12]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `pce_svn`");
-        u16::from_le_bytes(bytes).into()
+        self.raw_quote().pce_svn
     }
 
     /// Extended EPID group id
     fn extended_epid_group_id(&self) -> EpidGroupId {
-        let bytes: [u8; 4] = self.raw_quote().bytes[12..16]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `xeid`");
-        bytes.into()
+        self.raw_quote().extended_epid_group_id.clone()
     }
 
     /// Basename
     fn basename(&self) -> Basename {
-        let bytes: [u8; BASENAME_SIZE] = self.raw_quote().bytes[16..48]
-            .try_into()
-            .expect("Quote bytes aren't big enough to hold `basename`");
-        bytes.into()
+        self.raw_quote().basename.clone()
+    }
+
+    /// The basename quotes can be linked by.
+    ///
+    /// Quotes with a [`QuoteSignatureKind::Linkable`] signature, from the
+    /// same platform and with the same basename, can be linked to each other
+    /// by the attestation service. Quotes with a
+    /// [`QuoteSignatureKind::UnLinkable`] signature can not be linked, for
+    /// these `None` is returned.
+    fn linkable_basename(&self) -> Option<Basename> {
+        match self.signature_type() {
+            QuoteSignatureKind::Linkable => Some(self.basename()),
+            QuoteSignatureKind::UnLinkable => None,
+        }
     }
 
     /// Report body
-    fn report_body(&self) -> Result<ReportBody, FfiError> {
-        self.raw_quote().bytes[48..432].try_into()
+    fn report_body(&self) -> ReportBody {
+        self.raw_quote().report_body.clone()
+    }
+
+    /// The length of the signature in bytes
+    fn signature_len(&self) -> usize {
+        self.raw_quote().signature.len()
     }
-}
 
-impl<'a> From<&'a [u8]> for RawQuote<'a> {
-    fn from(bytes: &'a [u8]) -> Self {
-        Self { bytes }
+    /// The signature bytes
+    fn signature(&self) -> &[u8] {
+        self.raw_quote().signature
     }
 }
 
@@ -199,30 +264,130 @@ impl<'a> From<&'a [u8]> for RawQuote<'a> {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct Quote<'a>(RawQuote<'a>);
 
+impl Quote<'_> {
+    /// The EPID signature of the quote.
+    ///
+    /// # Errors
+    /// [`FfiError::InvalidInputLength`] if the signature is not a valid size
+    /// for an EPID signature.
+    pub fn epid_signature(&self) -> Result<EpidSignature<'_>, FfiError> {
+        EpidSignature::try_from(self.signature())
+    }
+}
+
 impl BaseQuote for Quote<'_> {
-    fn raw_quote(&self) -> &RawQuote {
+    fn raw_quote(&self) -> &RawQuote<'_> {
         &self.0
     }
 }
 
+impl AsRef<[u8]> for Quote<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.bytes
+    }
+}
+
 impl<'a> From<RawQuote<'a>> for Quote<'a> {
     fn from(raw: RawQuote<'a>) -> Self {
         Self(raw)
     }
 }
 
-impl<'a> From<&'a [u8]> for Quote<'a> {
-    fn from(bytes: &'a [u8]) -> Self {
-        Self(bytes.into())
+impl<'a> TryFrom<&'a [u8]> for Quote<'a> {
+    type Error = FfiError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, FfiError> {
+        Ok(Self(bytes.try_into()?))
+    }
+}
+
+/// The EPID signature of a [`Quote`].
+///
+/// The EPID signature itself is encrypted for the Intel Attestation Service
+/// (IAS) so only the envelope around it is available.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct EpidSignature<'a> {
+    wrapped_key: &'a [u8; WRAPPED_KEY_SIZE],
+    wrapped_key_hash: &'a [u8; WRAPPED_KEY_HASH_SIZE],
+    iv: &'a [u8; SIGNATURE_IV_SIZE],
+    encrypted_signature: &'a [u8],
+    mac: &'a [u8; SIGNATURE_MAC_SIZE],
+}
+
+impl<'a> EpidSignature<'a> {
+    /// The key the EPID signature is encrypted with, itself encrypted with
+    /// the IAS public key.
+    pub fn wrapped_key(&self) -> &'a [u8; WRAPPED_KEY_SIZE] {
+        self.wrapped_key
+    }
+
+    /// The SHA256 hash of the IAS public key used to wrap the key.
+    pub fn wrapped_key_hash(&self) -> &'a [u8; WRAPPED_KEY_HASH_SIZE] {
+        self.wrapped_key_hash
+    }
+
+    /// The initialization vector for the encrypted EPID signature.
+    pub fn iv(&self) -> &'a [u8; SIGNATURE_IV_SIZE] {
+        self.iv
+    }
+
+    /// The encrypted EPID signature, including the signature revocation list
+    /// version and non-revoked proofs.
+    pub fn encrypted_signature(&self) -> &'a [u8] {
+        self.encrypted_signature
+    }
+
+    /// The MAC of the encrypted EPID signature.
+    pub fn mac(&self) -> &'a [u8; SIGNATURE_MAC_SIZE] {
+        self.mac
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EpidSignature<'a> {
+    type Error = FfiError;
+
+    /// Parse the EPID signature in `bytes`.
+    ///
+    /// # Errors
+    /// [`FfiError::InvalidInputLength`] if `bytes` is not exactly the size of
+    /// an EPID signature with its encrypted payload size.
+    fn try_from(bytes: &'a [u8]) -> Result<Self, FfiError> {
+        let (rest, wrapped_key) = take(WRAPPED_KEY_SIZE)(bytes)?;
+        let (rest, wrapped_key_hash) = take(WRAPPED_KEY_HASH_SIZE)(rest)?;
+        let (rest, iv) = take(SIGNATURE_IV_SIZE)(rest)?;
+        let (rest, payload_size) = le_u32(rest)?;
+        let payload_size =
+            usize::try_from(payload_size).map_err(|_| FfiError::InvalidInputLength)?;
+        let (rest, encrypted_signature) = take(payload_size)(rest)?;
+        let (rest, mac) = take(SIGNATURE_MAC_SIZE)(rest)?;
+        if !rest.is_empty() {
+            return Err(FfiError::InvalidInputLength);
+        }
+
+        Ok(Self {
+            wrapped_key: fixed_size(wrapped_key)?,
+            wrapped_key_hash: fixed_size(wrapped_key_hash)?,
+            iv: fixed_size(iv)?,
+            encrypted_signature,
+            mac: fixed_size(mac)?,
+        })
     }
 }
 
+fn fixed_size<const N: usize>(bytes: &[u8]) -> Result<&[u8; N], FfiError> {
+    bytes.try_into().map_err(|_| FfiError::InvalidInputLength)
+}
+
 #[cfg(test)]
 mod test {
+    extern crate std;
+
     use super::*;
     use crate::{report::Report, TargetInfo};
-    use core::{mem, slice};
-    use mc_sgx_core_sys_types::{sgx_quote_t, sgx_report_body_t, sgx_report_t, sgx_target_info_t};
+    use core::slice;
+    use mc_sgx_core_sys_types::{sgx_quote_t, sgx_report_t, sgx_target_info_t};
+    use std::{vec, vec::Vec};
+    use yare::parameterized;
 
     #[allow(unsafe_code)]
     fn quote_to_bytes(report: sgx_quote_t) -> [u8; mem::size_of::<sgx_quote_t>()] {
@@ -277,37 +442,58 @@ mod test {
         }
     }
 
+    fn quote_bytes_with_signature(quote: sgx_quote_t) -> Vec<u8> {
+        let signature_len = quote.signature_len as usize;
+        let mut bytes = quote_to_bytes(quote).to_vec();
+        bytes.extend((0..signature_len).map(|i| i as u8));
+        bytes
+    }
+
+    fn epid_signature_bytes(payload_size: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend([1u8; WRAPPED_KEY_SIZE]);
+        bytes.extend([2u8; WRAPPED_KEY_HASH_SIZE]);
+        bytes.extend([3u8; SIGNATURE_IV_SIZE]);
+        bytes.extend(payload_size.to_le_bytes());
+        bytes.extend(vec![4u8; payload_size as usize]);
+        bytes.extend([5u8; SIGNATURE_MAC_SIZE]);
+        bytes
+    }
+
+    #[test]
+    fn quote_min_size_matches_sgx_quote_t() {
+        assert_eq!(QUOTE_MIN_SIZE, mem::size_of::<sgx_quote_t>());
+    }
+
     #[test]
     fn raw_quote_from_slice() {
-        let bytes = [3u8; 14].as_slice();
-        let raw: RawQuote = bytes.into();
-        assert_eq!(raw.bytes, bytes);
+        let bytes = quote_bytes_with_signature(base_quote_1());
+        let raw = RawQuote::try_from(bytes.as_slice()).expect("Failed to parse quote");
+        assert_eq!(raw.bytes, bytes.as_slice());
+        assert_eq!(raw.signature, &bytes[QUOTE_MIN_SIZE..]);
     }
 
     #[test]
     fn quote_from_raw_quote() {
-        let bytes = [8u8; 20].as_slice();
-        let raw: RawQuote = bytes.into();
+        let bytes = quote_bytes_with_signature(base_quote_2());
+        let raw = RawQuote::try_from(bytes.as_slice()).expect("Failed to parse quote");
         let quote: Quote = raw.clone().into();
         assert_eq!(quote.0, raw);
     }
 
     #[test]
     fn quote_from_slice() {
-        let bytes = [4u8; 6].as_slice();
-        let quote: Quote = bytes.into();
-        assert_eq!(quote.0.bytes, bytes);
+        let bytes = quote_bytes_with_signature(base_quote_1());
+        let quote = Quote::try_from(bytes.as_slice()).expect("Failed to parse quote");
+        assert_eq!(quote.as_ref(), bytes.as_slice());
     }
 
     #[test]
     fn quote_from_bytes_1x() {
-        let quote_bytes = quote_to_bytes(base_quote_1());
-        let quote = Quote::from(quote_bytes.as_slice());
+        let quote_bytes = quote_bytes_with_signature(base_quote_1());
+        let quote = Quote::try_from(quote_bytes.as_slice()).expect("Failed to parse quote");
         assert_eq!(quote.version(), 11.into());
-        assert_eq!(
-            quote.signature_type().unwrap(),
-            QuoteSignatureKind::UnLinkable
-        );
+        assert_eq!(quote.signature_type(), QuoteSignatureKind::UnLinkable);
         assert_eq!(quote.epid_group_id(), EpidGroupId::from([13u8; 4]));
         assert_eq!(quote.quoting_enclave_svn(), IsvSvn::from(14));
         assert_eq!(
@@ -319,21 +505,22 @@ mod test {
             EpidGroupId::from([16u8, 0u8, 0u8, 0u8])
         );
         assert_eq!(quote.basename(), Basename::from([17u8; BASENAME_SIZE]));
+        assert_eq!(quote.linkable_basename(), None);
 
         let mut report_body = sgx_report_body_t::default();
         report_body.misc_select = 18;
-        assert_eq!(quote.report_body().unwrap(), report_body.into());
+        assert_eq!(quote.report_body(), report_body.into());
+
+        assert_eq!(quote.signature_len(), 19);
+        assert_eq!(quote.signature(), (0..19).collect::<Vec<u8>>().as_slice());
     }
 
     #[test]
     fn quote_from_bytes_2x() {
-        let quote_bytes = quote_to_bytes(base_quote_2());
-        let quote = Quote::from(quote_bytes.as_slice());
+        let quote_bytes = quote_bytes_with_signature(base_quote_2());
+        let quote = Quote::try_from(quote_bytes.as_slice()).expect("Failed to parse quote");
         assert_eq!(quote.version(), 21.into());
-        assert_eq!(
-            quote.signature_type().unwrap(),
-            QuoteSignatureKind::Linkable
-        );
+        assert_eq!(quote.signature_type(), QuoteSignatureKind::Linkable);
         assert_eq!(quote.epid_group_id(), EpidGroupId::from([23u8; 4]));
         assert_eq!(quote.quoting_enclave_svn(), IsvSvn::from(24));
         assert_eq!(
@@ -341,10 +528,118 @@ mod test {
             IsvSvn::from(25)
         );
         assert_eq!(quote.basename(), Basename::from([27u8; BASENAME_SIZE]));
+        assert_eq!(
+            quote.linkable_basename(),
+            Some(Basename::from([27u8; BASENAME_SIZE]))
+        );
 
         let mut report_body = sgx_report_body_t::default();
         report_body.misc_select = 28;
-        assert_eq!(quote.report_body().unwrap(), report_body.into());
+        assert_eq!(quote.report_body(), report_body.into());
+
+        assert_eq!(quote.signature_len(), 29);
+        assert_eq!(quote.signature(), (0..29).collect::<Vec<u8>>().as_slice());
+    }
+
+    #[parameterized(
+    empty = {0},
+    partial_header = {20},
+    partial_report_body = {QUOTE_HEADER_SIZE + 10},
+    no_signature_len = {QUOTE_MIN_SIZE - 4},
+    no_signature = {QUOTE_MIN_SIZE},
+    partial_signature = {QUOTE_MIN_SIZE + 18},
+    )]
+    fn quote_too_short(size: usize) {
+        let bytes = quote_bytes_with_signature(base_quote_1());
+        assert_eq!(
+            Quote::try_from(&bytes[..size]),
+            Err(FfiError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn quote_with_trailing_bytes() {
+        let mut bytes = quote_bytes_with_signature(base_quote_1());
+        bytes.push(0);
+        assert_eq!(
+            Quote::try_from(bytes.as_slice()),
+            Err(FfiError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn quote_with_unknown_signature_type() {
+        let mut quote = base_quote_1();
+        quote.sign_type = 2;
+        let bytes = quote_bytes_with_signature(quote);
+        assert_eq!(
+            Quote::try_from(bytes.as_slice()),
+            Err(FfiError::UnknownEnumValue(2))
+        );
+    }
+
+    #[test]
+    fn epid_signature_from_quote() {
+        let signature = epid_signature_bytes(10);
+        let mut quote = base_quote_1();
+        quote.signature_len = signature.len() as u32;
+        let mut bytes = quote_to_bytes(quote).to_vec();
+        bytes.extend(&signature);
+        let quote = Quote::try_from(bytes.as_slice()).expect("Failed to parse quote");
+
+        let epid_signature = quote.epid_signature().expect("Failed to parse signature");
+        assert_eq!(epid_signature.wrapped_key(), &[1u8; WRAPPED_KEY_SIZE]);
+        assert_eq!(
+            epid_signature.wrapped_key_hash(),
+            &[2u8; WRAPPED_KEY_HASH_SIZE]
+        );
+        assert_eq!(epid_signature.iv(), &[3u8; SIGNATURE_IV_SIZE]);
+        assert_eq!(epid_signature.encrypted_signature(), &[4u8; 10]);
+        assert_eq!(epid_signature.mac(), &[5u8; SIGNATURE_MAC_SIZE]);
+    }
+
+    #[test]
+    fn epid_signature_with_empty_payload() {
+        let bytes = epid_signature_bytes(0);
+        let signature = EpidSignature::try_from(bytes.as_slice()).expect("Failed to parse");
+        assert!(signature.encrypted_signature().is_empty());
+    }
+
+    #[test]
+    fn epid_signature_too_short() {
+        let bytes = epid_signature_bytes(10);
+        assert_eq!(
+            EpidSignature::try_from(&bytes[..bytes.len() - 1]),
+            Err(FfiError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn epid_signature_with_trailing_bytes() {
+        let mut bytes = epid_signature_bytes(10);
+        bytes.push(0);
+        assert_eq!(
+            EpidSignature::try_from(bytes.as_slice()),
+            Err(FfiError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn epid_signature_payload_size_too_large() {
+        let mut bytes = epid_signature_bytes(10);
+        let size_offset = WRAPPED_KEY_SIZE + WRAPPED_KEY_HASH_SIZE + SIGNATURE_IV_SIZE;
+        bytes[size_offset..size_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            EpidSignature::try_from(bytes.as_slice()),
+            Err(FfiError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn epid_signature_from_short_quote_signature() {
+        let bytes = quote_bytes_with_signature(base_quote_1());
+        let quote = Quote::try_from(bytes.as_slice()).expect("Failed to parse quote");
+        assert_eq!(quote.epid_signature(), Err(FfiError::InvalidInputLength));
     }
 
     #[test]
@@ -0,0 +1,58 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs, missing_debug_implementations, unsafe_code)]
+
+mod pcs;
+mod resolver;
+mod store;
+
+pub use crate::pcs::{Issued, PcsClient, PCS_URL};
+pub use crate::resolver::{DirectoryPckCertificateResolver, PckCertificateResolver};
+pub use crate::store::StoreLayout;
+pub use mc_sgx_dcap_types::CaType;
+use mc_sgx_dcap_types::{Quote3Error, TcbError};
+
+/// Error retrieving collateral
+#[derive(Debug, displaydoc::Display, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The request to {0} failed: {1}
+    Http(String, String),
+    /// The request to {0} failed with HTTP status {1}
+    Status(String, u16),
+    /// The response from {0} is missing the {1} header
+    MissingHeader(String, &'static str),
+    /// The {0} header is not a URL encoded PEM certificate chain
+    IssuerChain(&'static str),
+    /// The response from {0} is not a CRL
+    Crl(String),
+    /// The response from {0} is not UTF-8
+    Utf8(String),
+    /// The PCK certificate from {0} is invalid
+    PckCertificate(String),
+    /// The Intel PCS requires an RSA 3072 encrypted PPID
+    UnsupportedPpid,
+    /// The certification data of the quote can not identify a PCK certificate
+    UnsupportedCertificationData,
+    /// The certification data of the quote is invalid: {0}
+    CertificationData(Quote3Error),
+    /// The CA of the PCK certificate is unknown: {0}
+    CaType(TcbError),
+    /// Failed to access {0}: {1}
+    Store(String, String),
+}
+
+impl std::error::Error for Error {}
+
+impl From<Quote3Error> for Error {
+    fn from(err: Quote3Error) -> Self {
+        Error::CertificationData(err)
+    }
+}
+
+impl From<TcbError> for Error {
+    fn from(err: TcbError) -> Self {
+        Error::CaType(err)
+    }
+}
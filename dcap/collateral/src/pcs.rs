@@ -0,0 +1,812 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Client for the Intel Provisioning Certification Service (PCS) and the
+//! Provisioning Certificate Caching Service (PCCS).
+//!
+//! Both provide version 4 of the API documented in
+//! <https://api.portal.trustedservices.intel.com/content/documentation.html>.
+//! Each signed response has the certificate chain of its signer, leaf first,
+//! as a URL encoded PEM chain in a response header.
+
+use crate::Error;
+use mc_sgx_dcap_types::{CaType, Collateral, PlatformId, PpidEncryption, FMSPC_SIZE, QE_ID_SIZE};
+use mc_sgx_util::trim_ascii_whitespace;
+use percent_encoding::percent_decode_str;
+use ureq::Agent;
+use x509_cert::crl::CertificateList;
+use x509_cert::der::{pem, Decode, DecodePem};
+use x509_cert::Certificate;
+
+/// The URL of the Intel PCS
+pub const PCS_URL: &str = "https://api.trustedservices.intel.com";
+
+/// The Intel SGX Root CA CRL, PCS has no API endpoint for it
+const PCS_ROOT_CA_CRL_URL: &str =
+    "https://certificates.trustedservices.intel.com/IntelSGXRootCA.der";
+
+const CRL_PEM_LABEL: &str = "X509 CRL";
+
+const API_PATH: &str = "/sgx/certification/v4";
+
+const PCK_CRL_ISSUER_CHAIN: &[&str] = &["SGX-PCK-CRL-Issuer-Chain"];
+// Version 3 of the API used the `SGX-` prefix for the TCB info issuer chain
+const TCB_INFO_ISSUER_CHAIN: &[&str] = &["TCB-Info-Issuer-Chain", "SGX-TCB-Info-Issuer-Chain"];
+const ENCLAVE_IDENTITY_ISSUER_CHAIN: &[&str] = &["SGX-Enclave-Identity-Issuer-Chain"];
+const PCK_CERTIFICATE_ISSUER_CHAIN: &[&str] = &["SGX-PCK-Certificate-Issuer-Chain"];
+
+/// The header with the subscription key of the Intel PCS
+const API_KEY_HEADER: &str = "Ocp-Apim-Subscription-Key";
+
+/// A response of the PCS, with the certificate chain of its signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issued<T> {
+    value: T,
+    issuer_chain: Vec<Certificate>,
+}
+
+impl<T> Issued<T> {
+    /// A `value` signed by the leaf of `issuer_chain`
+    pub fn new(value: T, issuer_chain: Vec<Certificate>) -> Self {
+        Self {
+            value,
+            issuer_chain,
+        }
+    }
+
+    /// The value of the response
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The certificate chain of the signer of the value, leaf first
+    pub fn issuer_chain(&self) -> &[Certificate] {
+        &self.issuer_chain
+    }
+
+    /// The value and the issuer chain
+    pub fn into_parts(self) -> (T, Vec<Certificate>) {
+        (self.value, self.issuer_chain)
+    }
+}
+
+/// The kind of service a [`PcsClient`] talks to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Service {
+    Pcs,
+    Pccs,
+}
+
+/// Client for the collateral endpoints of the PCS or a PCCS.
+///
+/// The TLS support comes from the provided [`Agent`], see the crate
+/// documentation.
+#[derive(Debug, Clone)]
+pub struct PcsClient {
+    agent: Agent,
+    service: Service,
+    api_url: String,
+    root_ca_crl_url: String,
+    api_key: Option<String>,
+}
+
+impl PcsClient {
+    /// A client for the Intel PCS.
+    pub fn pcs(agent: Agent) -> Self {
+        Self::new(agent, Service::Pcs, PCS_URL, PCS_ROOT_CA_CRL_URL)
+    }
+
+    /// A client for the PCCS at `url`, for instance `https://localhost:8081`.
+    pub fn pccs(agent: Agent, url: &str) -> Self {
+        let url = url.trim_end_matches('/');
+        let root_ca_crl_url = format!("{url}{API_PATH}/rootcacrl");
+        Self::new(agent, Service::Pccs, url, &root_ca_crl_url)
+    }
+
+    fn new(agent: Agent, service: Service, url: &str, root_ca_crl_url: &str) -> Self {
+        Self {
+            agent,
+            service,
+            api_url: format!("{url}{API_PATH}"),
+            root_ca_crl_url: root_ca_crl_url.into(),
+            api_key: None,
+        }
+    }
+
+    /// The subscription key for the Intel PCS.
+    ///
+    /// The PCS only requires it for [`Self::pck_certificate()`], the
+    /// collateral endpoints are public.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// The collateral to verify quotes from a platform.
+    ///
+    /// # Arguments
+    /// * `fmspc` - The FMSPC of the platform, from its PCK certificate.
+    /// * `ca` - The CA which issued the PCK certificate of the platform.
+    ///
+    /// # Errors
+    /// An [`Error`] from the first request that failed.
+    pub fn collateral(&self, fmspc: &[u8; FMSPC_SIZE], ca: CaType) -> Result<Collateral, Error> {
+        let root_ca_crl = self.root_ca_crl()?;
+        let (pck_crl, pck_crl_issuer_chain) = self.pck_crl(ca)?.into_parts();
+        let (tcb_info, tcb_issuer_chain) = self.tcb_info(fmspc)?.into_parts();
+        let (qe_identity, qe_identity_issuer_chain) = self.qe_identity()?.into_parts();
+        Ok(Collateral::new(
+            root_ca_crl,
+            pck_crl_issuer_chain,
+            pck_crl,
+            tcb_issuer_chain,
+            tcb_info,
+            qe_identity_issuer_chain,
+            qe_identity,
+        ))
+    }
+
+    /// The Intel SGX Root CA CRL.
+    pub fn root_ca_crl(&self) -> Result<CertificateList, Error> {
+        let url = &self.root_ca_crl_url;
+        let mut response = self.get(url)?;
+        let body = read_body(url, &mut response)?;
+        crl(url, &body)
+    }
+
+    /// The CRL of the PCK certificates issued by `ca`.
+    pub fn pck_crl(&self, ca: CaType) -> Result<Issued<CertificateList>, Error> {
+        let url = format!("{}/pckcrl?ca={}&encoding=der", self.api_url, ca.as_str());
+        let mut response = self.get(&url)?;
+        let issuer_chain = issuer_chain(&url, &response, PCK_CRL_ISSUER_CHAIN)?;
+        let body = read_body(&url, &mut response)?;
+        Ok(Issued {
+            value: crl(&url, &body)?,
+            issuer_chain,
+        })
+    }
+
+    /// The signed TCB info JSON for the platforms with `fmspc`.
+    pub fn tcb_info(&self, fmspc: &[u8; FMSPC_SIZE]) -> Result<Issued<String>, Error> {
+        let url = format!("{}/tcb?fmspc={}", self.api_url, hex::encode_upper(fmspc));
+        self.signed_json(&url, TCB_INFO_ISSUER_CHAIN)
+    }
+
+    /// The signed identity JSON of the quoting enclave (QE).
+    pub fn qe_identity(&self) -> Result<Issued<String>, Error> {
+        let url = format!("{}/qe/identity", self.api_url);
+        self.signed_json(&url, ENCLAVE_IDENTITY_ISSUER_CHAIN)
+    }
+
+    /// The signed identity JSON of the quote verification enclave (QvE).
+    pub fn qve_identity(&self) -> Result<Issued<String>, Error> {
+        let url = format!("{}/qve/identity", self.api_url);
+        self.signed_json(&url, ENCLAVE_IDENTITY_ISSUER_CHAIN)
+    }
+
+    /// The PCK certificate of a platform.
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform, only used by
+    ///   a PCCS to look up the certificate in its cache.
+    /// * `platform` - The platform identifiers, from the certification data of
+    ///   a quote.
+    ///
+    /// # Errors
+    /// * [`Error::UnsupportedPpid`] if the PPID of `platform` isn't encrypted
+    ///   with RSA 3072 and the client is for the Intel PCS.
+    /// * [`Error::PckCertificate`] if the response is not a PEM certificate.
+    /// * An [`Error`] from the request.
+    pub fn pck_certificate(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        platform: &PlatformId,
+    ) -> Result<Issued<Certificate>, Error> {
+        let mut query = vec![];
+        match (self.service, platform.encryption()) {
+            (_, PpidEncryption::Rsa3072) => query.push(format!(
+                "encrypted_ppid={}",
+                hex::encode_upper(platform.ppid())
+            )),
+            (Service::Pcs, _) => return Err(Error::UnsupportedPpid),
+            (Service::Pccs, _) => {}
+        }
+        query.push(format!("cpusvn={}", hex::encode_upper(platform.cpu_svn())));
+        query.push(format!(
+            "pcesvn={}",
+            hex::encode_upper(platform.pce_svn().to_le_bytes())
+        ));
+        query.push(format!(
+            "pceid={}",
+            hex::encode_upper(platform.pce_id().to_le_bytes())
+        ));
+        if self.service == Service::Pccs {
+            query.push(format!("qeid={}", hex::encode_upper(qe_id)));
+        }
+        let url = format!("{}/pckcert?{}", self.api_url, query.join("&"));
+
+        let mut request = self.agent.get(&url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        let mut response = call(&url, request)?;
+        let issuer_chain = issuer_chain(&url, &response, PCK_CERTIFICATE_ISSUER_CHAIN)?;
+        let body = read_body(&url, &mut response)?;
+        let value = Certificate::from_pem(body).map_err(|_| Error::PckCertificate(url.clone()))?;
+        Ok(Issued {
+            value,
+            issuer_chain,
+        })
+    }
+
+    fn signed_json(&self, url: &str, headers: &[&'static str]) -> Result<Issued<String>, Error> {
+        let mut response = self.get(url)?;
+        let issuer_chain = issuer_chain(url, &response, headers)?;
+        let body = read_body(url, &mut response)?;
+        // The signature is over the exact bytes of the JSON so lossy
+        // conversion is not an option.
+        let value = String::from_utf8(body).map_err(|_| Error::Utf8(url.into()))?;
+        Ok(Issued {
+            value,
+            issuer_chain,
+        })
+    }
+
+    fn get(&self, url: &str) -> Result<ureq::http::Response<ureq::Body>, Error> {
+        call(url, self.agent.get(url))
+    }
+}
+
+fn call(
+    url: &str,
+    request: ureq::RequestBuilder<ureq::typestate::WithoutBody>,
+) -> Result<ureq::http::Response<ureq::Body>, Error> {
+    request.call().map_err(|err| match err {
+        ureq::Error::StatusCode(status) => Error::Status(url.into(), status),
+        err => Error::Http(url.into(), err.to_string()),
+    })
+}
+
+fn read_body(url: &str, response: &mut ureq::http::Response<ureq::Body>) -> Result<Vec<u8>, Error> {
+    response
+        .body_mut()
+        .read_to_vec()
+        .map_err(|err| Error::Http(url.into(), err.to_string()))
+}
+
+/// Decode the issuer chain from the first of `headers` in `response`.
+fn issuer_chain(
+    url: &str,
+    response: &ureq::http::Response<ureq::Body>,
+    headers: &[&'static str],
+) -> Result<Vec<Certificate>, Error> {
+    let (name, value) = headers
+        .iter()
+        .find_map(|name| Some((*name, response.headers().get(*name)?)))
+        .ok_or_else(|| Error::MissingHeader(url.into(), headers[0]))?;
+    let value = value.to_str().map_err(|_| Error::IssuerChain(name))?;
+    let pem = percent_decode_str(value)
+        .decode_utf8()
+        .map_err(|_| Error::IssuerChain(name))?;
+    // `load_pem_chain()` panics on empty input
+    if pem.trim().is_empty() {
+        return Err(Error::IssuerChain(name));
+    }
+    Certificate::load_pem_chain(pem.as_bytes()).map_err(|_| Error::IssuerChain(name))
+}
+
+/// Decode a CRL from the `body` of a response.
+///
+/// PCS provides DER when asked, while PCCS versions differ between DER, PEM,
+/// and hex encoded DER.
+fn crl(url: &str, body: &[u8]) -> Result<CertificateList, Error> {
+    if let Ok(crl) = CertificateList::from_der(body) {
+        return Ok(crl);
+    }
+    if let Ok((CRL_PEM_LABEL, der)) = pem::decode_vec(body) {
+        return CertificateList::from_der(&der).map_err(|_| Error::Crl(url.into()));
+    }
+    let der = hex::decode(trim_ascii_whitespace(body)).map_err(|_| Error::Crl(url.into()))?;
+    CertificateList::from_der(&der).map_err(|_| Error::Crl(url.into()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use mc_sgx_dcap_types::test_utils::{TestCa, FMSPC, PCE_SVN, QE_ID};
+    use mc_sgx_dcap_types::CertificationData;
+    use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread::JoinHandle;
+    use tiny_http::{Header, Response, Server};
+    use x509_cert::der::pem::LineEnding;
+    use x509_cert::der::{Encode, EncodePem};
+    use yare::parameterized;
+
+    /// A canned response of the [`MockServer`]
+    #[derive(Clone)]
+    struct MockResponse {
+        status: u16,
+        headers: Vec<(&'static str, String)>,
+        body: Vec<u8>,
+    }
+
+    impl MockResponse {
+        fn ok(body: impl Into<Vec<u8>>) -> Self {
+            Self {
+                status: 200,
+                headers: vec![],
+                body: body.into(),
+            }
+        }
+
+        fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+            self.headers.push((name, value.into()));
+            self
+        }
+    }
+
+    /// A local HTTP server which responds to paths, including the query, with
+    /// canned responses.
+    ///
+    /// Paths without a response get a 404.
+    struct MockServer {
+        server: Arc<Server>,
+        requests: Arc<Mutex<Vec<String>>>,
+        api_keys: Arc<Mutex<Vec<String>>>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl MockServer {
+        fn new(responses: HashMap<String, MockResponse>) -> Self {
+            let server = Arc::new(Server::http("127.0.0.1:0").expect("Failed to start server"));
+            let requests = Arc::new(Mutex::new(vec![]));
+            let api_keys = Arc::new(Mutex::new(vec![]));
+            let handle = {
+                let server = server.clone();
+                let requests = requests.clone();
+                let api_keys = api_keys.clone();
+                std::thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        let path = request.url().to_string();
+                        requests.lock().expect("Poisoned").push(path.clone());
+                        let api_key = request
+                            .headers()
+                            .iter()
+                            .find(|header| header.field.equiv(API_KEY_HEADER));
+                        if let Some(header) = api_key {
+                            let value = header.value.to_string();
+                            api_keys.lock().expect("Poisoned").push(value);
+                        }
+                        let response = match responses.get(&path) {
+                            Some(mock) => {
+                                let mut response = Response::from_data(mock.body.clone())
+                                    .with_status_code(mock.status);
+                                for (name, value) in &mock.headers {
+                                    let header = Header::from_bytes(*name, value.as_bytes())
+                                        .expect("Invalid header");
+                                    response.add_header(header);
+                                }
+                                response
+                            }
+                            None => Response::from_data(vec![]).with_status_code(404),
+                        };
+                        let _ = request.respond(response);
+                    }
+                })
+            };
+            Self {
+                server,
+                requests,
+                api_keys,
+                handle: Some(handle),
+            }
+        }
+
+        fn url(&self) -> String {
+            let address = self.server.server_addr().to_ip().expect("Not an IP server");
+            format!("http://{address}")
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().expect("Poisoned").clone()
+        }
+
+        /// The values of the API key header, from the requests which had one
+        fn api_keys(&self) -> Vec<String> {
+            self.api_keys.lock().expect("Poisoned").clone()
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    fn url_encoded_chain(chain: &[Certificate]) -> String {
+        let pem = chain
+            .iter()
+            .map(|cert| cert.to_pem(LineEnding::LF).expect("Failed to encode PEM"))
+            .collect::<String>();
+        utf8_percent_encode(&pem, NON_ALPHANUMERIC).to_string()
+    }
+
+    /// The PCCS responses for `collateral`
+    fn pccs_responses(collateral: &Collateral) -> HashMap<String, MockResponse> {
+        let fmspc = hex::encode_upper(FMSPC);
+        let root_ca_crl = collateral.root_ca_crl().to_der().expect("Failed to encode");
+        let pck_crl = collateral.pck_crl().to_der().expect("Failed to encode");
+        let qe_identity = MockResponse::ok(collateral.qe_identity()).header(
+            "SGX-Enclave-Identity-Issuer-Chain",
+            url_encoded_chain(collateral.qe_identity_issuer_chain()),
+        );
+        [
+            (
+                format!("{API_PATH}/rootcacrl"),
+                MockResponse::ok(hex::encode(root_ca_crl)),
+            ),
+            (
+                format!("{API_PATH}/pckcrl?ca=processor&encoding=der"),
+                MockResponse::ok(pck_crl).header(
+                    "SGX-PCK-CRL-Issuer-Chain",
+                    url_encoded_chain(collateral.pck_crl_issuer_chain()),
+                ),
+            ),
+            (
+                format!("{API_PATH}/tcb?fmspc={fmspc}"),
+                MockResponse::ok(collateral.tcb_info()).header(
+                    "TCB-Info-Issuer-Chain",
+                    url_encoded_chain(collateral.tcb_issuer_chain()),
+                ),
+            ),
+            (format!("{API_PATH}/qe/identity"), qe_identity.clone()),
+            (format!("{API_PATH}/qve/identity"), qe_identity),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn pccs_client(server: &MockServer) -> PcsClient {
+        PcsClient::pccs(Agent::new_with_defaults(), &server.url())
+    }
+
+    #[test]
+    fn collateral_from_pccs() {
+        let collateral = TestCa::default().collateral();
+        let server = MockServer::new(pccs_responses(&collateral));
+        let client = pccs_client(&server);
+
+        assert_eq!(client.collateral(&FMSPC, CaType::Processor), Ok(collateral));
+        assert_eq!(
+            server.requests(),
+            vec![
+                format!("{API_PATH}/rootcacrl"),
+                format!("{API_PATH}/pckcrl?ca=processor&encoding=der"),
+                format!("{API_PATH}/tcb?fmspc=00906EA10000"),
+                format!("{API_PATH}/qe/identity"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pccs_url_with_trailing_slash() {
+        let collateral = TestCa::default().collateral();
+        let server = MockServer::new(pccs_responses(&collateral));
+        let client = PcsClient::pccs(Agent::new_with_defaults(), &format!("{}/", server.url()));
+
+        assert_eq!(client.root_ca_crl().as_ref(), Ok(collateral.root_ca_crl()));
+    }
+
+    #[test]
+    fn pcs_urls() {
+        let client = PcsClient::pcs(Agent::new_with_defaults());
+        assert_eq!(
+            client.api_url,
+            "https://api.trustedservices.intel.com/sgx/certification/v4"
+        );
+        assert_eq!(client.root_ca_crl_url, PCS_ROOT_CA_CRL_URL);
+    }
+
+    #[test]
+    fn platform_pck_crl() {
+        let collateral = TestCa::default().collateral();
+        let mut responses = pccs_responses(&collateral);
+        let processor = responses
+            .remove(&format!("{API_PATH}/pckcrl?ca=processor&encoding=der"))
+            .expect("Missing processor CRL");
+        responses.insert(
+            format!("{API_PATH}/pckcrl?ca=platform&encoding=der"),
+            processor,
+        );
+        let server = MockServer::new(responses);
+        let client = pccs_client(&server);
+
+        let pck_crl = client
+            .pck_crl(CaType::Platform)
+            .expect("Failed to get PCK CRL");
+        assert_eq!(pck_crl.value(), collateral.pck_crl());
+        assert_eq!(pck_crl.issuer_chain(), collateral.pck_crl_issuer_chain());
+    }
+
+    #[test]
+    fn qve_identity() {
+        let collateral = TestCa::default().collateral();
+        let server = MockServer::new(pccs_responses(&collateral));
+        let client = pccs_client(&server);
+
+        let qve_identity = client.qve_identity().expect("Failed to get QvE identity");
+        assert_eq!(qve_identity.value(), collateral.qe_identity());
+        assert_eq!(
+            qve_identity.issuer_chain(),
+            collateral.qe_identity_issuer_chain()
+        );
+    }
+
+    #[test]
+    fn version_3_tcb_info_issuer_chain() {
+        let collateral = TestCa::default().collateral();
+        let mut responses = pccs_responses(&collateral);
+        let path = format!("{API_PATH}/tcb?fmspc=00906EA10000");
+        responses.insert(
+            path,
+            MockResponse::ok(collateral.tcb_info()).header(
+                "SGX-TCB-Info-Issuer-Chain",
+                url_encoded_chain(collateral.tcb_issuer_chain()),
+            ),
+        );
+        let server = MockServer::new(responses);
+        let client = pccs_client(&server);
+
+        let tcb_info = client.tcb_info(&FMSPC).expect("Failed to get TCB info");
+        assert_eq!(tcb_info.value(), collateral.tcb_info());
+        assert_eq!(tcb_info.issuer_chain(), collateral.tcb_issuer_chain());
+    }
+
+    #[test]
+    fn missing_issuer_chain() {
+        let collateral = TestCa::default().collateral();
+        let mut responses = pccs_responses(&collateral);
+        responses.insert(
+            format!("{API_PATH}/qe/identity"),
+            MockResponse::ok(collateral.qe_identity()),
+        );
+        let server = MockServer::new(responses);
+        let client = pccs_client(&server);
+
+        assert_eq!(
+            client.qe_identity(),
+            Err(Error::MissingHeader(
+                format!("{}{API_PATH}/qe/identity", server.url()),
+                "SGX-Enclave-Identity-Issuer-Chain"
+            ))
+        );
+    }
+
+    #[parameterized(
+        not_url_encoded = { "%ZZ" },
+        not_pem = { "certificate" },
+        empty = { "" },
+    )]
+    fn invalid_issuer_chain(header: &str) {
+        let collateral = TestCa::default().collateral();
+        let mut responses = pccs_responses(&collateral);
+        responses.insert(
+            format!("{API_PATH}/qe/identity"),
+            MockResponse::ok(collateral.qe_identity())
+                .header("SGX-Enclave-Identity-Issuer-Chain", header),
+        );
+        let server = MockServer::new(responses);
+        let client = pccs_client(&server);
+
+        assert_eq!(
+            client.qe_identity(),
+            Err(Error::IssuerChain("SGX-Enclave-Identity-Issuer-Chain"))
+        );
+    }
+
+    #[test]
+    fn unknown_fmspc() {
+        let collateral = TestCa::default().collateral();
+        let server = MockServer::new(pccs_responses(&collateral));
+        let client = pccs_client(&server);
+
+        assert_eq!(
+            client.tcb_info(&[0xFF; FMSPC_SIZE]),
+            Err(Error::Status(
+                format!("{}{API_PATH}/tcb?fmspc=FFFFFFFFFFFF", server.url()),
+                404
+            ))
+        );
+    }
+
+    #[test]
+    fn json_not_utf8() {
+        let collateral = TestCa::default().collateral();
+        let mut responses = pccs_responses(&collateral);
+        responses.insert(
+            format!("{API_PATH}/qe/identity"),
+            MockResponse::ok(vec![0xFF, 0xFE]).header(
+                "SGX-Enclave-Identity-Issuer-Chain",
+                url_encoded_chain(collateral.qe_identity_issuer_chain()),
+            ),
+        );
+        let server = MockServer::new(responses);
+        let client = pccs_client(&server);
+
+        assert_eq!(
+            client.qe_identity(),
+            Err(Error::Utf8(format!(
+                "{}{API_PATH}/qe/identity",
+                server.url()
+            )))
+        );
+    }
+
+    #[test]
+    fn unreachable_server() {
+        let url = {
+            let server = MockServer::new(HashMap::new());
+            server.url()
+        };
+        let client = PcsClient::pccs(Agent::new_with_defaults(), &url);
+
+        assert_matches!(client.root_ca_crl(), Err(Error::Http(request_url, _)) if request_url == format!("{url}{API_PATH}/rootcacrl"));
+    }
+
+    #[parameterized(
+        der = { |der: Vec<u8>| der },
+        pem = { |der: Vec<u8>| {
+            pem::encode_string(CRL_PEM_LABEL, LineEnding::LF, &der)
+                .expect("Failed to encode PEM")
+                .into_bytes()
+        }},
+        hex = { |der: Vec<u8>| hex::encode(der).into_bytes() },
+        hex_with_newline = { |der: Vec<u8>| format!("{}\n", hex::encode(der)).into_bytes() },
+    )]
+    fn crl_encodings(encode: fn(Vec<u8>) -> Vec<u8>) {
+        let collateral = TestCa::default().collateral();
+        let expected = collateral.root_ca_crl();
+        let body = encode(expected.to_der().expect("Failed to encode"));
+        assert_eq!(crl("url", &body).as_ref(), Ok(expected));
+    }
+
+    #[test]
+    fn invalid_crl() {
+        assert_eq!(crl("url", b"not a crl"), Err(Error::Crl("url".into())));
+    }
+
+    /// Certification data with a PPID of `ppid_size` bytes of `0x11`, a CPUSVN
+    /// of `0x22`, and the PCESVN and PCEID of the test CA.
+    fn platform_data(kind: u16, ppid_size: usize) -> Vec<u8> {
+        let mut data = vec![0x11; ppid_size];
+        data.extend_from_slice(&[0x22; 16]);
+        data.extend_from_slice(&PCE_SVN.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+
+        let mut bytes = kind.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    fn pck_certificate_response(ca: &TestCa) -> MockResponse {
+        let pem = ca
+            .pck_certificate()
+            .to_pem(LineEnding::LF)
+            .expect("Failed to encode PEM");
+        let issuer_chain = [
+            ca.intermediate_certificate().clone(),
+            ca.root_certificate().clone(),
+        ];
+        MockResponse::ok(pem).header(
+            "SGX-PCK-Certificate-Issuer-Chain",
+            url_encoded_chain(&issuer_chain),
+        )
+    }
+
+    const CPUSVN_QUERY: &str = "cpusvn=22222222222222222222222222222222&pcesvn=0D00&pceid=0000";
+
+    #[test]
+    fn pck_certificate_from_pccs() {
+        let ca = TestCa::default();
+        let path = format!(
+            "{API_PATH}/pckcert?{CPUSVN_QUERY}&qeid={}",
+            hex::encode_upper(QE_ID)
+        );
+        let server = MockServer::new([(path.clone(), pck_certificate_response(&ca))].into());
+        let client = pccs_client(&server);
+        let bytes = platform_data(1, 16);
+        let certification_data =
+            CertificationData::try_from(bytes.as_slice()).expect("Invalid data");
+        let platform = PlatformId::try_from(&certification_data).expect("Not a PPID");
+
+        let pck = client
+            .pck_certificate(&QE_ID, &platform)
+            .expect("Failed to get PCK certificate");
+
+        assert_eq!(pck.value(), ca.pck_certificate());
+        assert_eq!(
+            pck.issuer_chain(),
+            [
+                ca.intermediate_certificate().clone(),
+                ca.root_certificate().clone()
+            ]
+        );
+        assert_eq!(server.requests(), vec![path]);
+        assert!(server.api_keys().is_empty());
+    }
+
+    #[test]
+    fn pck_certificate_from_pcs_with_api_key() {
+        let ca = TestCa::default();
+        let encrypted_ppid = "11".repeat(384);
+        let path = format!("{API_PATH}/pckcert?encrypted_ppid={encrypted_ppid}&{CPUSVN_QUERY}");
+        let server = MockServer::new([(path.clone(), pck_certificate_response(&ca))].into());
+        let client = PcsClient::new(
+            Agent::new_with_defaults(),
+            Service::Pcs,
+            &server.url(),
+            PCS_ROOT_CA_CRL_URL,
+        )
+        .api_key("secret");
+        let bytes = platform_data(3, 384);
+        let certification_data =
+            CertificationData::try_from(bytes.as_slice()).expect("Invalid data");
+        let platform = PlatformId::try_from(&certification_data).expect("Not a PPID");
+
+        let pck = client
+            .pck_certificate(&QE_ID, &platform)
+            .expect("Failed to get PCK certificate");
+
+        assert_eq!(pck.value(), ca.pck_certificate());
+        assert_eq!(server.requests(), vec![path]);
+        assert_eq!(server.api_keys(), vec!["secret".to_string()]);
+    }
+
+    #[parameterized(
+        plaintext = { 1, 16 },
+        rsa_2048 = { 2, 256 },
+    )]
+    fn pcs_requires_rsa_3072_ppid(kind: u16, ppid_size: usize) {
+        let client = PcsClient::pcs(Agent::new_with_defaults());
+        let bytes = platform_data(kind, ppid_size);
+        let certification_data =
+            CertificationData::try_from(bytes.as_slice()).expect("Invalid data");
+        let platform = PlatformId::try_from(&certification_data).expect("Not a PPID");
+
+        assert_eq!(
+            client.pck_certificate(&QE_ID, &platform),
+            Err(Error::UnsupportedPpid)
+        );
+    }
+
+    #[test]
+    fn invalid_pck_certificate() {
+        let ca = TestCa::default();
+        let path = format!(
+            "{API_PATH}/pckcert?{CPUSVN_QUERY}&qeid={}",
+            hex::encode_upper(QE_ID)
+        );
+        let response = pck_certificate_response(&ca);
+        let response = MockResponse {
+            body: b"not a certificate".to_vec(),
+            ..response
+        };
+        let server = MockServer::new([(path.clone(), response)].into());
+        let client = pccs_client(&server);
+        let bytes = platform_data(1, 16);
+        let certification_data =
+            CertificationData::try_from(bytes.as_slice()).expect("Invalid data");
+        let platform = PlatformId::try_from(&certification_data).expect("Not a PPID");
+
+        assert_eq!(
+            client.pck_certificate(&QE_ID, &platform),
+            Err(Error::PckCertificate(format!("{}{path}", server.url())))
+        );
+    }
+}
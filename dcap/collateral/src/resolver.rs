@@ -0,0 +1,542 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Resolution of the PCK certificate chain of a quote.
+//!
+//! Quotes only carry the PCK certificate chain when the quoting library was
+//! configured for it. Otherwise the certification data has the platform
+//! identifiers (PPID, CPUSVN, PCESVN, and PCEID) or only the PCK leaf
+//! certificate, and the rest has to come from the PCS, a PCCS, or a local
+//! store.
+
+use crate::{Error, Issued, PcsClient, StoreLayout};
+use mc_sgx_dcap_types::{
+    CaType, CertificationData, PlatformId, Quote3, TcbInfo, COMPONENT_SVN_COUNT, QE_ID_SIZE,
+};
+use mc_sgx_util::trim_ascii_whitespace;
+use std::fs;
+use std::path::{Path, PathBuf};
+use x509_cert::der::pem::LineEnding;
+use x509_cert::der::{Decode, DecodePem, EncodePem};
+use x509_cert::Certificate;
+
+/// The description of the quote as the source of a PCK certificate
+const QUOTE_SOURCE: &str = "the quote";
+
+/// The description of a chain being stored as the source of a PCK certificate
+const STORED_CHAIN_SOURCE: &str = "the chain to store";
+
+/// A source of PCK certificates and their issuer chains.
+pub trait PckCertificateResolver {
+    /// The PCK certificate of a platform.
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform.
+    /// * `platform` - The platform identifiers, from the certification data of
+    ///   a quote.
+    fn pck_certificate(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        platform: &PlatformId,
+    ) -> Result<Issued<Certificate>, Error>;
+
+    /// The certificate chain of `ca`, leaf first.
+    fn issuer_chain(&self, ca: CaType) -> Result<Vec<Certificate>, Error>;
+
+    /// The complete PCK certificate chain, leaf first, for verifying `quote`.
+    ///
+    /// An embedded chain is used as is. An embedded leaf certificate is
+    /// completed with the chain of its issuing CA. The platform identifiers
+    /// are used to resolve the leaf certificate and its issuer chain.
+    ///
+    /// The chain is not verified, that is left to the appraisal of the quote.
+    ///
+    /// # Errors
+    /// * [`Error::CertificationData`] if the certification data is malformed.
+    /// * [`Error::UnsupportedCertificationData`] if the certification data
+    ///   has no way to identify the PCK certificate.
+    /// * [`Error::PckCertificate`] if an embedded certificate is invalid.
+    /// * An [`Error`] from resolving the certificates.
+    fn pck_certificate_chain<T: AsRef<[u8]>>(
+        &self,
+        quote: &Quote3<T>,
+    ) -> Result<Vec<Certificate>, Error> {
+        let signature_data = quote.signature_data();
+        let certification_data = signature_data.certification_data();
+        match certification_data {
+            CertificationData::PckCertificateChain(pem_chain) => {
+                let chain = pem_chain
+                    .into_iter()
+                    .map(Certificate::from_pem)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| Error::PckCertificate(QUOTE_SOURCE.into()))?;
+                if chain.is_empty() {
+                    return Err(Error::PckCertificate(QUOTE_SOURCE.into()));
+                }
+                Ok(chain)
+            }
+            CertificationData::Pck(_) => {
+                let leaf = certificate(certification_data.raw_data())
+                    .ok_or_else(|| Error::PckCertificate(QUOTE_SOURCE.into()))?;
+                let ca = CaType::try_from(&leaf)?;
+                let mut chain = vec![leaf];
+                chain.extend(self.issuer_chain(ca)?);
+                Ok(chain)
+            }
+            CertificationData::Ppid(_)
+            | CertificationData::PpidEncryptedRsa2048(_)
+            | CertificationData::PpidEncryptedRsa3072(_) => {
+                let platform = PlatformId::try_from(certification_data)?;
+                let (leaf, issuer_chain) =
+                    self.pck_certificate(quote.qe_id(), &platform)?.into_parts();
+                let mut chain = vec![leaf];
+                chain.extend(issuer_chain);
+                Ok(chain)
+            }
+            _ => Err(Error::UnsupportedCertificationData),
+        }
+    }
+}
+
+impl PckCertificateResolver for PcsClient {
+    fn pck_certificate(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        platform: &PlatformId,
+    ) -> Result<Issued<Certificate>, Error> {
+        PcsClient::pck_certificate(self, qe_id, platform)
+    }
+
+    /// The issuer chain of the PCK CRL of `ca`, which is the chain of `ca`.
+    fn issuer_chain(&self, ca: CaType) -> Result<Vec<Certificate>, Error> {
+        let (_, issuer_chain) = self.pck_crl(ca)?.into_parts();
+        Ok(issuer_chain)
+    }
+}
+
+/// Resolves PCK certificates from PEM files in a directory.
+///
+/// This is for platforms which are registered out of band, for instance with
+/// the PCK ID retrieval tool, so that quotes can be verified without access
+/// to the PCS or a PCCS.
+///
+/// The files are the PCK certificate chains of the [`StoreLayout`], the one
+/// for the highest TCB level at or below the CPUSVN and PCESVN of a quote is
+/// used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryPckCertificateResolver {
+    layout: StoreLayout,
+}
+
+impl DirectoryPckCertificateResolver {
+    /// A resolver for the files in `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            layout: StoreLayout::new(path),
+        }
+    }
+
+    /// The directory of the files
+    pub fn path(&self) -> &Path {
+        self.layout.path()
+    }
+
+    /// Store a PCK certificate chain, leaf first, of a platform.
+    ///
+    /// The chain is stored for the TCB level of its PCK certificate, next to
+    /// the chains for the other TCB levels of the platform.
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform.
+    /// * `pce_id` - The PCEID of the platform.
+    /// * `chain` - A PCK certificate chain of the platform, leaf first.
+    ///
+    /// # Errors
+    /// * [`Error::PckCertificate`] if the chain is empty or the TCB level of
+    ///   the PCK certificate can't be read.
+    /// * [`Error::Store`] if the file can't be written.
+    pub fn store_pck_certificate_chain(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        pce_id: u16,
+        chain: &[Certificate],
+    ) -> Result<(), Error> {
+        let invalid = || Error::PckCertificate(STORED_CHAIN_SOURCE.into());
+        let leaf = chain.first().ok_or_else(invalid)?;
+        let tcb = TcbInfo::try_from(leaf).map_err(|_| invalid())?;
+        let mut cpu_svn = [0; COMPONENT_SVN_COUNT];
+        for (byte, svn) in cpu_svn.iter_mut().zip(tcb.svns()) {
+            *byte = u8::try_from(*svn).map_err(|_| invalid())?;
+        }
+        let pce_svn = u16::try_from(*tcb.pce_svn()).map_err(|_| invalid())?;
+        let path = self
+            .layout
+            .tcb_level_pck_certificate_chain_file(qe_id, pce_id, &cpu_svn, pce_svn);
+        self.store(&path, chain)
+    }
+
+    /// Store the certificate chain of `ca`, leaf first.
+    pub fn store_issuer_chain(&self, ca: CaType, chain: &[Certificate]) -> Result<(), Error> {
+        self.store(&self.layout.issuer_chain_file(ca), chain)
+    }
+
+    fn store(&self, path: &Path, chain: &[Certificate]) -> Result<(), Error> {
+        let pem = chain
+            .iter()
+            .map(|cert| cert.to_pem(LineEnding::LF))
+            .collect::<Result<String, _>>()
+            .map_err(|err| store_error(path, err))?;
+        let directory = self.layout.path();
+        fs::create_dir_all(directory).map_err(|err| store_error(directory, err))?;
+
+        // Write to a temporary file first so that a concurrent read never
+        // sees a partially written file.
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, pem).map_err(|err| store_error(&temp_path, err))?;
+        fs::rename(&temp_path, path).map_err(|err| store_error(path, err))
+    }
+
+    /// The file of the PCK certificate chain for `platform`.
+    fn pck_certificate_file(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        platform: &PlatformId,
+    ) -> Result<PathBuf, Error> {
+        let mut cpu_svn = [0; COMPONENT_SVN_COUNT];
+        cpu_svn.copy_from_slice(platform.cpu_svn().as_ref());
+        self.layout
+            .pck_certificate_chain_file(qe_id, platform.pce_id(), &cpu_svn, platform.pce_svn())
+            .map_err(|err| store_error(self.layout.path(), err))
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<Certificate>, Error> {
+        let pem = fs::read(path).map_err(|err| store_error(path, err))?;
+        let invalid = || Error::PckCertificate(path.display().to_string());
+        // `load_pem_chain()` panics on empty input
+        if trim_ascii_whitespace(&pem).is_empty() {
+            return Err(invalid());
+        }
+        Certificate::load_pem_chain(&pem).map_err(|_| invalid())
+    }
+}
+
+impl PckCertificateResolver for DirectoryPckCertificateResolver {
+    fn pck_certificate(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        platform: &PlatformId,
+    ) -> Result<Issued<Certificate>, Error> {
+        let mut chain = self
+            .load(&self.pck_certificate_file(qe_id, platform)?)?
+            .into_iter();
+        let leaf = chain
+            .next()
+            .expect("`load()` returns at least one certificate");
+        Ok(Issued::new(leaf, chain.collect()))
+    }
+
+    fn issuer_chain(&self, ca: CaType) -> Result<Vec<Certificate>, Error> {
+        self.load(&self.layout.issuer_chain_file(ca))
+    }
+}
+
+fn store_error(path: &Path, err: impl ToString) -> Error {
+    Error::Store(path.display().to_string(), err.to_string())
+}
+
+/// Decode a certificate which may be PEM or DER
+fn certificate(bytes: &[u8]) -> Option<Certificate> {
+    Certificate::from_pem(bytes)
+        .or_else(|_| Certificate::from_der(bytes))
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use mc_sgx_core_types::ReportBody;
+    use mc_sgx_dcap_types::test_utils::{TestCa, QE_ID};
+    use mc_sgx_dcap_types::Quote3Error;
+    use x509_cert::der::Encode;
+    use yare::parameterized;
+
+    const PPID_TYPE: u16 = 1;
+    const PPID_RSA3072_TYPE: u16 = 3;
+    const PCK_TYPE: u16 = 4;
+    const PLATFORM_MANIFEST_TYPE: u16 = 7;
+
+    fn ca_chain(ca: &TestCa) -> Vec<Certificate> {
+        vec![
+            ca.intermediate_certificate().clone(),
+            ca.root_certificate().clone(),
+        ]
+    }
+
+    fn pck_chain(ca: &TestCa) -> Vec<Certificate> {
+        let mut chain = vec![ca.pck_certificate().clone()];
+        chain.extend(ca_chain(ca));
+        chain
+    }
+
+    /// PPID certification data with a PCEID of `pce_id`
+    fn ppid_data(ppid_size: usize, pce_id: u16) -> Vec<u8> {
+        let mut data = vec![0x11; ppid_size + 16 + 2];
+        data.extend_from_slice(&pce_id.to_le_bytes());
+        data
+    }
+
+    /// Plaintext PPID certification data with a PCEID of 0 and the TCB level
+    /// `cpu_svn` for every component and `pce_svn`
+    fn ppid_data_at(cpu_svn: u8, pce_svn: u16) -> Vec<u8> {
+        let mut data = vec![0x11; 16];
+        data.extend_from_slice(&[cpu_svn; 16]);
+        data.extend_from_slice(&pce_svn.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data
+    }
+
+    /// The file name of a chain of the platform at a TCB level of `cpu_svn`
+    /// for every component and `pce_svn`
+    fn tcb_level_file(cpu_svn: u8, pce_svn: u16) -> String {
+        format!(
+            "{}-0000-{}-{}.pem",
+            hex::encode_upper(QE_ID),
+            hex::encode_upper([cpu_svn; 16]),
+            hex::encode_upper(pce_svn.to_le_bytes())
+        )
+    }
+
+    fn write_chain(dir: &Path, file_name: &str, chain: &[Certificate]) {
+        let pem = chain
+            .iter()
+            .map(|cert| cert.to_pem(LineEnding::LF).expect("Failed to encode"))
+            .collect::<String>();
+        fs::write(dir.join(file_name), pem).expect("Failed to write file");
+    }
+
+    /// A resolver with the chains of `ca` stored in a temporary directory
+    fn stored_resolver(ca: &TestCa) -> (tempfile::TempDir, DirectoryPckCertificateResolver) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let resolver = DirectoryPckCertificateResolver::new(dir.path());
+        resolver
+            .store_pck_certificate_chain(&QE_ID, 0, &pck_chain(ca))
+            .expect("Failed to store PCK chain");
+        resolver
+            .store_issuer_chain(CaType::Processor, &ca_chain(ca))
+            .expect("Failed to store CA chain");
+        (dir, resolver)
+    }
+
+    #[test]
+    fn embedded_chain_is_used_as_is() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let resolver = DirectoryPckCertificateResolver::new("/does/not/exist");
+
+        assert_eq!(resolver.pck_certificate_chain(&quote), Ok(pck_chain(&ca)));
+    }
+
+    #[parameterized(
+        plaintext = { PPID_TYPE, 16 },
+        rsa_3072 = { PPID_RSA3072_TYPE, 384 },
+    )]
+    fn chain_from_platform_id(kind: u16, ppid_size: usize) {
+        let ca = TestCa::default();
+        let (_dir, resolver) = stored_resolver(&ca);
+        let quote = ca.quote_with_certification_data(
+            &ReportBody::default(),
+            kind,
+            &ppid_data(ppid_size, 0),
+        );
+
+        assert_eq!(resolver.pck_certificate_chain(&quote), Ok(pck_chain(&ca)));
+    }
+
+    #[parameterized(
+        pem = { |cert: &Certificate| cert.to_pem(LineEnding::LF).unwrap().into_bytes() },
+        der = { |cert: &Certificate| cert.to_der().unwrap() },
+    )]
+    fn chain_from_leaf(encode: fn(&Certificate) -> Vec<u8>) {
+        let ca = TestCa::default();
+        let (_dir, resolver) = stored_resolver(&ca);
+        let leaf = encode(ca.pck_certificate());
+        let quote = ca.quote_with_certification_data(&ReportBody::default(), PCK_TYPE, &leaf);
+
+        assert_eq!(resolver.pck_certificate_chain(&quote), Ok(pck_chain(&ca)));
+    }
+
+    #[test]
+    fn invalid_leaf() {
+        let ca = TestCa::default();
+        let (_dir, resolver) = stored_resolver(&ca);
+        let quote = ca.quote_with_certification_data(&ReportBody::default(), PCK_TYPE, b"leaf");
+
+        assert_eq!(
+            resolver.pck_certificate_chain(&quote),
+            Err(Error::PckCertificate(QUOTE_SOURCE.into()))
+        );
+    }
+
+    #[test]
+    fn unknown_platform() {
+        let ca = TestCa::default();
+        let (dir, resolver) = stored_resolver(&ca);
+        let quote = ca.quote_with_certification_data(
+            &ReportBody::default(),
+            PPID_TYPE,
+            &ppid_data(16, 0x0102),
+        );
+
+        let path = dir
+            .path()
+            .join(format!("{}-0201.pem", hex::encode_upper(QE_ID)));
+        assert_matches!(
+            resolver.pck_certificate_chain(&quote),
+            Err(Error::Store(file, _)) if file == path.display().to_string()
+        );
+    }
+
+    #[test]
+    fn store_names_chain_by_tcb_level() {
+        let ca = TestCa::default();
+        let (dir, _resolver) = stored_resolver(&ca);
+
+        let file_name = format!(
+            "{}-0000-0102030405060708090A0B0C0D0E0F10-0D00.pem",
+            hex::encode_upper(QE_ID)
+        );
+        assert!(dir.path().join(file_name).is_file());
+    }
+
+    #[test]
+    fn store_empty_chain_fails() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let resolver = DirectoryPckCertificateResolver::new(dir.path());
+
+        assert_eq!(
+            resolver.store_pck_certificate_chain(&QE_ID, 0, &[]),
+            Err(Error::PckCertificate(STORED_CHAIN_SOURCE.into()))
+        );
+    }
+
+    #[parameterized(
+        exact = { 0x10, 0x10, 0x10 },
+        above_highest = { 0xFF, 0xFF, 0x20 },
+        between = { 0x1F, 0x10, 0x10 },
+        pce_svn_below = { 0x20, 0x0F, 0x01 },
+    )]
+    fn chain_for_highest_tcb_level_at_or_below_platform(
+        cpu_svn: u8,
+        pce_svn: u16,
+        expected_level: u8,
+    ) {
+        let ca = TestCa::default();
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let resolver = DirectoryPckCertificateResolver::new(dir.path());
+        // The chains are told apart by their length
+        let chains = [
+            (0x01, vec![ca.root_certificate().clone()]),
+            (0x10, ca_chain(&ca)),
+            (0x20, pck_chain(&ca)),
+        ];
+        for (level, chain) in &chains {
+            write_chain(dir.path(), &tcb_level_file(*level, *level as u16), chain);
+        }
+        let quote = ca.quote_with_certification_data(
+            &ReportBody::default(),
+            PPID_TYPE,
+            &ppid_data_at(cpu_svn, pce_svn),
+        );
+
+        let (leaf, issuer_chain) = resolver
+            .pck_certificate(
+                quote.qe_id(),
+                &PlatformId::try_from(&quote.signature_data().certification_data())
+                    .expect("Failed to get platform ID"),
+            )
+            .expect("Failed to resolve PCK certificate")
+            .into_parts();
+
+        let (_, expected) = chains
+            .iter()
+            .find(|(level, _)| *level == expected_level)
+            .expect("Unknown TCB level");
+        assert_eq!(leaf, expected[0]);
+        assert_eq!(issuer_chain, expected[1..]);
+    }
+
+    #[test]
+    fn platform_file_when_below_every_tcb_level() {
+        let ca = TestCa::default();
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let resolver = DirectoryPckCertificateResolver::new(dir.path());
+        write_chain(dir.path(), &tcb_level_file(0x20, 0x20), &ca_chain(&ca));
+        let platform_file = format!("{}-0000.pem", hex::encode_upper(QE_ID));
+        write_chain(dir.path(), &platform_file, &pck_chain(&ca));
+        let quote = ca.quote_with_certification_data(
+            &ReportBody::default(),
+            PPID_TYPE,
+            &ppid_data_at(0x10, 0x10),
+        );
+
+        assert_eq!(resolver.pck_certificate_chain(&quote), Ok(pck_chain(&ca)));
+    }
+
+    #[test]
+    fn malformed_platform_id() {
+        let ca = TestCa::default();
+        let (_dir, resolver) = stored_resolver(&ca);
+        let quote = ca.quote_with_certification_data(&ReportBody::default(), PPID_TYPE, &[0; 4]);
+
+        assert_eq!(
+            resolver.pck_certificate_chain(&quote),
+            Err(Error::CertificationData(Quote3Error::InputLength {
+                required: 36,
+                actual: 4
+            }))
+        );
+    }
+
+    #[test]
+    fn platform_manifest_is_unsupported() {
+        let ca = TestCa::default();
+        let (_dir, resolver) = stored_resolver(&ca);
+        let quote = ca.quote_with_certification_data(
+            &ReportBody::default(),
+            PLATFORM_MANIFEST_TYPE,
+            &[0; 8],
+        );
+
+        assert_eq!(
+            resolver.pck_certificate_chain(&quote),
+            Err(Error::UnsupportedCertificationData)
+        );
+    }
+
+    #[test]
+    fn empty_store_file() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let resolver = DirectoryPckCertificateResolver::new(dir.path());
+        let path = dir.path().join("processor-ca.pem");
+        fs::write(&path, "\n").expect("Failed to write file");
+
+        assert_eq!(
+            resolver.issuer_chain(CaType::Processor),
+            Err(Error::PckCertificate(path.display().to_string()))
+        );
+    }
+
+    #[test]
+    fn store_creates_directory() {
+        let ca = TestCa::default();
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let resolver = DirectoryPckCertificateResolver::new(dir.path().join("nested"));
+
+        resolver
+            .store_issuer_chain(CaType::Platform, &ca_chain(&ca))
+            .expect("Failed to store CA chain");
+
+        assert_eq!(resolver.issuer_chain(CaType::Platform), Ok(ca_chain(&ca)));
+        assert!(dir.path().join("nested/platform-ca.pem").exists());
+    }
+}
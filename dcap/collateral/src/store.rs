@@ -0,0 +1,319 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! The layout of a local directory of PCK certificates and collateral.
+
+use mc_sgx_dcap_types::{CaType, COMPONENT_SVN_COUNT, FMSPC_SIZE, QE_ID_SIZE};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+const PCK_CERTIFICATE_EXTENSION: &str = "pem";
+const COLLATERAL_EXTENSION: &str = "der";
+
+/// The file layout of a local directory of PCK certificate chains and quote
+/// verification collateral.
+///
+/// This is the layout read and written by the
+/// [`DirectoryPckCertificateResolver`](crate::DirectoryPckCertificateResolver),
+/// `mc-sgx-dcap-quoteverify::DirectoryCollateralProvider` and
+/// `mc-sgx-dcap-qpl::QplStore`, so that one directory can serve all of them.
+///
+/// The files are:
+/// * `{QEID}-{PCEID}-{CPUSVN}-{PCESVN}.pem` - A PCK certificate chain of a
+///   platform, leaf first, where the CPUSVN and PCESVN are the TCB level of
+///   the PCK certificate. The values are upper case hex as in the PCS API, so
+///   the PCEID and PCESVN are little endian. A platform has a certificate per
+///   TCB level, the highest one at or below the TCB level of the platform is
+///   used.
+/// * `{QEID}-{PCEID}.pem` - The PCK certificate chain of a platform, used
+///   when there is no certificate for the TCB level of the platform.
+/// * `{ca}-ca.pem` - The certificate chain of a PCK CA, leaf first, for
+///   instance `processor-ca.pem`.
+/// * `{fmspc}-{ca}-{tcb evaluation data number}.der` - The DER encoded
+///   collateral of the platforms with the FMSPC and PCK CA, for example
+///   `00906ed50000-processor-17.der`. The file with the highest TCB
+///   evaluation data number is used.
+///
+/// The components of the CPUSVN are compared with the TCB component SVNs of
+/// the PCK certificates, as for the TCB info with a TCB type of 0.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct StoreLayout {
+    path: PathBuf,
+}
+
+impl StoreLayout {
+    /// The layout of the directory at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The directory of the files
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file of the PCK certificate chain of a platform.
+    ///
+    /// The chain for the highest TCB level at or below the TCB level of the
+    /// platform, or the chain for any TCB level when there is none. The file
+    /// may not exist.
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform.
+    /// * `pce_id` - The PCEID of the platform.
+    /// * `cpu_svn` - The CPUSVN of the platform.
+    /// * `pce_svn` - The PCESVN of the platform.
+    ///
+    /// # Errors
+    /// If the directory exists but can't be read.
+    pub fn pck_certificate_chain_file(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        pce_id: u16,
+        cpu_svn: &[u8; COMPONENT_SVN_COUNT],
+        pce_svn: u16,
+    ) -> io::Result<PathBuf> {
+        let platform_file = format!(
+            "{}.{PCK_CERTIFICATE_EXTENSION}",
+            platform_file_stem(qe_id, pce_id)
+        );
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Ok(self.path.join(platform_file))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let prefix = format!("{}-", platform_file_stem(qe_id, pce_id));
+        let platform_level = TcbLevel {
+            cpu_svn: *cpu_svn,
+            pce_svn,
+        };
+        let mut best: Option<(TcbLevel, PathBuf)> = None;
+        for entry in entries {
+            let path = entry?.path();
+            let Some(level) = TcbLevel::from_path(&path, &prefix) else {
+                continue;
+            };
+            if level.is_at_or_below(&platform_level)
+                && best.as_ref().map_or(true, |(best, _)| level > *best)
+            {
+                best = Some((level, path));
+            }
+        }
+        Ok(best.map_or_else(|| self.path.join(platform_file), |(_, path)| path))
+    }
+
+    /// The file of the PCK certificate chain of a platform at a TCB level.
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform.
+    /// * `pce_id` - The PCEID of the platform.
+    /// * `cpu_svn` - The TCB component SVNs of the PCK certificate.
+    /// * `pce_svn` - The PCESVN of the PCK certificate.
+    pub fn tcb_level_pck_certificate_chain_file(
+        &self,
+        qe_id: &[u8; QE_ID_SIZE],
+        pce_id: u16,
+        cpu_svn: &[u8; COMPONENT_SVN_COUNT],
+        pce_svn: u16,
+    ) -> PathBuf {
+        self.path.join(format!(
+            "{}-{}-{}.{PCK_CERTIFICATE_EXTENSION}",
+            platform_file_stem(qe_id, pce_id),
+            hex::encode_upper(cpu_svn),
+            hex::encode_upper(pce_svn.to_le_bytes())
+        ))
+    }
+
+    /// The file of the certificate chain of `ca`.
+    pub fn issuer_chain_file(&self, ca: CaType) -> PathBuf {
+        self.path
+            .join(format!("{}-ca.{PCK_CERTIFICATE_EXTENSION}", ca.as_str()))
+    }
+
+    /// The file of the collateral, with `tcb_evaluation_data_number`, for the
+    /// platforms with `fmspc` and PCK certificates issued by `ca`.
+    pub fn collateral_file(
+        &self,
+        fmspc: &[u8; FMSPC_SIZE],
+        ca: CaType,
+        tcb_evaluation_data_number: u32,
+    ) -> PathBuf {
+        self.path.join(format!(
+            "{}{tcb_evaluation_data_number}.{COLLATERAL_EXTENSION}",
+            collateral_file_prefix(fmspc, ca)
+        ))
+    }
+
+    /// The file of the collateral, with the highest TCB evaluation data
+    /// number, for the platforms with `fmspc` and PCK certificates issued by
+    /// `ca`.
+    ///
+    /// Returns `None` when there is no collateral for the platforms, or the
+    /// directory does not exist.
+    ///
+    /// # Errors
+    /// If the directory exists but can't be read.
+    pub fn latest_collateral_file(
+        &self,
+        fmspc: &[u8; FMSPC_SIZE],
+        ca: CaType,
+    ) -> io::Result<Option<PathBuf>> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let prefix = collateral_file_prefix(fmspc, ca);
+        let mut latest = None;
+        for entry in entries {
+            let path = entry?.path();
+            let Some(number) = tcb_evaluation_data_number(&path, &prefix) else {
+                continue;
+            };
+            if latest.as_ref().map_or(true, |(latest, _)| number > *latest) {
+                latest = Some((number, path));
+            }
+        }
+        Ok(latest.map(|(_, path)| path))
+    }
+}
+
+/// The file name, without extension, of the PCK certificate chain of a
+/// platform for any TCB level.
+fn platform_file_stem(qe_id: &[u8; QE_ID_SIZE], pce_id: u16) -> String {
+    format!(
+        "{}-{}",
+        hex::encode_upper(qe_id),
+        hex::encode_upper(pce_id.to_le_bytes())
+    )
+}
+
+/// The start of the file names of the collateral for `fmspc` and `ca`.
+fn collateral_file_prefix(fmspc: &[u8; FMSPC_SIZE], ca: CaType) -> String {
+    format!("{}-{}-", hex::encode(fmspc), ca.as_str())
+}
+
+/// The TCB evaluation data number of the collateral file at `path`.
+///
+/// Returns `None` if `path` is not a collateral file starting with `prefix`.
+fn tcb_evaluation_data_number(path: &Path, prefix: &str) -> Option<u32> {
+    if path.extension()? != COLLATERAL_EXTENSION {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    stem.strip_prefix(prefix)?.parse().ok()
+}
+
+/// The TCB level of a PCK certificate, or the raw TCB of a platform.
+///
+/// Ordered by the CPUSVN and then the PCESVN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct TcbLevel {
+    cpu_svn: [u8; COMPONENT_SVN_COUNT],
+    pce_svn: u16,
+}
+
+impl TcbLevel {
+    /// The TCB level of the PCK certificate chain file at `path`, when its
+    /// name starts with `prefix`.
+    fn from_path(path: &Path, prefix: &str) -> Option<Self> {
+        if path.extension()? != PCK_CERTIFICATE_EXTENSION {
+            return None;
+        }
+        let (cpu_svn, pce_svn) = path
+            .file_stem()?
+            .to_str()?
+            .strip_prefix(prefix)?
+            .split_once('-')?;
+        let mut level = Self {
+            cpu_svn: [0; COMPONENT_SVN_COUNT],
+            pce_svn: 0,
+        };
+        hex::decode_to_slice(cpu_svn, &mut level.cpu_svn).ok()?;
+        let mut pce_svn_bytes = [0; 2];
+        hex::decode_to_slice(pce_svn, &mut pce_svn_bytes).ok()?;
+        level.pce_svn = u16::from_le_bytes(pce_svn_bytes);
+        Some(level)
+    }
+
+    /// Is every component of this TCB level at or below those of `other`.
+    fn is_at_or_below(&self, other: &Self) -> bool {
+        self.pce_svn <= other.pce_svn
+            && self
+                .cpu_svn
+                .iter()
+                .zip(other.cpu_svn.iter())
+                .all(|(svn, other)| svn <= other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_sgx_dcap_types::test_utils::{FMSPC, QE_ID};
+    use tempfile::tempdir;
+
+    #[test]
+    fn file_names() {
+        let layout = StoreLayout::new("/store");
+
+        assert_eq!(
+            layout.tcb_level_pck_certificate_chain_file(&QE_ID, 0x0102, &[0xAB; 16], 0x0D),
+            Path::new("/store").join(format!(
+                "{}-0201-ABABABABABABABABABABABABABABABAB-0D00.pem",
+                hex::encode_upper(QE_ID)
+            ))
+        );
+        assert_eq!(
+            layout.issuer_chain_file(CaType::Platform),
+            Path::new("/store/platform-ca.pem")
+        );
+        assert_eq!(
+            layout.collateral_file(&FMSPC, CaType::Processor, 17),
+            Path::new("/store").join(format!("{}-processor-17.der", hex::encode(FMSPC)))
+        );
+    }
+
+    #[test]
+    fn latest_collateral_file() {
+        let dir = tempdir().unwrap();
+        let layout = StoreLayout::new(dir.path());
+        for (ca, number) in [
+            (CaType::Processor, 16),
+            (CaType::Processor, 17),
+            (CaType::Platform, 18),
+        ] {
+            fs::write(layout.collateral_file(&FMSPC, ca, number), "collateral").unwrap();
+        }
+        fs::write(
+            dir.path()
+                .join(format!("{}-processor-19.tmp", hex::encode(FMSPC))),
+            "partial",
+        )
+        .unwrap();
+
+        assert_eq!(
+            layout
+                .latest_collateral_file(&FMSPC, CaType::Processor)
+                .unwrap(),
+            Some(layout.collateral_file(&FMSPC, CaType::Processor, 17))
+        );
+    }
+
+    #[test]
+    fn no_collateral_in_missing_directory() {
+        let dir = tempdir().unwrap();
+        let layout = StoreLayout::new(dir.path().join("missing"));
+
+        assert_eq!(
+            layout
+                .latest_collateral_file(&FMSPC, CaType::Platform)
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Quote generation from async code.
+
+use crate::{Error, Quoter};
+use mc_sgx_core_types::{Report, TargetInfo};
+use mc_sgx_dcap_types::Quote3;
+use mc_sgx_util::BlockingPool;
+use std::sync::Arc;
+
+/// Generates quotes from async code without blocking the runtime.
+///
+/// The calls to the [`Quoter`] run on the [`BlockingPool`], which limits how
+/// many run at once and how long they can take. Loading the QE(Quoting
+/// Enclave) can take hundreds of milliseconds.
+///
+/// Clones share the same quoter and pool.
+#[derive(Debug)]
+pub struct AsyncQuoter<Q> {
+    quoter: Arc<Q>,
+    pool: BlockingPool,
+}
+
+impl<Q> Clone for AsyncQuoter<Q> {
+    fn clone(&self) -> Self {
+        Self {
+            quoter: self.quoter.clone(),
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl<Q: Quoter + Send + Sync + 'static> AsyncQuoter<Q> {
+    /// Run the calls to `quoter` on `pool`
+    pub fn new(quoter: Q, pool: BlockingPool) -> Self {
+        Self {
+            quoter: Arc::new(quoter),
+            pool,
+        }
+    }
+
+    /// The wrapped quoter
+    pub fn quoter(&self) -> &Q {
+        &self.quoter
+    }
+
+    /// The target info of the QE(Quoting Enclave)
+    ///
+    /// # Errors
+    /// * [`Error::Blocking`] if the call times out or fails to run.
+    /// * The error of the wrapped quoter.
+    pub async fn target_info(&self) -> Result<TargetInfo, Error> {
+        let quoter = self.quoter.clone();
+        self.pool.run(move || quoter.target_info()).await?
+    }
+
+    /// Generate a quote for `report`
+    ///
+    /// # Errors
+    /// * [`Error::Blocking`] if the call times out or fails to run.
+    /// * The error of the wrapped quoter.
+    pub async fn quote(&self, report: Report) -> Result<Quote3<Vec<u8>>, Error> {
+        let quoter = self.quoter.clone();
+        self.pool.run(move || quoter.quote(&report)).await?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::time::Duration;
+    use mc_sgx_dcap_types::QlError;
+    use mc_sgx_util::BlockingError;
+    use std::thread;
+
+    /// A quoter which takes `delay` to fail with [`QlError::Busy`].
+    struct SlowQuoter {
+        delay: Duration,
+    }
+
+    impl Quoter for SlowQuoter {
+        fn target_info(&self) -> Result<TargetInfo, Error> {
+            thread::sleep(self.delay);
+            Ok(TargetInfo::default())
+        }
+
+        fn quote(&self, _report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+            thread::sleep(self.delay);
+            Err(Error::QuoteLibrary(QlError::Busy))
+        }
+    }
+
+    #[tokio::test]
+    async fn target_info_from_quoter() {
+        let quoter = AsyncQuoter::new(
+            SlowQuoter {
+                delay: Duration::ZERO,
+            },
+            BlockingPool::new(1),
+        );
+
+        assert_eq!(quoter.target_info().await, Ok(TargetInfo::default()));
+    }
+
+    #[tokio::test]
+    async fn quote_error_from_quoter() {
+        let quoter = AsyncQuoter::new(
+            SlowQuoter {
+                delay: Duration::ZERO,
+            },
+            BlockingPool::new(1),
+        );
+
+        let result = quoter.quote(Report::default()).await;
+
+        assert_eq!(result.err(), Some(Error::QuoteLibrary(QlError::Busy)));
+    }
+
+    #[tokio::test]
+    async fn slow_quote_times_out() {
+        let timeout = Duration::from_millis(10);
+        let quoter = AsyncQuoter::new(
+            SlowQuoter {
+                delay: Duration::from_millis(200),
+            },
+            BlockingPool::new(1).timeout(timeout),
+        );
+
+        let result = quoter.quote(Report::default()).await;
+
+        assert_eq!(
+            result.err(),
+            Some(Error::Blocking(BlockingError::Timeout(timeout)))
+        );
+    }
+}
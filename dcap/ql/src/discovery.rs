@@ -0,0 +1,507 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Discovery of the DCAP enclaves and quote provider library.
+//!
+//! Distributions install the DCAP packages in different library directories,
+//! `/usr/lib/x86_64-linux-gnu` on Debian and Ubuntu, `/usr/lib64` on RHEL and
+//! SUSE. [`PathDiscovery::from_env()`] searches the well known directories,
+//! after any of the following environment variables:
+//!
+//! * `SGX_QE3_PATH`, `SGX_PCE_PATH`, `SGX_IDE_PATH` and `SGX_QPL_PATH` - The
+//!   full file path of the quoting enclave, provisioning certificate enclave,
+//!   ID enclave and quote provider library respectively. When set, no other
+//!   location is searched for that file.
+//! * `SGX_DCAP_LIB_DIR` - A directory searched before the well known ones.
+//! * `SGX_SDK` - An SGX SDK install, whose `lib64` directory is searched
+//!   before the well known ones.
+
+use crate::Error;
+use mc_sgx_dcap_ql_types::PathKind;
+use std::{
+    env,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    mem,
+    path::{Path, PathBuf},
+};
+
+/// The directories the DCAP packages install to, in search order.
+const WELL_KNOWN_DIRECTORIES: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib64",
+    "/usr/lib",
+    "/usr/local/lib",
+    "/opt/intel/sgxsdk/lib64",
+];
+
+const LIBRARY_DIRECTORY_VARIABLE: &str = "SGX_DCAP_LIB_DIR";
+const SGX_SDK_VARIABLE: &str = "SGX_SDK";
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+
+// The layout of a 64 bit little endian ELF file, the only kind of enclave.
+// From `elf.h`.
+const ELF_HEADER_SIZE: usize = 64;
+const ELF_CLASS_OFFSET: usize = 4;
+const ELF_CLASS_64: u8 = 2;
+const ELF_DATA_OFFSET: usize = 5;
+const ELF_DATA_LITTLE_ENDIAN: u8 = 1;
+const SECTION_HEADERS_OFFSET: usize = 0x28;
+const SECTION_HEADER_SIZE_OFFSET: usize = 0x3A;
+const SECTION_COUNT_OFFSET: usize = 0x3C;
+const SECTION_NAMES_INDEX_OFFSET: usize = 0x3E;
+const SECTION_HEADER_SIZE: usize = 64;
+const SECTION_NAME_OFFSET: usize = 0;
+const SECTION_FILE_OFFSET: usize = 24;
+const SECTION_SIZE_OFFSET: usize = 32;
+const NOTE_HEADER_SIZE: usize = 12;
+
+// The section with the enclave metadata of a signed enclave, NUL terminated
+const METADATA_SECTION_NAME: &[u8; 14] = b".note.sgxmeta\0";
+
+// The magic at the start of the enclave metadata, the descriptor of the note
+// in the `.note.sgxmeta` section. From `metadata.h` of the SGX SDK.
+const METADATA_MAGIC: u64 = 0x86A8_0294_635D_0E4C;
+
+/// Search for the DCAP enclaves and quote provider library (QPL).
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct PathDiscovery {
+    directories: Vec<PathBuf>,
+    overrides: Vec<(PathKind, PathBuf)>,
+}
+
+impl PathDiscovery {
+    /// Search the well known directories, honoring the environment variable
+    /// overrides.
+    pub fn from_env() -> Self {
+        let mut discovery = Self::default();
+        for kind in [
+            PathKind::QuotingEnclave,
+            PathKind::ProvisioningCertificateEnclave,
+            PathKind::IdEnclave,
+            PathKind::QuoteProviderLibrary,
+        ] {
+            if let Some(path) = env::var_os(override_variable(kind)) {
+                discovery = discovery.override_path(kind, path);
+            }
+        }
+        if let Some(directory) = env::var_os(LIBRARY_DIRECTORY_VARIABLE) {
+            discovery = discovery.directory(directory);
+        }
+        if let Some(sdk) = env::var_os(SGX_SDK_VARIABLE) {
+            discovery = discovery.directory(Path::new(&sdk).join("lib64"));
+        }
+        WELL_KNOWN_DIRECTORIES
+            .iter()
+            .fold(discovery, |discovery, directory| {
+                discovery.directory(directory)
+            })
+    }
+
+    /// Search `directory`, after any previously added directories
+    pub fn directory(mut self, directory: impl AsRef<Path>) -> Self {
+        self.directories.push(directory.as_ref().into());
+        self
+    }
+
+    /// Use `path` for `kind` instead of searching the directories
+    pub fn override_path(mut self, kind: PathKind, path: impl AsRef<Path>) -> Self {
+        self.overrides.retain(|(k, _)| *k != kind);
+        self.overrides.push((kind, path.as_ref().into()));
+        self
+    }
+
+    /// Find the file for `kind`.
+    ///
+    /// Enclaves must be signed enclaves and the QPL must be a shared library
+    /// to be found.
+    ///
+    /// # Errors
+    /// [`Error::PathNotFound`] with each of the candidates tried and why they
+    /// were rejected.
+    pub fn find(&self, kind: PathKind) -> Result<PathBuf, Error> {
+        let candidates = match self.overrides.iter().find(|(k, _)| *k == kind) {
+            Some((_, path)) => vec![path.clone()],
+            None => self
+                .directories
+                .iter()
+                .flat_map(|directory| file_names(kind).iter().map(|name| directory.join(name)))
+                .collect(),
+        };
+
+        let mut tried = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            match validate(kind, &candidate) {
+                Ok(()) => return Ok(candidate),
+                Err(reason) => tried.push(format!("{} ({reason})", candidate.display())),
+            }
+        }
+        Err(Error::PathNotFound(format!("{kind:?}"), tried.join(", ")))
+    }
+
+    /// Find the file for `kind`, if it's been overridden or is in one of the
+    /// directories.
+    ///
+    /// # Errors
+    /// [`Error::PathNotFound`] if `kind` is overridden with an invalid file.
+    pub fn find_optional(&self, kind: PathKind) -> Result<Option<PathBuf>, Error> {
+        match self.find(kind) {
+            Ok(path) => Ok(Some(path)),
+            Err(_) if !self.overrides.iter().any(|(k, _)| *k == kind) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The environment variable overriding the path of `kind`
+fn override_variable(kind: PathKind) -> &'static str {
+    match kind {
+        PathKind::QuotingEnclave => "SGX_QE3_PATH",
+        PathKind::ProvisioningCertificateEnclave => "SGX_PCE_PATH",
+        PathKind::IdEnclave => "SGX_IDE_PATH",
+        _ => "SGX_QPL_PATH",
+    }
+}
+
+/// The file names of `kind`, in order of preference
+fn file_names(kind: PathKind) -> &'static [&'static str] {
+    match kind {
+        PathKind::QuotingEnclave => &["libsgx_qe3.signed.so.1", "libsgx_qe3.signed.so"],
+        PathKind::ProvisioningCertificateEnclave => {
+            &["libsgx_pce.signed.so.1", "libsgx_pce.signed.so"]
+        }
+        PathKind::IdEnclave => &[
+            "libsgx_id_enclave.signed.so.1",
+            "libsgx_id_enclave.signed.so",
+        ],
+        _ => &["libdcap_quoteprov.so.1", "libdcap_quoteprov.so"],
+    }
+}
+
+/// Validate `path` is a file for `kind`, returning why it's not.
+fn validate(kind: PathKind, path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Err("missing".into());
+    }
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    match read_at::<4>(&mut file, 0) {
+        Ok(magic) if &magic == ELF_MAGIC => (),
+        _ => return Err("not a shared library".into()),
+    }
+    let is_enclave = !matches!(kind, PathKind::QuoteProviderLibrary);
+    if is_enclave && !has_enclave_metadata(&mut file).unwrap_or(false) {
+        return Err("not a signed enclave".into());
+    }
+    Ok(())
+}
+
+/// Whether the ELF `file` has a `.note.sgxmeta` section holding the enclave
+/// metadata, as signed enclaves do.
+fn has_enclave_metadata(file: &mut File) -> io::Result<bool> {
+    let header = read_at::<ELF_HEADER_SIZE>(file, 0)?;
+    if header[ELF_CLASS_OFFSET] != ELF_CLASS_64 || header[ELF_DATA_OFFSET] != ELF_DATA_LITTLE_ENDIAN
+    {
+        return Ok(false);
+    }
+    let section_headers = le_u64(&header, SECTION_HEADERS_OFFSET);
+    let section_header_size = u64::from(le_u16(&header, SECTION_HEADER_SIZE_OFFSET));
+    let section_count = le_u16(&header, SECTION_COUNT_OFFSET);
+    let names_index = le_u16(&header, SECTION_NAMES_INDEX_OFFSET);
+    if section_header_size < SECTION_HEADER_SIZE as u64 || names_index >= section_count {
+        return Ok(false);
+    }
+
+    let section_header = |file: &mut File, index: u16| {
+        let offset = u64::from(index) * section_header_size;
+        read_at::<SECTION_HEADER_SIZE>(file, offset_in(section_headers, offset)?)
+    };
+    let names = section_header(file, names_index)?;
+    let names_offset = le_u64(&names, SECTION_FILE_OFFSET);
+    let names_size = le_u64(&names, SECTION_SIZE_OFFSET);
+    for index in 0..section_count {
+        let section = section_header(file, index)?;
+        let name_offset = u64::from(le_u32(&section, SECTION_NAME_OFFSET));
+        if name_offset + METADATA_SECTION_NAME.len() as u64 > names_size {
+            continue;
+        }
+        let name = read_at::<14>(file, offset_in(names_offset, name_offset)?)?;
+        if &name == METADATA_SECTION_NAME {
+            return note_has_metadata(file, &section);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether the note in `section` has a descriptor starting with the enclave
+/// metadata magic.
+fn note_has_metadata(file: &mut File, section: &[u8; SECTION_HEADER_SIZE]) -> io::Result<bool> {
+    let offset = le_u64(section, SECTION_FILE_OFFSET);
+    let size = le_u64(section, SECTION_SIZE_OFFSET);
+    if size < NOTE_HEADER_SIZE as u64 {
+        return Ok(false);
+    }
+    let note = read_at::<NOTE_HEADER_SIZE>(file, offset)?;
+    // The name is padded to 4 byte alignment before the descriptor
+    let name_size = (u64::from(le_u32(&note, 0)) + 3) & !3;
+    let descriptor_size = u64::from(le_u32(&note, 4));
+    let descriptor = NOTE_HEADER_SIZE as u64 + name_size;
+    let magic_size = mem::size_of_val(&METADATA_MAGIC) as u64;
+    if descriptor_size < magic_size || descriptor + magic_size > size {
+        return Ok(false);
+    }
+    let magic = read_at::<8>(file, offset_in(offset, descriptor)?)?;
+    Ok(u64::from_le_bytes(magic) == METADATA_MAGIC)
+}
+
+/// `base` + `offset`, failing on overflow from a malformed file.
+fn offset_in(base: u64, offset: u64) -> io::Result<u64> {
+    base.checked_add(offset)
+        .ok_or_else(|| io::ErrorKind::InvalidData.into())
+}
+
+/// Read `N` bytes at `offset` in `file`.
+fn read_at<const N: usize>(file: &mut File, offset: u64) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(value)
+}
+
+fn le_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+    use yare::parameterized;
+
+    /// A 64 bit ELF file with a section named `section_name` holding a note
+    /// whose descriptor is `descriptor`.
+    fn elf_with_note(section_name: &[u8], descriptor: &[u8]) -> Vec<u8> {
+        let mut names = b"\0.shstrtab\0".to_vec();
+        let name_offset = names.len() as u32;
+        names.extend_from_slice(section_name);
+        names.push(0);
+
+        let mut note = Vec::new();
+        note.extend_from_slice(&13u32.to_le_bytes());
+        note.extend_from_slice(&(descriptor.len() as u32).to_le_bytes());
+        note.extend_from_slice(&1u32.to_le_bytes());
+        note.extend_from_slice(b"sgx_metadata\0\0\0\0");
+        note.extend_from_slice(descriptor);
+
+        let names_offset = ELF_HEADER_SIZE as u64;
+        let note_offset = names_offset + names.len() as u64;
+        let section_headers = note_offset + note.len() as u64;
+
+        let mut contents = vec![0; ELF_HEADER_SIZE];
+        contents[..4].copy_from_slice(ELF_MAGIC);
+        contents[ELF_CLASS_OFFSET] = ELF_CLASS_64;
+        contents[ELF_DATA_OFFSET] = ELF_DATA_LITTLE_ENDIAN;
+        contents[SECTION_HEADERS_OFFSET..SECTION_HEADERS_OFFSET + 8]
+            .copy_from_slice(&section_headers.to_le_bytes());
+        contents[SECTION_HEADER_SIZE_OFFSET..SECTION_HEADER_SIZE_OFFSET + 2]
+            .copy_from_slice(&(SECTION_HEADER_SIZE as u16).to_le_bytes());
+        contents[SECTION_COUNT_OFFSET..SECTION_COUNT_OFFSET + 2]
+            .copy_from_slice(&3u16.to_le_bytes());
+        contents[SECTION_NAMES_INDEX_OFFSET..SECTION_NAMES_INDEX_OFFSET + 2]
+            .copy_from_slice(&1u16.to_le_bytes());
+        contents.extend_from_slice(&names);
+        contents.extend_from_slice(&note);
+
+        let sections = [
+            (0, 0, 0),
+            (1, names_offset, names.len() as u64),
+            (name_offset, note_offset, note.len() as u64),
+        ];
+        for (name, offset, size) in sections {
+            let mut section = [0; SECTION_HEADER_SIZE];
+            section[SECTION_NAME_OFFSET..SECTION_NAME_OFFSET + 4]
+                .copy_from_slice(&name.to_le_bytes());
+            section[SECTION_FILE_OFFSET..SECTION_FILE_OFFSET + 8]
+                .copy_from_slice(&offset.to_le_bytes());
+            section[SECTION_SIZE_OFFSET..SECTION_SIZE_OFFSET + 8]
+                .copy_from_slice(&size.to_le_bytes());
+            contents.extend_from_slice(&section);
+        }
+        contents
+    }
+
+    fn signed_enclave() -> Vec<u8> {
+        let mut metadata = METADATA_MAGIC.to_le_bytes().to_vec();
+        metadata.extend_from_slice(&[0; 8]);
+        elf_with_note(b".note.sgxmeta", &metadata)
+    }
+
+    #[parameterized(
+    qe = { PathKind::QuotingEnclave, "libsgx_qe3.signed.so.1" },
+    pce = { PathKind::ProvisioningCertificateEnclave, "libsgx_pce.signed.so.1" },
+    ide = { PathKind::IdEnclave, "libsgx_id_enclave.signed.so.1" },
+    )]
+    fn finds_signed_enclave(kind: PathKind, name: &str) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(name);
+        fs::write(&path, signed_enclave()).unwrap();
+
+        let discovery = PathDiscovery::default().directory(dir.path());
+
+        assert_eq!(discovery.find(kind), Ok(path));
+    }
+
+    #[test]
+    fn finds_quote_provider_library() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("libdcap_quoteprov.so.1");
+        fs::write(&path, ELF_MAGIC).unwrap();
+
+        let discovery = PathDiscovery::default().directory(dir.path());
+
+        assert_eq!(
+            discovery.find(PathKind::QuoteProviderLibrary),
+            Ok(path.clone())
+        );
+        assert_eq!(
+            discovery.find_optional(PathKind::QuoteProviderLibrary),
+            Ok(Some(path))
+        );
+    }
+
+    #[test]
+    fn directories_searched_in_order() {
+        let first = tempdir().unwrap();
+        let second = tempdir().unwrap();
+        let name = "libsgx_qe3.signed.so";
+        fs::write(first.path().join(name), signed_enclave()).unwrap();
+        fs::write(second.path().join(name), signed_enclave()).unwrap();
+
+        let discovery = PathDiscovery::default()
+            .directory(first.path())
+            .directory(second.path());
+
+        assert_eq!(
+            discovery.find(PathKind::QuotingEnclave),
+            Ok(first.path().join(name))
+        );
+    }
+
+    #[test]
+    fn unsigned_enclave_is_skipped() {
+        let first = tempdir().unwrap();
+        let second = tempdir().unwrap();
+        let name = "libsgx_pce.signed.so.1";
+        fs::write(first.path().join(name), ELF_MAGIC).unwrap();
+        fs::write(second.path().join(name), signed_enclave()).unwrap();
+
+        let discovery = PathDiscovery::default()
+            .directory(first.path())
+            .directory(second.path());
+
+        assert_eq!(
+            discovery.find(PathKind::ProvisioningCertificateEnclave),
+            Ok(second.path().join(name))
+        );
+    }
+
+    #[parameterized(
+    magic_outside_section = {{
+        let mut contents = elf_with_note(b".note.other", &[0; 16]);
+        contents.extend_from_slice(&METADATA_MAGIC.to_le_bytes());
+        contents
+    }},
+    wrong_section = { elf_with_note(b".note.other", &METADATA_MAGIC.to_le_bytes()) },
+    wrong_magic = { elf_with_note(b".note.sgxmeta", &[0; 16]) },
+    truncated_descriptor = { elf_with_note(b".note.sgxmeta", &METADATA_MAGIC.to_le_bytes()[..4]) },
+    )]
+    fn enclave_without_metadata_section_is_not_signed(contents: Vec<u8>) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("libsgx_qe3.signed.so.1");
+        fs::write(&path, contents).unwrap();
+
+        let discovery = PathDiscovery::default().override_path(PathKind::QuotingEnclave, &path);
+
+        assert_eq!(
+            discovery.find(PathKind::QuotingEnclave),
+            Err(Error::PathNotFound(
+                "QuotingEnclave".into(),
+                format!("{} (not a signed enclave)", path.display())
+            ))
+        );
+    }
+
+    #[test]
+    fn override_is_the_only_candidate() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("libsgx_qe3.signed.so.1"), signed_enclave()).unwrap();
+        let path = dir.path().join("qe3");
+        fs::write(&path, b"not an enclave").unwrap();
+
+        let discovery = PathDiscovery::default()
+            .directory(dir.path())
+            .override_path(PathKind::QuotingEnclave, &path);
+
+        assert_eq!(
+            discovery.find(PathKind::QuotingEnclave),
+            Err(Error::PathNotFound(
+                "QuotingEnclave".into(),
+                format!("{} (not a shared library)", path.display())
+            ))
+        );
+    }
+
+    #[test]
+    fn not_found_reports_candidates() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("libsgx_id_enclave.signed.so.1"), ELF_MAGIC).unwrap();
+
+        let discovery = PathDiscovery::default().directory(dir.path());
+
+        let tried = format!(
+            "{0}/libsgx_id_enclave.signed.so.1 (not a signed enclave), {0}/libsgx_id_enclave.signed.so (missing)",
+            dir.path().display()
+        );
+        assert_eq!(
+            discovery.find(PathKind::IdEnclave),
+            Err(Error::PathNotFound("IdEnclave".into(), tried))
+        );
+    }
+
+    #[test]
+    fn missing_optional_library() {
+        let dir = tempdir().unwrap();
+
+        let discovery = PathDiscovery::default().directory(dir.path());
+
+        assert_eq!(
+            discovery.find_optional(PathKind::QuoteProviderLibrary),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn invalid_optional_override_fails() {
+        let dir = tempdir().unwrap();
+
+        let discovery = PathDiscovery::default()
+            .override_path(PathKind::QuoteProviderLibrary, dir.path().join("qpl"));
+
+        assert!(matches!(
+            discovery.find_optional(PathKind::QuoteProviderLibrary),
+            Err(Error::PathNotFound(_, _))
+        ));
+    }
+}
@@ -5,12 +5,33 @@
 
 extern crate alloc;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+mod discovery;
+mod library;
+mod logging;
 mod quote3;
 mod quote_enclave;
+#[cfg(feature = "quote-ex")]
+mod quote_ex;
+mod quoter;
+mod registration;
 
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncQuoter;
+pub use discovery::PathDiscovery;
+pub use library::{QuotingLibrary, QuotingLibraryBuilder};
+pub use logging::{forward_logs, stop_forwarding_logs};
 use mc_sgx_dcap_types::{QlError, Quote3Error};
+use mc_sgx_util::{Classify, ErrorClass};
 pub use quote3::TryFromReport;
 pub use quote_enclave::{LoadPolicyInitializer, PathInitializer, QeTargetInfo};
+#[cfg(feature = "quote-ex")]
+pub use quote_ex::QuoteEx;
+#[cfg(feature = "software")]
+pub use quoter::SoftwareQuoter;
+pub use quoter::{Quoter, RetryingQuoter};
+pub use registration::PlatformRegistration;
 
 /// Errors interacting with quote library functions
 #[derive(Clone, Debug, displaydoc::Display, Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -30,6 +51,29 @@ pub enum Error {
     LoadPolicyInitialized,
     /// Error from Quote3 interface
     Quote3(Quote3Error),
+    /// Failed to read the platform manifest: {0}
+    PlatformManifest(String),
+    /// Error from SGX function: {0}
+    Sgx(mc_sgx_core_types::Error),
+    /// Only ECDSA P-256 attestation keys are supported
+    UnsupportedAttestationKey,
+    /// A library handle is already in use, only one can exist at a time
+    LibraryInUse,
+    /// Unable to find the {0}, tried: {1}
+    PathNotFound(String, String),
+    /// A quote provider library path was set earlier and can't be unset
+    QuoteProviderLibraryPathSet,
+    /// Platform registration needs a quoting library without a quote provider library
+    QuoteProviderLibraryInUse,
+    /// Error running a quoting library call from async code: {0}
+    #[cfg(feature = "async")]
+    Blocking(mc_sgx_util::BlockingError),
+}
+
+impl From<mc_sgx_core_types::Error> for Error {
+    fn from(src: mc_sgx_core_types::Error) -> Self {
+        Self::Sgx(src)
+    }
 }
 
 impl From<Quote3Error> for Error {
@@ -38,8 +82,47 @@ impl From<Quote3Error> for Error {
     }
 }
 
+#[cfg(feature = "async")]
+impl From<mc_sgx_util::BlockingError> for Error {
+    fn from(src: mc_sgx_util::BlockingError) -> Self {
+        Self::Blocking(src)
+    }
+}
+
 impl From<QlError> for Error {
     fn from(src: QlError) -> Self {
         Self::QuoteLibrary(src)
     }
 }
+
+impl Classify for Error {
+    /// Only errors from the quoting library or the SGX SDK can be transient,
+    /// see [`QlError::class()`](Classify::class) and
+    /// [`mc_sgx_core_types::Error::class()`](Classify::class).
+    fn class(&self) -> ErrorClass {
+        match self {
+            Self::QuoteLibrary(err) => err.class(),
+            Self::Sgx(err) => err.class(),
+            #[cfg(feature = "async")]
+            Self::Blocking(err) => err.class(),
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yare::parameterized;
+
+    #[parameterized(
+        out_of_epc = { Error::QuoteLibrary(QlError::OutOfEpc), ErrorClass::Transient },
+        enclave_lost = { Error::Sgx(mc_sgx_core_types::Error::EnclaveLost), ErrorClass::Transient },
+        invalid_report = { Error::QuoteLibrary(QlError::InvalidReport), ErrorClass::Permanent },
+        unsupported_attestation_key = { Error::UnsupportedAttestationKey, ErrorClass::Permanent },
+        library_in_use = { Error::LibraryInUse, ErrorClass::Permanent },
+    )]
+    fn error_class(error: Error, expected: ErrorClass) {
+        assert_eq!(error.class(), expected);
+    }
+}
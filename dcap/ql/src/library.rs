@@ -0,0 +1,443 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! An explicit handle to the configured quoting library.
+//!
+//! The quoting library keeps its paths and load policy in process global
+//! state. [`QuotingLibrary`] owns that configuration while it is alive, and
+//! unloads the quoting enclaves when it is dropped, so the configuration can
+//! be changed at runtime.
+
+use crate::{
+    logging, quote3, quote_enclave, Error, LoadPolicyInitializer, PathDiscovery, PathInitializer,
+    PlatformRegistration,
+};
+use mc_sgx_core_types::{Report, TargetInfo};
+use mc_sgx_dcap_ql_types::PathKind;
+use mc_sgx_dcap_types::{LogLevel, Quote3, RequestPolicy};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether a [`QuotingLibrary`] is alive. There can only be one as the
+/// configuration of the quoting library is process wide.
+static IN_USE: AtomicBool = AtomicBool::new(false);
+
+/// A handle to the quoting library configured with a [`QuotingLibraryBuilder`].
+///
+/// Only one handle can exist at a time. Dropping the handle cleans up the
+/// quoting enclaves per the load policy and releases the configuration.
+///
+/// While a handle exists, [`PathInitializer`] and [`LoadPolicyInitializer`]
+/// report being initialized, and the free quoting functions, like
+/// [`TryFromReport`](crate::TryFromReport), use the configuration of the
+/// handle.
+#[derive(Debug)]
+pub struct QuotingLibrary {
+    policy: RequestPolicy,
+    quote_provider_library: Option<PathBuf>,
+    log_level: Option<LogLevel>,
+}
+
+impl QuotingLibrary {
+    /// A builder for the quoting library, starting from the default paths
+    /// and load policy
+    pub fn builder() -> QuotingLibraryBuilder {
+        QuotingLibraryBuilder::default()
+    }
+
+    /// The quoting library with the default paths and load policy.
+    ///
+    /// # Errors
+    /// See [`QuotingLibraryBuilder::build()`].
+    pub fn try_default() -> Result<Self, Error> {
+        Self::builder().build()
+    }
+
+    /// The load policy of the quoting enclaves
+    pub fn policy(&self) -> RequestPolicy {
+        self.policy
+    }
+
+    /// The path of the QPL(Quote Provider Library), if one is used
+    pub fn quote_provider_library(&self) -> Option<&Path> {
+        self.quote_provider_library.as_deref()
+    }
+
+    /// The level the quoting library log messages are forwarded at, if they
+    /// are forwarded
+    pub fn log_level(&self) -> Option<LogLevel> {
+        self.log_level
+    }
+
+    /// The target info of the QE(Quoting Enclave)
+    ///
+    /// # Errors
+    /// [`Error::QuoteLibrary`] if there is a failure from the SGX SDK.
+    pub fn target_info(&self) -> Result<TargetInfo, Error> {
+        quote_enclave::target_info()
+    }
+
+    /// Generate a quote for `report`
+    ///
+    /// # Arguments
+    /// * `report` - A report of an application enclave targeting
+    ///   [`QuotingLibrary::target_info()`].
+    ///
+    /// # Errors
+    /// * [`Error::QuoteLibrary`] if there is a failure from the SGX SDK.
+    /// * [`Error::Quote3`] if the generated quote is malformed.
+    pub fn quote(&self, report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+        quote3::get_quote(report.clone())
+    }
+
+    /// Collect the data needed to register this platform with a PCCS.
+    ///
+    /// The quoting library only puts the encrypted PPID in a quote when it has
+    /// no QPL(Quote Provider Library), so the library must be built
+    /// [without one](QuotingLibraryBuilder::without_quote_provider_library).
+    ///
+    /// # Arguments
+    /// * `report` - A report of an application enclave targeting
+    ///   [`QuotingLibrary::target_info()`].
+    ///
+    /// # Errors
+    /// * [`Error::QuoteProviderLibraryInUse`] if the library has a QPL.
+    /// * [`Error::QuoteLibrary`] if there is a failure from the SGX SDK.
+    /// * [`Error::Quote3`] if the quote doesn't have the encrypted PPID.
+    /// * [`Error::PlatformManifest`] if the platform manifest UEFI variable
+    ///   can't be read.
+    pub fn platform_registration(&self, report: &Report) -> Result<PlatformRegistration, Error> {
+        if self.quote_provider_library.is_some() {
+            return Err(Error::QuoteProviderLibraryInUse);
+        }
+        let quote = self.quote(report)?;
+        PlatformRegistration::try_from_quote(&quote)
+    }
+}
+
+impl Drop for QuotingLibrary {
+    fn drop(&mut self) {
+        // Nothing can be done about a failure to unload the enclaves, the
+        // next configuration will still take effect when they are loaded.
+        let _ = unsafe { mc_sgx_dcap_ql_sys::sgx_qe_cleanup_by_policy() };
+        if self.log_level.is_some() {
+            let _ = logging::stop_forwarding_logs();
+        }
+        PathInitializer::reset();
+        LoadPolicyInitializer::reset();
+        IN_USE.store(false, Ordering::Release);
+    }
+}
+
+/// Builder for a [`QuotingLibrary`]
+///
+/// The defaults are the enclaves and quote provider library (QPL) found with
+/// [`PathDiscovery::from_env()`], the [`RequestPolicy::Persistent`] load
+/// policy, and no log forwarding.
+///
+/// Unlike discovered paths, the paths given to the builder aren't validated
+/// as signed enclaves or shared libraries; they only need to exist. A wrong
+/// file fails when the quoting library first loads it.
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct QuotingLibraryBuilder {
+    quoting_enclave: Option<PathBuf>,
+    provisioning_certificate_enclave: Option<PathBuf>,
+    id_enclave: Option<PathBuf>,
+    quote_provider_library: QuoteProviderLibrary,
+    policy: RequestPolicy,
+    log_level: Option<LogLevel>,
+}
+
+/// The QPL(Quote Provider Library) of a [`QuotingLibraryBuilder`]
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+enum QuoteProviderLibrary {
+    /// The QPL found with [`PathDiscovery`], if any
+    #[default]
+    Discovered,
+    /// The QPL at the path
+    Path(PathBuf),
+    /// No QPL
+    Disabled,
+}
+
+impl QuotingLibraryBuilder {
+    /// The full file path to the QE(Quoting Enclave)
+    #[must_use]
+    pub fn quoting_enclave(mut self, path: impl AsRef<Path>) -> Self {
+        self.quoting_enclave = Some(path.as_ref().into());
+        self
+    }
+
+    /// The full file path to the PCE(Provisioning Certificate Enclave)
+    #[must_use]
+    pub fn provisioning_certificate_enclave(mut self, path: impl AsRef<Path>) -> Self {
+        self.provisioning_certificate_enclave = Some(path.as_ref().into());
+        self
+    }
+
+    /// The full file path to the ID enclave
+    #[must_use]
+    pub fn id_enclave(mut self, path: impl AsRef<Path>) -> Self {
+        self.id_enclave = Some(path.as_ref().into());
+        self
+    }
+
+    /// The full file path to the QPL(Quote Provider Library)
+    ///
+    /// When no QPL is provided or found, quote generation is limited to local
+    /// quote generation.
+    #[must_use]
+    pub fn quote_provider_library(mut self, path: impl AsRef<Path>) -> Self {
+        self.quote_provider_library = QuoteProviderLibrary::Path(path.as_ref().into());
+        self
+    }
+
+    /// Don't use a QPL(Quote Provider Library), even if one can be found.
+    ///
+    /// Quote generation is limited to local quote generation, and the quotes
+    /// identify the platform by its encrypted PPID, as needed by
+    /// [`QuotingLibrary::platform_registration()`].
+    ///
+    /// The quoting library can't unset a QPL path, so
+    /// [`QuotingLibraryBuilder::build()`] fails if one was set earlier in the
+    /// process.
+    #[must_use]
+    pub fn without_quote_provider_library(mut self) -> Self {
+        self.quote_provider_library = QuoteProviderLibrary::Disabled;
+        self
+    }
+
+    /// The load policy of the quoting enclaves
+    #[must_use]
+    pub fn policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Forward the quoting library log messages, up to `level`, to the
+    /// [`log`] crate while the [`QuotingLibrary`] exists.
+    ///
+    /// See [`forward_logs()`](crate::forward_logs).
+    #[must_use]
+    pub fn forward_logs(mut self, level: LogLevel) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Configure the quoting library.
+    ///
+    /// Any configuration from [`PathInitializer`] or
+    /// [`LoadPolicyInitializer`] is replaced.
+    ///
+    /// # Errors
+    /// * [`Error::LibraryInUse`] if another [`QuotingLibrary`] exists.
+    /// * [`Error::PathNotFound`] if a path wasn't provided and can't be found.
+    /// * [`Error::QuoteProviderLibraryPathSet`] if there is no QPL to use and
+    ///   a QPL path was set earlier in the process.
+    /// * [`Error::PathStringConversion`], [`Error::PathDoesNotExist`],
+    ///   [`Error::PathLengthTooLong`] or [`Error::QuoteLibrary`] if one of
+    ///   the paths can't be set.
+    /// * [`Error::QuoteLibrary`] if the load policy or the log forwarding
+    ///   can't be set.
+    pub fn build(self) -> Result<QuotingLibrary, Error> {
+        if IN_USE
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(Error::LibraryInUse);
+        }
+
+        let configured = self.paths().and_then(|(qe, pce, qpl, ide)| {
+            PathInitializer::replace_paths(qe, pce, qpl.as_ref(), ide)?;
+            LoadPolicyInitializer::replace_policy(self.policy)?;
+            self.log_level.map_or(Ok(()), logging::forward_logs)?;
+            Ok(qpl)
+        });
+        let quote_provider_library = match configured {
+            Ok(quote_provider_library) => quote_provider_library,
+            Err(err) => {
+                PathInitializer::reset();
+                LoadPolicyInitializer::reset();
+                IN_USE.store(false, Ordering::Release);
+                return Err(err);
+            }
+        };
+
+        Ok(QuotingLibrary {
+            policy: self.policy,
+            quote_provider_library,
+            log_level: self.log_level,
+        })
+    }
+
+    /// The QE, PCE, QPL and ID enclave paths, discovering any that weren't
+    /// provided
+    fn paths(&self) -> Result<(PathBuf, PathBuf, Option<PathBuf>, PathBuf), Error> {
+        let discovery = PathDiscovery::from_env();
+        let find = |path: &Option<PathBuf>, kind| match path {
+            Some(path) => Ok(path.clone()),
+            None => discovery.find(kind),
+        };
+        let quote_provider_library = match &self.quote_provider_library {
+            QuoteProviderLibrary::Discovered => {
+                discovery.find_optional(PathKind::QuoteProviderLibrary)?
+            }
+            QuoteProviderLibrary::Path(path) => Some(path.clone()),
+            QuoteProviderLibrary::Disabled => None,
+        };
+        Ok((
+            find(&self.quoting_enclave, PathKind::QuotingEnclave)?,
+            find(
+                &self.provisioning_certificate_enclave,
+                PathKind::ProvisioningCertificateEnclave,
+            )?,
+            quote_provider_library,
+            find(&self.id_enclave, PathKind::IdEnclave)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serial_test::serial;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn builder_in(dir: &Path) -> QuotingLibraryBuilder {
+        let [qe, pce, ide, qpl] = ["qe", "pce", "ide", "qpl"].map(|name| {
+            let file_name = dir.join(name);
+            fs::write(&file_name, name).unwrap();
+            file_name
+        });
+        // An explicit QPL, as one set by a test can't be unset for the others
+        QuotingLibrary::builder()
+            .quoting_enclave(qe)
+            .provisioning_certificate_enclave(pce)
+            .id_enclave(ide)
+            .quote_provider_library(qpl)
+    }
+
+    #[test]
+    #[serial]
+    fn build_with_paths_and_policy() {
+        let dir = tempdir().unwrap();
+
+        let library = builder_in(dir.path())
+            .policy(RequestPolicy::Ephemeral)
+            .build()
+            .unwrap();
+
+        assert_eq!(library.policy(), RequestPolicy::Ephemeral);
+        assert_eq!(library.log_level(), None);
+        assert_eq!(PathInitializer::try_default(), Err(Error::PathsInitialized));
+        assert_eq!(
+            LoadPolicyInitializer::try_default(),
+            Err(Error::LoadPolicyInitialized)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn only_one_library_at_a_time() {
+        let dir = tempdir().unwrap();
+        let library = builder_in(dir.path()).build().unwrap();
+
+        assert!(matches!(
+            builder_in(dir.path()).build(),
+            Err(Error::LibraryInUse)
+        ));
+
+        drop(library);
+        assert!(builder_in(dir.path()).build().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn drop_releases_configuration() {
+        let dir = tempdir().unwrap();
+        let library = builder_in(dir.path()).build().unwrap();
+
+        drop(library);
+
+        assert_eq!(LoadPolicyInitializer::try_default(), Ok(()));
+        LoadPolicyInitializer::reset();
+    }
+
+    #[test]
+    #[serial]
+    fn bad_path_fails_and_can_be_retried() {
+        let dir = tempdir().unwrap();
+
+        let result = builder_in(dir.path())
+            .quoting_enclave(dir.path().join("missing"))
+            .build();
+
+        assert!(matches!(result, Err(Error::PathDoesNotExist(_))));
+        assert!(builder_in(dir.path()).build().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn without_quote_provider_library_after_one_was_set_fails() {
+        let dir = tempdir().unwrap();
+        let library = builder_in(dir.path()).build().unwrap();
+        drop(library);
+
+        let result = builder_in(dir.path())
+            .without_quote_provider_library()
+            .build();
+
+        assert!(matches!(result, Err(Error::QuoteProviderLibraryPathSet)));
+        assert!(builder_in(dir.path()).build().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn platform_registration_with_quote_provider_library_fails() {
+        let dir = tempdir().unwrap();
+        let library = builder_in(dir.path()).build().unwrap();
+
+        assert_eq!(
+            library.quote_provider_library(),
+            Some(dir.path().join("qpl").as_path())
+        );
+        assert_eq!(
+            library.platform_registration(&Report::default()),
+            Err(Error::QuoteProviderLibraryInUse)
+        );
+    }
+}
+
+#[cfg(all(test, not(feature = "sim")))]
+mod hw_test {
+    use super::*;
+    use mc_sgx_dcap_types::QlError;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn quote_from_invalid_report() {
+        let library = QuotingLibrary::try_default().unwrap();
+        // Target info must be gotten first in order to initialize sgx
+        assert!(library.target_info().is_ok());
+
+        assert_eq!(
+            library.quote(&Report::default()),
+            Err(Error::QuoteLibrary(QlError::InvalidReport))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn forward_logs_while_alive() {
+        let library = QuotingLibrary::builder()
+            .forward_logs(LogLevel::Debug)
+            .build()
+            .unwrap();
+
+        assert_eq!(library.log_level(), Some(LogLevel::Debug));
+        assert!(library.target_info().is_ok());
+    }
+}
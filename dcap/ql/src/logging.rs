@@ -0,0 +1,60 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Forwarding of the quoting library log messages to the [`log`] crate.
+//!
+//! The messages are logged with the `sgx_dcap_ql` target. Applications using
+//! `tracing` can collect them with the `tracing-log` crate.
+
+use crate::Error;
+use mc_sgx_dcap_sys_types::sgx_ql_log_level_t;
+use mc_sgx_dcap_types::{log_record, LogLevel};
+use mc_sgx_util::ResultInto;
+use std::{ffi::CStr, os::raw::c_char};
+
+const TARGET: &str = "sgx_dcap_ql";
+
+/// Forward the quoting library log messages, up to `level`, to the [`log`]
+/// crate.
+///
+/// This is process wide. It replaces any previous log forwarding, including
+/// that of a [`QuotingLibrary`](crate::QuotingLibrary).
+///
+/// # Errors
+/// [`Error::QuoteLibrary`] if the quoting library fails to set the callback.
+pub fn forward_logs(level: LogLevel) -> Result<(), Error> {
+    unsafe { mc_sgx_dcap_ql_sys::sgx_ql_set_trace_callback(Some(forward), level.into()) }
+        .into_result()?;
+    Ok(())
+}
+
+/// Stop forwarding the quoting library log messages
+///
+/// # Errors
+/// [`Error::QuoteLibrary`] if the quoting library fails to clear the callback.
+pub fn stop_forwarding_logs() -> Result<(), Error> {
+    unsafe { mc_sgx_dcap_ql_sys::sgx_ql_set_trace_callback(None, LogLevel::Error.into()) }
+        .into_result()?;
+    Ok(())
+}
+
+/// The callback given to the quoting library
+///
+/// # Safety
+/// `message` must be null or a null terminated string
+unsafe extern "C" fn forward(level: sgx_ql_log_level_t, message: *const c_char) {
+    if message.is_null() {
+        return;
+    }
+    let (level, message) = log_record(level, CStr::from_ptr(message));
+    log::log!(target: TARGET, level, "{message}");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn forward_ignores_null_message() {
+        unsafe { forward(sgx_ql_log_level_t::SGX_QL_LOG_ERROR, std::ptr::null()) };
+    }
+}
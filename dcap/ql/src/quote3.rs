@@ -28,25 +28,25 @@ pub trait TryFromReport {
     fn try_from_report(report: Report) -> Result<Quote3<Vec<u8>>, Error> {
         PathInitializer::ensure_initialized()?;
         LoadPolicyInitializer::ensure_initialized()?;
-
-        let mut size = 0;
-        unsafe { mc_sgx_dcap_ql_sys::sgx_qe_get_quote_size(&mut size) }.into_result()?;
-
-        let mut quote = vec![0; size as usize];
-        unsafe {
-            mc_sgx_dcap_ql_sys::sgx_qe_get_quote(
-                &report.into(),
-                quote.len() as u32,
-                quote.as_mut_ptr(),
-            )
-        }
-        .into_result()?;
-        Ok(quote.try_into()?)
+        get_quote(report)
     }
 }
 
 impl TryFromReport for Quote3<Vec<u8>> {}
 
+/// Generate a quote for `report` with the current paths and load policy.
+pub(crate) fn get_quote(report: Report) -> Result<Quote3<Vec<u8>>, Error> {
+    let mut size = 0;
+    unsafe { mc_sgx_dcap_ql_sys::sgx_qe_get_quote_size(&mut size) }.into_result()?;
+
+    let mut quote = vec![0; size as usize];
+    unsafe {
+        mc_sgx_dcap_ql_sys::sgx_qe_get_quote(&report.into(), quote.len() as u32, quote.as_mut_ptr())
+    }
+    .into_result()?;
+    Ok(quote.try_into()?)
+}
+
 #[cfg(all(test, not(feature = "sim")))]
 mod test {
     use super::*;
@@ -8,14 +8,22 @@
 //! has a mix up.  It uses the *verification* description for `sgx_ql_set_path`
 //! and the "generation" description for `sgx_qv_set_path`
 
-use crate::Error;
+use crate::{Error, PathDiscovery};
 use mc_sgx_core_sys_types::sgx_target_info_t;
 use mc_sgx_core_types::TargetInfo;
 use mc_sgx_dcap_ql_types::PathKind;
 use mc_sgx_dcap_types::RequestPolicy;
 use mc_sgx_util::ResultInto;
 use once_cell::sync::Lazy;
-use std::{ffi::CString, os::unix::ffi::OsStrExt, path::Path, sync::Mutex};
+use std::{
+    ffi::CString,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
 
 // Using the value from SGX to validate inputs and provide better error
 // messages.
@@ -35,34 +43,38 @@ pub type Result<T> = core::result::Result<T, Error>;
 /// If a consumer of this crate does not explicitly initialize the paths, then
 /// they will be defaulted on the first call to an SGX function that needs the
 /// paths set.
+///
+/// [`QuotingLibrary`](crate::QuotingLibrary) is an explicit handle to the paths and load
+/// policy, which can be reconfigured at runtime.
 #[derive(Debug)]
 pub struct PathInitializer;
 
 static PATH_INITIALIZER: Lazy<Mutex<Option<PathInitializer>>> = Lazy::new(|| Mutex::new(None));
 
-const QUOTING_ENCLAVE: &str = "/usr/lib/x86_64-linux-gnu/libsgx_qe3.signed.so.1";
-
-const PROVISIONING_CERTIFICATE_ENCLAVE: &str = "/usr/lib/x86_64-linux-gnu/libsgx_pce.signed.so.1";
-
-
-const ID_ENCLAVE: &str = "/usr/lib/x86_64-linux-gnu/libsgx_id_enclave.signed.so.1";
+/// Whether a quote provider library path has been set. The quoting library
+/// has no way to unset it, it stays in use for the life of the process.
+static QUOTE_PROVIDER_LIBRARY_SET: AtomicBool = AtomicBool::new(false);
 
 impl PathInitializer {
     /// Try to initialize the paths to the default for the system
     ///
-    /// Currently the defaults assume the default DCAP install on an Ubuntu
-    /// machine.
+    /// The enclaves and the quote provider library are found with
+    /// [`PathDiscovery::from_env()`]. When no quote provider library is found
+    /// quote generation is limited to local quote generation.
     ///
     /// # Errors
     /// * [`Error::PathsInitialized`] if the paths have been previously
     ///   initialized.
-    /// * [`Error::QuoteLibrary`] if any of the default paths don't exist on the system.
+    /// * [`Error::PathNotFound`] if any of the enclaves can't be found on the
+    ///   system.
+    /// * [`Error::QuoteLibrary`] if any of the found paths can't be set.
     pub fn try_default() -> Result<()> {
+        let discovery = PathDiscovery::from_env();
         Self::with_paths(
-            QUOTING_ENCLAVE,
-            PROVISIONING_CERTIFICATE_ENCLAVE,
-            None::<&Path>,
-            ID_ENCLAVE,
+            discovery.find(PathKind::QuotingEnclave)?,
+            discovery.find(PathKind::ProvisioningCertificateEnclave)?,
+            discovery.find_optional(PathKind::QuoteProviderLibrary)?,
+            discovery.find(PathKind::IdEnclave)?,
         )
     }
 
@@ -118,8 +130,8 @@ impl PathInitializer {
     /// If the paths have not been initialized will initialize to the default.
     ///
     /// # Errors
-    /// Will return [`Error::QuoteLibrary`] if the paths have not been initialized and
-    /// the default paths don't exist.
+    /// Will return [`Error::PathNotFound`] or [`Error::QuoteLibrary`] if the
+    /// paths have not been initialized and the default paths can't be set.
     ///
     /// Will *not* return an error if the paths were previously initialized.
     pub(crate) fn ensure_initialized() -> Result<()> {
@@ -129,6 +141,44 @@ impl PathInitializer {
         }
     }
 
+    /// Set the paths, replacing any prior initialization.
+    ///
+    /// The paths are left uninitialized on failure. A quote provider library
+    /// path set earlier can't be replaced with `None`, see
+    /// [`Error::QuoteProviderLibraryPathSet`].
+    pub(crate) fn replace_paths<P1, P2, P3, P4>(
+        quoting_enclave: P1,
+        provisioning_certificate_enclave: P2,
+        quote_provider_library: Option<P3>,
+        id_enclave: P4,
+    ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        P3: AsRef<Path>,
+        P4: AsRef<Path>,
+    {
+        let mut value = PATH_INITIALIZER.lock().expect("Mutex has been poisoned");
+        *value = None;
+        if quote_provider_library.is_none() && QUOTE_PROVIDER_LIBRARY_SET.load(Ordering::Acquire) {
+            return Err(Error::QuoteProviderLibraryPathSet);
+        }
+        Self::set_paths(
+            quoting_enclave,
+            provisioning_certificate_enclave,
+            quote_provider_library,
+            id_enclave,
+        )?;
+        *value = Some(PathInitializer);
+        Ok(())
+    }
+
+    /// Mark the paths as uninitialized, allowing them to be initialized again.
+    pub(crate) fn reset() {
+        let mut value = PATH_INITIALIZER.lock().expect("Mutex has been poisoned");
+        *value = None;
+    }
+
     fn set_paths<P1, P2, P3, P4>(
         quoting_enclave: P1,
         provisioning_certificate_enclave: P2,
@@ -147,9 +197,10 @@ impl PathInitializer {
             provisioning_certificate_enclave,
         )?;
         Self::set_path(PathKind::IdEnclave, id_enclave)?;
-        quote_provider_library.map_or(Ok(()), |path| {
-            Self::set_path(PathKind::QuoteProviderLibrary, path)
-        })?;
+        if let Some(path) = quote_provider_library {
+            Self::set_path(PathKind::QuoteProviderLibrary, path)?;
+            QUOTE_PROVIDER_LIBRARY_SET.store(true, Ordering::Release);
+        }
         Ok(PathInitializer)
     }
 
@@ -207,14 +258,19 @@ pub trait QeTargetInfo {
     fn for_quoting_enclave() -> Result<TargetInfo> {
         PathInitializer::ensure_initialized()?;
         LoadPolicyInitializer::ensure_initialized()?;
-        let mut info = sgx_target_info_t::default();
-        unsafe { mc_sgx_dcap_ql_sys::sgx_qe_get_target_info(&mut info) }.into_result()?;
-        Ok(info.into())
+        target_info()
     }
 }
 
 impl QeTargetInfo for TargetInfo {}
 
+/// The target info of the QE with the current paths and load policy.
+pub(crate) fn target_info() -> Result<TargetInfo> {
+    let mut info = sgx_target_info_t::default();
+    unsafe { mc_sgx_dcap_ql_sys::sgx_qe_get_target_info(&mut info) }.into_result()?;
+    Ok(info.into())
+}
+
 /// Initialization of the load policy for the quoting enclaves
 ///
 /// This should only be called once during process start up utilizing
@@ -278,6 +334,29 @@ impl LoadPolicyInitializer {
             Err(e) => Err(e),
         }
     }
+
+    /// Set the load policy, replacing any prior initialization.
+    ///
+    /// The load policy is left uninitialized on failure.
+    pub(crate) fn replace_policy(policy: RequestPolicy) -> Result<()> {
+        let mut value = LOAD_POLICY_INITIALIZER
+            .lock()
+            .expect("Mutex has been poisoned");
+        *value = None;
+        unsafe { mc_sgx_dcap_ql_sys::sgx_qe_set_enclave_load_policy(policy.into()) }
+            .into_result()?;
+        *value = Some(LoadPolicyInitializer);
+        Ok(())
+    }
+
+    /// Mark the load policy as uninitialized, allowing it to be initialized
+    /// again.
+    pub(crate) fn reset() {
+        let mut value = LOAD_POLICY_INITIALIZER
+            .lock()
+            .expect("Mutex has been poisoned");
+        *value = None;
+    }
 }
 
 #[cfg(test)]
@@ -291,26 +370,6 @@ mod test {
     use tempfile::tempdir;
     use yare::parameterized;
 
-    /// Resets the [`PATH_INITIALIZER`] to being uninitialized.
-    /// Since there is *one* [`PATH_INITIALIZER`] for the entire test process
-    /// any tests focusing on the functionality of the [`PATH_INITIALIZER`]
-    /// should be utilizing the `#[serial]` macro.
-    fn reset_path_initializer() {
-        let mut value = PATH_INITIALIZER.lock().expect("Mutex has been poisoned");
-        *value = None;
-    }
-
-    /// Resets the [`LOAD_POLICY_INITIALIZER`] to being uninitialized.
-    /// Since there is *one* [`LOAD_POLICY_INITIALIZER`] for the entire test
-    /// process any tests focusing on the functionality of the
-    /// [`LOAD_POLICY_INITIALIZER`] should be utilizing the `#[serial]` macro.
-    fn reset_load_policy_initializer() {
-        let mut value = LOAD_POLICY_INITIALIZER
-            .lock()
-            .expect("Mutex has been poisoned");
-        *value = None;
-    }
-
     #[parameterized(
     qe = { QuotingEnclave },
     qpl = { QuoteProviderLibrary },
@@ -395,7 +454,7 @@ mod test {
     #[test]
     #[serial]
     fn default_path_initializer_succeeds() {
-        reset_path_initializer();
+        PathInitializer::reset();
         let result = PathInitializer::try_default();
         assert_eq!(result, Ok(()));
     }
@@ -403,7 +462,7 @@ mod test {
     #[test]
     #[serial]
     fn default_path_initializer_fails_when_already_initialized() {
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::try_default().unwrap();
         let result = PathInitializer::try_default();
         assert_eq!(result, Err(Error::PathsInitialized));
@@ -422,7 +481,7 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        reset_path_initializer();
+        PathInitializer::reset();
         let result = PathInitializer::with_paths(&names[0], &names[1], Some(&names[2]), &names[3]);
         assert_eq!(result, Ok(()));
     }
@@ -440,7 +499,7 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::try_default().unwrap();
         let result = PathInitializer::with_paths(&names[0], &names[1], Some(&names[2]), &names[3]);
         assert_eq!(result, Err(Error::PathsInitialized));
@@ -459,7 +518,7 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::with_paths(&names[0], &names[1], Some(&names[2]), &names[3]).unwrap();
         let result = PathInitializer::with_paths(&names[0], &names[1], Some(&names[2]), &names[3]);
         assert_eq!(result, Err(Error::PathsInitialized));
@@ -484,7 +543,7 @@ mod test {
 
         for _ in 0..names.len() {
             names.rotate_right(1);
-            reset_path_initializer();
+            PathInitializer::reset();
             let result =
                 PathInitializer::with_paths(&names[0], &names[1], Some(&names[2]), &names[3]);
             assert!(matches!(result, Err(Error::PathLengthTooLong(_))));
@@ -496,7 +555,7 @@ mod test {
     #[test]
     #[serial]
     fn ensuring_paths_initialized_succeeds_when_already_initialized() {
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::try_default().unwrap();
         assert_eq!(PathInitializer::ensure_initialized(), Ok(()));
     }
@@ -507,14 +566,14 @@ mod test {
     )]
     #[serial]
     fn load_policy_succeeds(policy: RequestPolicy) {
-        reset_load_policy_initializer();
+        LoadPolicyInitializer::reset();
         assert_eq!(LoadPolicyInitializer::policy(policy), Ok(()));
     }
 
     #[test]
     #[serial]
     fn load_policy_fails_when_already_initialized() {
-        reset_load_policy_initializer();
+        LoadPolicyInitializer::reset();
         LoadPolicyInitializer::try_default().unwrap();
         assert_eq!(
             LoadPolicyInitializer::try_default(),
@@ -525,7 +584,7 @@ mod test {
     #[test]
     #[serial]
     fn ensuring_the_policy_is_set_is_ok_when_already_set() {
-        reset_load_policy_initializer();
+        LoadPolicyInitializer::reset();
         LoadPolicyInitializer::policy(RequestPolicy::Ephemeral).unwrap();
         assert_eq!(LoadPolicyInitializer::ensure_initialized(), Ok(()));
     }
@@ -0,0 +1,361 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Quote generation through the Architectural Enclave Service Manager (AESM).
+//!
+//! [`TryFromReport`](crate::TryFromReport) loads the quoting enclave into the
+//! application process, which needs access to `/dev/sgx_provision`. With the
+//! quote-ex API the AESM service hosts the quoting enclave instead, so the
+//! application only needs to reach the AESM socket.
+
+use crate::Error;
+use core::mem;
+use mc_sgx_core_sys_types::{
+    sgx_att_key_id_ext_t, sgx_att_key_id_t, sgx_qe_report_info_t, sgx_target_info_t,
+};
+use mc_sgx_core_types::{
+    Algorithm, ExtendedAttestationKeyId, QuoteNonce, QuotingEnclaveReportInfo, Report, TargetInfo,
+};
+use mc_sgx_dcap_types::{Quote3, ReportInfo};
+use mc_sgx_util::ResultInto;
+use std::ptr;
+
+// The header of an `sgx_ql_att_key_id_list_t`, the structure ID and version
+// are both 0.
+const KEY_ID_LIST_ID: u16 = 0;
+const KEY_ID_LIST_VERSION: u16 = 0;
+
+/// Quote generation with an attestation key of the AESM service.
+///
+/// The attestation key is initialized, and certified, when this is created.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct QuoteEx {
+    key_id: ExtendedAttestationKeyId,
+    target_info: TargetInfo,
+    public_key_id: Vec<u8>,
+}
+
+impl QuoteEx {
+    /// The attestation key IDs supported by the platform
+    ///
+    /// # Errors
+    /// [`Error::Sgx`] if the AESM service can't be reached.
+    pub fn supported_key_ids() -> Result<Vec<ExtendedAttestationKeyId>, Error> {
+        let mut count = 0;
+        unsafe { mc_sgx_dcap_ql_sys::sgx_get_supported_att_key_id_num(&mut count) }
+            .into_result()?;
+
+        let mut key_ids = vec![sgx_att_key_id_ext_t::default(); count as usize];
+        if !key_ids.is_empty() {
+            unsafe {
+                mc_sgx_dcap_ql_sys::sgx_get_supported_att_key_ids(key_ids.as_mut_ptr(), count)
+            }
+            .into_result()?;
+        }
+        Ok(key_ids.into_iter().map(Into::into).collect())
+    }
+
+    /// Select the attestation key ID to use from `candidates`.
+    ///
+    /// When more than one of `candidates` is supported by the platform, the
+    /// default quoting type of the AESM configuration decides.
+    ///
+    /// # Arguments
+    /// * `candidates` - The attestation key IDs acceptable to the quote
+    ///   verifier. When empty, the AESM service picks its default key.
+    ///
+    /// # Errors
+    /// [`Error::Sgx`] with [`UnsupportedAttKeyId`] if the platform supports
+    /// none of `candidates`.
+    ///
+    /// [`UnsupportedAttKeyId`]: mc_sgx_core_types::Error::UnsupportedAttKeyId
+    pub fn select(
+        candidates: &[ExtendedAttestationKeyId],
+    ) -> Result<ExtendedAttestationKeyId, Error> {
+        let mut selected = sgx_att_key_id_t {
+            att_key_id: [0; mem::size_of::<sgx_att_key_id_t>()],
+        };
+        let list = key_id_list(candidates);
+        let (list_ptr, list_size) = match list.is_empty() {
+            true => (ptr::null(), 0),
+            false => (list.as_ptr(), list.len() as u32),
+        };
+        unsafe { mc_sgx_dcap_ql_sys::sgx_select_att_key_id(list_ptr, list_size, &mut selected) }
+            .into_result()?;
+        Ok(from_att_key_id(selected))
+    }
+
+    /// Initialize quoting with the AESM default attestation key.
+    ///
+    /// # Errors
+    /// See [`QuoteEx::select()`] and [`QuoteEx::new()`].
+    pub fn try_default() -> Result<Self, Error> {
+        Self::new(Self::select(&[])?)
+    }
+
+    /// Initialize quoting with the attestation key `key_id`.
+    ///
+    /// This has the quoting enclave of `key_id` generate or retrieve its
+    /// attestation key, and certify it with the PCE.
+    ///
+    /// # Arguments
+    /// * `key_id` - An ECDSA P-256 attestation key ID, usually from
+    ///   [`QuoteEx::select()`] or [`QuoteEx::supported_key_ids()`].
+    ///
+    /// # Errors
+    /// * [`Error::UnsupportedAttestationKey`] if `key_id` is not for ECDSA
+    ///   P-256, the algorithm of version 3 quotes.
+    /// * [`Error::Sgx`] if the attestation key fails to initialize.
+    pub fn new(key_id: ExtendedAttestationKeyId) -> Result<Self, Error> {
+        if key_id.base_key_id().algorithm_id() != Ok(Algorithm::EcdsaP256) {
+            return Err(Error::UnsupportedAttestationKey);
+        }
+        let att_key_id = to_att_key_id(&key_id);
+
+        let mut size = 0;
+        unsafe {
+            mc_sgx_dcap_ql_sys::sgx_init_quote_ex(
+                &att_key_id,
+                ptr::null_mut(),
+                &mut size,
+                ptr::null_mut(),
+            )
+        }
+        .into_result()?;
+
+        let mut target_info = sgx_target_info_t::default();
+        let mut public_key_id = vec![0; size];
+        unsafe {
+            mc_sgx_dcap_ql_sys::sgx_init_quote_ex(
+                &att_key_id,
+                &mut target_info,
+                &mut size,
+                public_key_id.as_mut_ptr(),
+            )
+        }
+        .into_result()?;
+        public_key_id.truncate(size);
+
+        Ok(Self {
+            key_id,
+            target_info: target_info.into(),
+            public_key_id,
+        })
+    }
+
+    /// The attestation key ID quotes are signed with
+    pub fn key_id(&self) -> &ExtendedAttestationKeyId {
+        &self.key_id
+    }
+
+    /// The target info of the quoting enclave.
+    ///
+    /// Reports given to [`QuoteEx::quote()`] must target this.
+    pub fn target_info(&self) -> &TargetInfo {
+        &self.target_info
+    }
+
+    /// The public identifier of the attestation key
+    pub fn public_key_id(&self) -> &[u8] {
+        &self.public_key_id
+    }
+
+    /// Generate a quote for `report`.
+    ///
+    /// # Arguments
+    /// * `report` - A report of an application enclave targeting
+    ///   [`QuoteEx::target_info()`].
+    ///
+    /// # Errors
+    /// * [`Error::Sgx`] if quote generation fails. With
+    ///   [`AttKeyUninitialized`] a new [`QuoteEx`] needs to be created to
+    ///   initialize the attestation key again.
+    /// * [`Error::Quote3`] if the generated quote is malformed.
+    ///
+    /// [`AttKeyUninitialized`]: mc_sgx_core_types::Error::AttKeyUninitialized
+    pub fn quote(&self, report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+        self.get_quote(report, None)
+    }
+
+    /// Generate a quote for `report`, with a QE report binding the quote to
+    /// `nonce`.
+    ///
+    /// The QE report targets `target_info` and has the report data
+    /// `SHA256(nonce||quote)||32-0x00's`. The enclave of `target_info` can
+    /// verify it to ensure the quote was not swapped by the untrusted host.
+    /// See `mc_sgx_tservice::VerifyQeReport`.
+    ///
+    /// # Arguments
+    /// * `report` - A report of an application enclave targeting
+    ///   [`QuoteEx::target_info()`].
+    /// * `nonce` - A nonce from the application enclave.
+    /// * `target_info` - The target info of the application enclave.
+    ///
+    /// # Errors
+    /// See [`QuoteEx::quote()`].
+    pub fn quote_with_nonce(
+        &self,
+        report: &Report,
+        nonce: &QuoteNonce,
+        target_info: &TargetInfo,
+    ) -> Result<(Quote3<Vec<u8>>, ReportInfo), Error> {
+        let mut report_info = sgx_qe_report_info_t {
+            nonce: nonce.clone().into(),
+            app_enclave_target_info: target_info.clone().into(),
+            ..Default::default()
+        };
+        let quote = self.get_quote(report, Some(&mut report_info))?;
+        let report_info = QuotingEnclaveReportInfo::from(report_info);
+        Ok((quote, report_info.into()))
+    }
+
+    fn get_quote(
+        &self,
+        report: &Report,
+        report_info: Option<&mut sgx_qe_report_info_t>,
+    ) -> Result<Quote3<Vec<u8>>, Error> {
+        let att_key_id = to_att_key_id(&self.key_id);
+
+        let mut size = 0;
+        unsafe { mc_sgx_dcap_ql_sys::sgx_get_quote_size_ex(&att_key_id, &mut size) }
+            .into_result()?;
+
+        let mut quote = vec![0; size as usize];
+        unsafe {
+            mc_sgx_dcap_ql_sys::sgx_get_quote_ex(
+                &report.clone().into(),
+                &att_key_id,
+                report_info.map_or(ptr::null_mut(), |info| info as *mut _),
+                quote.as_mut_ptr(),
+                size,
+            )
+        }
+        .into_result()?;
+        Ok(quote.try_into()?)
+    }
+}
+
+/// The opaque form of `key_id` used by the quote-ex API.
+fn to_att_key_id(key_id: &ExtendedAttestationKeyId) -> sgx_att_key_id_t {
+    let key_id: sgx_att_key_id_ext_t = key_id.clone().into();
+    // SAFETY: `sgx_att_key_id_t` is the opaque, equally sized, form of the
+    // packed `sgx_att_key_id_ext_t`.
+    unsafe { mem::transmute(key_id) }
+}
+
+/// The extended attestation key ID of the opaque `key_id`.
+fn from_att_key_id(key_id: sgx_att_key_id_t) -> ExtendedAttestationKeyId {
+    // SAFETY: Every byte pattern is a valid `sgx_att_key_id_ext_t`.
+    let key_id: sgx_att_key_id_ext_t = unsafe { mem::transmute(key_id) };
+    key_id.into()
+}
+
+/// The `sgx_ql_att_key_id_list_t` of `candidates`, empty when there are no
+/// candidates.
+fn key_id_list(candidates: &[ExtendedAttestationKeyId]) -> Vec<u8> {
+    if candidates.is_empty() {
+        return vec![];
+    }
+    let mut list = Vec::with_capacity(8 + candidates.len() * mem::size_of::<sgx_att_key_id_t>());
+    list.extend_from_slice(&KEY_ID_LIST_ID.to_le_bytes());
+    list.extend_from_slice(&KEY_ID_LIST_VERSION.to_le_bytes());
+    list.extend_from_slice(&(candidates.len() as u32).to_le_bytes());
+    for candidate in candidates {
+        list.extend_from_slice(&to_att_key_id(candidate).att_key_id);
+    }
+    list
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_sgx_core_sys_types::sgx_ql_att_key_id_t;
+
+    fn ecdsa_key_id(id: u16) -> ExtendedAttestationKeyId {
+        let base = sgx_ql_att_key_id_t {
+            id,
+            algorithm_id: Algorithm::EcdsaP256 as u32,
+            ..Default::default()
+        };
+        sgx_att_key_id_ext_t {
+            base,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn att_key_id_round_trip() {
+        let key_id = ecdsa_key_id(3);
+
+        let att_key_id = to_att_key_id(&key_id);
+
+        assert_eq!(&att_key_id.att_key_id[..2], &[3, 0]);
+        assert_eq!(from_att_key_id(att_key_id), key_id);
+    }
+
+    #[test]
+    fn empty_key_id_list() {
+        assert!(key_id_list(&[]).is_empty());
+    }
+
+    #[test]
+    fn key_id_list_of_candidates() {
+        let candidates = [ecdsa_key_id(1), ecdsa_key_id(2)];
+
+        let list = key_id_list(&candidates);
+
+        assert_eq!(list.len(), 8 + 2 * mem::size_of::<sgx_att_key_id_t>());
+        assert_eq!(&list[..8], &[0, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(
+            &list[8..8 + mem::size_of::<sgx_att_key_id_t>()],
+            &to_att_key_id(&candidates[0]).att_key_id
+        );
+    }
+
+    #[test]
+    fn non_ecdsa_key_is_unsupported() {
+        let key_id = ExtendedAttestationKeyId::default();
+
+        assert_eq!(QuoteEx::new(key_id), Err(Error::UnsupportedAttestationKey));
+    }
+}
+
+#[cfg(all(test, not(feature = "sim")))]
+mod hw_test {
+    use super::*;
+
+    #[test]
+    fn default_key_id_is_supported() {
+        let selected = QuoteEx::select(&[]).expect("No default attestation key");
+
+        let supported = QuoteEx::supported_key_ids().expect("Failed to get supported keys");
+
+        assert!(supported
+            .iter()
+            .any(|key_id| key_id.base_key_id() == selected.base_key_id()));
+    }
+
+    #[test]
+    fn quote_with_nonce_from_invalid_report() {
+        let quote_ex = QuoteEx::try_default().expect("Failed to initialize attestation key");
+
+        assert!(matches!(
+            quote_ex.quote_with_nonce(
+                &Report::default(),
+                &QuoteNonce::default(),
+                &TargetInfo::default()
+            ),
+            Err(Error::Sgx(_))
+        ));
+    }
+
+    #[test]
+    fn quote_from_invalid_report() {
+        let quote_ex = QuoteEx::try_default().expect("Failed to initialize attestation key");
+
+        assert!(matches!(
+            quote_ex.quote(&Report::default()),
+            Err(Error::Sgx(_))
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Sources of quotes, the quoting library on SGX hardware or, with the
+//! `software` feature, a software quoting enclave.
+
+use crate::{Error, QuotingLibrary};
+use mc_sgx_core_types::{Report, TargetInfo};
+#[cfg(feature = "software")]
+use mc_sgx_dcap_types::software_ca::SoftwareCa;
+use mc_sgx_dcap_types::Quote3;
+use mc_sgx_util::RetryPolicy;
+
+/// A source of quotes for the reports of application enclaves.
+///
+/// Code written against a `Quoter` can be given a [`QuotingLibrary`] on SGX
+/// hardware or a [`SoftwareQuoter`] elsewhere, like in CI.
+pub trait Quoter {
+    /// The target info of the QE(Quoting Enclave)
+    ///
+    /// # Errors
+    /// If the target info can't be retrieved from the QE.
+    fn target_info(&self) -> Result<TargetInfo, Error>;
+
+    /// Generate a quote for `report`
+    ///
+    /// # Arguments
+    /// * `report` - A report of an application enclave targeting
+    ///   [`Quoter::target_info()`].
+    ///
+    /// # Errors
+    /// If the quote can't be generated.
+    fn quote(&self, report: &Report) -> Result<Quote3<Vec<u8>>, Error>;
+}
+
+impl Quoter for QuotingLibrary {
+    fn target_info(&self) -> Result<TargetInfo, Error> {
+        QuotingLibrary::target_info(self)
+    }
+
+    fn quote(&self, report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+        QuotingLibrary::quote(self, report)
+    }
+}
+
+/// Retries the transient failures of another [`Quoter`], like a busy AESM
+/// service or running out of EPC.
+#[derive(Debug, Clone)]
+pub struct RetryingQuoter<Q> {
+    quoter: Q,
+    policy: RetryPolicy,
+}
+
+impl<Q: Quoter> RetryingQuoter<Q> {
+    /// Retry the transient failures of `quoter` per `policy`.
+    pub fn new(quoter: Q, policy: RetryPolicy) -> Self {
+        Self { quoter, policy }
+    }
+
+    /// The wrapped quoter
+    pub fn quoter(&self) -> &Q {
+        &self.quoter
+    }
+
+    /// The retry policy
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+}
+
+impl<Q: Quoter> Quoter for RetryingQuoter<Q> {
+    fn target_info(&self) -> Result<TargetInfo, Error> {
+        self.policy.retry(|| self.quoter.target_info())
+    }
+
+    fn quote(&self, report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+        self.policy.retry(|| self.quoter.quote(report))
+    }
+}
+
+/// A software QE(Quoting Enclave) for running attestation flows without SGX
+/// hardware.
+///
+/// The quotes are signed by the attestation key of a [`SoftwareCa`] and carry
+/// its PCK certificate chain, they can be verified against the root
+/// certificate and collateral of the [`SoftwareCa`]. The target info is
+/// deterministic, see [`SoftwareCa::quoting_enclave_target_info()`].
+///
+/// The MAC of the reports isn't checked, there is no report key to check it
+/// with. Quotes from a `SoftwareQuoter` attest to nothing, they must only be
+/// trusted by environments without SGX hardware. The [`Default`] quoter uses
+/// a CA with publicly known keys, use [`SoftwareCa::builder()`] for a CA with
+/// its own keys.
+#[cfg(feature = "software")]
+#[derive(Debug, Clone, Default)]
+pub struct SoftwareQuoter {
+    ca: SoftwareCa,
+}
+
+#[cfg(feature = "software")]
+impl SoftwareQuoter {
+    /// A software QE with the attestation key and certificates of `ca`
+    pub fn new(ca: SoftwareCa) -> Self {
+        Self { ca }
+    }
+
+    /// The CA the quotes chain up to
+    pub fn ca(&self) -> &SoftwareCa {
+        &self.ca
+    }
+}
+
+#[cfg(feature = "software")]
+impl Quoter for SoftwareQuoter {
+    fn target_info(&self) -> Result<TargetInfo, Error> {
+        Ok(self.ca.quoting_enclave_target_info())
+    }
+
+    fn quote(&self, report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+        Ok(self.ca.quote(&report.body()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "software")]
+    use core::str::FromStr;
+    use core::{cell::Cell, time::Duration};
+    use mc_sgx_core_sys_types::sgx_report_t;
+    use mc_sgx_core_types::ReportData;
+    use mc_sgx_dcap_types::QlError;
+    #[cfg(feature = "software")]
+    use mc_sgx_dcap_types::{CertificationData, TcbInfo};
+    #[cfg(feature = "software")]
+    use p256::ecdsa::SigningKey;
+    #[cfg(feature = "software")]
+    use x509_cert::{der::DecodePem, name::Name, Certificate};
+
+    fn report_with_data(data: u8) -> Report {
+        let mut report = sgx_report_t::default();
+        report.body.report_data.d = [data; ReportData::SIZE];
+        report.into()
+    }
+
+    /// A quoter which fails with `error` for the first `failures` calls.
+    struct FailingQuoter {
+        error: Error,
+        failures: usize,
+        calls: Cell<usize>,
+    }
+
+    impl FailingQuoter {
+        fn new(error: Error, failures: usize) -> Self {
+            Self {
+                error,
+                failures,
+                calls: Cell::new(0),
+            }
+        }
+
+        fn result<T>(&self, value: T) -> Result<T, Error> {
+            let calls = self.calls.get() + 1;
+            self.calls.set(calls);
+            if calls <= self.failures {
+                Err(self.error.clone())
+            } else {
+                Ok(value)
+            }
+        }
+    }
+
+    impl Quoter for FailingQuoter {
+        fn target_info(&self) -> Result<TargetInfo, Error> {
+            self.result(TargetInfo::default())
+        }
+
+        fn quote(&self, _report: &Report) -> Result<Quote3<Vec<u8>>, Error> {
+            let quote = include_bytes!("../../types/data/tests/hw_quote.dat");
+            self.result(Quote3::try_from(quote.to_vec())?)
+        }
+    }
+
+    fn immediate_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::default()
+            .max_attempts(max_attempts)
+            .initial_delay(Duration::ZERO)
+    }
+
+    #[test]
+    fn retrying_quoter_recovers_from_transient_errors() {
+        let quoter = RetryingQuoter::new(
+            FailingQuoter::new(Error::QuoteLibrary(QlError::Busy), 2),
+            immediate_retries(3),
+        );
+
+        assert!(quoter.quote(&report_with_data(1)).is_ok());
+        assert_eq!(quoter.quoter().calls.get(), 3);
+    }
+
+    #[test]
+    fn retrying_quoter_gives_up_after_max_attempts() {
+        let error = Error::Sgx(mc_sgx_core_types::Error::OutOfEpc);
+        let quoter =
+            RetryingQuoter::new(FailingQuoter::new(error.clone(), 5), immediate_retries(3));
+
+        assert_eq!(quoter.target_info(), Err(error));
+        assert_eq!(quoter.quoter().calls.get(), 3);
+    }
+
+    #[test]
+    fn retrying_quoter_stops_on_permanent_error() {
+        let error = Error::QuoteLibrary(QlError::InvalidReport);
+        let quoter =
+            RetryingQuoter::new(FailingQuoter::new(error.clone(), 5), immediate_retries(3));
+
+        assert_eq!(quoter.quote(&report_with_data(1)).err(), Some(error));
+        assert_eq!(quoter.quoter().calls.get(), 1);
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn software_target_info_is_deterministic() {
+        let first = SoftwareQuoter::default();
+        let second = SoftwareQuoter::new(SoftwareCa::default());
+
+        assert_eq!(first.target_info().unwrap(), second.target_info().unwrap());
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn software_quote_contains_report_body() {
+        let report = report_with_data(7);
+        let quoter = SoftwareQuoter::default();
+
+        let quote = quoter.quote(&report).unwrap();
+
+        assert_eq!(quote.app_report_body(), &report.body());
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn software_quote_chains_to_ca() {
+        let quoter = SoftwareQuoter::default();
+
+        let quote = quoter.quote(&report_with_data(3)).unwrap();
+
+        let signature_data = quote.signature_data();
+        let CertificationData::PckCertificateChain(chain) = signature_data.certification_data()
+        else {
+            panic!("Expected a PCK certificate chain");
+        };
+        let root = chain
+            .into_iter()
+            .last()
+            .map(|pem| Certificate::from_pem(pem).unwrap())
+            .unwrap();
+        assert_eq!(&root, quoter.ca().root_certificate());
+        assert!(TcbInfo::try_from(&quote).is_ok());
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn software_quoter_with_configured_ca() {
+        let key = |byte| SigningKey::from_bytes(&[byte; 32].into()).unwrap();
+        let name = |name| Name::from_str(name).unwrap();
+        let ca = SoftwareCa::builder()
+            .root(key(21), name("CN=CI SGX Root CA,O=Example"))
+            .intermediate(key(22), name("CN=Intel SGX PCK Processor CA,O=Example"))
+            .pck(key(23), name("CN=CI SGX PCK Certificate,O=Example"))
+            .attestation_key(key(24))
+            .tcb_signing(key(25), name("CN=CI SGX TCB Signing,O=Example"))
+            .build();
+        let quoter = SoftwareQuoter::new(ca);
+
+        let quote = quoter.quote(&report_with_data(3)).unwrap();
+
+        let signature_data = quote.signature_data();
+        let CertificationData::PckCertificateChain(chain) = signature_data.certification_data()
+        else {
+            panic!("Expected a PCK certificate chain");
+        };
+        let root = chain
+            .into_iter()
+            .last()
+            .map(|pem| Certificate::from_pem(pem).unwrap())
+            .unwrap();
+        assert_eq!(
+            root.tbs_certificate.subject,
+            name("CN=CI SGX Root CA,O=Example")
+        );
+        assert_ne!(&root, SoftwareCa::default().root_certificate());
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn software_quoter_as_trait_object() {
+        let quoter: Box<dyn Quoter> = Box::new(SoftwareQuoter::default());
+
+        let target_info = quoter.target_info().unwrap();
+        let quote = quoter.quote(&report_with_data(1)).unwrap();
+
+        assert_eq!(
+            quote.signature_data().qe_report_body().mr_enclave(),
+            target_info.mr_enclave()
+        );
+    }
+}
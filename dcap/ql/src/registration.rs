@@ -0,0 +1,208 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Collection of the platform data needed to register a platform with a
+//! PCCS.
+//!
+//! This replaces running Intel's `PCKIDRetrievalTool` on each new machine.
+//! The output is compatible with the CSV and JSON output of the tool.
+
+use crate::Error;
+use mc_sgx_dcap_types::{PckCertId, Quote3};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// The UEFI variable with the platform manifest of a multi-package platform,
+/// as exposed by efivarfs.
+const PLATFORM_MANIFEST_EFI_VARIABLE: &str =
+    "/sys/firmware/efi/efivars/SgxRegistrationServerRequest-304e0796-d515-4698-ac6e-e76cb1a71c28";
+
+// efivarfs prefixes the value of a variable with its 4 byte attributes.
+const EFI_VARIABLE_ATTRIBUTES_SIZE: usize = 4;
+
+/// The data needed to register a platform with a PCCS.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PlatformRegistration {
+    pck_cert_id: PckCertId,
+    platform_manifest: Option<Vec<u8>>,
+}
+
+impl PlatformRegistration {
+    /// Registration data for a platform
+    ///
+    /// # Arguments
+    /// * `pck_cert_id` - The identifiers of the platform, with the encrypted
+    ///   PPID.
+    /// * `platform_manifest` - The platform manifest of a multi-package
+    ///   platform.
+    pub fn new(pck_cert_id: PckCertId, platform_manifest: Option<Vec<u8>>) -> Self {
+        Self {
+            pck_cert_id,
+            platform_manifest,
+        }
+    }
+
+    /// The registration data of this platform, from a `quote` with the
+    /// encrypted PPID.
+    ///
+    /// The platform manifest is read from the UEFI variable of the platform.
+    /// It is only present on multi-package platforms before registration.
+    ///
+    /// See [`QuotingLibrary::platform_registration()`](crate::QuotingLibrary::platform_registration).
+    ///
+    /// # Errors
+    /// * [`Error::Quote3`] if the quote doesn't have the encrypted PPID.
+    /// * [`Error::PlatformManifest`] if the UEFI variable can't be read.
+    pub(crate) fn try_from_quote<T: AsRef<[u8]>>(quote: &Quote3<T>) -> Result<Self, Error> {
+        let pck_cert_id = PckCertId::try_from(quote)?;
+        let platform_manifest = platform_manifest(PLATFORM_MANIFEST_EFI_VARIABLE)?;
+        Ok(Self::new(pck_cert_id, platform_manifest))
+    }
+
+    /// The identifiers of the platform
+    pub fn pck_cert_id(&self) -> &PckCertId {
+        &self.pck_cert_id
+    }
+
+    /// The platform manifest of a multi-package platform
+    pub fn platform_manifest(&self) -> Option<&[u8]> {
+        self.platform_manifest.as_deref()
+    }
+
+    /// The registration data as a CSV line, as written by the
+    /// `PCKIDRetrievalTool`.
+    ///
+    /// The columns are the encrypted PPID, PCEID, CPUSVN, PCESVN, QE ID and
+    /// the platform manifest, which is empty for single package platforms.
+    /// This is the format the `pccsadmin` tool reads.
+    pub fn to_csv(&self) -> String {
+        let [ppid, pce_id, cpu_svn, pce_svn, qe_id, platform_manifest] = self.fields();
+        format!("{ppid},{pce_id},{cpu_svn},{pce_svn},{qe_id},{platform_manifest}\n")
+    }
+
+    /// The registration data as JSON, the body of the platform registration
+    /// request of a PCCS.
+    pub fn to_json(&self) -> String {
+        let [ppid, pce_id, cpu_svn, pce_svn, qe_id, platform_manifest] = self.fields();
+        format!(
+            r#"{{"enc_ppid":"{ppid}","pce_id":"{pce_id}","cpu_svn":"{cpu_svn}","pce_svn":"{pce_svn}","qe_id":"{qe_id}","platform_manifest":"{platform_manifest}"}}"#
+        )
+    }
+
+    /// The hex encoded fields, in the order of the CSV columns.
+    ///
+    /// The PCEID and PCESVN are little endian, as in the PCS API.
+    fn fields(&self) -> [String; 6] {
+        let id = &self.pck_cert_id;
+        let pce_info = id.pce_info();
+        [
+            hex::encode_upper(id.ppid()),
+            hex::encode_upper(pce_info.pce_id().to_le_bytes()),
+            hex::encode_upper(id.cpu_svn()),
+            hex::encode_upper(pce_info.pce_svn().to_le_bytes()),
+            hex::encode_upper(id.qe_id()),
+            self.platform_manifest
+                .as_ref()
+                .map(hex::encode_upper)
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+/// The platform manifest in the UEFI variable at `path`, if the variable
+/// exists.
+fn platform_manifest(path: impl AsRef<Path>) -> Result<Option<Vec<u8>>, Error> {
+    let path = path.as_ref();
+    let variable = match fs::read(path) {
+        Ok(variable) => variable,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(Error::PlatformManifest(format!(
+                "{}: {err}",
+                path.display()
+            )))
+        }
+    };
+    match variable.get(EFI_VARIABLE_ATTRIBUTES_SIZE..) {
+        Some(manifest) if !manifest.is_empty() => Ok(Some(manifest.to_vec())),
+        _ => Err(Error::PlatformManifest(format!(
+            "{}: empty variable",
+            path.display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_sgx_core_types::CpuSvn;
+    use mc_sgx_dcap_types::{PceInfo, PpidEncryption, Qe3Id};
+    use tempfile::tempdir;
+
+    fn pck_cert_id() -> PckCertId {
+        PckCertId::new(
+            Qe3Id::from([0xAB; 16]),
+            CpuSvn::from([0x0C; CpuSvn::SIZE]),
+            PceInfo::new(13, 0),
+            PpidEncryption::Rsa3072,
+            [0x01, 0x23],
+        )
+    }
+
+    #[test]
+    fn csv_without_platform_manifest() {
+        let registration = PlatformRegistration::new(pck_cert_id(), None);
+
+        assert_eq!(
+            registration.to_csv(),
+            format!("0123,0000,{},0D00,{},\n", "0C".repeat(16), "AB".repeat(16))
+        );
+    }
+
+    #[test]
+    fn csv_with_platform_manifest() {
+        let registration = PlatformRegistration::new(pck_cert_id(), Some(vec![0xFE, 0xED]));
+
+        assert!(registration.to_csv().ends_with(",FEED\n"));
+    }
+
+    #[test]
+    fn json_with_platform_manifest() {
+        let registration = PlatformRegistration::new(pck_cert_id(), Some(vec![0xFE, 0xED]));
+
+        assert_eq!(
+            registration.to_json(),
+            format!(
+                r#"{{"enc_ppid":"0123","pce_id":"0000","cpu_svn":"{}","pce_svn":"0D00","qe_id":"{}","platform_manifest":"FEED"}}"#,
+                "0C".repeat(16),
+                "AB".repeat(16)
+            )
+        );
+    }
+
+    #[test]
+    fn platform_manifest_skips_attributes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("variable");
+        fs::write(&path, [7, 0, 0, 0, 1, 2, 3]).unwrap();
+
+        assert_eq!(platform_manifest(&path), Ok(Some(vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn missing_platform_manifest() {
+        let dir = tempdir().unwrap();
+
+        assert_eq!(platform_manifest(dir.path().join("missing")), Ok(None));
+    }
+
+    #[test]
+    fn empty_platform_manifest_fails() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("variable");
+        fs::write(&path, [7, 0, 0, 0]).unwrap();
+
+        assert!(matches!(
+            platform_manifest(&path),
+            Err(Error::PlatformManifest(_))
+        ));
+    }
+}
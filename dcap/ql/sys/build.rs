@@ -1,6 +1,8 @@
 // Copyright (c) 2022-2024 The MobileCoin Foundation
 //! Builds the FFI function bindings for dcap ql library of the Intel SGX SDK
 
+use std::env;
+
 const DCAP_QL_FUNCTIONS: &[&str] = &[
     "sgx_qe_cleanup_by_policy",
     "sgx_qe_get_quote",
@@ -8,6 +10,16 @@ const DCAP_QL_FUNCTIONS: &[&str] = &[
     "sgx_qe_get_target_info",
     "sgx_qe_set_enclave_load_policy",
     "sgx_ql_set_path",
+    "sgx_ql_set_trace_callback",
+];
+
+const QUOTE_EX_FUNCTIONS: &[&str] = &[
+    "sgx_get_quote_ex",
+    "sgx_get_quote_size_ex",
+    "sgx_get_supported_att_key_id_num",
+    "sgx_get_supported_att_key_ids",
+    "sgx_init_quote_ex",
+    "sgx_select_att_key_id",
 ];
 
 fn main() {
@@ -21,6 +33,16 @@ fn main() {
         builder = builder.allowlist_function(f);
     }
 
+    // The quote-ex library is part of the platform software, it hands the
+    // quoting off to the AESM service.
+    if env::var_os("CARGO_FEATURE_QUOTE_EX").is_some() {
+        cargo_emit::rustc_link_lib!("dylib=sgx_quote_ex");
+        builder = builder.header("quote_ex_wrapper.h");
+        for f in QUOTE_EX_FUNCTIONS {
+            builder = builder.allowlist_function(f);
+        }
+    }
+
     let out_path = mc_sgx_core_build::build_output_dir();
     builder
         .generate()
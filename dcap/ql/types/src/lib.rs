@@ -9,7 +9,7 @@ use mc_sgx_dcap_ql_sys_types::sgx_ql_path_type_t;
 
 /// Paths (location and filename) to be override the default entries.
 #[non_exhaustive]
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug)]
 pub enum PathKind {
     /// Quoting Enclave (QE3)
     QuotingEnclave,
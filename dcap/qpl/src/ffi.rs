@@ -0,0 +1,850 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! The C API of a quote provider library (QPL), as declared in
+//! `sgx_default_quote_provider.h` of the DCAP quote provider library.
+//!
+//! The data returned to the DCAP libraries is allocated here, and must be
+//! freed with the matching `sgx_ql_free_*()` function.
+
+use crate::{Error, QplStore};
+use mc_sgx_core_types::CpuSvn;
+use mc_sgx_dcap_sys_types::{
+    quote3_error_t, sgx_ql_config_t, sgx_ql_config_version_t, sgx_ql_log_level_t,
+    sgx_ql_logging_callback_t, sgx_ql_pck_cert_id_t, sgx_ql_qve_collateral_t,
+};
+use mc_sgx_dcap_types::{CaType, Collateral, LogLevel, QuoteConfig, FMSPC_SIZE, QE_ID_SIZE};
+use std::{
+    ffi::{CStr, CString},
+    mem,
+    os::raw::c_char,
+    ptr,
+    sync::{Mutex, PoisonError},
+};
+use x509_cert::{
+    der::{pem::LineEnding, Encode, EncodePem},
+    Certificate,
+};
+
+// The collateral format of the PCCS 3.1 API, with raw DER CRLs. See
+// `mc-sgx-dcap-types::Collateral`.
+const COLLATERAL_VERSION_MAJOR: u16 = 3;
+const COLLATERAL_VERSION_MINOR: u16 = 1;
+const SGX_TEE: u32 = 0;
+
+const LOG_PREFIX: &str = "[mc-sgx-dcap-qpl]";
+
+/// The size of the length stored before each allocation, so that it can be
+/// freed with only its pointer.
+const HEADER_SIZE: usize = mem::size_of::<usize>();
+
+/// The logging callback set by the DCAP library which loaded this QPL
+#[derive(Clone, Copy)]
+struct Logger {
+    callback: unsafe extern "C" fn(sgx_ql_log_level_t, *const c_char),
+    level: LogLevel,
+}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+/// Get the quote configuration, the PCK certificate chain, of a platform.
+///
+/// # Safety
+/// * `p_pck_cert_id` must be null or point to a valid `sgx_ql_pck_cert_id_t`
+///   whose `p_qe3_id` points to `qe3_id_size` bytes.
+/// * `pp_quote_config` must be null or valid for writes.
+///
+/// The configuration must be freed with [`sgx_ql_free_quote_config()`].
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_get_quote_config(
+    p_pck_cert_id: *const sgx_ql_pck_cert_id_t,
+    pp_quote_config: *mut *mut sgx_ql_config_t,
+) -> quote3_error_t {
+    get_quote_config(&QplStore::from_env(), p_pck_cert_id, pp_quote_config)
+}
+
+/// Free a quote configuration from [`sgx_ql_get_quote_config()`].
+///
+/// # Safety
+/// `p_quote_config` must be null or from [`sgx_ql_get_quote_config()`], and
+/// not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_free_quote_config(
+    p_quote_config: *mut sgx_ql_config_t,
+) -> quote3_error_t {
+    if !p_quote_config.is_null() {
+        let config = Box::from_raw(p_quote_config);
+        free_bytes(config.p_cert_data);
+    }
+    quote3_error_t::SGX_QL_SUCCESS
+}
+
+/// Get the quote verification collateral for the platforms with `fmspc` and
+/// PCK certificates issued by `pck_ca`.
+///
+/// # Safety
+/// * `fmspc` must be null or point to `fmspc_size` bytes.
+/// * `pck_ca` must be null or a null terminated string, `"processor"` or
+///   `"platform"`.
+/// * `pp_quote_collateral` must be null or valid for writes.
+///
+/// The collateral must be freed with
+/// [`sgx_ql_free_quote_verification_collateral()`].
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_get_quote_verification_collateral(
+    fmspc: *const u8,
+    fmspc_size: u16,
+    pck_ca: *const c_char,
+    pp_quote_collateral: *mut *mut sgx_ql_qve_collateral_t,
+) -> quote3_error_t {
+    get_quote_verification_collateral(
+        &QplStore::from_env(),
+        fmspc,
+        fmspc_size,
+        pck_ca,
+        pp_quote_collateral,
+    )
+}
+
+/// Free collateral from [`sgx_ql_get_quote_verification_collateral()`].
+///
+/// # Safety
+/// `p_quote_collateral` must be null or from
+/// [`sgx_ql_get_quote_verification_collateral()`], and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_free_quote_verification_collateral(
+    p_quote_collateral: *mut sgx_ql_qve_collateral_t,
+) -> quote3_error_t {
+    if !p_quote_collateral.is_null() {
+        let collateral = Box::from_raw(p_quote_collateral);
+        for field in [
+            collateral.pck_crl_issuer_chain,
+            collateral.root_ca_crl,
+            collateral.pck_crl,
+            collateral.tcb_info_issuer_chain,
+            collateral.tcb_info,
+            collateral.qe_identity_issuer_chain,
+            collateral.qe_identity,
+        ] {
+            free_bytes(field as *mut u8);
+        }
+    }
+    quote3_error_t::SGX_QL_SUCCESS
+}
+
+/// Get the signed QvE identity and the issuer chain of its signer.
+///
+/// # Safety
+/// All arguments must be null or valid for writes.
+///
+/// The identity and issuer chain must be freed with
+/// [`sgx_ql_free_qve_identity()`].
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_get_qve_identity(
+    pp_qve_identity: *mut *mut c_char,
+    p_qve_identity_size: *mut u32,
+    pp_qve_identity_issuer_chain: *mut *mut c_char,
+    p_qve_identity_issuer_chain_size: *mut u32,
+) -> quote3_error_t {
+    get_qve_identity(
+        &QplStore::from_env(),
+        pp_qve_identity,
+        p_qve_identity_size,
+        pp_qve_identity_issuer_chain,
+        p_qve_identity_issuer_chain_size,
+    )
+}
+
+/// Free a QvE identity and issuer chain from [`sgx_ql_get_qve_identity()`].
+///
+/// # Safety
+/// Each argument must be null or from [`sgx_ql_get_qve_identity()`], and not
+/// already freed.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_free_qve_identity(
+    p_qve_identity: *mut c_char,
+    p_qve_identity_issuer_chain: *mut c_char,
+) -> quote3_error_t {
+    free_bytes(p_qve_identity as *mut u8);
+    free_bytes(p_qve_identity_issuer_chain as *mut u8);
+    quote3_error_t::SGX_QL_SUCCESS
+}
+
+/// Get the DER encoded Intel SGX Root CA CRL.
+///
+/// # Safety
+/// All arguments must be null or valid for writes.
+///
+/// The CRL must be freed with [`sgx_ql_free_root_ca_crl()`].
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_get_root_ca_crl(
+    pp_root_ca_crl: *mut *mut u8,
+    p_root_ca_crl_size: *mut u16,
+) -> quote3_error_t {
+    get_root_ca_crl(&QplStore::from_env(), pp_root_ca_crl, p_root_ca_crl_size)
+}
+
+/// Free a CRL from [`sgx_ql_get_root_ca_crl()`].
+///
+/// # Safety
+/// `p_root_ca_crl` must be null or from [`sgx_ql_get_root_ca_crl()`], and
+/// not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_free_root_ca_crl(p_root_ca_crl: *mut u8) -> quote3_error_t {
+    free_bytes(p_root_ca_crl);
+    quote3_error_t::SGX_QL_SUCCESS
+}
+
+/// Set the callback for the log messages of this QPL, up to `loglevel`.
+///
+/// A null `logger` stops the log messages.
+///
+/// # Safety
+/// `logger` must be safe to call from any thread until it's replaced.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_set_logging_callback(
+    logger: sgx_ql_logging_callback_t,
+    loglevel: sgx_ql_log_level_t,
+) -> quote3_error_t {
+    let Ok(level) = LogLevel::try_from(loglevel) else {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    };
+    *LOGGER.lock().unwrap_or_else(PoisonError::into_inner) =
+        logger.map(|callback| Logger { callback, level });
+    quote3_error_t::SGX_QL_SUCCESS
+}
+
+unsafe fn get_quote_config(
+    store: &QplStore,
+    p_pck_cert_id: *const sgx_ql_pck_cert_id_t,
+    pp_quote_config: *mut *mut sgx_ql_config_t,
+) -> quote3_error_t {
+    if p_pck_cert_id.is_null() || pp_quote_config.is_null() {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+    // The structure is packed, so it's copied out rather than referenced.
+    let cert_id = ptr::read_unaligned(p_pck_cert_id);
+    if cert_id.p_qe3_id.is_null()
+        || cert_id.qe3_id_size as usize != QE_ID_SIZE
+        || cert_id.p_platform_cpu_svn.is_null()
+        || cert_id.p_platform_pce_isv_svn.is_null()
+    {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+    let mut qe_id = [0; QE_ID_SIZE];
+    ptr::copy_nonoverlapping(cert_id.p_qe3_id, qe_id.as_mut_ptr(), QE_ID_SIZE);
+    let cpu_svn = CpuSvn::from(ptr::read_unaligned(cert_id.p_platform_cpu_svn));
+    let pce_svn = ptr::read_unaligned(cert_id.p_platform_pce_isv_svn);
+
+    let config = store
+        .quote_config(&qe_id.into(), cert_id.pce_id, &cpu_svn, pce_svn)
+        .and_then(|config| sgx_quote_config(&config));
+    match config {
+        Ok(config) => {
+            *pp_quote_config = Box::into_raw(Box::new(config));
+            quote3_error_t::SGX_QL_SUCCESS
+        }
+        Err(err) => failure(err, quote3_error_t::SGX_QL_NO_PLATFORM_CERT_DATA),
+    }
+}
+
+unsafe fn get_quote_verification_collateral(
+    store: &QplStore,
+    fmspc: *const u8,
+    fmspc_size: u16,
+    pck_ca: *const c_char,
+    pp_quote_collateral: *mut *mut sgx_ql_qve_collateral_t,
+) -> quote3_error_t {
+    if fmspc.is_null()
+        || fmspc_size as usize != FMSPC_SIZE
+        || pck_ca.is_null()
+        || pp_quote_collateral.is_null()
+    {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+    let mut fmspc_bytes = [0; FMSPC_SIZE];
+    ptr::copy_nonoverlapping(fmspc, fmspc_bytes.as_mut_ptr(), FMSPC_SIZE);
+    let pck_ca = CStr::from_ptr(pck_ca);
+    let Some(ca) = [CaType::Processor, CaType::Platform]
+        .into_iter()
+        .find(|ca| ca.as_str().as_bytes() == pck_ca.to_bytes())
+    else {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    };
+
+    let collateral = store
+        .collateral(&fmspc_bytes, ca)
+        .and_then(|collateral| sgx_collateral(&collateral));
+    match collateral {
+        Ok(collateral) => {
+            *pp_quote_collateral = Box::into_raw(Box::new(collateral));
+            quote3_error_t::SGX_QL_SUCCESS
+        }
+        Err(err) => failure(err, quote3_error_t::SGX_QL_NO_QUOTE_COLLATERAL_DATA),
+    }
+}
+
+unsafe fn get_qve_identity(
+    store: &QplStore,
+    pp_qve_identity: *mut *mut c_char,
+    p_qve_identity_size: *mut u32,
+    pp_qve_identity_issuer_chain: *mut *mut c_char,
+    p_qve_identity_issuer_chain_size: *mut u32,
+) -> quote3_error_t {
+    if pp_qve_identity.is_null()
+        || p_qve_identity_size.is_null()
+        || pp_qve_identity_issuer_chain.is_null()
+        || p_qve_identity_issuer_chain_size.is_null()
+    {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+    let identity = store.qve_identity().and_then(|(identity, issuer_chain)| {
+        let identity_size = c_size::<u32>(identity.as_bytes())?;
+        let issuer_chain_size = c_size::<u32>(issuer_chain.as_bytes())?;
+        Ok((identity, identity_size, issuer_chain, issuer_chain_size))
+    });
+    let (identity, identity_size, issuer_chain, issuer_chain_size) = match identity {
+        Ok(identity) => identity,
+        Err(err) => return failure(err, quote3_error_t::SGX_QL_NO_QVE_IDENTITY_DATA),
+    };
+
+    *pp_qve_identity = alloc_null_terminated(identity.as_bytes()) as *mut c_char;
+    *p_qve_identity_size = identity_size;
+    *pp_qve_identity_issuer_chain = alloc_null_terminated(issuer_chain.as_bytes()) as *mut c_char;
+    *p_qve_identity_issuer_chain_size = issuer_chain_size;
+    quote3_error_t::SGX_QL_SUCCESS
+}
+
+unsafe fn get_root_ca_crl(
+    store: &QplStore,
+    pp_root_ca_crl: *mut *mut u8,
+    p_root_ca_crl_size: *mut u16,
+) -> quote3_error_t {
+    if pp_root_ca_crl.is_null() || p_root_ca_crl_size.is_null() {
+        return quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+    let crl = store
+        .root_ca_crl()
+        .and_then(|crl| Ok((c_size::<u16>(&crl)?, crl)));
+    match crl {
+        Ok((size, crl)) => {
+            *pp_root_ca_crl = alloc_null_terminated(&crl);
+            *p_root_ca_crl_size = size;
+            quote3_error_t::SGX_QL_SUCCESS
+        }
+        Err(err) => failure(err, quote3_error_t::SGX_QL_NO_QUOTE_COLLATERAL_DATA),
+    }
+}
+
+/// Log `err` and convert it to a [`quote3_error_t`], `not_found` when there
+/// is no data for the request.
+fn failure(err: Error, not_found: quote3_error_t) -> quote3_error_t {
+    log(LogLevel::Error, &err.to_string());
+    match err {
+        Error::NotFound(_) => not_found,
+        _ => quote3_error_t::SGX_QL_ERROR_UNEXPECTED,
+    }
+}
+
+fn log(level: LogLevel, message: &str) {
+    let logger = *LOGGER.lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(logger) = logger.filter(|logger| level <= logger.level) else {
+        return;
+    };
+    let Ok(message) = CString::new(format!("{LOG_PREFIX} {message}\n")) else {
+        return;
+    };
+    // SAFETY: The DCAP library which set the callback is responsible for it
+    // being callable.
+    unsafe { (logger.callback)(level.into(), message.as_ptr()) };
+}
+
+fn sgx_quote_config(config: &QuoteConfig) -> Result<sgx_ql_config_t, Error> {
+    let cert_data_size = c_size(config.cert_data())?;
+    Ok(sgx_ql_config_t {
+        version: sgx_ql_config_version_t::SGX_QL_CONFIG_VERSION_1,
+        cert_cpu_svn: config.cert_cpu_svn().clone().into(),
+        cert_pce_isv_svn: config.cert_pce_svn(),
+        cert_data_size,
+        p_cert_data: alloc_null_terminated(config.cert_data()),
+    })
+}
+
+/// The collateral in the format of the PCCS 3.1 API, the CRLs are raw DER
+/// and the issuer chains are PEM.
+fn sgx_collateral(collateral: &Collateral) -> Result<sgx_ql_qve_collateral_t, Error> {
+    let encoding_error =
+        |err: x509_cert::der::Error| Error::Contents("collateral".into(), err.to_string());
+    let pck_crl_issuer_chain = pem_chain(collateral.pck_crl_issuer_chain())?;
+    let root_ca_crl = collateral.root_ca_crl().to_der().map_err(encoding_error)?;
+    let pck_crl = collateral.pck_crl().to_der().map_err(encoding_error)?;
+    let tcb_info_issuer_chain = pem_chain(collateral.tcb_issuer_chain())?;
+    let qe_identity_issuer_chain = pem_chain(collateral.qe_identity_issuer_chain())?;
+    let fields = [
+        pck_crl_issuer_chain.as_bytes(),
+        &root_ca_crl,
+        &pck_crl,
+        tcb_info_issuer_chain.as_bytes(),
+        collateral.tcb_info().as_bytes(),
+        qe_identity_issuer_chain.as_bytes(),
+        collateral.qe_identity().as_bytes(),
+    ];
+    let mut sizes = [0u32; 7];
+    for (size, field) in sizes.iter_mut().zip(fields) {
+        *size = c_size(field)?;
+    }
+    let [pck_crl_issuer_chain, root_ca_crl, pck_crl, tcb_info_issuer_chain, tcb_info, qe_identity_issuer_chain, qe_identity] =
+        fields.map(|field| alloc_null_terminated(field) as *mut c_char);
+
+    let mut sgx_collateral = sgx_ql_qve_collateral_t {
+        tee_type: SGX_TEE,
+        pck_crl_issuer_chain,
+        pck_crl_issuer_chain_size: sizes[0],
+        root_ca_crl,
+        root_ca_crl_size: sizes[1],
+        pck_crl,
+        pck_crl_size: sizes[2],
+        tcb_info_issuer_chain,
+        tcb_info_issuer_chain_size: sizes[3],
+        tcb_info,
+        tcb_info_size: sizes[4],
+        qe_identity_issuer_chain,
+        qe_identity_issuer_chain_size: sizes[5],
+        qe_identity,
+        qe_identity_size: sizes[6],
+        ..Default::default()
+    };
+    // SAFETY: The version fields are a union, setting the major and minor
+    // flavor of it.
+    unsafe {
+        let version = sgx_collateral.__bindgen_anon_1.__bindgen_anon_1.as_mut();
+        version.major_version = COLLATERAL_VERSION_MAJOR;
+        version.minor_version = COLLATERAL_VERSION_MINOR;
+    }
+    Ok(sgx_collateral)
+}
+
+fn pem_chain(chain: &[Certificate]) -> Result<String, Error> {
+    chain
+        .iter()
+        .map(|cert| cert.to_pem(LineEnding::LF))
+        .collect::<Result<String, _>>()
+        .map_err(|err| Error::Contents("collateral".into(), err.to_string()))
+}
+
+/// The size of `bytes` when null terminated, as the size type `S` of the C
+/// API.
+fn c_size<S: TryFrom<usize>>(bytes: &[u8]) -> Result<S, Error> {
+    S::try_from(bytes.len() + 1).map_err(|_| {
+        Error::Contents(
+            "response".into(),
+            format!("{} bytes is too large", bytes.len()),
+        )
+    })
+}
+
+/// Copy `bytes`, null terminated, into an allocation for the C API.
+///
+/// The allocation must be freed with [`free_bytes()`].
+fn alloc_null_terminated(bytes: &[u8]) -> *mut u8 {
+    let size = bytes.len() + 1;
+    let mut buffer = Vec::with_capacity(HEADER_SIZE + size);
+    buffer.extend_from_slice(&size.to_ne_bytes());
+    buffer.extend_from_slice(bytes);
+    buffer.push(0);
+    let buffer = Box::into_raw(buffer.into_boxed_slice()) as *mut u8;
+    // SAFETY: The buffer is at least `HEADER_SIZE` bytes
+    unsafe { buffer.add(HEADER_SIZE) }
+}
+
+/// Free an allocation from [`alloc_null_terminated()`].
+///
+/// # Safety
+/// `bytes` must be null or from [`alloc_null_terminated()`], and not already
+/// freed.
+unsafe fn free_bytes(bytes: *mut u8) {
+    if bytes.is_null() {
+        return;
+    }
+    let buffer = bytes.sub(HEADER_SIZE);
+    let mut size = [0; HEADER_SIZE];
+    ptr::copy_nonoverlapping(buffer, size.as_mut_ptr(), HEADER_SIZE);
+    let size = HEADER_SIZE + usize::from_ne_bytes(size);
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, size)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_sgx_core_sys_types::sgx_cpu_svn_t;
+    use mc_sgx_dcap_types::test_utils::{TestCa, FMSPC, PCE_SVN, QE_ID, TCB_COMPONENT_SVNS};
+    use std::{fs, slice};
+    use tempfile::{tempdir, TempDir};
+
+    const PROCESSOR: &[u8] = b"processor\0";
+
+    fn store_with_test_ca() -> (TempDir, TestCa) {
+        let dir = tempdir().unwrap();
+        let ca = TestCa::default();
+        fs::write(
+            dir.path()
+                .join(format!("{}-0000.pem", hex::encode_upper(QE_ID))),
+            ca.pck_certificate_chain_pem(),
+        )
+        .unwrap();
+        fs::write(
+            dir.path()
+                .join(format!("{}-processor-17.der", hex::encode(FMSPC))),
+            ca.collateral().to_der().unwrap(),
+        )
+        .unwrap();
+        (dir, ca)
+    }
+
+    fn pck_cert_id(
+        qe_id: &mut [u8],
+        cpu_svn: &mut sgx_cpu_svn_t,
+        pce_svn: &mut u16,
+    ) -> sgx_ql_pck_cert_id_t {
+        sgx_ql_pck_cert_id_t {
+            p_qe3_id: qe_id.as_mut_ptr(),
+            qe3_id_size: qe_id.len() as u32,
+            p_platform_cpu_svn: cpu_svn,
+            p_platform_pce_isv_svn: pce_svn,
+            p_encrypted_ppid: ptr::null_mut(),
+            encrypted_ppid_size: 0,
+            crypto_suite: 1,
+            pce_id: 0,
+        }
+    }
+
+    #[test]
+    fn quote_config_round_trip() {
+        let (dir, ca) = store_with_test_ca();
+        let store = QplStore::new(dir.path());
+        let mut qe_id = QE_ID;
+        let mut cpu_svn = sgx_cpu_svn_t::default();
+        let mut pce_svn = 0;
+        let cert_id = pck_cert_id(&mut qe_id, &mut cpu_svn, &mut pce_svn);
+        let mut config = ptr::null_mut();
+
+        let result = unsafe { get_quote_config(&store, &cert_id, &mut config) };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_SUCCESS);
+        let sgx_config = unsafe { ptr::read_unaligned(config) };
+        assert_eq!(
+            { sgx_config.version },
+            sgx_ql_config_version_t::SGX_QL_CONFIG_VERSION_1
+        );
+        assert_eq!(
+            { sgx_config.cert_cpu_svn }.svn,
+            TCB_COMPONENT_SVNS.map(|svn| svn as u8)
+        );
+        assert_eq!({ sgx_config.cert_pce_isv_svn }, PCE_SVN);
+        let pem = ca.pck_certificate_chain_pem();
+        let mut expected = pem.into_bytes();
+        expected.push(0);
+        let cert_data = unsafe {
+            slice::from_raw_parts(sgx_config.p_cert_data, sgx_config.cert_data_size as usize)
+        };
+        assert_eq!(cert_data, expected);
+
+        assert_eq!(
+            unsafe { sgx_ql_free_quote_config(config) },
+            quote3_error_t::SGX_QL_SUCCESS
+        );
+    }
+
+    #[test]
+    fn quote_config_for_unknown_platform() {
+        let dir = tempdir().unwrap();
+        let store = QplStore::new(dir.path());
+        let mut qe_id = QE_ID;
+        let mut cpu_svn = sgx_cpu_svn_t::default();
+        let mut pce_svn = 0;
+        let cert_id = pck_cert_id(&mut qe_id, &mut cpu_svn, &mut pce_svn);
+        let mut config = ptr::null_mut();
+
+        let result = unsafe { get_quote_config(&store, &cert_id, &mut config) };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_NO_PLATFORM_CERT_DATA);
+        assert!(config.is_null());
+    }
+
+    #[test]
+    fn quote_config_with_short_qe_id() {
+        let (dir, _) = store_with_test_ca();
+        let store = QplStore::new(dir.path());
+        let mut qe_id = [0x9E; 8];
+        let mut cpu_svn = sgx_cpu_svn_t::default();
+        let mut pce_svn = 0;
+        let cert_id = pck_cert_id(&mut qe_id, &mut cpu_svn, &mut pce_svn);
+        let mut config = ptr::null_mut();
+
+        let result = unsafe { get_quote_config(&store, &cert_id, &mut config) };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn quote_config_without_platform_tcb() {
+        let (dir, _) = store_with_test_ca();
+        let store = QplStore::new(dir.path());
+        let mut qe_id = QE_ID;
+        let mut cpu_svn = sgx_cpu_svn_t::default();
+        let mut pce_svn = 0;
+        let mut cert_id = pck_cert_id(&mut qe_id, &mut cpu_svn, &mut pce_svn);
+        cert_id.p_platform_cpu_svn = ptr::null_mut();
+        let mut config = ptr::null_mut();
+
+        let result = unsafe { get_quote_config(&store, &cert_id, &mut config) };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn collateral_round_trip() {
+        let (dir, ca) = store_with_test_ca();
+        let store = QplStore::new(dir.path());
+        let mut sgx_collateral = ptr::null_mut();
+
+        let result = unsafe {
+            get_quote_verification_collateral(
+                &store,
+                FMSPC.as_ptr(),
+                FMSPC.len() as u16,
+                PROCESSOR.as_ptr() as *const c_char,
+                &mut sgx_collateral,
+            )
+        };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_SUCCESS);
+        let collateral = Collateral::try_from(unsafe { &*sgx_collateral }).unwrap();
+        assert_eq!(collateral, ca.collateral());
+
+        assert_eq!(
+            unsafe { sgx_ql_free_quote_verification_collateral(sgx_collateral) },
+            quote3_error_t::SGX_QL_SUCCESS
+        );
+    }
+
+    #[test]
+    fn collateral_for_other_ca_not_found() {
+        let (dir, _) = store_with_test_ca();
+        let store = QplStore::new(dir.path());
+        let mut sgx_collateral = ptr::null_mut();
+
+        let result = unsafe {
+            get_quote_verification_collateral(
+                &store,
+                FMSPC.as_ptr(),
+                FMSPC.len() as u16,
+                b"platform\0".as_ptr() as *const c_char,
+                &mut sgx_collateral,
+            )
+        };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_NO_QUOTE_COLLATERAL_DATA);
+        assert!(sgx_collateral.is_null());
+    }
+
+    #[test]
+    fn collateral_for_unknown_ca_fails() {
+        let (dir, _) = store_with_test_ca();
+        let store = QplStore::new(dir.path());
+        let mut sgx_collateral = ptr::null_mut();
+
+        let result = unsafe {
+            get_quote_verification_collateral(
+                &store,
+                FMSPC.as_ptr(),
+                FMSPC.len() as u16,
+                b"other\0".as_ptr() as *const c_char,
+                &mut sgx_collateral,
+            )
+        };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn qve_identity_round_trip() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("qve_identity.json"), "{}").unwrap();
+        fs::write(dir.path().join("qve_identity_issuer_chain.pem"), "chain").unwrap();
+        let store = QplStore::new(dir.path());
+        let mut identity = ptr::null_mut();
+        let mut identity_size = 0;
+        let mut chain = ptr::null_mut();
+        let mut chain_size = 0;
+
+        let result = unsafe {
+            get_qve_identity(
+                &store,
+                &mut identity,
+                &mut identity_size,
+                &mut chain,
+                &mut chain_size,
+            )
+        };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_SUCCESS);
+        assert_eq!(identity_size, 3);
+        assert_eq!(unsafe { CStr::from_ptr(identity) }.to_bytes(), b"{}");
+        assert_eq!(chain_size, 6);
+        assert_eq!(unsafe { CStr::from_ptr(chain) }.to_bytes(), b"chain");
+
+        assert_eq!(
+            unsafe { sgx_ql_free_qve_identity(identity, chain) },
+            quote3_error_t::SGX_QL_SUCCESS
+        );
+    }
+
+    #[test]
+    fn missing_qve_identity() {
+        let dir = tempdir().unwrap();
+        let store = QplStore::new(dir.path());
+        let mut identity = ptr::null_mut();
+        let mut identity_size = 0;
+        let mut chain = ptr::null_mut();
+        let mut chain_size = 0;
+
+        let result = unsafe {
+            get_qve_identity(
+                &store,
+                &mut identity,
+                &mut identity_size,
+                &mut chain,
+                &mut chain_size,
+            )
+        };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_NO_QVE_IDENTITY_DATA);
+    }
+
+    #[test]
+    fn root_ca_crl_round_trip() {
+        let dir = tempdir().unwrap();
+        let crl = TestCa::default()
+            .collateral()
+            .root_ca_crl()
+            .to_der()
+            .unwrap();
+        fs::write(dir.path().join("root_ca_crl.der"), &crl).unwrap();
+        let store = QplStore::new(dir.path());
+        let mut sgx_crl = ptr::null_mut();
+        let mut size = 0;
+
+        let result = unsafe { get_root_ca_crl(&store, &mut sgx_crl, &mut size) };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_SUCCESS);
+        let bytes = unsafe { slice::from_raw_parts(sgx_crl, size as usize) };
+        assert_eq!(bytes[..crl.len()], crl);
+        assert_eq!(bytes[crl.len()..], [0]);
+
+        assert_eq!(
+            unsafe { sgx_ql_free_root_ca_crl(sgx_crl) },
+            quote3_error_t::SGX_QL_SUCCESS
+        );
+    }
+
+    #[test]
+    fn null_parameters_fail() {
+        let store = QplStore::new("unused");
+
+        assert_eq!(
+            unsafe { get_quote_config(&store, ptr::null(), ptr::null_mut()) },
+            quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER
+        );
+        assert_eq!(
+            unsafe {
+                get_quote_verification_collateral(
+                    &store,
+                    ptr::null(),
+                    0,
+                    ptr::null(),
+                    ptr::null_mut(),
+                )
+            },
+            quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER
+        );
+        assert_eq!(
+            unsafe {
+                get_qve_identity(
+                    &store,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            },
+            quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER
+        );
+        assert_eq!(
+            unsafe { get_root_ca_crl(&store, ptr::null_mut(), ptr::null_mut()) },
+            quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER
+        );
+    }
+
+    #[test]
+    fn freeing_null_succeeds() {
+        unsafe {
+            assert_eq!(
+                sgx_ql_free_quote_config(ptr::null_mut()),
+                quote3_error_t::SGX_QL_SUCCESS
+            );
+            assert_eq!(
+                sgx_ql_free_quote_verification_collateral(ptr::null_mut()),
+                quote3_error_t::SGX_QL_SUCCESS
+            );
+            assert_eq!(
+                sgx_ql_free_qve_identity(ptr::null_mut(), ptr::null_mut()),
+                quote3_error_t::SGX_QL_SUCCESS
+            );
+            assert_eq!(
+                sgx_ql_free_root_ca_crl(ptr::null_mut()),
+                quote3_error_t::SGX_QL_SUCCESS
+            );
+        }
+    }
+
+    static MESSAGES: Mutex<Vec<(LogLevel, String)>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn record(level: sgx_ql_log_level_t, message: *const c_char) {
+        let message = CStr::from_ptr(message).to_string_lossy().into_owned();
+        MESSAGES
+            .lock()
+            .unwrap()
+            .push((level.try_into().unwrap(), message));
+    }
+
+    #[test]
+    fn failures_are_logged() {
+        let dir = tempdir().unwrap();
+        let store = QplStore::new(dir.path());
+        let mut crl = ptr::null_mut();
+        let mut size = 0;
+
+        unsafe {
+            assert_eq!(
+                sgx_ql_set_logging_callback(Some(record), LogLevel::Info.into()),
+                quote3_error_t::SGX_QL_SUCCESS
+            );
+            get_root_ca_crl(&store, &mut crl, &mut size);
+            sgx_ql_set_logging_callback(None, LogLevel::Error.into());
+        }
+
+        let messages = MESSAGES.lock().unwrap();
+        assert!(messages
+            .iter()
+            .any(|(level, message)| *level == LogLevel::Error
+                && message.starts_with(LOG_PREFIX)
+                && message.contains("root_ca_crl.der")));
+    }
+
+    #[test]
+    fn unknown_log_level_fails() {
+        let result = unsafe { sgx_ql_set_logging_callback(Some(record), sgx_ql_log_level_t(10)) };
+
+        assert_eq!(result, quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER);
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs, missing_debug_implementations)]
+
+mod ffi;
+mod store;
+
+pub use crate::ffi::{
+    sgx_ql_free_quote_config, sgx_ql_free_quote_verification_collateral, sgx_ql_free_qve_identity,
+    sgx_ql_free_root_ca_crl, sgx_ql_get_quote_config, sgx_ql_get_quote_verification_collateral,
+    sgx_ql_get_qve_identity, sgx_ql_get_root_ca_crl, sgx_ql_set_logging_callback,
+};
+pub use crate::store::{QplStore, DEFAULT_STORE_DIRECTORY, STORE_DIRECTORY_VARIABLE};
+
+/// Error serving data from a [`QplStore`]
+#[derive(Debug, displaydoc::Display, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Nothing in the store at {0}
+    NotFound(String),
+    /// Failed to access {0}: {1}
+    Store(String, String),
+    /// Invalid contents in {0}: {1}
+    Contents(String, String),
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,376 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! The local directory the quote provider library serves from.
+
+use crate::Error;
+use mc_sgx_core_types::CpuSvn;
+use mc_sgx_dcap_collateral::StoreLayout;
+use mc_sgx_dcap_types::{
+    CaType, Collateral, Qe3Id, QuoteConfig, TcbInfo, COMPONENT_SVN_COUNT, FMSPC_SIZE,
+};
+use mc_sgx_util::trim_ascii_whitespace;
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use x509_cert::{der::Decode, Certificate};
+
+/// The environment variable with the directory of the [`QplStore`] used by
+/// the C API.
+pub const STORE_DIRECTORY_VARIABLE: &str = "MC_SGX_DCAP_QPL_DIR";
+
+/// The directory of the [`QplStore`] used by the C API when
+/// [`STORE_DIRECTORY_VARIABLE`] isn't set.
+pub const DEFAULT_STORE_DIRECTORY: &str = "/var/cache/mc-sgx-dcap-qpl";
+
+const QVE_IDENTITY_FILE: &str = "qve_identity.json";
+const QVE_IDENTITY_ISSUER_CHAIN_FILE: &str = "qve_identity_issuer_chain.pem";
+const ROOT_CA_CRL_FILE: &str = "root_ca_crl.der";
+
+/// A directory of PCK certificates and quote verification collateral.
+///
+/// The PCK certificate chains and collateral are in the [`StoreLayout`]
+/// shared with `mc-sgx-dcap-collateral::DirectoryPckCertificateResolver` and
+/// `mc-sgx-dcap-quoteverify::DirectoryCollateralProvider`. The other files
+/// are:
+/// * `qve_identity.json` and `qve_identity_issuer_chain.pem` - The signed QvE
+///   identity and its issuer chain, from the PCS `qve/identity` endpoint.
+/// * `root_ca_crl.der` - The Intel SGX Root CA CRL.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct QplStore {
+    layout: StoreLayout,
+}
+
+impl QplStore {
+    /// A store for the directory at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            layout: StoreLayout::new(path),
+        }
+    }
+
+    /// The store in the [`STORE_DIRECTORY_VARIABLE`] directory, or the
+    /// [`DEFAULT_STORE_DIRECTORY`].
+    pub fn from_env() -> Self {
+        Self::new(
+            env::var_os(STORE_DIRECTORY_VARIABLE)
+                .map_or_else(|| DEFAULT_STORE_DIRECTORY.into(), PathBuf::from),
+        )
+    }
+
+    /// The directory of the store
+    pub fn path(&self) -> &Path {
+        self.layout.path()
+    }
+
+    /// The quote configuration of a platform.
+    ///
+    /// The PCK certificate chain is the one for the highest TCB level at or
+    /// below the TCB level of the platform, or the one for any TCB level when
+    /// there is none. The TCB level of the configuration is the TCB level of
+    /// the PCK certificate, and the certification data is the PEM PCK
+    /// certificate chain.
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform.
+    /// * `pce_id` - The PCEID of the platform.
+    /// * `cpu_svn` - The CPUSVN of the platform.
+    /// * `pce_svn` - The PCESVN of the platform.
+    ///
+    /// # Errors
+    /// * [`Error::NotFound`] if there is no PCK certificate chain for the
+    ///   platform.
+    /// * [`Error::Store`] if the directory or file can't be read.
+    /// * [`Error::Contents`] if the PCK certificate chain is invalid.
+    pub fn quote_config(
+        &self,
+        qe_id: &Qe3Id,
+        pce_id: u16,
+        cpu_svn: &CpuSvn,
+        pce_svn: u16,
+    ) -> Result<QuoteConfig, Error> {
+        let mut platform_cpu_svn = [0; COMPONENT_SVN_COUNT];
+        platform_cpu_svn.copy_from_slice(cpu_svn.as_ref());
+        let path = self
+            .layout
+            .pck_certificate_chain_file(qe_id.id(), pce_id, &platform_cpu_svn, pce_svn)
+            .map_err(|err| store_error(self.path(), err))?;
+        let pem = read(&path)?;
+        let contents_error =
+            |reason: &str| Error::Contents(path.display().to_string(), reason.into());
+
+        // `load_pem_chain()` panics on empty input
+        if trim_ascii_whitespace(&pem).is_empty() {
+            return Err(contents_error("empty PCK certificate chain"));
+        }
+        let chain =
+            Certificate::load_pem_chain(&pem).map_err(|err| contents_error(&err.to_string()))?;
+        let leaf = chain
+            .first()
+            .ok_or_else(|| contents_error("empty PCK certificate chain"))?;
+        let tcb = TcbInfo::try_from(leaf).map_err(|err| contents_error(&err.to_string()))?;
+
+        let mut cpu_svn = [0; COMPONENT_SVN_COUNT];
+        for (byte, svn) in cpu_svn.iter_mut().zip(tcb.svns()) {
+            *byte =
+                u8::try_from(*svn).map_err(|_| contents_error("TCB component SVN too large"))?;
+        }
+        let pce_svn =
+            u16::try_from(*tcb.pce_svn()).map_err(|_| contents_error("PCE SVN too large"))?;
+
+        Ok(QuoteConfig::new(CpuSvn::from(cpu_svn), pce_svn, pem))
+    }
+
+    /// The collateral, with the highest TCB evaluation data number, for the
+    /// platforms with `fmspc` and PCK certificates issued by `ca`.
+    ///
+    /// # Errors
+    /// * [`Error::NotFound`] if there is no collateral for the platforms.
+    /// * [`Error::Store`] if the directory or file can't be read.
+    /// * [`Error::Contents`] if the file is not a DER encoded [`Collateral`].
+    pub fn collateral(&self, fmspc: &[u8; FMSPC_SIZE], ca: CaType) -> Result<Collateral, Error> {
+        let latest = self
+            .layout
+            .latest_collateral_file(fmspc, ca)
+            .map_err(|err| store_error(self.path(), err))?;
+        let Some(path) = latest else {
+            return Err(Error::NotFound(format!(
+                "{} for FMSPC {} and {} CA",
+                self.path().display(),
+                hex::encode(fmspc),
+                ca.as_str()
+            )));
+        };
+        let bytes = read(&path)?;
+        Collateral::from_der(&bytes)
+            .map_err(|err| Error::Contents(path.display().to_string(), err.to_string()))
+    }
+
+    /// The signed QvE identity JSON and the PEM issuer chain of its signer.
+    ///
+    /// # Errors
+    /// * [`Error::NotFound`] if either file is missing.
+    /// * [`Error::Store`] if a file can't be read.
+    /// * [`Error::Contents`] if a file is not UTF-8.
+    pub fn qve_identity(&self) -> Result<(String, String), Error> {
+        Ok((
+            read_string(&self.path().join(QVE_IDENTITY_FILE))?,
+            read_string(&self.path().join(QVE_IDENTITY_ISSUER_CHAIN_FILE))?,
+        ))
+    }
+
+    /// The DER encoded Intel SGX Root CA CRL.
+    ///
+    /// # Errors
+    /// * [`Error::NotFound`] if the file is missing.
+    /// * [`Error::Store`] if the file can't be read.
+    pub fn root_ca_crl(&self) -> Result<Vec<u8>, Error> {
+        read(&self.path().join(ROOT_CA_CRL_FILE))
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|err| match err.kind() {
+        ErrorKind::NotFound => Error::NotFound(path.display().to_string()),
+        _ => store_error(path, err),
+    })
+}
+
+fn read_string(path: &Path) -> Result<String, Error> {
+    String::from_utf8(read(path)?)
+        .map_err(|err| Error::Contents(path.display().to_string(), err.to_string()))
+}
+
+fn store_error(path: &Path, err: std::io::Error) -> Error {
+    Error::Store(path.display().to_string(), err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use mc_sgx_dcap_types::test_utils::{TestCa, FMSPC, PCE_SVN, QE_ID, TCB_COMPONENT_SVNS};
+    use tempfile::tempdir;
+    use x509_cert::der::Encode;
+
+    const PCE_ID: u16 = 0;
+
+    fn pck_file_name() -> String {
+        format!("{}-0000.pem", hex::encode_upper(QE_ID))
+    }
+
+    /// The file name of a chain of the platform at a TCB level of `svn` for
+    /// every CPUSVN component and the PCESVN
+    fn tcb_level_file_name(svn: u8) -> String {
+        format!(
+            "{}-0000-{}-{}.pem",
+            hex::encode_upper(QE_ID),
+            hex::encode_upper([svn; 16]),
+            hex::encode_upper(u16::from(svn).to_le_bytes())
+        )
+    }
+
+    /// The quote configuration of the platform at a TCB level of `svn` for
+    /// every CPUSVN component and the PCESVN
+    fn quote_config(store: &QplStore, svn: u8) -> Result<QuoteConfig, Error> {
+        store.quote_config(
+            &QE_ID.into(),
+            PCE_ID,
+            &CpuSvn::from([svn; 16]),
+            u16::from(svn),
+        )
+    }
+
+    #[test]
+    fn quote_config_from_pck_certificate_chain() {
+        let dir = tempdir().unwrap();
+        let ca = TestCa::default();
+        let pem = ca.pck_certificate_chain_pem();
+        fs::write(dir.path().join(pck_file_name()), &pem).unwrap();
+        let store = QplStore::new(dir.path());
+
+        let config = quote_config(&store, 0).unwrap();
+
+        let cpu_svn = TCB_COMPONENT_SVNS.map(|svn| svn as u8);
+        assert_eq!(config.cert_cpu_svn(), &CpuSvn::from(cpu_svn));
+        assert_eq!(config.cert_pce_svn(), PCE_SVN);
+        assert_eq!(config.cert_data(), pem.as_bytes());
+    }
+
+    #[test]
+    fn quote_config_for_unknown_platform() {
+        let dir = tempdir().unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_matches!(
+            quote_config(&store, 0),
+            Err(Error::NotFound(path)) if path.ends_with(&pck_file_name())
+        );
+    }
+
+    #[test]
+    fn quote_config_with_empty_chain() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(pck_file_name()), "\n").unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_matches!(quote_config(&store, 0), Err(Error::Contents(_, _)));
+    }
+
+    #[test]
+    fn quote_config_for_highest_tcb_level_at_or_below_platform() {
+        let dir = tempdir().unwrap();
+        let ca = TestCa::default();
+        let pem = ca.pck_certificate_chain_pem();
+        fs::write(dir.path().join(tcb_level_file_name(0x01)), &pem).unwrap();
+        fs::write(dir.path().join(tcb_level_file_name(0x20)), "junk").unwrap();
+        fs::write(dir.path().join(pck_file_name()), "junk").unwrap();
+        let store = QplStore::new(dir.path());
+
+        let config = quote_config(&store, 0x10).unwrap();
+        assert_eq!(config.cert_data(), pem.as_bytes());
+
+        assert_matches!(
+            quote_config(&store, 0x20),
+            Err(Error::Contents(path, _)) if path.ends_with(&tcb_level_file_name(0x20))
+        );
+    }
+
+    #[test]
+    fn latest_collateral() {
+        let dir = tempdir().unwrap();
+        let ca = TestCa::default();
+        let fmspc = hex::encode(FMSPC);
+        let older = ca
+            .collateral_builder()
+            .tcb_evaluation_data_number(16)
+            .build();
+        let newer = ca.collateral();
+        fs::write(
+            dir.path().join(format!("{fmspc}-processor-16.der")),
+            older.to_der().unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(format!("{fmspc}-processor-17.der")),
+            newer.to_der().unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.path().join(format!("{fmspc}-platform-18.der")), "other").unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_eq!(store.collateral(&FMSPC, CaType::Processor), Ok(newer));
+    }
+
+    #[test]
+    fn missing_collateral() {
+        let dir = tempdir().unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_matches!(
+            store.collateral(&FMSPC, CaType::Platform),
+            Err(Error::NotFound(_))
+        );
+    }
+
+    #[test]
+    fn collateral_in_missing_directory() {
+        let dir = tempdir().unwrap();
+        let store = QplStore::new(dir.path().join("missing"));
+
+        assert_matches!(
+            store.collateral(&FMSPC, CaType::Processor),
+            Err(Error::NotFound(_))
+        );
+    }
+
+    #[test]
+    fn invalid_collateral() {
+        let dir = tempdir().unwrap();
+        let fmspc = hex::encode(FMSPC);
+        fs::write(dir.path().join(format!("{fmspc}-processor-17.der")), "junk").unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_matches!(
+            store.collateral(&FMSPC, CaType::Processor),
+            Err(Error::Contents(_, _))
+        );
+    }
+
+    #[test]
+    fn qve_identity_and_issuer_chain() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(QVE_IDENTITY_FILE), "{\"identity\":1}").unwrap();
+        fs::write(dir.path().join(QVE_IDENTITY_ISSUER_CHAIN_FILE), "chain").unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_eq!(
+            store.qve_identity(),
+            Ok(("{\"identity\":1}".into(), "chain".into()))
+        );
+    }
+
+    #[test]
+    fn qve_identity_without_issuer_chain() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(QVE_IDENTITY_FILE), "{}").unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_matches!(store.qve_identity(), Err(Error::NotFound(_)));
+    }
+
+    #[test]
+    fn root_ca_crl() {
+        let dir = tempdir().unwrap();
+        let crl = TestCa::default()
+            .collateral()
+            .root_ca_crl()
+            .to_der()
+            .unwrap();
+        fs::write(dir.path().join(ROOT_CA_CRL_FILE), &crl).unwrap();
+        let store = QplStore::new(dir.path());
+
+        assert_eq!(store.root_ca_crl(), Ok(crl));
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Appraising a batch of quotes one at a time with a [`QuoteAppraiser`]
+//! versus with a [`BatchAppraiser`].
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use mc_sgx_core_sys_types::sgx_report_body_t;
+use mc_sgx_core_types::ReportBody;
+use mc_sgx_dcap_quoteverify::{BatchAppraiser, CollateralProvider, Error};
+use mc_sgx_dcap_types::{test_utils::TestCa, Collateral, Quote3, QuoteAppraiser};
+use std::time::Duration;
+
+// 2024-06-01T00:00:00Z
+const NOW: Duration = Duration::from_secs(1_717_200_000);
+
+const BATCH_SIZES: [usize; 2] = [16, 256];
+
+/// Provides the same collateral for every quote, as a warm collateral cache
+/// would.
+struct FixedProvider(Collateral);
+
+impl CollateralProvider for FixedProvider {
+    fn collateral<T: AsRef<[u8]>>(&self, _quote: &Quote3<T>) -> Result<Collateral, Error> {
+        Ok(self.0.clone())
+    }
+}
+
+fn quotes(ca: &TestCa, count: usize) -> Vec<Quote3<Vec<u8>>> {
+    (0..count)
+        .map(|svn| {
+            let mut body: sgx_report_body_t = ReportBody::default().into();
+            body.isv_svn = svn as u16;
+            ca.quote(&body.into())
+        })
+        .collect()
+}
+
+fn appraise_batch(c: &mut Criterion) {
+    let ca = TestCa::default();
+    let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+    let provider = FixedProvider(ca.collateral());
+    let mut group = c.benchmark_group("appraise_batch");
+
+    for size in BATCH_SIZES {
+        let quotes = quotes(&ca, size);
+        group.throughput(Throughput::Elements(size as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("quote_appraiser", size),
+            &quotes,
+            |b, quotes| {
+                b.iter(|| {
+                    for quote in quotes {
+                        let collateral = provider.collateral(quote).expect("No collateral");
+                        appraiser
+                            .appraise(quote, &collateral, NOW)
+                            .expect("Failed to appraise quote");
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("batch_appraiser_single_thread", size),
+            &quotes,
+            |b, quotes| {
+                b.iter_batched(
+                    || BatchAppraiser::new(appraiser.clone()).threads(1),
+                    |batch| {
+                        batch
+                            .appraise_all(quotes, &provider, NOW)
+                            .expect("Invalid time")
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("batch_appraiser", size),
+            &quotes,
+            |b, quotes| {
+                b.iter_batched(
+                    || BatchAppraiser::new(appraiser.clone()),
+                    |batch| {
+                        batch
+                            .appraise_all(quotes, &provider, NOW)
+                            .expect("Invalid time")
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, appraise_batch);
+criterion_main!(benches);
@@ -0,0 +1,287 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Collateral retrieval, quote appraisal and the quote verification library
+//! from async code.
+
+use crate::{CollateralProvider, Error, VerificationLibrary};
+use mc_sgx_dcap_types::{
+    Appraisal, AppraisalError, Collateral, Quote3, QuoteAppraiser, TimeSource,
+};
+use mc_sgx_util::BlockingPool;
+use std::sync::Arc;
+
+/// Retrieves collateral, and appraises quotes with it, from async code
+/// without blocking the runtime.
+///
+/// The calls to the [`CollateralProvider`] run on the [`BlockingPool`], which
+/// limits how many run at once and how long they can take. Retrieving
+/// collateral through the QVL(Quote Verification Library) is a round trip to
+/// the PCCS.
+///
+/// Clones share the same provider and pool.
+#[derive(Debug)]
+pub struct AsyncCollateralProvider<P> {
+    provider: Arc<P>,
+    pool: BlockingPool,
+}
+
+impl<P> Clone for AsyncCollateralProvider<P> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: self.provider.clone(),
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl<P: CollateralProvider + Send + Sync + 'static> AsyncCollateralProvider<P> {
+    /// Run the calls to `provider` on `pool`
+    pub fn new(provider: P, pool: BlockingPool) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            pool,
+        }
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Get the collateral to verify `quote`.
+    ///
+    /// # Errors
+    /// * [`Error::Blocking`] if the call times out or fails to run.
+    /// * The error of the wrapped provider.
+    pub async fn collateral<T>(&self, quote: Quote3<T>) -> Result<Collateral, Error>
+    where
+        T: AsRef<[u8]> + Send + 'static,
+    {
+        let provider = self.provider.clone();
+        self.pool.run(move || provider.collateral(&quote)).await?
+    }
+
+    /// Appraise `quote` with its collateral from the wrapped provider.
+    ///
+    /// Both the collateral retrieval and the appraisal run on the pool.
+    ///
+    /// # Arguments
+    /// * `appraiser` - The appraiser with the trust anchor for `quote`.
+    /// * `quote` - The quote to appraise.
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`]. The time is read before
+    ///   retrieving the collateral.
+    ///
+    /// # Errors
+    /// * [`Error::Blocking`] if the call times out or fails to run.
+    /// * [`Error::Appraisal`] if the time can't be read or the quote fails
+    ///   appraisal.
+    /// * The error of the wrapped provider.
+    pub async fn appraise<T, S>(
+        &self,
+        appraiser: &QuoteAppraiser,
+        quote: Quote3<T>,
+        time: S,
+    ) -> Result<Appraisal, Error>
+    where
+        T: AsRef<[u8]> + Send + 'static,
+        S: TimeSource,
+    {
+        let now = time.now().map_err(AppraisalError::from)?;
+        let provider = self.provider.clone();
+        let appraiser = appraiser.clone();
+        self.pool
+            .run(move || {
+                let collateral = provider.collateral(&quote)?;
+                Ok(appraiser.appraise(&quote, &collateral, now)?)
+            })
+            .await?
+    }
+}
+
+/// Calls the quote verification library from async code without blocking the
+/// runtime.
+///
+/// The calls to the [`VerificationLibrary`] run on the [`BlockingPool`], which
+/// limits how many run at once and how long they can take. The first call
+/// loads the QVE(Quote Verification Enclave).
+///
+/// Clones share the same library and pool.
+#[derive(Debug, Clone)]
+pub struct AsyncVerificationLibrary {
+    library: Arc<VerificationLibrary>,
+    pool: BlockingPool,
+}
+
+impl AsyncVerificationLibrary {
+    /// Run the calls to `library` on `pool`
+    pub fn new(library: VerificationLibrary, pool: BlockingPool) -> Self {
+        Self {
+            library: Arc::new(library),
+            pool,
+        }
+    }
+
+    /// The wrapped library
+    pub fn library(&self) -> &VerificationLibrary {
+        &self.library
+    }
+
+    /// The size of the supplemental data of a quote verification
+    ///
+    /// # Errors
+    /// * [`Error::Blocking`] if the call times out or fails to run.
+    /// * [`Error::QuoteLibrary`] if there is any error retrieving the
+    ///   supplemental size from SGX.
+    pub async fn supplemental_data_size(&self) -> Result<usize, Error> {
+        let library = self.library.clone();
+        self.pool
+            .run(move || library.supplemental_data_size())
+            .await?
+    }
+
+    /// The collateral for `quote`, from the quote provider library
+    ///
+    /// # Errors
+    /// * [`Error::Blocking`] if the call times out or fails to run.
+    /// * [`Error::QuoteLibrary`] if the quote provider library fails to
+    ///   provide the collateral, or another [`Error`] if the collateral is
+    ///   malformed.
+    pub async fn collateral<T>(&self, quote: Quote3<T>) -> Result<Collateral, Error>
+    where
+        T: AsRef<[u8]> + Send + 'static,
+    {
+        let library = self.library.clone();
+        self.pool.run(move || library.collateral(&quote)).await?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use core::time::Duration;
+    use mc_sgx_core_types::ReportBody;
+    use mc_sgx_dcap_sys_types::sgx_ql_qv_supplemental_t;
+    use mc_sgx_dcap_types::{test_utils::TestCa, QlError};
+    use mc_sgx_util::BlockingError;
+    use serial_test::serial;
+    use std::{mem, thread};
+    use x509_cert::der::DateTime;
+
+    /// A provider which takes `delay` to return `result`.
+    struct SlowProvider {
+        result: Result<Collateral, Error>,
+        delay: Duration,
+    }
+
+    impl CollateralProvider for SlowProvider {
+        fn collateral<T: AsRef<[u8]>>(&self, _quote: &Quote3<T>) -> Result<Collateral, Error> {
+            thread::sleep(self.delay);
+            self.result.clone()
+        }
+    }
+
+    fn provider(
+        result: Result<Collateral, Error>,
+        pool: BlockingPool,
+    ) -> AsyncCollateralProvider<SlowProvider> {
+        AsyncCollateralProvider::new(
+            SlowProvider {
+                result,
+                delay: Duration::ZERO,
+            },
+            pool,
+        )
+    }
+
+    fn now() -> Duration {
+        DateTime::new(2024, 6, 1, 0, 0, 0)
+            .expect("Invalid date")
+            .unix_duration()
+    }
+
+    #[tokio::test]
+    async fn collateral_from_provider() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let provider = provider(Ok(ca.collateral()), BlockingPool::new(1));
+
+        assert_eq!(provider.collateral(quote).await, Ok(ca.collateral()));
+    }
+
+    #[tokio::test]
+    async fn appraise_quote() {
+        let ca = TestCa::default();
+        let report_body = ReportBody::default();
+        let quote = ca.quote(&report_body);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let provider = provider(Ok(ca.collateral()), BlockingPool::new(1));
+
+        let appraisal = provider
+            .appraise(&appraiser, quote, now())
+            .await
+            .expect("Failed to appraise quote");
+
+        assert_eq!(appraisal.report_body(), &report_body);
+    }
+
+    #[tokio::test]
+    async fn appraise_with_wrong_trust_anchor_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let appraiser = QuoteAppraiser::new(ca.intermediate_certificate().clone());
+        let provider = provider(Ok(ca.collateral()), BlockingPool::new(1));
+
+        let result = provider.appraise(&appraiser, quote, now()).await;
+
+        assert_matches!(result, Err(Error::Appraisal(_)));
+    }
+
+    #[tokio::test]
+    async fn appraise_provider_error() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let error = Error::QuoteLibrary(QlError::NoQuoteCollateralData);
+        let provider = provider(Err(error.clone()), BlockingPool::new(1));
+
+        assert_eq!(
+            provider.appraise(&appraiser, quote, now()).await.err(),
+            Some(error)
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_collateral_times_out() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let timeout = Duration::from_millis(10);
+        let provider = AsyncCollateralProvider::new(
+            SlowProvider {
+                result: Ok(ca.collateral()),
+                delay: Duration::from_millis(200),
+            },
+            BlockingPool::new(1).timeout(timeout),
+        );
+
+        assert_eq!(
+            provider.collateral(quote).await,
+            Err(Error::Blocking(BlockingError::Timeout(timeout)))
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn async_library_supplemental_size() {
+        let library = AsyncVerificationLibrary::new(
+            VerificationLibrary::try_default().expect("Failed to load the library"),
+            BlockingPool::new(1),
+        );
+
+        assert_eq!(
+            library.supplemental_data_size().await,
+            Ok(mem::size_of::<sgx_ql_qv_supplemental_t>())
+        );
+    }
+}
@@ -0,0 +1,628 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Appraisal of many quotes, sharing the work common to their platforms.
+
+use crate::{CollateralKey, CollateralProvider, Error};
+use mc_sgx_dcap_types::{
+    Appraisal, AppraisalError, CertificationData, Collateral, Quote3, QuoteAppraiser, TimeSource,
+    VerifiedCollateral, VerifiedPckChain,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use x509_cert::der::{self, DecodePem, Encode};
+use x509_cert::Certificate;
+
+/// The number of PCK certificate chains a [`BatchAppraiser`] caches by
+/// default.
+const DEFAULT_PCK_CHAIN_CAPACITY: usize = 1024;
+
+/// The SHA-256 hash of the contents of a cache entry
+type ContentHash = [u8; 32];
+
+/// Appraises many quotes, verifying each distinct [`Collateral`] and each
+/// distinct PCK certificate chain only once.
+///
+/// Verifying the collateral, its CRLs, issuer chains, and signed TCB info and
+/// QE identity, is most of the work of appraising a quote. Every quote from
+/// platforms with the same FMSPC and PCK CA shares the same collateral, and
+/// every quote from the same platform shares the same PCK certificate chain.
+///
+/// Both are cached keyed by the hash of their contents, so updated collateral
+/// is verified when first seen. Verified collateral is evicted once it's no
+/// longer valid. PCK certificate chains are evicted, all at once, when the
+/// cache reaches its capacity. A cached PCK certificate chain is checked
+/// against the CRLs of the collateral of each quote, so it's no longer
+/// trusted once revoked.
+///
+/// The appraisals are the same as those of the [`QuoteAppraiser`], including
+/// the errors.
+#[derive(Debug)]
+pub struct BatchAppraiser {
+    appraiser: QuoteAppraiser,
+    threads: usize,
+    pck_chain_capacity: usize,
+    collateral: Mutex<HashMap<ContentHash, Arc<VerifiedCollateral>>>,
+    pck_chains: Mutex<HashMap<ContentHash, Arc<VerifiedPckChain>>>,
+}
+
+/// The PCK certificate chain of a quote, from the cache or parsed from the
+/// quote.
+enum PckChain {
+    Verified(Arc<VerifiedPckChain>),
+    Parsed(ContentHash, Vec<Certificate>),
+}
+
+impl BatchAppraiser {
+    /// Appraise quotes with `appraiser`.
+    ///
+    /// Defaults to as many threads as the available parallelism.
+    pub fn new(appraiser: QuoteAppraiser) -> Self {
+        Self {
+            appraiser,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            pck_chain_capacity: DEFAULT_PCK_CHAIN_CAPACITY,
+            collateral: Mutex::new(HashMap::new()),
+            pck_chains: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Appraise batches with up to `threads` threads.
+    ///
+    /// Values less than 1 are treated as 1.
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Cache up to `capacity` PCK certificate chains, one per platform.
+    ///
+    /// Defaults to 1024.
+    #[must_use]
+    pub fn pck_chain_capacity(mut self, capacity: usize) -> Self {
+        self.pck_chain_capacity = capacity;
+        self
+    }
+
+    /// The wrapped appraiser
+    pub fn appraiser(&self) -> &QuoteAppraiser {
+        &self.appraiser
+    }
+
+    /// Appraise `quote` with `collateral`.
+    ///
+    /// # Arguments
+    /// * `quote` - The quote to appraise.
+    /// * `collateral` - The collateral for the platform of `quote`.
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// [`Error::Appraisal`] describing the first check that failed.
+    pub fn appraise<T: AsRef<[u8]>, S: TimeSource>(
+        &self,
+        quote: &Quote3<T>,
+        collateral: &Collateral,
+        time: S,
+    ) -> Result<Appraisal, Error> {
+        let now = time.now().map_err(AppraisalError::from)?;
+        self.appraise_at(quote, now, || self.verified_collateral(collateral, now))
+    }
+
+    /// Appraise `quotes`, in parallel, with their collateral from `provider`.
+    ///
+    /// The collateral is retrieved from `provider`, and verified, once per
+    /// [`CollateralKey`] in the batch. Quotes whose key can't be determined
+    /// have their collateral retrieved individually. `provider` should still
+    /// be caching, like a [`crate::CachedCollateralProvider`], to avoid a
+    /// round trip per platform model for each batch.
+    ///
+    /// # Arguments
+    /// * `quotes` - The quotes to appraise.
+    /// * `provider` - The provider of the collateral for `quotes`.
+    /// * `time` - The time of the appraisals, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`]. The time is read once, every
+    ///   quote is appraised at the same time.
+    ///
+    /// Returns the result for each quote, in the order of `quotes`.
+    ///
+    /// # Errors
+    /// [`Error::Appraisal`] if the time can't be read from `time`.
+    pub fn appraise_all<T, P, S>(
+        &self,
+        quotes: &[Quote3<T>],
+        provider: &P,
+        time: S,
+    ) -> Result<Vec<Result<Appraisal, Error>>, Error>
+    where
+        T: AsRef<[u8]> + Sync,
+        P: CollateralProvider + Sync,
+        S: TimeSource,
+    {
+        let now = time.now().map_err(AppraisalError::from)?;
+        let batch_collateral = Mutex::new(HashMap::new());
+        let next = AtomicUsize::new(0);
+        let appraise_next = || {
+            let mut results = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(quote) = quotes.get(index) else {
+                    return results;
+                };
+                let result = self.appraise_in_batch(quote, provider, now, &batch_collateral);
+                results.push((index, result));
+            }
+        };
+
+        let mut results = thread::scope(|scope| {
+            let workers = (0..self.threads.min(quotes.len()))
+                .map(|_| scope.spawn(appraise_next))
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|err| panic::resume_unwind(err))
+                })
+                .collect::<Vec<_>>()
+        });
+        results.sort_unstable_by_key(|(index, _)| *index);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+
+    /// Remove all cached collateral and PCK certificate chains.
+    pub fn clear(&self) {
+        self.lock_collateral().clear();
+        self.lock_pck_chains().clear();
+    }
+
+    /// Appraise `quote` at `now`, with the collateral from `batch_collateral`
+    /// or retrieved from `provider`.
+    fn appraise_in_batch<T: AsRef<[u8]>, P: CollateralProvider>(
+        &self,
+        quote: &Quote3<T>,
+        provider: &P,
+        now: Duration,
+        batch_collateral: &Mutex<HashMap<CollateralKey, Arc<VerifiedCollateral>>>,
+    ) -> Result<Appraisal, Error> {
+        let lock = || {
+            batch_collateral
+                .lock()
+                .expect("Batch collateral mutex poisoned")
+        };
+        let Ok(key) = CollateralKey::try_from(quote) else {
+            let collateral = provider.collateral(quote)?;
+            return self.appraise(quote, &collateral, now);
+        };
+        let cached = lock().get(&key).cloned();
+        if let Some(verified) = cached {
+            return self.appraise_at(quote, now, || Ok(verified));
+        }
+
+        let collateral = provider.collateral(quote)?;
+        self.appraise_at(quote, now, || {
+            let verified = self.verified_collateral(&collateral, now)?;
+            lock().insert(key, verified.clone());
+            Ok(verified)
+        })
+    }
+
+    /// Appraise `quote` at `now` with the collateral from
+    /// `verified_collateral`.
+    ///
+    /// The checks are done in the same order as the [`QuoteAppraiser`], so
+    /// the PCK certificate chain is parsed before the collateral is verified.
+    fn appraise_at<T: AsRef<[u8]>>(
+        &self,
+        quote: &Quote3<T>,
+        now: Duration,
+        verified_collateral: impl FnOnce() -> Result<Arc<VerifiedCollateral>, Error>,
+    ) -> Result<Appraisal, Error> {
+        let pck_chain = self.pck_chain(quote)?;
+        let collateral = verified_collateral()?;
+        let pck_chain = match pck_chain {
+            PckChain::Verified(chain) => chain,
+            PckChain::Parsed(hash, chain) => {
+                self.verified_pck_chain(hash, &chain, &collateral, now)?
+            }
+        };
+        Ok(self
+            .appraiser
+            .appraise_with_verified_pck_chain(quote, &pck_chain, &collateral, now)?)
+    }
+
+    /// The verified `collateral`, from the cache or verified at `now`.
+    ///
+    /// Cached collateral which is no longer valid at `now` fails appraisal
+    /// the same as verifying it again would.
+    fn verified_collateral(
+        &self,
+        collateral: &Collateral,
+        now: Duration,
+    ) -> Result<Arc<VerifiedCollateral>, Error> {
+        let hash = collateral_hash(collateral)?;
+        if let Some(verified) = self.lock_collateral().get(&hash) {
+            return Ok(verified.clone());
+        }
+
+        // Verified without holding the lock, concurrent misses for the same
+        // collateral each verify it rather than wait on each other.
+        let verified = Arc::new(self.appraiser.verify_collateral(collateral, now)?);
+        let mut cache = self.lock_collateral();
+        cache.retain(|_, cached| cached.is_valid_at(now));
+        cache.insert(hash, verified.clone());
+        Ok(verified)
+    }
+
+    /// The PCK certificate chain, leaf first, from the certification data of
+    /// `quote`.
+    ///
+    /// The chain is only parsed when it's not in the cache, it's verified and
+    /// cached by [`BatchAppraiser::verified_pck_chain()`] once the collateral
+    /// for it is known.
+    fn pck_chain<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<PckChain, Error> {
+        let signature_data = quote.signature_data();
+        let CertificationData::PckCertificateChain(pem_chain) = signature_data.certification_data()
+        else {
+            return Err(AppraisalError::UnsupportedCertificationData.into());
+        };
+        let hash: ContentHash =
+            Sha256::digest(signature_data.certification_data().raw_data()).into();
+        if let Some(chain) = self.lock_pck_chains().get(&hash) {
+            return Ok(PckChain::Verified(chain.clone()));
+        }
+
+        let chain = pem_chain
+            .into_iter()
+            .map(Certificate::from_pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(der_error)?;
+        Ok(PckChain::Parsed(hash, chain))
+    }
+
+    /// Verify `chain`, with the hash `hash`, against `collateral` at `now`
+    /// and cache it.
+    fn verified_pck_chain(
+        &self,
+        hash: ContentHash,
+        chain: &[Certificate],
+        collateral: &VerifiedCollateral,
+        now: Duration,
+    ) -> Result<Arc<VerifiedPckChain>, Error> {
+        let verified = Arc::new(self.appraiser.verify_pck_chain(chain, collateral, now)?);
+        let mut cache = self.lock_pck_chains();
+        if cache.len() >= self.pck_chain_capacity {
+            cache.clear();
+        }
+        cache.insert(hash, verified.clone());
+        Ok(verified)
+    }
+
+    fn lock_collateral(&self) -> MutexGuard<HashMap<ContentHash, Arc<VerifiedCollateral>>> {
+        self.collateral
+            .lock()
+            .expect("Collateral cache mutex poisoned")
+    }
+
+    fn lock_pck_chains(&self) -> MutexGuard<HashMap<ContentHash, Arc<VerifiedPckChain>>> {
+        self.pck_chains
+            .lock()
+            .expect("PCK chain cache mutex poisoned")
+    }
+}
+
+/// The hash of the contents of `collateral`.
+///
+/// Each part is length prefixed so the boundaries between parts are part of
+/// the hash.
+fn collateral_hash(collateral: &Collateral) -> Result<ContentHash, Error> {
+    let mut hasher = Sha256::new();
+    let mut update = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    let chains = [
+        collateral.pck_crl_issuer_chain(),
+        collateral.tcb_issuer_chain(),
+        collateral.qe_identity_issuer_chain(),
+    ];
+    for crl in [collateral.root_ca_crl(), collateral.pck_crl()] {
+        update(&crl.to_der().map_err(der_error)?);
+    }
+    for chain in chains {
+        update(&(chain.len() as u64).to_le_bytes());
+        for certificate in chain {
+            update(&certificate.to_der().map_err(der_error)?);
+        }
+    }
+    update(collateral.tcb_info().as_bytes());
+    update(collateral.qe_identity().as_bytes());
+    Ok(hasher.finalize().into())
+}
+
+fn der_error(err: der::Error) -> Error {
+    AppraisalError::from(err).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use core::str::FromStr;
+    use mc_sgx_core_sys_types::sgx_report_body_t;
+    use mc_sgx_core_types::ReportBody;
+    use mc_sgx_dcap_types::{test_utils::TestCa, MonotonicFloor, TimeError};
+    use p256::ecdsa::SigningKey;
+    use x509_cert::der::DateTime;
+    use x509_cert::name::Name;
+
+    /// Provides the same collateral for every quote.
+    struct FixedProvider(Collateral);
+
+    impl CollateralProvider for FixedProvider {
+        fn collateral<T: AsRef<[u8]>>(&self, _quote: &Quote3<T>) -> Result<Collateral, Error> {
+            Ok(self.0.clone())
+        }
+    }
+
+    /// Provides the same collateral for every quote and counts the calls to
+    /// it.
+    struct CountingProvider {
+        collateral: Collateral,
+        calls: AtomicUsize,
+    }
+
+    impl CollateralProvider for CountingProvider {
+        fn collateral<T: AsRef<[u8]>>(&self, _quote: &Quote3<T>) -> Result<Collateral, Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(self.collateral.clone())
+        }
+    }
+
+    /// A CA sharing the root and intermediate CA of [`TestCa::default()`],
+    /// with a different PCK certificate.
+    fn other_platform_ca() -> TestCa {
+        let key = SigningKey::from_bytes(&[0x33; 32].into()).expect("Invalid key");
+        let name =
+            Name::from_str("CN=Other SGX PCK Certificate,O=MobileCoin").expect("Invalid name");
+        TestCa::builder().pck(key, name).build()
+    }
+
+    fn date(year: u16, month: u8, day: u8) -> Duration {
+        DateTime::new(year, month, day, 0, 0, 0)
+            .expect("Invalid date")
+            .unix_duration()
+    }
+
+    fn now() -> Duration {
+        date(2024, 6, 1)
+    }
+
+    fn report_body(isv_svn: u16) -> ReportBody {
+        let mut body: sgx_report_body_t = ReportBody::default().into();
+        body.isv_svn = isv_svn;
+        body.into()
+    }
+
+    fn hardware_quote() -> Quote3<Vec<u8>> {
+        let quote = include_bytes!("../../types/data/tests/hw_quote.dat");
+        Quote3::try_from(quote.to_vec()).expect("Failed to parse quote")
+    }
+
+    #[test]
+    fn appraise_matches_quote_appraiser() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(1));
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let batch = BatchAppraiser::new(appraiser.clone());
+
+        assert_eq!(
+            batch.appraise(&quote, &ca.collateral(), now()),
+            appraiser
+                .appraise(&quote, &ca.collateral(), now())
+                .map_err(Error::from)
+        );
+    }
+
+    #[test]
+    fn appraise_all_in_order() {
+        let ca = TestCa::default();
+        let quotes = (0..20)
+            .map(|svn| ca.quote(&report_body(svn)))
+            .collect::<Vec<_>>();
+        let batch =
+            BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone())).threads(4);
+
+        let results = batch
+            .appraise_all(&quotes, &FixedProvider(ca.collateral()), now())
+            .expect("Failed to read the time");
+
+        assert_eq!(results.len(), quotes.len());
+        for (svn, result) in results.into_iter().enumerate() {
+            let appraisal = result.expect("Failed to appraise quote");
+            assert_eq!(appraisal.report_body(), &report_body(svn as u16));
+        }
+        assert_eq!(batch.lock_collateral().len(), 1);
+        assert_eq!(batch.lock_pck_chains().len(), 1);
+    }
+
+    #[test]
+    fn appraise_all_retrieves_collateral_once_per_key() {
+        let ca = TestCa::default();
+        let other = other_platform_ca();
+        let quotes = (0..10)
+            .map(|svn| ca.quote(&report_body(svn)))
+            .chain((0..10).map(|svn| other.quote(&report_body(svn))))
+            .collect::<Vec<_>>();
+        let provider = CountingProvider {
+            collateral: ca.collateral(),
+            calls: AtomicUsize::new(0),
+        };
+        let batch =
+            BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone())).threads(1);
+
+        let results = batch
+            .appraise_all(&quotes, &provider, now())
+            .expect("Failed to read the time");
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(provider.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(batch.lock_pck_chains().len(), 2);
+
+        batch
+            .appraise_all(&quotes, &provider, now())
+            .expect("Failed to read the time");
+        assert_eq!(provider.calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn appraise_all_reports_each_failure() {
+        let ca = TestCa::default();
+        let quotes = vec![
+            ca.quote(&report_body(1)),
+            hardware_quote(),
+            ca.quote(&report_body(2)),
+        ];
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+
+        let results = batch
+            .appraise_all(&quotes, &FixedProvider(ca.collateral()), now())
+            .expect("Failed to read the time");
+
+        assert_matches!(results[0], Ok(_));
+        assert_eq!(
+            results[1],
+            Err(Error::Appraisal(AppraisalError::UntrustedCertificateChain))
+        );
+        assert_matches!(results[2], Ok(_));
+    }
+
+    #[test]
+    fn appraise_all_with_no_quotes() {
+        let ca = TestCa::default();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+
+        let results = batch
+            .appraise_all::<Vec<u8>, _, _>(&[], &FixedProvider(ca.collateral()), now())
+            .expect("Failed to read the time");
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn appraise_all_fails_when_time_goes_backwards() {
+        let ca = TestCa::default();
+        let quotes = vec![ca.quote(&report_body(1))];
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+        let floor = now() + Duration::from_secs(1);
+        let time = MonotonicFloor::new(now()).initial_floor(floor);
+
+        assert_eq!(
+            batch.appraise_all(&quotes, &FixedProvider(ca.collateral()), &time),
+            Err(Error::Appraisal(AppraisalError::Time(
+                TimeError::Backwards { floor, now: now() }
+            )))
+        );
+    }
+
+    #[test]
+    fn cached_collateral_expires() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(1));
+        let collateral = ca
+            .collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, 2, 1))
+            .build();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+
+        assert_matches!(
+            batch.appraise(&quote, &collateral, date(2024, 1, 15)),
+            Ok(_)
+        );
+        assert_eq!(
+            batch.appraise(&quote, &collateral, date(2024, 3, 1)),
+            Err(Error::Appraisal(AppraisalError::CrlValidity))
+        );
+    }
+
+    #[test]
+    fn expired_collateral_evicted_for_new_collateral() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(1));
+        let expiring = ca
+            .collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, 2, 1))
+            .build();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+
+        assert_matches!(batch.appraise(&quote, &expiring, date(2024, 1, 15)), Ok(_));
+        assert_matches!(batch.appraise(&quote, &ca.collateral(), now()), Ok(_));
+
+        let cache = batch.lock_collateral();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.values().all(|verified| verified.is_valid_at(now())));
+    }
+
+    #[test]
+    fn cached_pck_chain_checked_for_revocation() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(1));
+        let revoking = ca.collateral_builder().revoke_pck().build();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+
+        assert_matches!(batch.appraise(&quote, &ca.collateral(), now()), Ok(_));
+        assert_eq!(batch.lock_pck_chains().len(), 1);
+        assert_eq!(
+            batch.appraise(&quote, &revoking, now()),
+            Err(Error::Appraisal(AppraisalError::CertificateRevoked))
+        );
+    }
+
+    #[test]
+    fn untrusted_pck_chain_not_cached() {
+        let ca = TestCa::default();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+
+        let _ = batch.appraise(&hardware_quote(), &ca.collateral(), now());
+
+        assert!(batch.lock_pck_chains().is_empty());
+    }
+
+    #[test]
+    fn pck_chains_cleared_at_capacity() {
+        let ca = TestCa::default();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()))
+            .pck_chain_capacity(1);
+
+        let other_quote = other_platform_ca().quote(&report_body(1));
+        assert_matches!(batch.appraise(&other_quote, &ca.collateral(), now()), Ok(_));
+        assert_matches!(
+            batch.appraise(&ca.quote(&report_body(1)), &ca.collateral(), now()),
+            Ok(_)
+        );
+
+        assert_eq!(batch.lock_pck_chains().len(), 1);
+    }
+
+    #[test]
+    fn clear_empties_caches() {
+        let ca = TestCa::default();
+        let batch = BatchAppraiser::new(QuoteAppraiser::new(ca.root_certificate().clone()));
+        assert_matches!(
+            batch.appraise(&ca.quote(&report_body(1)), &ca.collateral(), now()),
+            Ok(_)
+        );
+
+        batch.clear();
+
+        assert!(batch.lock_collateral().is_empty());
+        assert!(batch.lock_pck_chains().is_empty());
+    }
+}
@@ -59,49 +59,53 @@ impl<T: AsRef<[u8]>> Collateral for Quote3<T> {
     fn collateral(&self) -> Result<mc_sgx_dcap_types::Collateral, Error> {
         PathInitializer::ensure_initialized()?;
         LoadPolicyInitializer::ensure_initialized()?;
+        get_collateral(self.as_ref())
+    }
+}
 
-        let quote_slice = self.as_ref();
-        let mut sgx_collateral = core::ptr::null_mut();
-        let mut collateral_size = 0;
+/// Get the collateral for the quote in `quote_slice` with the current paths
+/// and load policy.
+pub(crate) fn get_collateral(quote_slice: &[u8]) -> Result<mc_sgx_dcap_types::Collateral, Error> {
+    let mut sgx_collateral = core::ptr::null_mut();
+    let mut collateral_size = 0;
 
-        // SAFETY: `tee_qv_get_collateral()` is a C function that is inherently unsafe.
-        // The `sgx_collateral` and `collateral_size` will be checked after this
-        // call to ensure they are valid.
-        unsafe {
-            mc_sgx_dcap_quoteverify_sys::tee_qv_get_collateral(
-                quote_slice.as_ptr(),
-                quote_slice.len() as u32,
-                &mut sgx_collateral,
-                &mut collateral_size,
-            )
-        }
-        .into_result()?;
+    // SAFETY: `tee_qv_get_collateral()` is a C function that is inherently unsafe.
+    // The `sgx_collateral` and `collateral_size` will be checked after this
+    // call to ensure they are valid.
+    unsafe {
+        mc_sgx_dcap_quoteverify_sys::tee_qv_get_collateral(
+            quote_slice.as_ptr(),
+            quote_slice.len() as u32,
+            &mut sgx_collateral,
+            &mut collateral_size,
+        )
+    }
+    .into_result()?;
 
-        // It shouldn't happen that `tee_qv_get_collateral()` returns success
-        // while keeping the collateral as a null pointer, but we defend against
-        // it since we don't control the C implementation.
-        if sgx_collateral.is_null() {
-            return Err(Error::QuoteLibrary(QlError::NoQuoteCollateralData));
-        }
+    // It shouldn't happen that `tee_qv_get_collateral()` returns success
+    // while keeping the collateral as a null pointer, but we defend against
+    // it since we don't control the C implementation.
+    if sgx_collateral.is_null() {
+        return Err(Error::QuoteLibrary(QlError::NoQuoteCollateralData));
+    }
 
-        // Wrap up the pointer to ensure drop will be called to free the pointer
-        // if a later error occurs.
-        let collateral_pointer = CollateralPointer {
-            collateral: sgx_collateral as *mut _,
-        };
+    // Wrap up the pointer to ensure drop will be called to free the pointer
+    // if a later error occurs.
+    let collateral_pointer = CollateralPointer {
+        collateral: sgx_collateral as *mut _,
+    };
 
-        // The `collateral_size` is the size of the base structure plus the size
-        // of all the bytes that the members point to. Thus it should always be
-        // greater than `MIN_SGX_COLLATERAL_SIZE`.
-        if collateral_size < MIN_SGX_COLLATERAL_SIZE {
-            return Err(Error::CollateralSizeTooSmall(
-                MIN_SGX_COLLATERAL_SIZE,
-                collateral_size,
-            ));
-        }
+    // The `collateral_size` is the size of the base structure plus the size
+    // of all the bytes that the members point to. Thus it should always be
+    // greater than `MIN_SGX_COLLATERAL_SIZE`.
+    if collateral_size < MIN_SGX_COLLATERAL_SIZE {
+        return Err(Error::CollateralSizeTooSmall(
+            MIN_SGX_COLLATERAL_SIZE,
+            collateral_size,
+        ));
+    }
 
-        let collateral = mc_sgx_dcap_types::Collateral::try_from(collateral_pointer.as_ref())?;
+    let collateral = mc_sgx_dcap_types::Collateral::try_from(collateral_pointer.as_ref())?;
 
-        Ok(collateral)
-    }
+    Ok(collateral)
 }
@@ -3,12 +3,28 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs, missing_debug_implementations)]
 
+#[cfg(feature = "async")]
+mod asynchronous;
+mod batch;
 mod collateral;
+mod library;
+mod logging;
+mod provider;
 mod quote_enclave;
 mod verify;
 
+#[cfg(feature = "async")]
+pub use asynchronous::{AsyncCollateralProvider, AsyncVerificationLibrary};
+pub use batch::BatchAppraiser;
 pub use collateral::Collateral;
-use mc_sgx_dcap_types::{CollateralError, QlError};
+pub use library::{VerificationLibrary, VerificationLibraryBuilder};
+pub use logging::{forward_logs, stop_forwarding_logs};
+use mc_sgx_dcap_types::{AppraisalError, CollateralError, QlError, TcbError, TimeError};
+use mc_sgx_util::{Classify, ErrorClass};
+pub use provider::{
+    CachedCollateralProvider, CollateralKey, CollateralProvider, DirectoryCollateralProvider,
+    QvlCollateralProvider, RetryingCollateralProvider,
+};
 pub use quote_enclave::{LoadPolicyInitializer, PathInitializer};
 pub use verify::supplemental_data_size;
 
@@ -32,6 +48,29 @@ pub enum Error {
     CollateralSizeTooSmall(u32, u32),
     /// Error converting C data to rust Collateral type {0}
     CollateralConversion(CollateralError),
+    /// Error reading the TCB of the PCK certificate {0}
+    Tcb(TcbError),
+    /// Error reading the contents of the collateral: {0}
+    CollateralContents(String),
+    /// Error accessing the collateral store at {0}: {1}
+    CollateralStore(String, String),
+    /// No collateral for {0}
+    CollateralNotFound(CollateralKey),
+    /// A library handle is already in use, only one can exist at a time
+    LibraryInUse,
+    /// Failed to load the quote provider library: {0}
+    QuoteProviderLibrary(String),
+    /// A quote provider library path was set earlier and can't be unset
+    QuoteProviderLibraryPathSet,
+    /// The library handle was built without a quote provider library
+    NoQuoteProviderLibrary,
+    /// The quote failed appraisal: {0}
+    Appraisal(AppraisalError),
+    /// Failed to get the current time: {0}
+    Time(TimeError),
+    /// Error running a quote verification library call from async code: {0}
+    #[cfg(feature = "async")]
+    Blocking(mc_sgx_util::BlockingError),
 }
 
 impl From<QlError> for Error {
@@ -45,3 +84,58 @@ impl From<CollateralError> for Error {
         Self::CollateralConversion(src)
     }
 }
+
+impl From<TcbError> for Error {
+    fn from(src: TcbError) -> Self {
+        Self::Tcb(src)
+    }
+}
+
+impl From<AppraisalError> for Error {
+    fn from(src: AppraisalError) -> Self {
+        Self::Appraisal(src)
+    }
+}
+
+impl From<TimeError> for Error {
+    fn from(src: TimeError) -> Self {
+        Self::Time(src)
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<mc_sgx_util::BlockingError> for Error {
+    fn from(src: mc_sgx_util::BlockingError) -> Self {
+        Self::Blocking(src)
+    }
+}
+
+impl Classify for Error {
+    /// Only errors from the quote verification library, or timeouts, can be
+    /// transient, see [`QlError::class()`](Classify::class).
+    fn class(&self) -> ErrorClass {
+        match self {
+            Self::QuoteLibrary(err) => err.class(),
+            #[cfg(feature = "async")]
+            Self::Blocking(err) => err.class(),
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yare::parameterized;
+
+    #[parameterized(
+        busy = { Error::QuoteLibrary(QlError::Busy), ErrorClass::Transient },
+        network_failure = { Error::QuoteLibrary(QlError::NetworkFailure), ErrorClass::Transient },
+        platform_unknown = { Error::QuoteLibrary(QlError::PlatformUnknown), ErrorClass::Permanent },
+        library_in_use = { Error::LibraryInUse, ErrorClass::Permanent },
+        collateral_contents = { Error::CollateralContents("bad".into()), ErrorClass::Permanent },
+    )]
+    fn error_class(error: Error, expected: ErrorClass) {
+        assert_eq!(error.class(), expected);
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! An explicit handle to the configured quote verification library.
+//!
+//! The quote verification library keeps its paths and load policy in process
+//! global state. [`VerificationLibrary`] owns that configuration while it is
+//! alive, so the configuration can be changed at runtime.
+
+use crate::{collateral, logging, verify, Error, LoadPolicyInitializer, PathInitializer};
+use mc_sgx_dcap_types::{Collateral, LogLevel, Quote3, RequestPolicy};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether a [`VerificationLibrary`] is alive. There can only be one as the
+/// configuration of the quote verification library is process wide.
+static IN_USE: AtomicBool = AtomicBool::new(false);
+
+/// A handle to the quote verification library configured with a
+/// [`VerificationLibraryBuilder`].
+///
+/// Only one handle can exist at a time. Dropping the handle releases the
+/// configuration.
+///
+/// While a handle exists, [`PathInitializer`] and [`LoadPolicyInitializer`]
+/// report being initialized, and the free verification functions, like
+/// [`supplemental_data_size()`](crate::supplemental_data_size), use the
+/// configuration of the handle.
+#[derive(Debug)]
+pub struct VerificationLibrary {
+    policy: RequestPolicy,
+    quote_provider_library: QuoteProviderLibrary,
+    log_level: Option<LogLevel>,
+}
+
+impl VerificationLibrary {
+    /// A builder for the quote verification library, starting from the
+    /// default paths and load policy
+    pub fn builder() -> VerificationLibraryBuilder {
+        VerificationLibraryBuilder::default()
+    }
+
+    /// The quote verification library with the default paths and load policy.
+    ///
+    /// # Errors
+    /// See [`VerificationLibraryBuilder::build()`].
+    pub fn try_default() -> Result<Self, Error> {
+        Self::builder().build()
+    }
+
+    /// The load policy of the quote verification enclave
+    pub fn policy(&self) -> RequestPolicy {
+        self.policy
+    }
+
+    /// The level the QPL(Quote Provider Library) log messages are forwarded
+    /// at, if they are forwarded
+    pub fn log_level(&self) -> Option<LogLevel> {
+        self.log_level
+    }
+
+    /// The size of the supplemental data of a quote verification
+    ///
+    /// # Errors
+    /// [`Error::QuoteLibrary`] if there is any error retrieving the
+    /// supplemental size from SGX.
+    pub fn supplemental_data_size(&self) -> Result<usize, Error> {
+        verify::get_supplemental_data_size()
+    }
+
+    /// The collateral for `quote`, from the quote provider library
+    ///
+    /// # Errors
+    /// * [`Error::NoQuoteProviderLibrary`] if the library was built
+    ///   [without a QPL](VerificationLibraryBuilder::without_quote_provider_library).
+    /// * [`Error::QuoteLibrary`] if the quote provider library fails to
+    ///   provide the collateral, or another [`Error`] if the collateral is
+    ///   malformed.
+    pub fn collateral<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<Collateral, Error> {
+        if self.quote_provider_library == QuoteProviderLibrary::Disabled {
+            return Err(Error::NoQuoteProviderLibrary);
+        }
+        collateral::get_collateral(quote.as_ref())
+    }
+}
+
+impl Drop for VerificationLibrary {
+    fn drop(&mut self) {
+        if self.log_level.is_some() {
+            // Nothing can be done about a failure to clear the callback, the
+            // messages will continue to be forwarded.
+            let _ = logging::set_logging_callback(
+                self.quote_provider_library.path(),
+                None,
+                LogLevel::Error,
+            );
+        }
+        PathInitializer::reset();
+        LoadPolicyInitializer::reset();
+        IN_USE.store(false, Ordering::Release);
+    }
+}
+
+/// Builder for a [`VerificationLibrary`]
+///
+/// The defaults are the quote verification enclave embedded in the quote
+/// verification library, the quote provider library (QPL) found in the
+/// system path, the [`RequestPolicy::Persistent`] load policy, and no log
+/// forwarding.
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct VerificationLibraryBuilder {
+    quote_verification_enclave: Option<PathBuf>,
+    quote_provider_library: QuoteProviderLibrary,
+    policy: RequestPolicy,
+    log_level: Option<LogLevel>,
+}
+
+/// The QPL(Quote Provider Library) of a [`VerificationLibraryBuilder`]
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+enum QuoteProviderLibrary {
+    /// The QPL the quote verification library loads by default
+    #[default]
+    Default,
+    /// The QPL at the path
+    Path(PathBuf),
+    /// No QPL
+    Disabled,
+}
+
+impl QuoteProviderLibrary {
+    /// The path to set in the quote verification library, if any
+    fn configured_path(&self) -> Option<&Path> {
+        match self {
+            Self::Path(path) => Some(path),
+            Self::Default | Self::Disabled => None,
+        }
+    }
+
+    /// The QPL at the configured path, or the one the quote verification
+    /// library loads by default
+    fn path(&self) -> &Path {
+        self.configured_path()
+            .unwrap_or_else(|| Path::new(logging::QUOTE_PROVIDER_LIBRARY))
+    }
+}
+
+impl VerificationLibraryBuilder {
+    /// The full file path to the QVE(Quote Verification Enclave)
+    #[must_use]
+    pub fn quote_verification_enclave(mut self, path: impl AsRef<Path>) -> Self {
+        self.quote_verification_enclave = Some(path.as_ref().into());
+        self
+    }
+
+    /// The full file path to the QPL(Quote Provider Library)
+    #[must_use]
+    pub fn quote_provider_library(mut self, path: impl AsRef<Path>) -> Self {
+        self.quote_provider_library = QuoteProviderLibrary::Path(path.as_ref().into());
+        self
+    }
+
+    /// Don't use a QPL(Quote Provider Library).
+    ///
+    /// Quotes can only be verified with collateral from elsewhere,
+    /// [`VerificationLibrary::collateral()`] fails, and there are no QPL log
+    /// messages to forward.
+    ///
+    /// The quote verification library can't unset a QPL path, so
+    /// [`VerificationLibraryBuilder::build()`] fails if one was set earlier in
+    /// the process.
+    #[must_use]
+    pub fn without_quote_provider_library(mut self) -> Self {
+        self.quote_provider_library = QuoteProviderLibrary::Disabled;
+        self
+    }
+
+    /// The load policy of the quote verification enclave
+    #[must_use]
+    pub fn policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Forward the QPL(Quote Provider Library) log messages, up to `level`, to
+    /// the [`log`] crate while the [`VerificationLibrary`] exists.
+    ///
+    /// See [`forward_logs()`](crate::forward_logs).
+    #[must_use]
+    pub fn forward_logs(mut self, level: LogLevel) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Configure the quote verification library.
+    ///
+    /// Any configuration from [`PathInitializer`] or
+    /// [`LoadPolicyInitializer`] is replaced.
+    ///
+    /// # Errors
+    /// * [`Error::LibraryInUse`] if another [`VerificationLibrary`] exists.
+    /// * [`Error::QuoteProviderLibraryPathSet`] if no QPL path is provided
+    ///   and one was set earlier in the process.
+    /// * [`Error::PathStringConversion`], [`Error::PathDoesNotExist`],
+    ///   [`Error::PathLengthTooLong`] or [`Error::QuoteLibrary`] if one of
+    ///   the paths can't be set.
+    /// * [`Error::QuoteLibrary`] if the load policy can't be set.
+    /// * [`Error::QuoteProviderLibrary`] or [`Error::QuoteLibrary`] if the log
+    ///   forwarding can't be set.
+    pub fn build(self) -> Result<VerificationLibrary, Error> {
+        if IN_USE
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(Error::LibraryInUse);
+        }
+
+        let log_level = match self.quote_provider_library {
+            QuoteProviderLibrary::Disabled => None,
+            _ => self.log_level,
+        };
+        let configured = PathInitializer::replace_paths(
+            self.quote_verification_enclave.as_ref(),
+            self.quote_provider_library.configured_path(),
+        )
+        .and_then(|_| LoadPolicyInitializer::replace_policy(self.policy))
+        .and_then(|_| {
+            log_level.map_or(Ok(()), |level| {
+                logging::set_logging_callback(
+                    self.quote_provider_library.path(),
+                    Some(logging::forward),
+                    level,
+                )
+            })
+        });
+        if let Err(err) = configured {
+            PathInitializer::reset();
+            LoadPolicyInitializer::reset();
+            IN_USE.store(false, Ordering::Release);
+            return Err(err);
+        }
+
+        Ok(VerificationLibrary {
+            policy: self.policy,
+            quote_provider_library: self.quote_provider_library,
+            log_level,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::mem;
+    use mc_sgx_dcap_sys_types::sgx_ql_qv_supplemental_t;
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    #[test]
+    #[serial]
+    fn default_library_supplemental_size() {
+        let library = VerificationLibrary::try_default().unwrap();
+
+        assert_eq!(library.policy(), RequestPolicy::Persistent);
+        assert_eq!(library.log_level(), None);
+        assert_eq!(
+            library.supplemental_data_size(),
+            Ok(mem::size_of::<sgx_ql_qv_supplemental_t>())
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn only_one_library_at_a_time() {
+        let library = VerificationLibrary::try_default().unwrap();
+
+        assert!(matches!(
+            VerificationLibrary::try_default(),
+            Err(Error::LibraryInUse)
+        ));
+
+        drop(library);
+        assert!(VerificationLibrary::try_default().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn library_holds_configuration() {
+        let library = VerificationLibrary::builder()
+            .policy(RequestPolicy::Ephemeral)
+            .build()
+            .unwrap();
+
+        assert_eq!(PathInitializer::try_default(), Err(Error::PathsInitialized));
+        assert_eq!(
+            LoadPolicyInitializer::try_default(),
+            Err(Error::LoadPolicyInitialized)
+        );
+
+        drop(library);
+        assert_eq!(PathInitializer::try_default(), Ok(()));
+        PathInitializer::reset();
+    }
+
+    #[test]
+    #[serial]
+    fn bad_path_fails_and_can_be_retried() {
+        let dir = tempdir().unwrap();
+
+        let result = VerificationLibrary::builder()
+            .quote_verification_enclave(dir.path().join("missing"))
+            .build();
+
+        assert!(matches!(result, Err(Error::PathDoesNotExist(_))));
+        assert!(VerificationLibrary::try_default().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn without_quote_provider_library_has_no_collateral() {
+        let hw_quote = include_bytes!("../../types/data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).unwrap();
+        let library = VerificationLibrary::builder()
+            .without_quote_provider_library()
+            .forward_logs(LogLevel::Info)
+            .build()
+            .unwrap();
+
+        assert_eq!(library.log_level(), None);
+        assert_eq!(
+            library.collateral(&quote),
+            Err(Error::NoQuoteProviderLibrary)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Forwarding of the quote provider library (QPL) log messages to the [`log`]
+//! crate.
+//!
+//! The quote verification library doesn't log, the diagnostics of collateral
+//! retrieval, like PCCS failures, come from the QPL. The messages are logged
+//! with the `sgx_dcap_quoteprov` target. Applications using `tracing` can
+//! collect them with the `tracing-log` crate.
+
+use crate::Error;
+use libloading::Library;
+use mc_sgx_dcap_sys_types::{quote3_error_t, sgx_ql_log_level_t, sgx_ql_logging_callback_t};
+use mc_sgx_dcap_types::{log_record, LogLevel};
+use mc_sgx_util::ResultInto;
+use std::{ffi::CStr, mem, os::raw::c_char, path::Path};
+
+const TARGET: &str = "sgx_dcap_quoteprov";
+
+/// The QPL the quote verification library loads when no path is set
+pub(crate) const QUOTE_PROVIDER_LIBRARY: &str = "libdcap_quoteprov.so.1";
+
+const SET_LOGGING_CALLBACK: &[u8] = b"sgx_ql_set_logging_callback\0";
+
+type SetLoggingCallback =
+    unsafe extern "C" fn(sgx_ql_logging_callback_t, sgx_ql_log_level_t) -> quote3_error_t;
+
+/// Forward the QPL log messages, up to `level`, to the [`log`] crate.
+///
+/// This is process wide and applies to the QPL found in the system library
+/// path. It replaces any previous log forwarding, including that of a
+/// [`VerificationLibrary`](crate::VerificationLibrary).
+///
+/// # Errors
+/// * [`Error::QuoteProviderLibrary`] if the QPL can't be loaded.
+/// * [`Error::QuoteLibrary`] if the QPL fails to set the callback.
+pub fn forward_logs(level: LogLevel) -> Result<(), Error> {
+    set_logging_callback(QUOTE_PROVIDER_LIBRARY, Some(forward), level)
+}
+
+/// Stop forwarding the QPL log messages
+///
+/// # Errors
+/// * [`Error::QuoteProviderLibrary`] if the QPL can't be loaded.
+/// * [`Error::QuoteLibrary`] if the QPL fails to clear the callback.
+pub fn stop_forwarding_logs() -> Result<(), Error> {
+    set_logging_callback(QUOTE_PROVIDER_LIBRARY, None, LogLevel::Error)
+}
+
+/// Set the logging callback of the QPL at `path`.
+///
+/// `path` is loaded the same way the quote verification library loads it, so
+/// both get the same instance of the QPL.
+pub(crate) fn set_logging_callback(
+    path: impl AsRef<Path>,
+    callback: sgx_ql_logging_callback_t,
+    level: LogLevel,
+) -> Result<(), Error> {
+    let library = unsafe { Library::new(path.as_ref().as_os_str()) }
+        .map_err(|e| Error::QuoteProviderLibrary(e.to_string()))?;
+    {
+        let set = unsafe { library.get::<SetLoggingCallback>(SET_LOGGING_CALLBACK) }
+            .map_err(|e| Error::QuoteProviderLibrary(e.to_string()))?;
+        unsafe { set(callback, level.into()) }.into_result()?;
+    }
+    // The quote verification library loads the QPL on first use, unloading it
+    // before then would lose the callback.
+    mem::forget(library);
+    Ok(())
+}
+
+/// The callback given to the QPL
+///
+/// # Safety
+/// `message` must be null or a null terminated string
+pub(crate) unsafe extern "C" fn forward(level: sgx_ql_log_level_t, message: *const c_char) {
+    if message.is_null() {
+        return;
+    }
+    let (level, message) = log_record(level, CStr::from_ptr(message));
+    log::log!(target: TARGET, level, "{message}");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn forward_ignores_null_message() {
+        unsafe { forward(sgx_ql_log_level_t::SGX_QL_LOG_ERROR, std::ptr::null()) };
+    }
+
+    #[test]
+    fn missing_library_fails() {
+        let dir = tempdir().unwrap();
+
+        let result = set_logging_callback(dir.path().join("missing.so"), None, LogLevel::Info);
+
+        assert_matches!(result, Err(Error::QuoteProviderLibrary(_)));
+    }
+
+    #[test]
+    fn invalid_library_fails() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("libdcap_quoteprov.so.1");
+        fs::write(&path, "not a library").unwrap();
+
+        let result = set_logging_callback(&path, None, LogLevel::Info);
+
+        assert_matches!(result, Err(Error::QuoteProviderLibrary(_)));
+    }
+}
@@ -0,0 +1,904 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Providers of the [`Collateral`] needed to verify a [`Quote3`].
+//!
+//! Getting collateral from the quote verification library (QVL) is a round
+//! trip to the PCCS, or the Intel PCS, for every quote. The
+//! [`CachedCollateralProvider`] keeps the collateral of each platform in
+//! memory, and optionally on disk, until the earliest `nextUpdate` of its
+//! CRLs, TCB info and QE identity. The [`RetryingCollateralProvider`] retries
+//! transient failures, like an unreachable PCCS.
+
+use crate::{Collateral as _, Error};
+use core::fmt::{Display, Formatter};
+use mc_sgx_dcap_collateral::StoreLayout;
+use mc_sgx_dcap_types::{
+    CaType, Collateral, FmspcTcbInfo, QeIdentity, Quote3, SystemClock, TcbInfo, TimeSource,
+    FMSPC_SIZE,
+};
+use mc_sgx_util::RetryPolicy;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use x509_cert::der::{Decode, Encode};
+
+/// A source of the [`Collateral`] for a [`Quote3`].
+pub trait CollateralProvider {
+    /// Get the collateral to verify `quote`.
+    fn collateral<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<Collateral, Error>;
+}
+
+/// Provides collateral from the quote verification library (QVL).
+///
+/// The QVL retrieves the collateral using the PCCS configured in
+/// `/etc/sgx_default_qcnl.conf`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QvlCollateralProvider;
+
+impl CollateralProvider for QvlCollateralProvider {
+    fn collateral<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<Collateral, Error> {
+        quote.collateral()
+    }
+}
+
+/// Identifies the platforms which share the same [`Collateral`].
+///
+/// The TCB info is specific to the FMSPC of a platform and the PCK CRL is
+/// specific to the CA which issued the platform's PCK certificate.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct CollateralKey {
+    fmspc: [u8; FMSPC_SIZE],
+    ca: CaType,
+}
+
+impl CollateralKey {
+    /// Create a new [`CollateralKey`].
+    pub fn new(fmspc: [u8; FMSPC_SIZE], ca: CaType) -> Self {
+        Self { fmspc, ca }
+    }
+
+    /// The FMSPC of the platform
+    pub fn fmspc(&self) -> &[u8; FMSPC_SIZE] {
+        &self.fmspc
+    }
+
+    /// The CA which issued the platform's PCK certificate
+    pub fn ca(&self) -> CaType {
+        self.ca
+    }
+
+    /// The lowercase hex encoding of the FMSPC
+    fn fmspc_to_hex(&self) -> String {
+        self.fmspc
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+impl Display for CollateralKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "FMSPC {} and {} CA",
+            self.fmspc_to_hex(),
+            self.ca.as_str()
+        )
+    }
+}
+
+impl<T: AsRef<[u8]>> TryFrom<&Quote3<T>> for CollateralKey {
+    type Error = Error;
+
+    /// The key of the platform which signed `quote`.
+    ///
+    /// Only quotes with a PCK certificate chain in the certification data are
+    /// supported.
+    fn try_from(quote: &Quote3<T>) -> Result<Self, Self::Error> {
+        let tcb_info = TcbInfo::try_from(quote)?;
+        let ca = CaType::try_from(quote)?;
+        Ok(Self::new(*tcb_info.fmspc(), ca))
+    }
+}
+
+/// How long a [`Collateral`] can be used before it needs to be refreshed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Freshness {
+    tcb_evaluation_data_number: u32,
+    next_update: Duration,
+}
+
+impl TryFrom<&Collateral> for Freshness {
+    type Error = Error;
+
+    /// The TCB evaluation data number and the earliest `nextUpdate` of the
+    /// CRLs, TCB info and QE identity of `collateral`.
+    ///
+    /// The signatures of the TCB info and QE identity are checked against the
+    /// leaf of their issuer chains, the chains themselves are *not* verified.
+    /// That is left to the appraisal of the quote.
+    fn try_from(collateral: &Collateral) -> Result<Self, Self::Error> {
+        let contents_error =
+            |err: mc_sgx_dcap_types::AppraisalError| Error::CollateralContents(err.to_string());
+        let tcb_signer = collateral
+            .tcb_issuer_chain()
+            .first()
+            .ok_or_else(|| Error::CollateralContents("empty TCB issuer chain".into()))?;
+        let tcb_info = FmspcTcbInfo::from_signed_json(collateral.tcb_info(), tcb_signer)
+            .map_err(contents_error)?;
+        let qe_identity_signer = collateral
+            .qe_identity_issuer_chain()
+            .first()
+            .ok_or_else(|| Error::CollateralContents("empty QE identity issuer chain".into()))?;
+        let qe_identity =
+            QeIdentity::from_signed_json(collateral.qe_identity(), qe_identity_signer)
+                .map_err(contents_error)?;
+
+        let next_update = [collateral.root_ca_crl(), collateral.pck_crl()]
+            .into_iter()
+            .filter_map(|crl| crl.tbs_cert_list.next_update)
+            .map(|time| time.to_unix_duration())
+            .chain([
+                tcb_info.next_update().unix_duration(),
+                qe_identity.next_update().unix_duration(),
+            ])
+            .min()
+            .expect("TCB info and QE identity always have a next update");
+
+        Ok(Self {
+            tcb_evaluation_data_number: tcb_info.tcb_evaluation_data_number(),
+            next_update,
+        })
+    }
+}
+
+/// Provides collateral from DER encoded [`Collateral`] files in a local
+/// directory.
+///
+/// This is meant for air-gapped deployments, where the collateral is
+/// retrieved elsewhere and copied onto the machine. The files are the
+/// collateral files of the [`StoreLayout`], named
+/// `<fmspc>-<ca>-<tcb evaluation data number>.der`, for example
+/// `00906ed50000-processor-17.der`. When there are multiple files for a
+/// platform, the one with the highest TCB evaluation data number is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryCollateralProvider {
+    layout: StoreLayout,
+}
+
+impl DirectoryCollateralProvider {
+    /// Create a new [`DirectoryCollateralProvider`] for the directory at
+    /// `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            layout: StoreLayout::new(path),
+        }
+    }
+
+    /// The directory of the collateral files
+    pub fn path(&self) -> &Path {
+        self.layout.path()
+    }
+
+    /// Load the collateral, with the highest TCB evaluation data number, for
+    /// `key`.
+    ///
+    /// Returns `None` when there is no collateral for `key`, or the directory
+    /// does not exist.
+    ///
+    /// # Errors
+    /// * [`Error::CollateralStore`] if the directory or a file can not be read.
+    /// * [`Error::CollateralConversion`] if the file is not a DER encoded
+    ///   [`Collateral`].
+    pub fn load(&self, key: &CollateralKey) -> Result<Option<Collateral>, Error> {
+        let latest = self
+            .layout
+            .latest_collateral_file(key.fmspc(), key.ca())
+            .map_err(|err| store_error(self.path(), err))?;
+        let Some(path) = latest else {
+            return Ok(None);
+        };
+        let bytes = fs::read(&path).map_err(|err| store_error(&path, err))?;
+        let collateral =
+            Collateral::from_der(&bytes).map_err(mc_sgx_dcap_types::CollateralError::from)?;
+        Ok(Some(collateral))
+    }
+
+    /// Store `collateral` as the collateral for `key`.
+    ///
+    /// The file name uses the TCB evaluation data number of the collateral's
+    /// TCB info, replacing any existing file with the same number. The
+    /// directory is created if it does not exist.
+    ///
+    /// # Errors
+    /// * [`Error::CollateralContents`] if the TCB info or QE identity can not
+    ///   be read.
+    /// * [`Error::CollateralStore`] if the file can not be written.
+    pub fn store(&self, key: &CollateralKey, collateral: &Collateral) -> Result<(), Error> {
+        let freshness = Freshness::try_from(collateral)?;
+        self.store_with_number(key, collateral, freshness.tcb_evaluation_data_number)
+    }
+
+    fn store_with_number(
+        &self,
+        key: &CollateralKey,
+        collateral: &Collateral,
+        tcb_evaluation_data_number: u32,
+    ) -> Result<(), Error> {
+        let bytes = collateral
+            .to_der()
+            .map_err(mc_sgx_dcap_types::CollateralError::from)?;
+        fs::create_dir_all(self.path()).map_err(|err| store_error(self.path(), err))?;
+
+        let path = self
+            .layout
+            .collateral_file(key.fmspc(), key.ca(), tcb_evaluation_data_number);
+
+        // Write to a temporary file first so that a concurrent `load()` never
+        // sees a partially written file.
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, bytes).map_err(|err| store_error(&temp_path, err))?;
+        fs::rename(&temp_path, &path).map_err(|err| store_error(&path, err))
+    }
+}
+
+impl CollateralProvider for DirectoryCollateralProvider {
+    fn collateral<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<Collateral, Error> {
+        let key = CollateralKey::try_from(quote)?;
+        self.load(&key)?.ok_or(Error::CollateralNotFound(key))
+    }
+}
+
+fn store_error(path: &Path, err: std::io::Error) -> Error {
+    Error::CollateralStore(path.display().to_string(), err.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheEntry {
+    collateral: Collateral,
+    freshness: Freshness,
+}
+
+impl CacheEntry {
+    fn new(collateral: Collateral) -> Result<Self, Error> {
+        let freshness = Freshness::try_from(&collateral)?;
+        Ok(Self {
+            collateral,
+            freshness,
+        })
+    }
+
+    /// Is the collateral still usable at `time`.
+    fn is_fresh_at(&self, time: Duration) -> bool {
+        time < self.freshness.next_update
+    }
+}
+
+/// A layered cache of the [`Collateral`] from another [`CollateralProvider`].
+///
+/// Collateral is looked up in memory, then in an optional
+/// [`DirectoryCollateralProvider`], and finally retrieved from the wrapped
+/// provider. Collateral is cached per [`CollateralKey`], with the disk cache
+/// keeping a file per TCB evaluation data number.
+///
+/// Cached collateral is refreshed once the earliest `nextUpdate` of its CRLs,
+/// TCB info and QE identity is within the refresh margin. If the refresh
+/// fails, the cached collateral continues to be used until that `nextUpdate`.
+#[derive(Debug)]
+pub struct CachedCollateralProvider<P> {
+    provider: P,
+    store: Option<DirectoryCollateralProvider>,
+    refresh_margin: Duration,
+    entries: Mutex<HashMap<CollateralKey, CacheEntry>>,
+}
+
+impl<P: CollateralProvider> CachedCollateralProvider<P> {
+    /// Create an in memory cache of the collateral from `provider`.
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            store: None,
+            refresh_margin: Duration::ZERO,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Persist the cached collateral in `store`.
+    ///
+    /// Collateral in `store` is used on startup, and is shared with any other
+    /// process using the same directory.
+    #[must_use]
+    pub fn disk_store(mut self, store: DirectoryCollateralProvider) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Refresh cached collateral `margin` before its `nextUpdate`.
+    ///
+    /// Defaults to refreshing at the `nextUpdate`.
+    #[must_use]
+    pub fn refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Get the collateral for `quote` at the time from `time`.
+    ///
+    /// Failures to read or write the disk store are logged and otherwise
+    /// ignored, a disk store which can't be read is treated as a cache miss.
+    ///
+    /// # Arguments
+    /// * `quote` - The quote to get the collateral for.
+    /// * `time` - The current time, as the duration since the UNIX epoch or
+    ///   from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// * [`Error::Time`] if the time can't be read from `time`.
+    /// * [`Error::Tcb`] if the FMSPC or CA of the quote can not be determined.
+    /// * The error of the wrapped provider when there is no cached collateral
+    ///   which is still valid at the current time.
+    pub fn collateral_at<T: AsRef<[u8]>, S: TimeSource>(
+        &self,
+        quote: &Quote3<T>,
+        time: S,
+    ) -> Result<Collateral, Error> {
+        let now = time.now()?;
+        let key = CollateralKey::try_from(quote)?;
+        let refresh_time = now.saturating_add(self.refresh_margin);
+
+        let cached = self.cached(&key, refresh_time);
+        if let Some(entry) = cached
+            .as_ref()
+            .filter(|entry| entry.is_fresh_at(refresh_time))
+        {
+            return Ok(entry.collateral.clone());
+        }
+
+        let entry = match self.provider.collateral(quote).and_then(CacheEntry::new) {
+            Ok(entry) => entry,
+            Err(err) => {
+                return match cached {
+                    Some(entry) if entry.is_fresh_at(now) => Ok(entry.collateral),
+                    _ => Err(err),
+                };
+            }
+        };
+
+        if let Some(store) = &self.store {
+            if let Err(err) = store.store_with_number(
+                &key,
+                &entry.collateral,
+                entry.freshness.tcb_evaluation_data_number,
+            ) {
+                log::warn!("Failed to store the collateral for {key}: {err}");
+            }
+        }
+        let collateral = entry.collateral.clone();
+        self.entries
+            .lock()
+            .expect("Collateral cache lock poisoned")
+            .insert(key, entry);
+        Ok(collateral)
+    }
+
+    /// The cached collateral for `key`.
+    ///
+    /// The disk store is only consulted when the in memory collateral is
+    /// missing or needs refreshing at `refresh_time`. This allows picking up
+    /// collateral refreshed by other processes. The in memory cache isn't
+    /// locked while reading the disk store.
+    fn cached(&self, key: &CollateralKey, refresh_time: Duration) -> Option<CacheEntry> {
+        let in_memory = self
+            .entries
+            .lock()
+            .expect("Collateral cache lock poisoned")
+            .get(key)
+            .cloned();
+        if in_memory
+            .as_ref()
+            .map_or(false, |entry| entry.is_fresh_at(refresh_time))
+        {
+            return in_memory;
+        }
+
+        let Some(store) = &self.store else {
+            return in_memory;
+        };
+        let on_disk = match store
+            .load(key)
+            .and_then(|collateral| collateral.map(CacheEntry::new).transpose())
+        {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return in_memory,
+            Err(err) => {
+                log::warn!("Ignoring the stored collateral for {key}: {err}");
+                return in_memory;
+            }
+        };
+
+        // Another caller may have updated the entry while the store was read.
+        let mut entries = self.entries.lock().expect("Collateral cache lock poisoned");
+        match entries.get(key) {
+            Some(entry) if entry.freshness.next_update >= on_disk.freshness.next_update => {
+                Some(entry.clone())
+            }
+            _ => {
+                entries.insert(*key, on_disk.clone());
+                Some(on_disk)
+            }
+        }
+    }
+}
+
+impl<P: CollateralProvider> CollateralProvider for CachedCollateralProvider<P> {
+    fn collateral<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<Collateral, Error> {
+        self.collateral_at(quote, SystemClock)
+    }
+}
+
+/// Retries the transient failures of another [`CollateralProvider`].
+///
+/// Wrapping a [`CachedCollateralProvider`] retries only the retrievals, while
+/// wrapping the provider of a [`CachedCollateralProvider`] lets it fall back
+/// to cached collateral once the retries are exhausted.
+#[derive(Debug, Clone)]
+pub struct RetryingCollateralProvider<P> {
+    provider: P,
+    policy: RetryPolicy,
+}
+
+impl<P: CollateralProvider> RetryingCollateralProvider<P> {
+    /// Retry the transient failures of `provider` per `policy`.
+    pub fn new(provider: P, policy: RetryPolicy) -> Self {
+        Self { provider, policy }
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// The retry policy
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+}
+
+impl<P: CollateralProvider> CollateralProvider for RetryingCollateralProvider<P> {
+    fn collateral<T: AsRef<[u8]>>(&self, quote: &Quote3<T>) -> Result<Collateral, Error> {
+        self.policy.retry(|| self.provider.collateral(quote))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use core::cell::{Cell, RefCell};
+    use mc_sgx_core_types::ReportBody;
+    use mc_sgx_dcap_types::test_utils::{TestCa, FMSPC, TCB_EVALUATION_DATA_NUMBER};
+    use mc_sgx_dcap_types::QlError;
+    use tempfile::tempdir;
+    use x509_cert::der::DateTime;
+    use yare::parameterized;
+
+    /// A provider which returns a fixed result and counts the calls to it.
+    struct TestProvider {
+        result: RefCell<Result<Collateral, Error>>,
+        calls: Cell<usize>,
+    }
+
+    impl TestProvider {
+        fn new(result: Result<Collateral, Error>) -> Self {
+            Self {
+                result: RefCell::new(result),
+                calls: Cell::new(0),
+            }
+        }
+
+        fn set_result(&self, result: Result<Collateral, Error>) {
+            *self.result.borrow_mut() = result;
+        }
+    }
+
+    impl CollateralProvider for TestProvider {
+        fn collateral<T: AsRef<[u8]>>(&self, _quote: &Quote3<T>) -> Result<Collateral, Error> {
+            self.calls.set(self.calls.get() + 1);
+            self.result.borrow().clone()
+        }
+    }
+
+    fn date(year: u16, month: u8, day: u8) -> DateTime {
+        DateTime::new(year, month, day, 0, 0, 0).expect("Invalid date")
+    }
+
+    /// Collateral valid from 2024-01-01 until the 1st of `month` in 2024.
+    fn collateral_until(ca: &TestCa, month: u8, tcb_evaluation_data_number: u32) -> Collateral {
+        ca.collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, month, 1))
+            .tcb_evaluation_data_number(tcb_evaluation_data_number)
+            .build()
+    }
+
+    fn key() -> CollateralKey {
+        CollateralKey::new(FMSPC, CaType::Processor)
+    }
+
+    #[test]
+    fn key_from_quote() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+
+        assert_eq!(CollateralKey::try_from(&quote), Ok(key()));
+    }
+
+    #[test]
+    fn key_from_hardware_quote() {
+        let hw_quote = include_bytes!("../../types/data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+
+        assert_eq!(
+            CollateralKey::try_from(&quote),
+            Ok(CollateralKey::new(
+                [0x00, 0x90, 0x6E, 0xD5, 0x00, 0x00],
+                CaType::Processor
+            ))
+        );
+    }
+
+    #[parameterized(
+        processor = { CaType::Processor, "FMSPC 00906ea10000 and processor CA" },
+        platform = { CaType::Platform, "FMSPC 00906ea10000 and platform CA" },
+    )]
+    fn key_display(ca: CaType, expected: &str) {
+        assert_eq!(CollateralKey::new(FMSPC, ca).to_string(), expected);
+    }
+
+    #[test]
+    fn freshness_is_earliest_next_update() {
+        let ca = TestCa::default();
+        let collateral = collateral_until(&ca, 3, 12);
+
+        assert_eq!(
+            Freshness::try_from(&collateral),
+            Ok(Freshness {
+                tcb_evaluation_data_number: 12,
+                next_update: date(2024, 3, 1).unix_duration(),
+            })
+        );
+    }
+
+    #[test]
+    fn freshness_of_placeholder_collateral_fails() {
+        let collateral_der = include_bytes!("../../types/data/tests/collateral.der");
+        let collateral = Collateral::from_der(collateral_der).expect("Failed to decode");
+
+        assert_matches!(
+            Freshness::try_from(&collateral),
+            Err(Error::CollateralContents(_))
+        );
+    }
+
+    #[test]
+    fn directory_store_and_load() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path().join("collateral"));
+        let collateral = TestCa::default().collateral();
+
+        store.store(&key(), &collateral).expect("Failed to store");
+
+        let file_name = format!("00906ea10000-processor-{TCB_EVALUATION_DATA_NUMBER}.der");
+        assert!(store.path().join(file_name).is_file());
+        assert_eq!(store.load(&key()), Ok(Some(collateral)));
+    }
+
+    #[test]
+    fn directory_load_uses_highest_tcb_evaluation_data_number() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path());
+        let ca = TestCa::default();
+        let newest = collateral_until(&ca, 3, 10);
+        for (month, number) in [(2, 2), (3, 10), (4, 9)] {
+            let collateral = collateral_until(&ca, month, number);
+            store.store(&key(), &collateral).expect("Failed to store");
+        }
+
+        assert_eq!(store.load(&key()), Ok(Some(newest)));
+    }
+
+    #[test]
+    fn directory_load_ignores_other_files() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path());
+        let collateral = TestCa::default().collateral();
+        store.store(&key(), &collateral).expect("Failed to store");
+        let other_files = [
+            "00906ea10000-platform-99.der",
+            "00906ea10001-processor-99.der",
+            "00906ea10000-processor-99.tmp",
+            "00906ea10000-processor-latest.der",
+        ];
+        for file in other_files {
+            fs::write(dir.path().join(file), b"not collateral").expect("Failed to write");
+        }
+
+        assert_eq!(store.load(&key()), Ok(Some(collateral)));
+    }
+
+    #[test]
+    fn directory_load_missing_directory() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path().join("missing"));
+
+        assert_eq!(store.load(&key()), Ok(None));
+    }
+
+    #[test]
+    fn directory_load_invalid_file() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path());
+        fs::write(dir.path().join("00906ea10000-processor-1.der"), b"bad").expect("Failed");
+
+        assert_matches!(store.load(&key()), Err(Error::CollateralConversion(_)));
+    }
+
+    #[test]
+    fn directory_provider_for_quote() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path());
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        store.store(&key(), &collateral).expect("Failed to store");
+        let quote = ca.quote(&ReportBody::default());
+
+        assert_eq!(store.collateral(&quote), Ok(collateral));
+    }
+
+    #[test]
+    fn directory_provider_without_collateral() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path());
+        let quote = TestCa::default().quote(&ReportBody::default());
+
+        assert_eq!(
+            store.collateral(&quote),
+            Err(Error::CollateralNotFound(key()))
+        );
+    }
+
+    #[test]
+    fn cache_with_time_before_floor_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(ca.collateral())));
+        let now = date(2024, 2, 1).unix_duration();
+        let floor = now + Duration::from_secs(1);
+        let time = mc_sgx_dcap_types::MonotonicFloor::new(now).initial_floor(floor);
+
+        assert_eq!(
+            cache.collateral_at(&quote, &time),
+            Err(Error::Time(mc_sgx_dcap_types::TimeError::Backwards {
+                floor,
+                now
+            }))
+        );
+        assert_eq!(cache.provider().calls.get(), 0);
+    }
+
+    #[test]
+    fn cache_reuses_collateral_until_next_update() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let first = collateral_until(&ca, 3, 1);
+        let second = collateral_until(&ca, 5, 2);
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(first.clone())));
+
+        let february = date(2024, 2, 1).unix_duration();
+        assert_eq!(cache.collateral_at(&quote, february), Ok(first.clone()));
+        cache.provider().set_result(Ok(second.clone()));
+        assert_eq!(cache.collateral_at(&quote, february), Ok(first));
+        assert_eq!(cache.provider().calls.get(), 1);
+
+        let march = date(2024, 3, 1).unix_duration();
+        assert_eq!(cache.collateral_at(&quote, march), Ok(second.clone()));
+        assert_eq!(cache.collateral_at(&quote, march), Ok(second));
+        assert_eq!(cache.provider().calls.get(), 2);
+    }
+
+    #[test]
+    fn cache_refreshes_within_margin() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let collateral = collateral_until(&ca, 3, 1);
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(collateral)))
+            .refresh_margin(Duration::from_secs(60 * 60 * 24 * 7));
+
+        let early_february = date(2024, 2, 1).unix_duration();
+        cache
+            .collateral_at(&quote, early_february)
+            .expect("Failed to get collateral");
+        cache
+            .collateral_at(&quote, early_february)
+            .expect("Failed to get collateral");
+        assert_eq!(cache.provider().calls.get(), 1);
+
+        let late_february = date(2024, 2, 25).unix_duration();
+        cache
+            .collateral_at(&quote, late_february)
+            .expect("Failed to get collateral");
+        assert_eq!(cache.provider().calls.get(), 2);
+    }
+
+    #[test]
+    fn cache_uses_valid_collateral_when_refresh_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let collateral = collateral_until(&ca, 3, 1);
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(collateral.clone())))
+            .refresh_margin(Duration::from_secs(60 * 60 * 24 * 7));
+        let error = Error::QuoteLibrary(QlError::NetworkFailure);
+
+        let early_february = date(2024, 2, 1).unix_duration();
+        cache
+            .collateral_at(&quote, early_february)
+            .expect("Failed to get collateral");
+        cache.provider().set_result(Err(error.clone()));
+
+        let late_february = date(2024, 2, 25).unix_duration();
+        assert_eq!(cache.collateral_at(&quote, late_february), Ok(collateral));
+
+        let march = date(2024, 3, 1).unix_duration();
+        assert_eq!(cache.collateral_at(&quote, march), Err(error));
+        assert_eq!(cache.provider().calls.get(), 3);
+    }
+
+    #[test]
+    fn cache_fails_for_invalid_collateral() {
+        let collateral_der = include_bytes!("../../types/data/tests/collateral.der");
+        let collateral = Collateral::from_der(collateral_der).expect("Failed to decode");
+        let quote = TestCa::default().quote(&ReportBody::default());
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(collateral)));
+
+        assert_matches!(
+            cache.collateral_at(&quote, date(2024, 2, 1).unix_duration()),
+            Err(Error::CollateralContents(_))
+        );
+    }
+
+    #[test]
+    fn cache_persists_to_disk_store() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let collateral = collateral_until(&ca, 3, 4);
+        let february = date(2024, 2, 1).unix_duration();
+
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(collateral.clone())))
+            .disk_store(DirectoryCollateralProvider::new(dir.path()));
+        cache
+            .collateral_at(&quote, february)
+            .expect("Failed to get collateral");
+        assert!(dir.path().join("00906ea10000-processor-4.der").is_file());
+
+        // A new cache, as after a restart, uses the stored collateral without
+        // going to the provider.
+        let error = Error::QuoteLibrary(QlError::NetworkFailure);
+        let restarted = CachedCollateralProvider::new(TestProvider::new(Err(error)))
+            .disk_store(DirectoryCollateralProvider::new(dir.path()));
+        assert_eq!(restarted.collateral_at(&quote, february), Ok(collateral));
+        assert_eq!(restarted.provider().calls.get(), 0);
+    }
+
+    #[test]
+    fn cache_picks_up_collateral_refreshed_on_disk() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let store = DirectoryCollateralProvider::new(dir.path());
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let first = collateral_until(&ca, 3, 1);
+        let refreshed = collateral_until(&ca, 5, 2);
+
+        let cache =
+            CachedCollateralProvider::new(TestProvider::new(Ok(first))).disk_store(store.clone());
+        cache
+            .collateral_at(&quote, date(2024, 2, 1).unix_duration())
+            .expect("Failed to get collateral");
+
+        // Another process refreshes the collateral on disk
+        store.store(&key(), &refreshed).expect("Failed to store");
+
+        let march = date(2024, 3, 1).unix_duration();
+        assert_eq!(cache.collateral_at(&quote, march), Ok(refreshed));
+        assert_eq!(cache.provider().calls.get(), 1);
+    }
+
+    #[test]
+    fn cache_ignores_unreadable_disk_store() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        fs::write(dir.path().join("00906ea10000-processor-9.der"), b"bad").expect("Failed");
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let collateral = collateral_until(&ca, 3, 4);
+
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(collateral.clone())))
+            .disk_store(DirectoryCollateralProvider::new(dir.path()));
+
+        let february = date(2024, 2, 1).unix_duration();
+        assert_eq!(cache.collateral_at(&quote, february), Ok(collateral));
+        assert_eq!(cache.provider().calls.get(), 1);
+    }
+
+    #[test]
+    fn cache_ignores_unwritable_disk_store() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let file = dir.path().join("collateral");
+        fs::write(&file, b"not a directory").expect("Failed to write");
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let collateral = collateral_until(&ca, 3, 4);
+
+        let cache = CachedCollateralProvider::new(TestProvider::new(Ok(collateral.clone())))
+            .disk_store(DirectoryCollateralProvider::new(file));
+
+        let february = date(2024, 2, 1).unix_duration();
+        assert_eq!(
+            cache.collateral_at(&quote, february),
+            Ok(collateral.clone())
+        );
+        assert_eq!(cache.collateral_at(&quote, february), Ok(collateral));
+        assert_eq!(cache.provider().calls.get(), 1);
+    }
+
+    fn immediate_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::default()
+            .max_attempts(max_attempts)
+            .initial_delay(Duration::ZERO)
+    }
+
+    #[test]
+    fn retrying_provider_retries_transient_errors() {
+        let quote = TestCa::default().quote(&ReportBody::default());
+        let error = Error::QuoteLibrary(QlError::ServiceUnavailable);
+        let provider = RetryingCollateralProvider::new(
+            TestProvider::new(Err(error.clone())),
+            immediate_retries(4),
+        );
+
+        assert_eq!(provider.collateral(&quote), Err(error));
+        assert_eq!(provider.provider().calls.get(), 4);
+    }
+
+    #[test]
+    fn retrying_provider_stops_on_permanent_error() {
+        let quote = TestCa::default().quote(&ReportBody::default());
+        let error = Error::QuoteLibrary(QlError::PlatformUnknown);
+        let provider = RetryingCollateralProvider::new(
+            TestProvider::new(Err(error.clone())),
+            immediate_retries(4),
+        );
+
+        assert_eq!(provider.collateral(&quote), Err(error));
+        assert_eq!(provider.provider().calls.get(), 1);
+    }
+
+    #[test]
+    fn retrying_provider_returns_collateral() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&ReportBody::default());
+        let collateral = ca.collateral();
+        let provider = RetryingCollateralProvider::new(
+            TestProvider::new(Ok(collateral.clone())),
+            immediate_retries(4),
+        );
+
+        assert_eq!(provider.collateral(&quote), Ok(collateral));
+        assert_eq!(provider.provider().calls.get(), 1);
+    }
+}
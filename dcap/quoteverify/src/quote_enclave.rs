@@ -12,7 +12,15 @@ use mc_sgx_dcap_quoteverify_types::PathKind;
 use mc_sgx_dcap_types::RequestPolicy;
 use mc_sgx_util::ResultInto;
 use once_cell::sync::Lazy;
-use std::{ffi::CString, os::unix::ffi::OsStrExt, path::Path, sync::Mutex};
+use std::{
+    ffi::CString,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
 
 // Using the value from SGX to validate inputs and provide better error
 // messages.
@@ -32,11 +40,19 @@ pub type Result<T> = core::result::Result<T, Error>;
 /// If a consumer of this crate does not explicitly initialize the paths, then
 /// they will be defaulted on the first call to an SGX function that needs the
 /// paths set.
+///
+/// [`VerificationLibrary`](crate::VerificationLibrary) is an explicit handle to the paths and load
+/// policy, which can be reconfigured at runtime.
 #[derive(Debug)]
 pub struct PathInitializer;
 
 static PATH_INITIALIZER: Lazy<Mutex<Option<PathInitializer>>> = Lazy::new(|| Mutex::new(None));
 
+/// Whether a quote provider library path has been set. The quote verification
+/// library has no way to unset it, it stays in use for the life of the
+/// process.
+static QUOTE_PROVIDER_LIBRARY_SET: AtomicBool = AtomicBool::new(false);
+
 impl PathInitializer {
     /// Try to initialize the paths to the default for the system
     ///
@@ -84,9 +100,7 @@ impl PathInitializer {
             quote_verification_enclave.map_or(Ok(()), |path| {
                 Self::set_path(PathKind::QuoteVerificationEnclave, path)
             })?;
-            quote_provider_library.map_or(Ok(()), |path| {
-                Self::set_path(PathKind::QuoteProviderLibrary, path)
-            })?;
+            quote_provider_library.map_or(Ok(()), Self::set_quote_provider_library)?;
             *value = Some(PathInitializer);
             Ok(())
         } else {
@@ -110,6 +124,46 @@ impl PathInitializer {
         }
     }
 
+    /// Set the paths, replacing any prior initialization.
+    ///
+    /// The paths are left uninitialized on failure. A quote provider library
+    /// path set earlier can't be replaced with `None`, see
+    /// [`Error::QuoteProviderLibraryPathSet`].
+    pub(crate) fn replace_paths<P1, P2>(
+        quote_verification_enclave: Option<P1>,
+        quote_provider_library: Option<P2>,
+    ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        let mut value = PATH_INITIALIZER.lock().expect("Mutex has been poisoned");
+        *value = None;
+        if quote_provider_library.is_none() && QUOTE_PROVIDER_LIBRARY_SET.load(Ordering::Acquire) {
+            return Err(Error::QuoteProviderLibraryPathSet);
+        }
+        quote_verification_enclave.map_or(Ok(()), |path| {
+            Self::set_path(PathKind::QuoteVerificationEnclave, path)
+        })?;
+        quote_provider_library.map_or(Ok(()), Self::set_quote_provider_library)?;
+        *value = Some(PathInitializer);
+        Ok(())
+    }
+
+    /// Mark the paths as uninitialized, allowing them to be initialized again.
+    pub(crate) fn reset() {
+        let mut value = PATH_INITIALIZER.lock().expect("Mutex has been poisoned");
+        *value = None;
+    }
+
+    /// Set the path of the QPL(Quote Provider Library), which stays set for
+    /// the life of the process
+    fn set_quote_provider_library<P: AsRef<Path>>(path: P) -> Result<()> {
+        Self::set_path(PathKind::QuoteProviderLibrary, path)?;
+        QUOTE_PROVIDER_LIBRARY_SET.store(true, Ordering::Release);
+        Ok(())
+    }
+
     /// Set path for QVE(Quoting Verification Enclave) or
     /// QPL(Quote Provider Library)
     ///
@@ -214,6 +268,29 @@ impl LoadPolicyInitializer {
             Err(e) => Err(e),
         }
     }
+
+    /// Set the load policy, replacing any prior initialization.
+    ///
+    /// The load policy is left uninitialized on failure.
+    pub(crate) fn replace_policy(policy: RequestPolicy) -> Result<()> {
+        let mut value = LOAD_POLICY_INITIALIZER
+            .lock()
+            .expect("Mutex has been poisoned");
+        *value = None;
+        unsafe { mc_sgx_dcap_quoteverify_sys::sgx_qv_set_enclave_load_policy(policy.into()) }
+            .into_result()?;
+        *value = Some(LoadPolicyInitializer);
+        Ok(())
+    }
+
+    /// Mark the load policy as uninitialized, allowing it to be initialized
+    /// again.
+    pub(crate) fn reset() {
+        let mut value = LOAD_POLICY_INITIALIZER
+            .lock()
+            .expect("Mutex has been poisoned");
+        *value = None;
+    }
 }
 
 #[cfg(test)]
@@ -224,15 +301,6 @@ mod test {
     use std::fs;
     use tempfile::tempdir;
 
-    /// Resets the [`PATH_INITIALIZER`] to being uninitialized.
-    /// Since there is *one* [`PATH_INITIALIZER`] for the entire test process
-    /// any tests focusing on the functionality of the [`PATH_INITIALIZER`]
-    /// should be utilizing the `#[serial]` macro.
-    fn reset_path_initializer() {
-        let mut value = PATH_INITIALIZER.lock().expect("Mutex has been poisoned");
-        *value = None;
-    }
-
     #[test]
     fn qve_path_succeeds() {
         let dir = tempdir().unwrap();
@@ -295,7 +363,7 @@ mod test {
     #[test]
     #[serial]
     fn default_path_initializer_succeeds() {
-        reset_path_initializer();
+        PathInitializer::reset();
         let result = PathInitializer::try_default();
         assert_eq!(result, Ok(()));
     }
@@ -303,7 +371,7 @@ mod test {
     #[test]
     #[serial]
     fn default_path_initializer_fails_when_already_initialized() {
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::try_default().unwrap();
         let result = PathInitializer::try_default();
         assert_eq!(result, Err(Error::PathsInitialized));
@@ -322,7 +390,7 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        reset_path_initializer();
+        PathInitializer::reset();
         let result = PathInitializer::with_paths(Some(&names[0]), Some(&names[1]));
         assert_eq!(result, Ok(()));
     }
@@ -340,7 +408,7 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::try_default().unwrap();
         let result = PathInitializer::with_paths(Some(&names[0]), Some(&names[1]));
         assert_eq!(result, Err(Error::PathsInitialized));
@@ -359,7 +427,7 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::with_paths(Some(&names[0]), Some(&names[1])).unwrap();
         let result = PathInitializer::with_paths(Some(&names[0]), Some(&names[1]));
         assert_eq!(result, Err(Error::PathsInitialized));
@@ -384,7 +452,7 @@ mod test {
 
         for _ in 0..names.len() {
             names.rotate_right(1);
-            reset_path_initializer();
+            PathInitializer::reset();
             let result = PathInitializer::with_paths(Some(&names[0]), Some(&names[1]));
             assert!(matches!(result, Err(Error::PathLengthTooLong(_))));
 
@@ -395,16 +463,16 @@ mod test {
     #[test]
     #[serial]
     fn ensuring_paths_initialized_succeeds_when_already_initialized() {
-        reset_path_initializer();
+        PathInitializer::reset();
         PathInitializer::try_default().unwrap();
         assert_eq!(PathInitializer::ensure_initialized(), Ok(()));
     }
 
     #[test]
+    #[serial]
     fn load_policy() {
-        // The load policy can only be set once per process, as such this is a
-        // bit of a workflow test and no other test can set or change the load
-        // policy.
+        // The load policy can only be set once until it's reset, as such this
+        // is a bit of a workflow test.
         //
         // The workflow is:
         // 1. The first call to set the policy should succeed.
@@ -413,6 +481,7 @@ mod test {
         // 3. Ensuring the policy is initialized should *not* result in an
         //    error if it's already been initialized.
 
+        LoadPolicyInitializer::reset();
         assert_eq!(
             LoadPolicyInitializer::policy(RequestPolicy::Ephemeral),
             Ok(())
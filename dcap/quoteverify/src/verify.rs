@@ -20,6 +20,11 @@ use mc_sgx_util::ResultInto;
 pub fn supplemental_data_size() -> Result<usize, Error> {
     PathInitializer::ensure_initialized()?;
     LoadPolicyInitializer::ensure_initialized()?;
+    get_supplemental_data_size()
+}
+
+/// Get the supplemental data size with the current paths and load policy.
+pub(crate) fn get_supplemental_data_size() -> Result<usize, Error> {
     let mut size: u32 = 0;
     unsafe { mc_sgx_dcap_quoteverify_sys::sgx_qv_get_quote_supplemental_data_size(&mut size) }
         .into_result()?;
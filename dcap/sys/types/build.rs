@@ -53,6 +53,7 @@ fn main() {
         ])
         .derive_copy([
             "quote3_error_t",
+            "sgx_ql_qe3_id_t",
             "sgx_ql_pck_cert_id_t",
             "sgx_ql_config_t",
             "sgx_pce_info_t",
@@ -71,7 +72,7 @@ fn main() {
             "sgx_ql_att_key_id_param_t",
             "sgx_ql_att_id_list_t",
         ])
-        .derive_default(["sgx_ql_qe_report_info_t"]);
+        .derive_default(["sgx_ql_qe_report_info_t", "sgx_ql_qe3_id_t"]);
     let mut builder = mc_sgx_core_build::sgx_builder()
         .header("wrapper.h")
         .parse_callbacks(Box::new(callback))
@@ -0,0 +1,133 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs, missing_debug_implementations, unsafe_code)]
+
+mod signing_key;
+#[cfg(test)]
+mod test_provider;
+mod verifier;
+
+pub use crate::{
+    signing_key::{certified_key, RaTlsSigningKey},
+    verifier::RaTlsVerifier,
+};
+
+use mc_sgx_dcap_types::{AppraisalError, RaTlsError};
+use rustls::{CertificateError, OtherError, PeerMisbehaved, SignatureScheme};
+use std::sync::Arc;
+
+/// Error verifying an RA-TLS peer
+#[derive(Debug, displaydoc::Display, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Error with the RA-TLS certificate {0}
+    RaTls(RaTlsError),
+    /// Error appraising the quote {0}
+    Appraisal(AppraisalError),
+    /// No collateral was provided and the certificate does not contain any
+    MissingCollateral,
+    /// The RA-TLS certificate is not valid at the time of verification
+    CertificateValidity,
+    /// The signature scheme {0:?} is not supported, only ECDSA P-256 with SHA-256 is
+    UnsupportedSignatureScheme(SignatureScheme),
+    /// The handshake signature is invalid
+    HandshakeSignature,
+    /// The signing key does not match the public key of the RA-TLS certificate
+    KeyMismatch,
+}
+
+impl std::error::Error for Error {}
+
+impl From<RaTlsError> for Error {
+    fn from(err: RaTlsError) -> Self {
+        match err {
+            RaTlsError::Appraisal(err) => Self::Appraisal(err),
+            err => Self::RaTls(err),
+        }
+    }
+}
+
+impl From<AppraisalError> for Error {
+    fn from(err: AppraisalError) -> Self {
+        Self::Appraisal(err)
+    }
+}
+
+impl From<Error> for rustls::Error {
+    fn from(err: Error) -> Self {
+        let certificate_error = match err {
+            Error::RaTls(RaTlsError::Der(_)) => CertificateError::BadEncoding,
+            Error::HandshakeSignature => CertificateError::BadSignature,
+            Error::UnsupportedSignatureScheme(_) => {
+                return rustls::Error::PeerMisbehaved(
+                    PeerMisbehaved::SignedHandshakeWithUnadvertisedSigScheme,
+                )
+            }
+            err => CertificateError::Other(OtherError(Arc::new(err))),
+        };
+        rustls::Error::InvalidCertificate(certificate_error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+    use mc_sgx_dcap_types::Quote3Error;
+    use mc_sgx_dcap_types::RaTlsCertificate;
+    use yare::parameterized;
+
+    #[test]
+    fn der_error_is_bad_encoding() {
+        let err =
+            RaTlsCertificate::from_der(&[0x30, 0x03, 0x01]).expect_err("Should fail to decode");
+        let err = rustls::Error::from(Error::from(err));
+        assert_eq!(
+            err,
+            rustls::Error::InvalidCertificate(CertificateError::BadEncoding)
+        );
+    }
+
+    #[test]
+    fn handshake_signature_error_is_bad_signature() {
+        let err = rustls::Error::from(Error::HandshakeSignature);
+        assert_eq!(
+            err,
+            rustls::Error::InvalidCertificate(CertificateError::BadSignature)
+        );
+    }
+
+    #[parameterized(
+        missing_collateral = { Error::MissingCollateral },
+        quote = { Error::RaTls(RaTlsError::Quote(Quote3Error::SignatureVerification)) },
+        appraisal = { Error::Appraisal(AppraisalError::MrEnclaveMismatch) },
+    )]
+    fn error_to_other_certificate_error(err: Error) {
+        let rustls_err = rustls::Error::from(err.clone());
+        let other = assert_matches!(
+            rustls_err,
+            rustls::Error::InvalidCertificate(CertificateError::Other(other)) => other
+        );
+        assert_eq!(other.0.to_string(), err.to_string());
+    }
+
+    #[test]
+    fn unsupported_scheme_is_peer_misbehaving() {
+        let err = rustls::Error::from(Error::UnsupportedSignatureScheme(
+            SignatureScheme::RSA_PSS_SHA256,
+        ));
+        assert_eq!(
+            err,
+            rustls::Error::PeerMisbehaved(PeerMisbehaved::SignedHandshakeWithUnadvertisedSigScheme)
+        );
+    }
+
+    #[test]
+    fn display_includes_source() {
+        let err = Error::RaTls(RaTlsError::Quote(Quote3Error::SignatureVerification));
+        assert!(err
+            .to_string()
+            .starts_with("Error with the RA-TLS certificate"));
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Signing the TLS handshake with the key of an RA-TLS certificate.
+
+use crate::Error;
+use mc_sgx_dcap_types::RaTlsCertificate;
+use p256::ecdsa::signature::Signer as _;
+use p256::ecdsa::{DerSignature, SigningKey};
+use rustls::pki_types::CertificateDer;
+use rustls::sign::{CertifiedKey, Signer};
+use rustls::{SignatureAlgorithm, SignatureScheme};
+use std::sync::Arc;
+
+/// The only signature scheme supported by RA-TLS certificates.
+pub(crate) const SIGNATURE_SCHEME: SignatureScheme = SignatureScheme::ECDSA_NISTP256_SHA256;
+
+/// An ECDSA P-256 key which signs the TLS handshake for an
+/// [`RaTlsCertificate`].
+#[derive(Debug, Clone)]
+pub struct RaTlsSigningKey {
+    key: SigningKey,
+}
+
+impl From<SigningKey> for RaTlsSigningKey {
+    fn from(key: SigningKey) -> Self {
+        Self { key }
+    }
+}
+
+impl rustls::sign::SigningKey for RaTlsSigningKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        if offered.contains(&SIGNATURE_SCHEME) {
+            Some(Box::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::ECDSA
+    }
+}
+
+impl Signer for RaTlsSigningKey {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        let signature: DerSignature = self.key.sign(message);
+        Ok(signature.as_bytes().to_vec())
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        SIGNATURE_SCHEME
+    }
+}
+
+/// Create the rustls [`CertifiedKey`] for `certificate`.
+///
+/// The result can be provided to rustls with
+/// [`rustls::sign::SingleCertAndKey`] for either the client or the server
+/// side of a connection.
+///
+/// # Arguments
+/// * `certificate` - The RA-TLS certificate to present to the peer.
+/// * `key` - The signing key for the public key of `certificate`.
+///
+/// # Errors
+/// * [`Error::KeyMismatch`] if `key` is not the key of `certificate`.
+/// * [`Error::RaTls`] if `certificate` can not be encoded.
+pub fn certified_key(
+    certificate: &RaTlsCertificate,
+    key: SigningKey,
+) -> Result<CertifiedKey, Error> {
+    if &certificate.public_key()? != key.verifying_key() {
+        return Err(Error::KeyMismatch);
+    }
+    let der = CertificateDer::from(certificate.to_der()?);
+    Ok(CertifiedKey::new(
+        vec![der],
+        Arc::new(RaTlsSigningKey::from(key)),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_sgx_dcap_types::ra_tls::report_data_for_key;
+    use mc_sgx_dcap_types::test_utils::TestCa;
+    use mc_sgx_dcap_types::RaTlsCertificateBuilder;
+    use p256::ecdsa::signature::Verifier;
+    use rustls::sign::SigningKey as _;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_slice(&[seed; 32]).expect("Invalid signing key")
+    }
+
+    fn certificate(key: &SigningKey) -> RaTlsCertificate {
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        let quote = TestCa::default().quote_for_report_data(&report_data);
+        RaTlsCertificateBuilder::new(key, &quote)
+            .build()
+            .expect("Failed to build certificate")
+    }
+
+    #[test]
+    fn chooses_ecdsa_p256() {
+        let key = RaTlsSigningKey::from(signing_key(1));
+        let offered = [SignatureScheme::RSA_PSS_SHA256, SIGNATURE_SCHEME];
+        let signer = key.choose_scheme(&offered).expect("Should choose a scheme");
+        assert_eq!(signer.scheme(), SIGNATURE_SCHEME);
+        assert_eq!(key.algorithm(), SignatureAlgorithm::ECDSA);
+    }
+
+    #[test]
+    fn no_scheme_without_ecdsa_p256() {
+        let key = RaTlsSigningKey::from(signing_key(1));
+        let offered = [
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+        ];
+        assert!(key.choose_scheme(&offered).is_none());
+    }
+
+    #[test]
+    fn signs_as_der() {
+        let key = signing_key(2);
+        let signer = RaTlsSigningKey::from(key.clone());
+        let message = b"handshake transcript";
+        let signature = signer.sign(message).expect("Failed to sign");
+        let signature = DerSignature::try_from(signature.as_slice()).expect("Not DER");
+        assert!(key.verifying_key().verify(message, &signature).is_ok());
+    }
+
+    #[test]
+    fn certified_key_for_certificate() {
+        let key = signing_key(3);
+        let certificate = certificate(&key);
+        let certified_key = certified_key(&certificate, key).expect("Failed to certify key");
+        let der = certificate.to_der().expect("Failed to encode certificate");
+        assert_eq!(certified_key.cert, vec![CertificateDer::from(der)]);
+        assert_eq!(certified_key.key.algorithm(), SignatureAlgorithm::ECDSA);
+    }
+
+    #[test]
+    fn certified_key_with_wrong_key_fails() {
+        let certificate = certificate(&signing_key(3));
+        assert_eq!(
+            certified_key(&certificate, signing_key(4)).map(|_| ()),
+            Err(Error::KeyMismatch)
+        );
+    }
+}
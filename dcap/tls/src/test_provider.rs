@@ -0,0 +1,293 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! A minimal rustls [`CryptoProvider`] for tests.
+//!
+//! The rustls providers are built on `ring` or `aws-lc-rs` which are not
+//! permitted dependencies, so this provides TLS 1.3 with
+//! `TLS13_AES_128_GCM_SHA256` and `secp256r1` key exchange using the RustCrypto
+//! crates. It is *only* meant to exercise the RA-TLS verifiers.
+//!
+//! Signature verification is done by the RA-TLS verifiers, so no webpki
+//! algorithms are provided.
+
+use aes_gcm::aead::AeadInPlace;
+use aes_gcm::{Aes128Gcm, KeyInit};
+use hmac::Mac;
+use p256::ecdh::EphemeralSecret;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::PublicKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rustls::crypto::cipher::{
+    make_tls13_aad, AeadKey, InboundOpaqueMessage, InboundPlainMessage, Iv, MessageDecrypter,
+    MessageEncrypter, Nonce, OutboundOpaqueMessage, OutboundPlainMessage, PrefixedPayload,
+    Tls13AeadAlgorithm, UnsupportedOperationError,
+};
+use rustls::crypto::tls13::HkdfUsingHmac;
+use rustls::crypto::{
+    hash, hmac as rustls_hmac, ActiveKeyExchange, CipherSuiteCommon, CryptoProvider,
+    GetRandomFailed, KeyProvider, SecureRandom, SharedSecret, SupportedKxGroup,
+    WebPkiSupportedAlgorithms,
+};
+use rustls::pki_types::PrivateKeyDer;
+use rustls::sign::SigningKey;
+use rustls::{
+    CipherSuite, ConnectionTrafficSecrets, ContentType, NamedGroup, ProtocolVersion,
+    SupportedCipherSuite, Tls13CipherSuite,
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+const GCM_TAG_SIZE: usize = 16;
+
+/// The [`CryptoProvider`] for tests.
+pub(crate) fn provider() -> CryptoProvider {
+    CryptoProvider {
+        cipher_suites: vec![SupportedCipherSuite::Tls13(&TLS13_AES_128_GCM_SHA256)],
+        kx_groups: vec![&Secp256r1],
+        signature_verification_algorithms: WebPkiSupportedAlgorithms {
+            all: &[],
+            mapping: &[],
+        },
+        secure_random: &Random,
+        key_provider: &NoKeys,
+    }
+}
+
+static TLS13_AES_128_GCM_SHA256: Tls13CipherSuite = Tls13CipherSuite {
+    common: CipherSuiteCommon {
+        suite: CipherSuite::TLS13_AES_128_GCM_SHA256,
+        hash_provider: &Sha256Hash,
+        confidentiality_limit: 1 << 24,
+    },
+    hkdf_provider: &HkdfUsingHmac(&HmacSha256),
+    aead_alg: &Aes128GcmAead,
+    quic: None,
+};
+
+struct Sha256Hash;
+
+impl hash::Hash for Sha256Hash {
+    fn start(&self) -> Box<dyn hash::Context> {
+        Box::new(Sha256Context(Sha256::new()))
+    }
+
+    fn hash(&self, data: &[u8]) -> hash::Output {
+        hash::Output::new(&Sha256::digest(data))
+    }
+
+    fn output_len(&self) -> usize {
+        32
+    }
+
+    fn algorithm(&self) -> hash::HashAlgorithm {
+        hash::HashAlgorithm::SHA256
+    }
+}
+
+struct Sha256Context(Sha256);
+
+impl hash::Context for Sha256Context {
+    fn fork_finish(&self) -> hash::Output {
+        hash::Output::new(&self.0.clone().finalize())
+    }
+
+    fn fork(&self) -> Box<dyn hash::Context> {
+        Box::new(Sha256Context(self.0.clone()))
+    }
+
+    fn finish(self: Box<Self>) -> hash::Output {
+        hash::Output::new(&self.0.finalize())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+}
+
+struct HmacSha256;
+
+impl rustls_hmac::Hmac for HmacSha256 {
+    fn with_key(&self, key: &[u8]) -> Box<dyn rustls_hmac::Key> {
+        let mac =
+            <hmac::Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key size");
+        Box::new(HmacSha256Key(mac))
+    }
+
+    fn hash_output_len(&self) -> usize {
+        32
+    }
+}
+
+struct HmacSha256Key(hmac::Hmac<Sha256>);
+
+impl rustls_hmac::Key for HmacSha256Key {
+    fn sign_concat(&self, first: &[u8], middle: &[&[u8]], last: &[u8]) -> rustls_hmac::Tag {
+        let mut mac = self.0.clone();
+        mac.update(first);
+        for data in middle {
+            mac.update(data);
+        }
+        mac.update(last);
+        rustls_hmac::Tag::new(&mac.finalize().into_bytes())
+    }
+
+    fn tag_len(&self) -> usize {
+        32
+    }
+}
+
+struct Aes128GcmAead;
+
+impl Tls13AeadAlgorithm for Aes128GcmAead {
+    fn encrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageEncrypter> {
+        Box::new(Aes128GcmCrypter::new(key, iv))
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageDecrypter> {
+        Box::new(Aes128GcmCrypter::new(key, iv))
+    }
+
+    fn key_len(&self) -> usize {
+        16
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ConnectionTrafficSecrets::Aes128Gcm { key, iv })
+    }
+}
+
+struct Aes128GcmCrypter {
+    cipher: Aes128Gcm,
+    iv: Iv,
+}
+
+impl Aes128GcmCrypter {
+    fn new(key: AeadKey, iv: Iv) -> Self {
+        let cipher = Aes128Gcm::new_from_slice(key.as_ref()).expect("Invalid AES-128 key");
+        Self { cipher, iv }
+    }
+}
+
+impl MessageEncrypter for Aes128GcmCrypter {
+    fn encrypt(
+        &mut self,
+        msg: OutboundPlainMessage<'_>,
+        seq: u64,
+    ) -> Result<OutboundOpaqueMessage, rustls::Error> {
+        let total_len = self.encrypted_payload_len(msg.payload.len());
+        let mut payload = PrefixedPayload::with_capacity(total_len);
+        payload.extend_from_chunks(&msg.payload);
+        payload.extend_from_slice(&u8::from(msg.typ).to_be_bytes());
+
+        let nonce = Nonce::new(&self.iv, seq);
+        let aad = make_tls13_aad(total_len);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce.0.into(), &aad, payload.as_mut())
+            .map_err(|_| rustls::Error::EncryptError)?;
+        payload.extend_from_slice(&tag);
+
+        Ok(OutboundOpaqueMessage::new(
+            ContentType::ApplicationData,
+            ProtocolVersion::TLSv1_2,
+            payload,
+        ))
+    }
+
+    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
+        // The content type byte and the tag
+        payload_len + 1 + GCM_TAG_SIZE
+    }
+}
+
+impl MessageDecrypter for Aes128GcmCrypter {
+    fn decrypt<'a>(
+        &mut self,
+        mut msg: InboundOpaqueMessage<'a>,
+        seq: u64,
+    ) -> Result<InboundPlainMessage<'a>, rustls::Error> {
+        let payload = &mut msg.payload;
+        let payload_len = payload
+            .len()
+            .checked_sub(GCM_TAG_SIZE)
+            .ok_or(rustls::Error::DecryptError)?;
+        let nonce = Nonce::new(&self.iv, seq);
+        let aad = make_tls13_aad(payload.len());
+        let (ciphertext, tag) = payload.split_at_mut(payload_len);
+        self.cipher
+            .decrypt_in_place_detached(&nonce.0.into(), &aad, ciphertext, (&*tag).into())
+            .map_err(|_| rustls::Error::DecryptError)?;
+        payload.truncate(payload_len);
+        msg.into_tls13_unpadded_message()
+    }
+}
+
+#[derive(Debug)]
+struct Secp256r1;
+
+impl SupportedKxGroup for Secp256r1 {
+    fn start(&self) -> Result<Box<dyn ActiveKeyExchange>, rustls::Error> {
+        let secret = EphemeralSecret::random(&mut OsRng);
+        let public_key = secret
+            .public_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec();
+        Ok(Box::new(Secp256r1KeyExchange { secret, public_key }))
+    }
+
+    fn name(&self) -> NamedGroup {
+        NamedGroup::secp256r1
+    }
+}
+
+struct Secp256r1KeyExchange {
+    secret: EphemeralSecret,
+    public_key: Vec<u8>,
+}
+
+impl ActiveKeyExchange for Secp256r1KeyExchange {
+    fn complete(self: Box<Self>, peer_pub_key: &[u8]) -> Result<SharedSecret, rustls::Error> {
+        let peer = PublicKey::from_sec1_bytes(peer_pub_key)
+            .map_err(|_| rustls::Error::from(rustls::PeerMisbehaved::InvalidKeyShare))?;
+        let shared = self.secret.diffie_hellman(&peer);
+        Ok(SharedSecret::from(shared.raw_secret_bytes().to_vec()))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    fn group(&self) -> NamedGroup {
+        NamedGroup::secp256r1
+    }
+}
+
+#[derive(Debug)]
+struct Random;
+
+impl SecureRandom for Random {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), GetRandomFailed> {
+        OsRng.try_fill_bytes(buf).map_err(|_| GetRandomFailed)
+    }
+}
+
+/// Keys are provided directly with [`crate::certified_key()`], never as DER.
+#[derive(Debug)]
+struct NoKeys;
+
+impl KeyProvider for NoKeys {
+    fn load_private_key(
+        &self,
+        _key_der: PrivateKeyDer<'static>,
+    ) -> Result<Arc<dyn SigningKey>, rustls::Error> {
+        Err(rustls::Error::General(
+            "Loading keys is not supported".into(),
+        ))
+    }
+}
@@ -0,0 +1,476 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Verifying the RA-TLS certificate of a TLS peer.
+
+use crate::signing_key::SIGNATURE_SCHEME;
+use crate::Error;
+use core::time::Duration;
+use mc_sgx_dcap_types::{Appraisal, Collateral, IdentityPolicy, QuoteAppraiser, RaTlsCertificate};
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::DerSignature;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{DigitallySignedStruct, DistinguishedName, SignatureScheme};
+
+/// Verifies the RA-TLS certificate of a TLS peer.
+///
+/// Instead of validating a WebPKI certificate chain, the quote in the peer's
+/// [`RaTlsCertificate`] is appraised with a [`QuoteAppraiser`] and the
+/// resulting [`Appraisal`] must satisfy an [`IdentityPolicy`].
+///
+/// This implements both [`ServerCertVerifier`], for clients, and
+/// [`ClientCertVerifier`], for servers. The peer's identity comes from the
+/// quote, so the server name is *not* checked against the certificate.
+/// Intermediate certificates sent by the peer are ignored.
+#[derive(Debug, Clone)]
+pub struct RaTlsVerifier {
+    appraiser: QuoteAppraiser,
+    policy: IdentityPolicy,
+    collateral: Option<Collateral>,
+}
+
+impl RaTlsVerifier {
+    /// Create a new verifier.
+    ///
+    /// Quotes are appraised with the collateral embedded in the peer's
+    /// certificate, unless [`RaTlsVerifier::collateral()`] is used.
+    ///
+    /// # Arguments
+    /// * `appraiser` - The appraiser for the quote in the peer's certificate.
+    /// * `policy` - The policy the appraisal of the quote must satisfy.
+    pub fn new(appraiser: QuoteAppraiser, policy: IdentityPolicy) -> Self {
+        Self {
+            appraiser,
+            policy,
+            collateral: None,
+        }
+    }
+
+    /// Appraise quotes with `collateral` instead of the collateral embedded
+    /// in the peer's certificate.
+    #[must_use]
+    pub fn collateral(mut self, collateral: Collateral) -> Self {
+        self.collateral = Some(collateral);
+        self
+    }
+
+    /// Verify the DER encoded RA-TLS `certificate` at `now`.
+    ///
+    /// # Arguments
+    /// * `certificate` - The DER encoded RA-TLS certificate of the peer.
+    /// * `now` - The time of the verification, as the duration since the UNIX
+    ///   epoch.
+    ///
+    /// # Errors
+    /// * [`Error::RaTls`] if `certificate` is not a valid RA-TLS certificate.
+    /// * [`Error::CertificateValidity`] if `certificate` is not valid at
+    ///   `now`.
+    /// * [`Error::MissingCollateral`] if no collateral was provided and
+    ///   `certificate` does not contain any.
+    /// * [`Error::Appraisal`] if the quote fails the appraisal or the
+    ///   identity policy.
+    pub fn verify(&self, certificate: &[u8], now: Duration) -> Result<Appraisal, Error> {
+        let certificate = RaTlsCertificate::from_der(certificate)?;
+        let validity = &certificate.certificate().tbs_certificate.validity;
+        if now < validity.not_before.to_unix_duration()
+            || now > validity.not_after.to_unix_duration()
+        {
+            return Err(Error::CertificateValidity);
+        }
+
+        let embedded_collateral;
+        let collateral = match &self.collateral {
+            Some(collateral) => collateral,
+            None => {
+                embedded_collateral = certificate.collateral()?.ok_or(Error::MissingCollateral)?;
+                &embedded_collateral
+            }
+        };
+
+        let appraisal = certificate.verify(&self.appraiser, collateral, now)?;
+        self.policy.check(&appraisal)?;
+        Ok(appraisal)
+    }
+
+    /// Verify the handshake `signature` of `message` by the key of
+    /// `certificate`.
+    fn verify_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        if signature.scheme != SIGNATURE_SCHEME {
+            return Err(Error::UnsupportedSignatureScheme(signature.scheme));
+        }
+        let key = RaTlsCertificate::from_der(certificate)?.public_key()?;
+        let signature =
+            DerSignature::try_from(signature.signature()).map_err(|_| Error::HandshakeSignature)?;
+        key.verify(message, &signature)
+            .map_err(|_| Error::HandshakeSignature)?;
+        Ok(HandshakeSignatureValid::assertion())
+    }
+}
+
+fn unix_duration(now: UnixTime) -> Duration {
+    Duration::from_secs(now.as_secs())
+}
+
+impl ServerCertVerifier for RaTlsVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verify(end_entity, unix_duration(now))?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(self.verify_signature(message, cert, dss)?)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(self.verify_signature(message, cert, dss)?)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![SIGNATURE_SCHEME]
+    }
+}
+
+impl ClientCertVerifier for RaTlsVerifier {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        // RA-TLS certificates are self signed so there is no CA to hint at
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        self.verify(end_entity, unix_duration(now))?;
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(self.verify_signature(message, cert, dss)?)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(self.verify_signature(message, cert, dss)?)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![SIGNATURE_SCHEME]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certified_key;
+    use crate::test_provider::provider;
+    use assert_matches::assert_matches;
+    use mc_sgx_core_types::MrEnclave;
+    use mc_sgx_dcap_types::ra_tls::report_data_for_key;
+    use mc_sgx_dcap_types::test_utils::TestCa;
+    use mc_sgx_dcap_types::{AppraisalError, RaTlsCertificateBuilder, TcbStatus};
+    use p256::ecdsa::SigningKey;
+    use rustls::sign::SingleCertAndKey;
+    use rustls::version::TLS13;
+    use rustls::{
+        CertificateError, ClientConfig, ClientConnection, ConnectionCommon, ServerConfig,
+        ServerConnection,
+    };
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    // 2024-06-01
+    const NOW: Duration = Duration::from_secs(1_717_200_000);
+
+    struct Peer {
+        key: SigningKey,
+        certificate: RaTlsCertificate,
+    }
+
+    impl Peer {
+        fn new(seed: u8, collateral: Option<&Collateral>) -> Self {
+            let key = SigningKey::from_slice(&[seed; 32]).expect("Invalid signing key");
+            let report_data =
+                report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+            let quote = TestCa::default().quote_for_report_data(&report_data);
+            let mut builder = RaTlsCertificateBuilder::new(&key, &quote);
+            if let Some(collateral) = collateral {
+                builder = builder.collateral(collateral);
+            }
+            let certificate = builder.build().expect("Failed to build certificate");
+            Self { key, certificate }
+        }
+
+        fn certified_key(&self) -> Arc<SingleCertAndKey> {
+            let certified_key =
+                certified_key(&self.certificate, self.key.clone()).expect("Failed to certify key");
+            Arc::new(SingleCertAndKey::from(certified_key))
+        }
+
+        fn der(&self) -> Vec<u8> {
+            self.certificate
+                .to_der()
+                .expect("Failed to encode certificate")
+        }
+    }
+
+    fn verifier() -> RaTlsVerifier {
+        let ca = TestCa::default();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        // The synthesized quotes have an all zero report body
+        let policy = IdentityPolicy::mr_enclave(MrEnclave::default());
+        RaTlsVerifier::new(appraiser, policy)
+    }
+
+    fn connect(
+        client: &Peer,
+        client_verifier: RaTlsVerifier,
+        server: &Peer,
+        server_verifier: RaTlsVerifier,
+    ) -> Result<(ClientConnection, ServerConnection), rustls::Error> {
+        let server_config = ServerConfig::builder_with_provider(Arc::new(provider()))
+            .with_protocol_versions(&[&TLS13])?
+            .with_client_cert_verifier(Arc::new(server_verifier))
+            .with_cert_resolver(server.certified_key());
+        let client_config = ClientConfig::builder_with_provider(Arc::new(provider()))
+            .with_protocol_versions(&[&TLS13])?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(client_verifier))
+            .with_client_cert_resolver(client.certified_key());
+
+        let server_name = "enclave".try_into().expect("Invalid server name");
+        let mut client = ClientConnection::new(Arc::new(client_config), server_name)?;
+        let mut server = ServerConnection::new(Arc::new(server_config))?;
+
+        // Bounded to catch a stalled handshake
+        for _ in 0..10 {
+            if !client.is_handshaking() && !server.is_handshaking() {
+                return Ok((client, server));
+            }
+            transfer(&mut client, &mut server)?;
+            transfer(&mut server, &mut client)?;
+        }
+        panic!("Handshake did not complete");
+    }
+
+    /// Send all pending TLS data from `from` to `to`.
+    fn transfer<A, B>(
+        from: &mut ConnectionCommon<A>,
+        to: &mut ConnectionCommon<B>,
+    ) -> Result<(), rustls::Error> {
+        let mut buffer = Vec::new();
+        while from.wants_write() {
+            from.write_tls(&mut buffer).expect("Failed to write");
+        }
+        let mut data = buffer.as_slice();
+        while !data.is_empty() {
+            to.read_tls(&mut data).expect("Failed to read");
+            to.process_new_packets()?;
+        }
+        Ok(())
+    }
+
+    fn ra_tls_error(err: rustls::Error) -> Error {
+        let other = assert_matches!(
+            err,
+            rustls::Error::InvalidCertificate(CertificateError::Other(other)) => other
+        );
+        other
+            .0
+            .downcast_ref::<Error>()
+            .expect("Not an RA-TLS error")
+            .clone()
+    }
+
+    #[test]
+    fn mutual_tls_round_trip() {
+        let collateral = TestCa::default().collateral();
+        let client = Peer::new(1, None);
+        let server = Peer::new(2, Some(&collateral));
+        let (mut client_connection, mut server_connection) = connect(
+            &client,
+            verifier(),
+            &server,
+            verifier().collateral(collateral),
+        )
+        .expect("Failed to connect");
+
+        client_connection
+            .writer()
+            .write_all(b"ping")
+            .expect("Failed to write");
+        transfer(&mut client_connection, &mut server_connection).expect("Failed to transfer");
+        let mut message = [0u8; 4];
+        server_connection
+            .reader()
+            .read_exact(&mut message)
+            .expect("Failed to read");
+        assert_eq!(&message, b"ping");
+
+        let client_certificates = server_connection
+            .peer_certificates()
+            .expect("Missing client certificate");
+        assert_eq!(client_certificates[0].as_ref(), client.der());
+        let server_certificates = client_connection
+            .peer_certificates()
+            .expect("Missing server certificate");
+        assert_eq!(server_certificates[0].as_ref(), server.der());
+    }
+
+    #[test]
+    fn server_fails_client_policy() {
+        let collateral = TestCa::default().collateral();
+        let client = Peer::new(1, None);
+        let server = Peer::new(2, Some(&collateral));
+        let ca = TestCa::default();
+        let policy = IdentityPolicy::mr_enclave(MrEnclave::from([1u8; 32]));
+        let client_verifier =
+            RaTlsVerifier::new(QuoteAppraiser::new(ca.root_certificate().clone()), policy);
+
+        let err = connect(
+            &client,
+            client_verifier,
+            &server,
+            verifier().collateral(collateral),
+        )
+        .expect_err("Should fail to connect");
+
+        assert_eq!(
+            ra_tls_error(err),
+            Error::Appraisal(AppraisalError::MrEnclaveMismatch)
+        );
+    }
+
+    #[test]
+    fn client_with_untrusted_collateral() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        let client = Peer::new(1, None);
+        let server = Peer::new(2, Some(&collateral));
+        let policy = IdentityPolicy::mr_enclave(MrEnclave::default());
+        // The CRLs and chains don't lead to the intermediate CA
+        let server_verifier = RaTlsVerifier::new(
+            QuoteAppraiser::new(ca.intermediate_certificate().clone()),
+            policy,
+        )
+        .collateral(collateral);
+
+        let err = connect(&client, verifier(), &server, server_verifier)
+            .expect_err("Should fail to connect");
+
+        assert_eq!(
+            ra_tls_error(err),
+            Error::Appraisal(AppraisalError::CrlSignature)
+        );
+    }
+
+    #[test]
+    fn client_without_collateral() {
+        let collateral = TestCa::default().collateral();
+        let client = Peer::new(1, None);
+        let server = Peer::new(2, Some(&collateral));
+
+        let err =
+            connect(&client, verifier(), &server, verifier()).expect_err("Should fail to connect");
+
+        assert_eq!(ra_tls_error(err), Error::MissingCollateral);
+    }
+
+    #[test]
+    fn verify_with_embedded_collateral() {
+        let collateral = TestCa::default().collateral();
+        let peer = Peer::new(3, Some(&collateral));
+
+        let appraisal = verifier()
+            .verify(&peer.der(), NOW)
+            .expect("Failed to verify");
+
+        assert_eq!(appraisal.tcb_status(), TcbStatus::UpToDate);
+        assert_eq!(appraisal.report_body().mr_enclave(), MrEnclave::default());
+    }
+
+    #[test]
+    fn provided_collateral_is_preferred() {
+        let ca = TestCa::default();
+        let embedded = ca.collateral();
+        let provided = ca
+            .collateral_builder()
+            .tcb_status(TcbStatus::SwHardeningNeeded)
+            .build();
+        let peer = Peer::new(3, Some(&embedded));
+
+        let err = verifier()
+            .collateral(provided)
+            .verify(&peer.der(), NOW)
+            .expect_err("Should fail the policy");
+
+        assert_eq!(
+            err,
+            Error::Appraisal(AppraisalError::TcbStatus(TcbStatus::SwHardeningNeeded))
+        );
+    }
+
+    #[test]
+    fn verify_before_certificate_validity() {
+        let collateral = TestCa::default().collateral();
+        let key = SigningKey::from_slice(&[4; 32]).expect("Invalid signing key");
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        let quote = TestCa::default().quote_for_report_data(&report_data);
+        let certificate = RaTlsCertificateBuilder::new(&key, &quote)
+            .collateral(&collateral)
+            .validity(NOW + Duration::from_secs(1), NOW + Duration::from_secs(60))
+            .build()
+            .expect("Failed to build certificate");
+        let der = certificate.to_der().expect("Failed to encode certificate");
+
+        assert_eq!(
+            verifier().verify(&der, NOW),
+            Err(Error::CertificateValidity)
+        );
+        assert!(verifier()
+            .verify(&der, NOW + Duration::from_secs(30))
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_invalid_der() {
+        assert_matches!(
+            verifier().verify(&[0x30, 0x03, 0x01], NOW),
+            Err(Error::RaTls(_))
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! The cost of parsing a [`Quote3`], accessing its signature data, and
+//! verifying its signatures.
+//!
+//! `signature_data` is the repeated access to the signature data decoded when
+//! the quote was created, `parse_signature_data` is the cost each access had
+//! when the signature data, including the certification data, was parsed
+//! again every time.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mc_sgx_dcap_types::{CertificationData, Quote3, SignatureData};
+use p256::ecdsa::VerifyingKey;
+use x509_cert::{der::DecodePem, Certificate};
+
+const HW_QUOTE: &[u8] = include_bytes!("../data/tests/hw_quote.dat");
+
+// The signature data follows the quote header, the report body and the
+// signature data length.
+const SIGNATURE_DATA_OFFSET: usize = 436;
+
+/// The public key of the PCK leaf certificate of `quote`
+fn pck_leaf_key(quote: &Quote3<&[u8]>) -> VerifyingKey {
+    let signature_data = quote.signature_data();
+    let CertificationData::PckCertificateChain(chain) = signature_data.certification_data() else {
+        panic!("Expected a PCK certificate chain");
+    };
+    let leaf_pem = chain
+        .into_iter()
+        .next()
+        .expect("Empty PCK certificate chain");
+    let leaf = Certificate::from_pem(leaf_pem).expect("Failed to parse PCK certificate");
+    let key = leaf
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .expect("Unaligned public key");
+    VerifyingKey::from_sec1_bytes(key).expect("Invalid public key")
+}
+
+fn quote3(c: &mut Criterion) {
+    let quote = Quote3::try_from(HW_QUOTE).expect("Failed to parse quote");
+    let key = pck_leaf_key(&quote);
+    let mut group = c.benchmark_group("quote3");
+
+    group.bench_function("parse", |b| {
+        b.iter(|| Quote3::try_from(black_box(HW_QUOTE)).expect("Failed to parse quote"))
+    });
+    group.bench_function("signature_data", |b| {
+        b.iter(|| {
+            let signature_data = black_box(&quote).signature_data();
+            (
+                signature_data.qe_report_body().isv_svn(),
+                signature_data.certification_data().raw_data().len(),
+            )
+        })
+    });
+    group.bench_function("parse_signature_data", |b| {
+        b.iter(|| {
+            let signature_data =
+                SignatureData::try_from(black_box(&HW_QUOTE[SIGNATURE_DATA_OFFSET..]))
+                    .expect("Failed to parse signature data");
+            (
+                signature_data.qe_report_body().isv_svn(),
+                signature_data.certification_data().raw_data().len(),
+            )
+        })
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            black_box(&quote)
+                .verify(&key)
+                .expect("Failed to verify quote")
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, quote3);
+criterion_main!(benches);
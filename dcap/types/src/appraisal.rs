@@ -0,0 +1,1377 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Appraisal of a [`Quote3`] with its [`Collateral`].
+//!
+//! Appraisal is done without the Intel quote verification library. The
+//! [`QuoteAppraiser`] verifies:
+//! 1. The PCK certificate chain of the quote, and each collateral issuer
+//!    chain, lead to the trust anchor, usually the Intel SGX Root CA.
+//! 2. No certificate in the chains has been revoked by the collateral CRLs.
+//! 3. The certificates, CRLs, TCB info, and QE identity are valid at the time
+//!    of appraisal.
+//! 4. The signatures of the quote.
+//! 5. The quoting enclave matches the QE identity.
+//!
+//! The result is an [`Appraisal`] with the TCB status of the platform and of
+//! the quoting enclave. An [`IdentityPolicy`] is then used to decide if the
+//! application enclave, and those TCB statuses, are acceptable.
+//!
+//! Quotes from platforms sharing collateral can be appraised against a
+//! [`VerifiedCollateral`], from [`QuoteAppraiser::verify_collateral()`], to
+//! only verify the collateral once. Likewise a [`VerifiedPckChain`], from
+//! [`QuoteAppraiser::verify_pck_chain()`], only verifies the PCK certificate
+//! chain of a platform once.
+
+use crate::x509::{verify, verify_certificate, verifying_key, SignatureError};
+use crate::{
+    CertificationData, Collateral, FmspcTcbInfo, QeIdentity, Quote3, Quote3Error, TcbError,
+    TcbInfo, TcbStatus, TimeError, TimeSource,
+};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::slice;
+use core::time::Duration;
+use mc_sgx_core_types::{AttributeFlags, IsvProductId, IsvSvn, MrEnclave, MrSigner, ReportBody};
+use p256::ecdsa::VerifyingKey;
+use x509_cert::crl::CertificateList;
+use x509_cert::der::{self, DecodePem, Encode};
+use x509_cert::ext::pkix::BasicConstraints;
+use x509_cert::name::Name;
+use x509_cert::Certificate;
+
+/// Error appraising a [`Quote3`]
+#[derive(Debug, displaydoc::Display, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Error encoding or decoding DER {0}
+    Der(der::Error),
+    /// Error parsing the collateral JSON {0}
+    Json(String),
+    /// Invalid hex value in the collateral JSON: {0}
+    Hex(String),
+    /// Invalid date in the collateral JSON: {0}
+    Date(String),
+    /// The signature of the collateral JSON is invalid
+    JsonSignature,
+    /// A public key is not an ECDSA P-256 key
+    UnsupportedKey,
+    /// A signature algorithm is not ECDSA with SHA-256
+    UnsupportedSignatureAlgorithm,
+    /// A certificate chain is empty
+    EmptyCertificateChain,
+    /// A certificate chain does not end in the trust anchor
+    UntrustedCertificateChain,
+    /// A certificate signature is invalid
+    CertificateSignature,
+    /// A certificate is signed by a certificate which is not a certificate authority
+    NotCertificateAuthority,
+    /// A certificate is not valid at the time of appraisal
+    CertificateValidity,
+    /// A certificate has been revoked
+    CertificateRevoked,
+    /// There is no CRL for the issuer of a certificate
+    MissingCrl,
+    /// A CRL signature is invalid
+    CrlSignature,
+    /// A CRL is not valid at the time of appraisal
+    CrlValidity,
+    /// The TCB info is not version 3 SGX TCB info
+    UnsupportedTcbInfo,
+    /// The TCB info has {0} TCB components, expected 16
+    TcbComponentCount(usize),
+    /// The TCB info is not valid at the time of appraisal
+    TcbInfoValidity,
+    /// The QE identity is not a version 2 QE identity
+    UnsupportedQeIdentity,
+    /// The QE identity is not valid at the time of appraisal
+    QeIdentityValidity,
+    /// The quote's certification data is not a PCK certificate chain
+    UnsupportedCertificationData,
+    /// Error with the quote {0}
+    Quote(Quote3Error),
+    /// Error reading the TCB of the PCK certificate {0}
+    Tcb(TcbError),
+    /// The FMSPC of the TCB info does not match the PCK certificate
+    FmspcMismatch,
+    /// The platform TCB is below every TCB level of the TCB info
+    UnsupportedTcbLevel,
+    /// The quoting enclave does not match the QE identity
+    QeIdentityMismatch,
+    /// The quoting enclave ISV SVN is below every TCB level of the QE identity
+    UnsupportedQeTcbLevel,
+    /// The MRENCLAVE of the enclave is not allowed
+    MrEnclaveMismatch,
+    /// The MRSIGNER of the enclave is not allowed
+    MrSignerMismatch,
+    /// The product ID of the enclave is not allowed
+    IsvProductIdMismatch,
+    /// The ISV SVN of the enclave, {0}, is below the minimum of {1}
+    IsvSvnTooLow(IsvSvn, IsvSvn),
+    /// The enclave is a debug enclave
+    DebugEnclave,
+    /// The TCB status {0} is not accepted
+    TcbStatus(TcbStatus),
+    /// Error getting the time of appraisal: {0}
+    Time(TimeError),
+}
+
+impl From<der::Error> for Error {
+    fn from(err: der::Error) -> Self {
+        Self::Der(err)
+    }
+}
+
+impl From<Quote3Error> for Error {
+    fn from(err: Quote3Error) -> Self {
+        Self::Quote(err)
+    }
+}
+
+impl From<TcbError> for Error {
+    fn from(err: TcbError) -> Self {
+        Self::Tcb(err)
+    }
+}
+
+impl From<TimeError> for Error {
+    fn from(err: TimeError) -> Self {
+        Self::Time(err)
+    }
+}
+
+/// The result of appraising a [`Quote3`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Appraisal {
+    report_body: ReportBody,
+    tcb_status: TcbStatus,
+    qe_tcb_status: TcbStatus,
+    advisory_ids: Vec<String>,
+    tcb_evaluation_data_number: u32,
+}
+
+impl Appraisal {
+    /// The report body of the application enclave.
+    pub fn report_body(&self) -> &ReportBody {
+        &self.report_body
+    }
+
+    /// The TCB status of the platform.
+    pub fn tcb_status(&self) -> TcbStatus {
+        self.tcb_status
+    }
+
+    /// The TCB status of the quoting enclave.
+    pub fn qe_tcb_status(&self) -> TcbStatus {
+        self.qe_tcb_status
+    }
+
+    /// The Intel security advisories which apply to the platform and the
+    /// quoting enclave.
+    pub fn advisory_ids(&self) -> &[String] {
+        &self.advisory_ids
+    }
+
+    /// The TCB evaluation data number of the TCB info used for the appraisal.
+    pub fn tcb_evaluation_data_number(&self) -> u32 {
+        self.tcb_evaluation_data_number
+    }
+}
+
+/// Appraises quotes against a trust anchor.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QuoteAppraiser {
+    trust_anchor: Certificate,
+}
+
+impl QuoteAppraiser {
+    /// Create a new appraiser.
+    ///
+    /// # Arguments
+    /// * `trust_anchor` - The root CA certificate that all certificate chains
+    ///   must lead to. For hardware quotes this is the Intel SGX Root CA,
+    ///   available at
+    ///   <https://certificates.trustedservices.intel.com/Intel_SGX_Provisioning_Certification_RootCA.cer>.
+    pub fn new(trust_anchor: Certificate) -> Self {
+        Self { trust_anchor }
+    }
+
+    /// The trust anchor of the appraiser.
+    pub fn trust_anchor(&self) -> &Certificate {
+        &self.trust_anchor
+    }
+
+    /// Appraise `quote` with `collateral`.
+    ///
+    /// # Arguments
+    /// * `quote` - The quote to appraise.
+    /// * `collateral` - The collateral for the platform of `quote`.
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    pub fn appraise<T: AsRef<[u8]>, S: TimeSource>(
+        &self,
+        quote: &Quote3<T>,
+        collateral: &Collateral,
+        time: S,
+    ) -> Result<Appraisal, Error> {
+        let pck_chain = pck_certificate_chain(quote)?;
+        self.appraise_with_pck_chain(quote, &pck_chain, collateral, time)
+    }
+
+    /// Appraise `quote` with `collateral` and the PCK certificate chain of
+    /// the platform.
+    ///
+    /// This is for quotes whose certification data is not a PCK certificate
+    /// chain, for instance the PPID forms, where the chain is retrieved
+    /// separately. The chain does not need to be trusted, it is verified the
+    /// same as an embedded chain.
+    ///
+    /// # Arguments
+    /// * `quote` - The quote to appraise.
+    /// * `pck_chain` - The PCK certificate chain, leaf first, of the platform
+    ///   of `quote`.
+    /// * `collateral` - The collateral for the platform of `quote`.
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    pub fn appraise_with_pck_chain<T: AsRef<[u8]>, S: TimeSource>(
+        &self,
+        quote: &Quote3<T>,
+        pck_chain: &[Certificate],
+        collateral: &Collateral,
+        time: S,
+    ) -> Result<Appraisal, Error> {
+        // Read once, so the collateral and the quote are checked at the same
+        // time.
+        let now = time.now()?;
+        let collateral = self.verify_collateral(collateral, now)?;
+        self.appraise_with_verified_collateral(quote, pck_chain, &collateral, now)
+    }
+
+    /// Verify `collateral` once, for appraising any number of quotes with it.
+    ///
+    /// Verifying the CRLs, the issuer chains and the signed JSON of the
+    /// collateral is most of the work of an appraisal. Quotes from platforms
+    /// sharing the collateral can be appraised with
+    /// [`QuoteAppraiser::appraise_with_verified_collateral()`] without
+    /// repeating it.
+    ///
+    /// # Arguments
+    /// * `collateral` - The collateral to verify.
+    /// * `time` - The time of the verification, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    pub fn verify_collateral<S: TimeSource>(
+        &self,
+        collateral: &Collateral,
+        time: S,
+    ) -> Result<VerifiedCollateral, Error> {
+        let now = time.now()?;
+        let mut validity = Vec::new();
+
+        let root_crl = collateral.root_ca_crl();
+        validity.push(verify_crl(root_crl, &self.trust_anchor, now)?);
+        let root_revoked = RevokedSerials::from(root_crl);
+
+        let pck_crl_issuer_chain = collateral.pck_crl_issuer_chain();
+        validity.extend(self.verify_chain(
+            pck_crl_issuer_chain,
+            slice::from_ref(&root_revoked),
+            now,
+        )?);
+        let pck_crl = collateral.pck_crl();
+        validity.push(verify_crl(pck_crl, &pck_crl_issuer_chain[0], now)?);
+
+        validity.extend(self.verify_chain(
+            collateral.tcb_issuer_chain(),
+            slice::from_ref(&root_revoked),
+            now,
+        )?);
+        let tcb_info = FmspcTcbInfo::from_signed_json(
+            collateral.tcb_info(),
+            &collateral.tcb_issuer_chain()[0],
+        )?;
+        validity.push(
+            Validity::new(
+                tcb_info.issue_date().unix_duration(),
+                Some(tcb_info.next_update().unix_duration()),
+                Error::TcbInfoValidity,
+            )
+            .ensure(now)?,
+        );
+
+        validity.extend(self.verify_chain(
+            collateral.qe_identity_issuer_chain(),
+            slice::from_ref(&root_revoked),
+            now,
+        )?);
+        let qe_identity = QeIdentity::from_signed_json(
+            collateral.qe_identity(),
+            &collateral.qe_identity_issuer_chain()[0],
+        )?;
+        validity.push(
+            Validity::new(
+                qe_identity.issue_date().unix_duration(),
+                Some(qe_identity.next_update().unix_duration()),
+                Error::QeIdentityValidity,
+            )
+            .ensure(now)?,
+        );
+
+        Ok(VerifiedCollateral {
+            trust_anchor: self.trust_anchor.clone(),
+            revoked: [root_revoked, RevokedSerials::from(pck_crl)],
+            tcb_info,
+            qe_identity,
+            validity,
+        })
+    }
+
+    /// Appraise `quote` with collateral verified by
+    /// [`QuoteAppraiser::verify_collateral()`].
+    ///
+    /// Only the checks specific to `quote` are done, the PCK certificate
+    /// chain, the quote signatures, and the TCB levels. The verified
+    /// collateral is checked to still be valid at the time of the appraisal.
+    ///
+    /// # Arguments
+    /// * `quote` - The quote to appraise.
+    /// * `pck_chain` - The PCK certificate chain, leaf first, of the platform
+    ///   of `quote`.
+    /// * `collateral` - The verified collateral for the platform of `quote`.
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    /// [`Error::UntrustedCertificateChain`] if `collateral` was verified with
+    /// a different trust anchor.
+    pub fn appraise_with_verified_collateral<T: AsRef<[u8]>, S: TimeSource>(
+        &self,
+        quote: &Quote3<T>,
+        pck_chain: &[Certificate],
+        collateral: &VerifiedCollateral,
+        time: S,
+    ) -> Result<Appraisal, Error> {
+        let now = time.now()?;
+        if collateral.trust_anchor != self.trust_anchor {
+            return Err(Error::UntrustedCertificateChain);
+        }
+        collateral.ensure_valid_at(now)?;
+        let pck_chain = self.verify_pck_chain(pck_chain, collateral, now)?;
+        self.appraise_with_verified_pck_chain(quote, &pck_chain, collateral, now)
+    }
+
+    /// Verify the PCK certificate chain of a platform once, for appraising
+    /// any number of its quotes with it.
+    ///
+    /// The chain is verified to lead to the trust anchor, with none of its
+    /// certificates revoked by the CRLs of `collateral`. Quotes from the
+    /// platform can be appraised with
+    /// [`QuoteAppraiser::appraise_with_verified_pck_chain()`] without
+    /// repeating the signature checks of the chain.
+    ///
+    /// # Arguments
+    /// * `pck_chain` - The PCK certificate chain, leaf first, of a platform.
+    /// * `collateral` - The verified collateral for the platform.
+    /// * `time` - The time of the verification, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    pub fn verify_pck_chain<S: TimeSource>(
+        &self,
+        pck_chain: &[Certificate],
+        collateral: &VerifiedCollateral,
+        time: S,
+    ) -> Result<VerifiedPckChain, Error> {
+        let now = time.now()?;
+        let validity = self.verify_chain(pck_chain, &collateral.revoked, now)?;
+        let pck_key = verifying_key(&pck_chain[0]).map_err(|_| Error::UnsupportedKey)?;
+        Ok(VerifiedPckChain {
+            trust_anchor: self.trust_anchor.clone(),
+            certificates: pck_chain.to_vec(),
+            pck_key,
+            validity,
+        })
+    }
+
+    /// Appraise `quote` with a PCK certificate chain verified by
+    /// [`QuoteAppraiser::verify_pck_chain()`] and collateral verified by
+    /// [`QuoteAppraiser::verify_collateral()`].
+    ///
+    /// The chain may have been verified with older collateral, so its
+    /// certificates are checked against the CRLs of `collateral` again. Both
+    /// are checked to still be valid at the time of the appraisal.
+    ///
+    /// # Arguments
+    /// * `quote` - The quote to appraise.
+    /// * `pck_chain` - The verified PCK certificate chain of the platform of
+    ///   `quote`.
+    /// * `collateral` - The verified collateral for the platform of `quote`.
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    /// [`Error::UntrustedCertificateChain`] if `pck_chain` or `collateral` was
+    /// verified with a different trust anchor.
+    pub fn appraise_with_verified_pck_chain<T: AsRef<[u8]>, S: TimeSource>(
+        &self,
+        quote: &Quote3<T>,
+        pck_chain: &VerifiedPckChain,
+        collateral: &VerifiedCollateral,
+        time: S,
+    ) -> Result<Appraisal, Error> {
+        let now = time.now()?;
+        if collateral.trust_anchor != self.trust_anchor
+            || pck_chain.trust_anchor != self.trust_anchor
+        {
+            return Err(Error::UntrustedCertificateChain);
+        }
+        collateral.ensure_valid_at(now)?;
+        pck_chain.ensure_valid_at(now)?;
+        pck_chain.ensure_not_revoked(&collateral.revoked)?;
+        quote.verify(&pck_chain.pck_key)?;
+
+        let pck_chain = &pck_chain.certificates;
+        let tcb_info = &collateral.tcb_info;
+        let pck_tcb = TcbInfo::try_from(&pck_chain[0])?;
+        if pck_tcb.fmspc() != tcb_info.fmspc() {
+            return Err(Error::FmspcMismatch);
+        }
+        let tcb_level = tcb_info
+            .tcb_level(&pck_tcb)
+            .ok_or(Error::UnsupportedTcbLevel)?;
+
+        let qe_identity = &collateral.qe_identity;
+        let signature_data = quote.signature_data();
+        let qe_report_body = signature_data.qe_report_body();
+        if !qe_identity.matches(qe_report_body) {
+            return Err(Error::QeIdentityMismatch);
+        }
+        let qe_tcb_level = qe_identity
+            .tcb_level(qe_report_body.isv_svn())
+            .ok_or(Error::UnsupportedQeTcbLevel)?;
+
+        let mut advisory_ids = tcb_level.advisory_ids().to_vec();
+        for id in qe_tcb_level.advisory_ids() {
+            if !advisory_ids.contains(id) {
+                advisory_ids.push(id.clone());
+            }
+        }
+
+        Ok(Appraisal {
+            report_body: quote.app_report_body().clone(),
+            tcb_status: tcb_level.tcb_status(),
+            qe_tcb_status: qe_tcb_level.tcb_status(),
+            advisory_ids,
+            tcb_evaluation_data_number: tcb_info.tcb_evaluation_data_number(),
+        })
+    }
+
+    /// Verify `chain`, leaf first, leads to the trust anchor.
+    ///
+    /// Every certificate, other than the trust anchor, must have a CRL in
+    /// `revoked` from its issuer and must not be revoked by that CRL.
+    ///
+    /// Returns the validity periods of the certificates in the chain.
+    fn verify_chain(
+        &self,
+        chain: &[Certificate],
+        revoked: &[RevokedSerials],
+        now: Duration,
+    ) -> Result<Vec<Validity>, Error> {
+        let (anchor, certificates) = chain.split_last().ok_or(Error::EmptyCertificateChain)?;
+        if anchor != &self.trust_anchor {
+            return Err(Error::UntrustedCertificateChain);
+        }
+        let mut validity = vec![certificate_validity(anchor).ensure(now)?];
+
+        for (certificate, issuer) in certificates.iter().zip(chain.iter().skip(1)) {
+            if certificate.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+                return Err(Error::UntrustedCertificateChain);
+            }
+            ensure_certificate_authority(issuer)?;
+            let key = verifying_key(issuer).map_err(|_| Error::UnsupportedKey)?;
+            verify_certificate(certificate, &key)
+                .map_err(|err| signature_error(err, Error::CertificateSignature))?;
+            validity.push(certificate_validity(certificate).ensure(now)?);
+            ensure_not_revoked(certificate, revoked)?;
+        }
+
+        Ok(validity)
+    }
+}
+
+/// [`Collateral`] which has been verified by a [`QuoteAppraiser`].
+///
+/// Holds the parsed TCB info and QE identity, the revoked serial numbers of
+/// the CRLs, and the validity periods of everything that was verified.
+/// Appraising with it at a time outside any of those periods fails the same
+/// as appraising with the [`Collateral`] would.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedCollateral {
+    trust_anchor: Certificate,
+    revoked: [RevokedSerials; 2],
+    tcb_info: FmspcTcbInfo,
+    qe_identity: QeIdentity,
+    validity: Vec<Validity>,
+}
+
+impl VerifiedCollateral {
+    /// The TCB info of the collateral.
+    pub fn tcb_info(&self) -> &FmspcTcbInfo {
+        &self.tcb_info
+    }
+
+    /// The QE identity of the collateral.
+    pub fn qe_identity(&self) -> &QeIdentity {
+        &self.qe_identity
+    }
+
+    /// Is every part of the collateral valid at `now`, the duration since the
+    /// UNIX epoch.
+    pub fn is_valid_at(&self, now: Duration) -> bool {
+        self.ensure_valid_at(now).is_ok()
+    }
+
+    /// The end of the earliest validity period of the collateral, as the
+    /// duration since the UNIX epoch.
+    ///
+    /// Usually the `nextUpdate` of the TCB info, QE identity or a CRL.
+    pub fn valid_until(&self) -> Option<Duration> {
+        self.validity
+            .iter()
+            .filter_map(|validity| validity.end)
+            .min()
+    }
+
+    fn ensure_valid_at(&self, now: Duration) -> Result<(), Error> {
+        ensure_valid_at(&self.validity, now)
+    }
+}
+
+/// A PCK certificate chain which has been verified by a [`QuoteAppraiser`].
+///
+/// Holds the certificates, the key of the PCK certificate, and the validity
+/// periods of the certificates. Appraising with it at a time outside any of
+/// those periods fails the same as appraising with the chain would.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedPckChain {
+    trust_anchor: Certificate,
+    certificates: Vec<Certificate>,
+    pck_key: VerifyingKey,
+    validity: Vec<Validity>,
+}
+
+impl VerifiedPckChain {
+    /// The certificates of the chain, leaf first.
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    /// Is every certificate of the chain valid at `now`, the duration since
+    /// the UNIX epoch.
+    pub fn is_valid_at(&self, now: Duration) -> bool {
+        self.ensure_valid_at(now).is_ok()
+    }
+
+    fn ensure_valid_at(&self, now: Duration) -> Result<(), Error> {
+        ensure_valid_at(&self.validity, now)
+    }
+
+    /// Ensure no certificate, other than the trust anchor, is revoked by
+    /// `revoked`.
+    fn ensure_not_revoked(&self, revoked: &[RevokedSerials]) -> Result<(), Error> {
+        let (_, certificates) = self
+            .certificates
+            .split_last()
+            .ok_or(Error::EmptyCertificateChain)?;
+        certificates
+            .iter()
+            .try_for_each(|certificate| ensure_not_revoked(certificate, revoked))
+    }
+}
+
+/// The serial numbers revoked by a CRL.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct RevokedSerials {
+    issuer: Name,
+    serial_numbers: BTreeSet<Vec<u8>>,
+}
+
+impl From<&CertificateList> for RevokedSerials {
+    fn from(crl: &CertificateList) -> Self {
+        let tbs = &crl.tbs_cert_list;
+        let serial_numbers = tbs
+            .revoked_certificates
+            .iter()
+            .flatten()
+            .map(|revoked| revoked.serial_number.as_bytes().to_vec())
+            .collect();
+        Self {
+            issuer: tbs.issuer.clone(),
+            serial_numbers,
+        }
+    }
+}
+
+/// The period something is valid for, and the error when it's not.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Validity {
+    start: Duration,
+    end: Option<Duration>,
+    error: Error,
+}
+
+impl Validity {
+    fn new(start: Duration, end: Option<Duration>, error: Error) -> Self {
+        Self { start, end, error }
+    }
+
+    fn contains(&self, now: Duration) -> bool {
+        now >= self.start && self.end.map_or(true, |end| now <= end)
+    }
+
+    /// Ensure `now` is within the period, returning the period for later
+    /// checks.
+    fn ensure(self, now: Duration) -> Result<Self, Error> {
+        match self.contains(now) {
+            true => Ok(self),
+            false => Err(self.error),
+        }
+    }
+}
+
+/// The requirements on an enclave, and its platform, for an [`Appraisal`] to
+/// be accepted.
+///
+/// By default only the [`TcbStatus::UpToDate`] status is accepted and debug
+/// enclaves are rejected.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IdentityPolicy {
+    mr_enclave: Option<MrEnclave>,
+    mr_signer: Option<(MrSigner, IsvProductId, IsvSvn)>,
+    allow_debug: bool,
+    accepted_tcb_statuses: Vec<TcbStatus>,
+}
+
+impl IdentityPolicy {
+    /// A policy requiring the enclave to have `mr_enclave`.
+    pub fn mr_enclave(mr_enclave: MrEnclave) -> Self {
+        Self::new(Some(mr_enclave), None)
+    }
+
+    /// A policy requiring the enclave to be signed by `mr_signer`, for
+    /// `product_id`, with an ISV SVN of at least `minimum_svn`.
+    pub fn mr_signer(mr_signer: MrSigner, product_id: IsvProductId, minimum_svn: IsvSvn) -> Self {
+        Self::new(None, Some((mr_signer, product_id, minimum_svn)))
+    }
+
+    fn new(
+        mr_enclave: Option<MrEnclave>,
+        mr_signer: Option<(MrSigner, IsvProductId, IsvSvn)>,
+    ) -> Self {
+        Self {
+            mr_enclave,
+            mr_signer,
+            allow_debug: false,
+            accepted_tcb_statuses: vec![TcbStatus::UpToDate],
+        }
+    }
+
+    /// Allow, or disallow, debug enclaves.
+    #[must_use]
+    pub fn allow_debug(mut self, allow_debug: bool) -> Self {
+        self.allow_debug = allow_debug;
+        self
+    }
+
+    /// Accept the TCB `status` for the platform and quoting enclave, in
+    /// addition to [`TcbStatus::UpToDate`].
+    #[must_use]
+    pub fn accept_tcb_status(mut self, status: TcbStatus) -> Self {
+        if !self.accepted_tcb_statuses.contains(&status) {
+            self.accepted_tcb_statuses.push(status);
+        }
+        self
+    }
+
+    /// Check that `appraisal` satisfies this policy.
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first requirement that was not met.
+    pub fn check(&self, appraisal: &Appraisal) -> Result<(), Error> {
+        for status in [appraisal.tcb_status(), appraisal.qe_tcb_status()] {
+            if !self.accepted_tcb_statuses.contains(&status) {
+                return Err(Error::TcbStatus(status));
+            }
+        }
+
+        let report_body = appraisal.report_body();
+        if let Some(mr_enclave) = self.mr_enclave {
+            if report_body.mr_enclave() != mr_enclave {
+                return Err(Error::MrEnclaveMismatch);
+            }
+        }
+        if let Some((mr_signer, product_id, minimum_svn)) = self.mr_signer {
+            if report_body.mr_signer() != mr_signer {
+                return Err(Error::MrSignerMismatch);
+            }
+            if report_body.isv_product_id() != product_id {
+                return Err(Error::IsvProductIdMismatch);
+            }
+            let svn = report_body.isv_svn();
+            if u16::from(svn) < u16::from(minimum_svn) {
+                return Err(Error::IsvSvnTooLow(svn, minimum_svn));
+            }
+        }
+
+        let flags = AttributeFlags::from_bits_retain(report_body.attributes().as_ref().flags);
+        if !self.allow_debug && flags.contains(AttributeFlags::DEBUG) {
+            return Err(Error::DebugEnclave);
+        }
+
+        Ok(())
+    }
+}
+
+/// The PCK certificate chain, leaf first, from the certification data of
+/// `quote`.
+fn pck_certificate_chain<T: AsRef<[u8]>>(quote: &Quote3<T>) -> Result<Vec<Certificate>, Error> {
+    let signature_data = quote.signature_data();
+    let CertificationData::PckCertificateChain(pem_chain) = signature_data.certification_data()
+    else {
+        return Err(Error::UnsupportedCertificationData);
+    };
+    let chain = pem_chain
+        .into_iter()
+        .map(Certificate::from_pem)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(chain)
+}
+
+/// Verify `crl` is signed by `issuer` and is valid at `now`.
+///
+/// Returns the validity period of `crl`.
+fn verify_crl(
+    crl: &CertificateList,
+    issuer: &Certificate,
+    now: Duration,
+) -> Result<Validity, Error> {
+    let tbs = &crl.tbs_cert_list;
+    if tbs.issuer != issuer.tbs_certificate.subject || tbs.signature != crl.signature_algorithm {
+        return Err(Error::CrlSignature);
+    }
+    let key = verifying_key(issuer).map_err(|_| Error::UnsupportedKey)?;
+    verify(
+        &tbs.to_der()?,
+        &crl.signature_algorithm,
+        &crl.signature,
+        &key,
+    )
+    .map_err(|err| signature_error(err, Error::CrlSignature))?;
+
+    Validity::new(
+        tbs.this_update.to_unix_duration(),
+        tbs.next_update.map(|time| time.to_unix_duration()),
+        Error::CrlValidity,
+    )
+    .ensure(now)
+}
+
+/// Ensure `now` is within every period of `validity`.
+fn ensure_valid_at(validity: &[Validity], now: Duration) -> Result<(), Error> {
+    match validity.iter().find(|validity| !validity.contains(now)) {
+        Some(validity) => Err(validity.error.clone()),
+        None => Ok(()),
+    }
+}
+
+fn certificate_validity(certificate: &Certificate) -> Validity {
+    let validity = &certificate.tbs_certificate.validity;
+    Validity::new(
+        validity.not_before.to_unix_duration(),
+        Some(validity.not_after.to_unix_duration()),
+        Error::CertificateValidity,
+    )
+}
+
+fn ensure_certificate_authority(certificate: &Certificate) -> Result<(), Error> {
+    let (_, constraints) = certificate
+        .tbs_certificate
+        .get::<BasicConstraints>()?
+        .ok_or(Error::NotCertificateAuthority)?;
+    match constraints.ca {
+        true => Ok(()),
+        false => Err(Error::NotCertificateAuthority),
+    }
+}
+
+fn ensure_not_revoked(certificate: &Certificate, revoked: &[RevokedSerials]) -> Result<(), Error> {
+    let issuer = &certificate.tbs_certificate.issuer;
+    let revoked = revoked
+        .iter()
+        .find(|revoked| &revoked.issuer == issuer)
+        .ok_or(Error::MissingCrl)?;
+    let serial_number = certificate.tbs_certificate.serial_number.as_bytes();
+    match revoked.serial_numbers.contains(serial_number) {
+        true => Err(Error::CertificateRevoked),
+        false => Ok(()),
+    }
+}
+
+fn signature_error(err: SignatureError, invalid: Error) -> Error {
+    match err {
+        SignatureError::Der(err) => Error::Der(err),
+        SignatureError::UnsupportedKey => Error::UnsupportedKey,
+        SignatureError::UnsupportedAlgorithm => Error::UnsupportedSignatureAlgorithm,
+        SignatureError::Invalid => invalid,
+    }
+}
+
+#[cfg(all(test, feature = "ra-tls"))]
+mod test {
+    use super::*;
+    use crate::test_utils::{TestCa, TCB_EVALUATION_DATA_NUMBER};
+    use assert_matches::assert_matches;
+    use mc_sgx_core_sys_types::{sgx_measurement_t, sgx_report_body_t};
+    use mc_sgx_core_types::ReportData;
+    use x509_cert::der::DateTime;
+    use yare::parameterized;
+
+    const MR_ENCLAVE: [u8; 32] = [0xAE; 32];
+    const MR_SIGNER: [u8; 32] = [0x51; 32];
+    const PRODUCT_ID: u16 = 3;
+    const ISV_SVN: u16 = 5;
+
+    // 2024-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_717_200_000);
+
+    fn report_body(flags: u64) -> ReportBody {
+        let mut body: sgx_report_body_t = ReportBody::default().into();
+        body.mr_enclave = sgx_measurement_t { m: MR_ENCLAVE };
+        body.mr_signer = sgx_measurement_t { m: MR_SIGNER };
+        body.isv_prod_id = PRODUCT_ID;
+        body.isv_svn = ISV_SVN;
+        body.attributes.flags = flags;
+        body.into()
+    }
+
+    fn appraise(ca: &TestCa, collateral: &Collateral) -> Result<Appraisal, Error> {
+        let quote = ca.quote(&report_body(0));
+        QuoteAppraiser::new(ca.root_certificate().clone()).appraise(&quote, collateral, NOW)
+    }
+
+    fn date(year: u16, month: u8, day: u8) -> DateTime {
+        DateTime::new(year, month, day, 0, 0, 0).expect("Invalid date")
+    }
+
+    #[test]
+    fn appraise_up_to_date_quote() {
+        let ca = TestCa::default();
+
+        let appraisal = appraise(&ca, &ca.collateral()).expect("Failed to appraise quote");
+
+        assert_eq!(appraisal.tcb_status(), TcbStatus::UpToDate);
+        assert_eq!(appraisal.qe_tcb_status(), TcbStatus::UpToDate);
+        assert!(appraisal.advisory_ids().is_empty());
+        assert_eq!(
+            appraisal.tcb_evaluation_data_number(),
+            TCB_EVALUATION_DATA_NUMBER
+        );
+        assert_eq!(appraisal.report_body(), &report_body(0));
+    }
+
+    #[test]
+    fn appraise_reports_tcb_status_and_advisories() {
+        let ca = TestCa::default();
+        let collateral = ca
+            .collateral_builder()
+            .tcb_status(TcbStatus::SwHardeningNeeded)
+            .advisory_ids(&["INTEL-SA-00615", "INTEL-SA-00657"])
+            .qe_tcb_status(TcbStatus::OutOfDate)
+            .build();
+
+        let appraisal = appraise(&ca, &collateral).expect("Failed to appraise quote");
+
+        assert_eq!(appraisal.tcb_status(), TcbStatus::SwHardeningNeeded);
+        assert_eq!(appraisal.qe_tcb_status(), TcbStatus::OutOfDate);
+        assert_eq!(
+            appraisal.advisory_ids(),
+            ["INTEL-SA-00615", "INTEL-SA-00657"]
+        );
+    }
+
+    #[test]
+    fn revoked_pck_certificate_fails() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral_builder().revoke_pck().build();
+
+        assert_eq!(appraise(&ca, &collateral), Err(Error::CertificateRevoked));
+    }
+
+    #[test]
+    fn different_trust_anchor_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote_for_report_data(&ReportData::default());
+        let appraiser = QuoteAppraiser::new(ca.intermediate_certificate().clone());
+
+        // The root CA CRL is the first item checked against the trust anchor
+        assert_eq!(
+            appraiser.appraise(&quote, &ca.collateral(), NOW),
+            Err(Error::CrlSignature)
+        );
+    }
+
+    #[test]
+    fn hardware_quote_fails_with_test_trust_anchor() {
+        let ca = TestCa::default();
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        assert_eq!(
+            appraiser.appraise(&quote, &ca.collateral(), NOW),
+            Err(Error::UntrustedCertificateChain)
+        );
+    }
+
+    /// A quote from the test CA with plaintext PPID certification data
+    fn ppid_quote(ca: &TestCa) -> Quote3<Vec<u8>> {
+        // PPID, CPUSVN, PCESVN, and PCEID
+        let ppid_data = [0x11; 16 + 16 + 2 + 2];
+        ca.quote_with_certification_data(&report_body(0), 1, &ppid_data)
+    }
+
+    fn pck_chain(ca: &TestCa) -> Vec<Certificate> {
+        Certificate::load_pem_chain(ca.pck_certificate_chain_pem().as_bytes())
+            .expect("Failed to decode PCK chain")
+    }
+
+    #[test]
+    fn appraise_ppid_quote_fails_without_chain() {
+        let ca = TestCa::default();
+        let quote = ppid_quote(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        assert_eq!(
+            appraiser.appraise(&quote, &ca.collateral(), NOW),
+            Err(Error::UnsupportedCertificationData)
+        );
+    }
+
+    #[test]
+    fn appraise_ppid_quote_with_pck_chain() {
+        let ca = TestCa::default();
+        let quote = ppid_quote(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        let appraisal = appraiser
+            .appraise_with_pck_chain(&quote, &pck_chain(&ca), &ca.collateral(), NOW)
+            .expect("Failed to appraise quote");
+
+        assert_eq!(appraisal.tcb_status(), TcbStatus::UpToDate);
+        assert_eq!(appraisal.report_body(), &report_body(0));
+    }
+
+    #[test]
+    fn appraise_with_pck_chain_of_another_platform_fails() {
+        let ca = TestCa::default();
+        let quote = ppid_quote(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        // The TCB signing certificate is issued by the same root but its key
+        // didn't sign the QE report.
+        let chain = [
+            ca.tcb_signing_certificate().clone(),
+            ca.root_certificate().clone(),
+        ];
+
+        assert_eq!(
+            appraiser.appraise_with_pck_chain(&quote, &chain, &ca.collateral(), NOW),
+            Err(Error::Quote(Quote3Error::SignatureVerification))
+        );
+    }
+
+    #[test]
+    fn appraise_with_empty_pck_chain_fails() {
+        let ca = TestCa::default();
+        let quote = ppid_quote(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        assert_eq!(
+            appraiser.appraise_with_pck_chain(&quote, &[], &ca.collateral(), NOW),
+            Err(Error::EmptyCertificateChain)
+        );
+    }
+
+    #[parameterized(
+        before_collateral = { date(2023, 12, 31), Error::CrlValidity },
+        after_collateral = { date(2024, 3, 1), Error::CrlValidity },
+    )]
+    fn collateral_validity(now: DateTime, expected: Error) {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let collateral = ca
+            .collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, 2, 1))
+            .build();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        assert_eq!(
+            appraiser.appraise(&quote, &collateral, now.unix_duration()),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn expired_certificates_fail() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let collateral = ca
+            .collateral_builder()
+            .validity(date(2024, 1, 1), date(2099, 1, 1))
+            .build();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        assert_eq!(
+            appraiser.appraise(&quote, &collateral, date(2050, 1, 1).unix_duration()),
+            Err(Error::CertificateValidity)
+        );
+    }
+
+    #[test]
+    fn modified_tcb_info_fails() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        let tcb_info = collateral
+            .tcb_info()
+            .replacen("\"UpToDate\"", "\"Revoked\"", 1);
+        let collateral = Collateral::new(
+            collateral.root_ca_crl().clone(),
+            collateral.pck_crl_issuer_chain().to_vec(),
+            collateral.pck_crl().clone(),
+            collateral.tcb_issuer_chain().to_vec(),
+            tcb_info,
+            collateral.qe_identity_issuer_chain().to_vec(),
+            collateral.qe_identity().into(),
+        );
+
+        assert_eq!(appraise(&ca, &collateral), Err(Error::JsonSignature));
+    }
+
+    #[test]
+    fn missing_pck_crl_fails() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        // Use the root CRL in place of the PCK CRL, leaving no CRL from the
+        // PCK certificate's issuer
+        let collateral = Collateral::new(
+            collateral.root_ca_crl().clone(),
+            vec![ca.root_certificate().clone()],
+            collateral.root_ca_crl().clone(),
+            collateral.tcb_issuer_chain().to_vec(),
+            collateral.tcb_info().into(),
+            collateral.qe_identity_issuer_chain().to_vec(),
+            collateral.qe_identity().into(),
+        );
+
+        assert_eq!(appraise(&ca, &collateral), Err(Error::MissingCrl));
+    }
+
+    #[test]
+    fn corrupt_quote_signature_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let mut bytes = quote.as_ref().to_vec();
+        // The first byte of the ISV enclave signature
+        bytes[436] ^= 0xFF;
+        let quote = Quote3::try_from(bytes).expect("Failed to parse quote");
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        assert_matches!(
+            appraiser.appraise(&quote, &ca.collateral(), NOW),
+            Err(Error::Quote(Quote3Error::SignatureVerification))
+        );
+    }
+
+    #[test]
+    fn verified_collateral_appraises_many_quotes() {
+        let ca = TestCa::default();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let collateral = appraiser
+            .verify_collateral(&ca.collateral(), NOW)
+            .expect("Failed to verify collateral");
+
+        for flags in [0, 2] {
+            let quote = ca.quote(&report_body(flags));
+            let pck_chain = pck_certificate_chain(&quote).expect("Failed to get PCK chain");
+            assert_eq!(
+                appraiser.appraise_with_verified_collateral(&quote, &pck_chain, &collateral, NOW),
+                appraiser.appraise(&quote, &ca.collateral(), NOW)
+            );
+        }
+    }
+
+    #[test]
+    fn verified_collateral_valid_until_earliest_next_update() {
+        let ca = TestCa::default();
+        let collateral = ca
+            .collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, 2, 1))
+            .build();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        let verified = appraiser
+            .verify_collateral(&collateral, date(2024, 1, 15).unix_duration())
+            .expect("Failed to verify collateral");
+
+        assert_eq!(
+            verified.valid_until(),
+            Some(date(2024, 2, 1).unix_duration())
+        );
+        assert!(verified.is_valid_at(date(2024, 1, 31).unix_duration()));
+        assert!(!verified.is_valid_at(date(2024, 2, 2).unix_duration()));
+    }
+
+    #[parameterized(
+        before_collateral = { date(2023, 12, 31), Error::CrlValidity },
+        after_collateral = { date(2024, 3, 1), Error::CrlValidity },
+    )]
+    fn verified_collateral_rechecks_validity(now: DateTime, expected: Error) {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let pck_chain = pck_certificate_chain(&quote).expect("Failed to get PCK chain");
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let collateral = ca
+            .collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, 2, 1))
+            .build();
+        let verified = appraiser
+            .verify_collateral(&collateral, date(2024, 1, 15).unix_duration())
+            .expect("Failed to verify collateral");
+
+        let now = now.unix_duration();
+        assert_eq!(
+            appraiser.appraise_with_verified_collateral(&quote, &pck_chain, &verified, now),
+            Err(expected.clone())
+        );
+        assert_eq!(
+            appraiser.appraise_with_pck_chain(&quote, &pck_chain, &collateral, now),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn verified_collateral_from_another_trust_anchor_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let pck_chain = pck_certificate_chain(&quote).expect("Failed to get PCK chain");
+        let appraiser = QuoteAppraiser::new(ca.intermediate_certificate().clone());
+        let verified = QuoteAppraiser::new(ca.root_certificate().clone())
+            .verify_collateral(&ca.collateral(), NOW)
+            .expect("Failed to verify collateral");
+
+        assert_eq!(
+            appraiser.appraise_with_verified_collateral(&quote, &pck_chain, &verified, NOW),
+            Err(Error::UntrustedCertificateChain)
+        );
+    }
+
+    #[test]
+    fn verified_pck_chain_appraises_many_quotes() {
+        let ca = TestCa::default();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let collateral = appraiser
+            .verify_collateral(&ca.collateral(), NOW)
+            .expect("Failed to verify collateral");
+        let verified_chain = appraiser
+            .verify_pck_chain(&pck_chain(&ca), &collateral, NOW)
+            .expect("Failed to verify PCK chain");
+
+        assert_eq!(verified_chain.certificates(), pck_chain(&ca));
+        for flags in [0, 2] {
+            let quote = ca.quote(&report_body(flags));
+            assert_eq!(
+                appraiser.appraise_with_verified_pck_chain(
+                    &quote,
+                    &verified_chain,
+                    &collateral,
+                    NOW
+                ),
+                appraiser.appraise(&quote, &ca.collateral(), NOW)
+            );
+        }
+    }
+
+    #[test]
+    fn verified_pck_chain_rechecks_revocation() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let collateral = appraiser
+            .verify_collateral(&ca.collateral(), NOW)
+            .expect("Failed to verify collateral");
+        let verified_chain = appraiser
+            .verify_pck_chain(&pck_chain(&ca), &collateral, NOW)
+            .expect("Failed to verify PCK chain");
+        let revoking = appraiser
+            .verify_collateral(&ca.collateral_builder().revoke_pck().build(), NOW)
+            .expect("Failed to verify collateral");
+
+        assert_eq!(
+            appraiser.appraise_with_verified_pck_chain(&quote, &verified_chain, &revoking, NOW),
+            Err(Error::CertificateRevoked)
+        );
+    }
+
+    #[test]
+    fn verified_pck_chain_rechecks_validity() {
+        let ca = TestCa::default();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let collateral = appraiser
+            .verify_collateral(&ca.collateral(), NOW)
+            .expect("Failed to verify collateral");
+        let verified_chain = appraiser
+            .verify_pck_chain(&pck_chain(&ca), &collateral, NOW)
+            .expect("Failed to verify PCK chain");
+        let not_before = ca
+            .pck_certificate()
+            .tbs_certificate
+            .validity
+            .not_before
+            .to_unix_duration();
+
+        assert!(verified_chain.is_valid_at(NOW));
+        assert!(!verified_chain.is_valid_at(not_before - Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn verified_pck_chain_from_another_trust_anchor_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let collateral = appraiser
+            .verify_collateral(&ca.collateral(), NOW)
+            .expect("Failed to verify collateral");
+        let verified_chain = appraiser
+            .verify_pck_chain(&pck_chain(&ca), &collateral, NOW)
+            .expect("Failed to verify PCK chain");
+        let other = QuoteAppraiser::new(ca.intermediate_certificate().clone());
+
+        assert_eq!(
+            other.appraise_with_verified_pck_chain(&quote, &verified_chain, &collateral, NOW),
+            Err(Error::UntrustedCertificateChain)
+        );
+    }
+
+    #[test]
+    fn appraise_with_monotonic_floor() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let time = crate::MonotonicFloor::new(NOW);
+
+        assert_eq!(
+            appraiser.appraise(&quote, &ca.collateral(), &time),
+            appraiser.appraise(&quote, &ca.collateral(), NOW)
+        );
+    }
+
+    #[test]
+    fn appraise_with_time_before_floor_fails() {
+        let ca = TestCa::default();
+        let quote = ca.quote(&report_body(0));
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let floor = NOW + Duration::from_secs(1);
+        let time = crate::MonotonicFloor::new(NOW).initial_floor(floor);
+
+        assert_eq!(
+            appraiser.appraise(&quote, &ca.collateral(), &time),
+            Err(Error::Time(TimeError::Backwards { floor, now: NOW }))
+        );
+    }
+
+    fn appraisal(flags: u64, status: TcbStatus) -> Appraisal {
+        Appraisal {
+            report_body: report_body(flags),
+            tcb_status: status,
+            qe_tcb_status: TcbStatus::UpToDate,
+            advisory_ids: vec![],
+            tcb_evaluation_data_number: TCB_EVALUATION_DATA_NUMBER,
+        }
+    }
+
+    fn mr_signer_policy(minimum_svn: u16) -> IdentityPolicy {
+        IdentityPolicy::mr_signer(
+            MrSigner::from(MR_SIGNER),
+            IsvProductId::from(PRODUCT_ID),
+            IsvSvn::from(minimum_svn),
+        )
+    }
+
+    #[parameterized(
+        mr_enclave = { IdentityPolicy::mr_enclave(MrEnclave::from(MR_ENCLAVE)) },
+        mr_signer = { mr_signer_policy(ISV_SVN) },
+        mr_signer_older_svn = { mr_signer_policy(ISV_SVN - 1) },
+    )]
+    fn policy_accepts(policy: IdentityPolicy) {
+        assert_eq!(policy.check(&appraisal(0, TcbStatus::UpToDate)), Ok(()));
+    }
+
+    #[parameterized(
+        mr_enclave = { IdentityPolicy::mr_enclave(MrEnclave::from([0; 32])), Error::MrEnclaveMismatch },
+        mr_signer = { IdentityPolicy::mr_signer(MrSigner::from([0; 32]), IsvProductId::from(PRODUCT_ID), IsvSvn::from(0)), Error::MrSignerMismatch },
+        product_id = { IdentityPolicy::mr_signer(MrSigner::from(MR_SIGNER), IsvProductId::from(PRODUCT_ID + 1), IsvSvn::from(0)), Error::IsvProductIdMismatch },
+        newer_svn = { mr_signer_policy(ISV_SVN + 1), Error::IsvSvnTooLow(ISV_SVN.into(), (ISV_SVN + 1).into()) },
+    )]
+    fn policy_rejects(policy: IdentityPolicy, expected: Error) {
+        assert_eq!(
+            policy.check(&appraisal(0, TcbStatus::UpToDate)),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn policy_rejects_debug_enclave() {
+        let policy = IdentityPolicy::mr_enclave(MrEnclave::from(MR_ENCLAVE));
+        let appraisal = appraisal(AttributeFlags::DEBUG.bits(), TcbStatus::UpToDate);
+
+        assert_eq!(policy.check(&appraisal), Err(Error::DebugEnclave));
+        assert_eq!(policy.allow_debug(true).check(&appraisal), Ok(()));
+    }
+
+    #[test]
+    fn policy_tcb_status() {
+        let policy = IdentityPolicy::mr_enclave(MrEnclave::from(MR_ENCLAVE));
+        let appraisal = appraisal(0, TcbStatus::SwHardeningNeeded);
+
+        assert_eq!(
+            policy.check(&appraisal),
+            Err(Error::TcbStatus(TcbStatus::SwHardeningNeeded))
+        );
+        let policy = policy.accept_tcb_status(TcbStatus::SwHardeningNeeded);
+        assert_eq!(policy.check(&appraisal), Ok(()));
+    }
+
+    #[test]
+    fn policy_checks_qe_tcb_status() {
+        let policy = IdentityPolicy::mr_enclave(MrEnclave::from(MR_ENCLAVE));
+        let mut appraisal = appraisal(0, TcbStatus::UpToDate);
+        appraisal.qe_tcb_status = TcbStatus::OutOfDate;
+
+        assert_eq!(
+            policy.check(&appraisal),
+            Err(Error::TcbStatus(TcbStatus::OutOfDate))
+        );
+    }
+}
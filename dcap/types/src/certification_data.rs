@@ -4,6 +4,7 @@
 
 use crate::quote3::{le_u16, le_u32};
 use crate::Quote3Error;
+use mc_sgx_core_types::CpuSvn;
 
 /// The minimum size of a byte array to contain a [`CertificationData`]
 /// The 2(type) + 4(size) for QE certification data
@@ -74,7 +75,16 @@ impl<'a> TryFrom<&'a [u8]> for CertificationData<'a> {
             return Err(Quote3Error::InputLength { required, actual });
         }
         let bytes = &bytes[..data_size];
-        let data = match data_type {
+        Self::from_kind(data_type, bytes).ok_or(Quote3Error::CertificationDataType(data_type))
+    }
+}
+
+impl<'a> CertificationData<'a> {
+    /// The certification data of type `kind` with the data `bytes`.
+    ///
+    /// Returns `None` for an unknown `kind`.
+    pub(crate) fn from_kind(kind: u16, bytes: &'a [u8]) -> Option<Self> {
+        let data = match kind {
             Ppid::KIND => CertificationData::Ppid(Ppid(bytes)),
             PpidEncryptedRsa2048::KIND => {
                 CertificationData::PpidEncryptedRsa2048(PpidEncryptedRsa2048(bytes))
@@ -90,13 +100,24 @@ impl<'a> TryFrom<&'a [u8]> for CertificationData<'a> {
                 CertificationData::EcdsaSignatureAuxData(EcdsaSignatureAuxData(bytes))
             }
             PlatformManifest::KIND => CertificationData::PlatformManifest(PlatformManifest(bytes)),
-            x => return Err(Quote3Error::CertificationDataType(x)),
+            _ => return None,
         };
-        Ok(data)
+        Some(data)
+    }
+
+    /// The certification data type
+    pub(crate) fn kind(&self) -> u16 {
+        match self {
+            Self::Ppid(_) => Ppid::KIND,
+            Self::PpidEncryptedRsa2048(_) => PpidEncryptedRsa2048::KIND,
+            Self::PpidEncryptedRsa3072(_) => PpidEncryptedRsa3072::KIND,
+            Self::Pck(_) => Pck::KIND,
+            Self::PckCertificateChain(_) => PckCertificateChain::KIND,
+            Self::EcdsaSignatureAuxData(_) => EcdsaSignatureAuxData::KIND,
+            Self::PlatformManifest(_) => PlatformManifest::KIND,
+        }
     }
-}
 
-impl<'a> CertificationData<'a> {
     /// Data which makes up the certification data
     ///
     /// `Certification Data` member from Table 9 of
@@ -195,12 +216,133 @@ impl<'a> CertificationDataKind for EcdsaSignatureAuxData<'a> {
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 /// Platform manifest
-pub struct PlatformManifest<'a>(&'a [u8]);
+pub struct PlatformManifest<'a>(pub(crate) &'a [u8]);
 
 impl<'a> CertificationDataKind for PlatformManifest<'a> {
     const KIND: u16 = 7;
 }
 
+/// The size of a plaintext platform provisioning ID (PPID)
+const PPID_SIZE: usize = 16;
+
+/// The size of a PPID encrypted with RSA 2048
+const PPID_RSA2048_SIZE: usize = 256;
+
+/// The size of a PPID encrypted with RSA 3072
+const PPID_RSA3072_SIZE: usize = 384;
+
+/// The size of the CPUSVN, PCESVN, and PCEID following the PPID
+const PLATFORM_TCB_SIZE: usize = CpuSvn::SIZE + 2 + 2;
+
+/// How the platform provisioning ID (PPID) of a [`PlatformId`] is encrypted.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PpidEncryption {
+    /// The PPID is in plaintext
+    None,
+    /// The PPID is encrypted with RSA 2048 OAEP
+    Rsa2048,
+    /// The PPID is encrypted with RSA 3072 OAEP, as required by the Intel PCS
+    Rsa3072,
+}
+
+/// The platform identifiers of the PPID forms of [`CertificationData`].
+///
+/// These identify the Provisioning Certification Key (PCK) certificate of
+/// the platform, which can be retrieved from the Intel PCS or a PCCS.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PlatformId<'a> {
+    ppid: &'a [u8],
+    encryption: PpidEncryption,
+    cpu_svn: CpuSvn,
+    pce_svn: u16,
+    pce_id: u16,
+}
+
+impl<'a> PlatformId<'a> {
+    /// The platform provisioning ID (PPID), encrypted per [`Self::encryption()`]
+    pub fn ppid(&self) -> &'a [u8] {
+        self.ppid
+    }
+
+    /// How the PPID is encrypted
+    pub fn encryption(&self) -> PpidEncryption {
+        self.encryption
+    }
+
+    /// The CPU security version number (CPUSVN) of the platform
+    pub fn cpu_svn(&self) -> &CpuSvn {
+        &self.cpu_svn
+    }
+
+    /// The provisioning certification enclave security version number (PCESVN)
+    pub fn pce_svn(&self) -> u16 {
+        self.pce_svn
+    }
+
+    /// The provisioning certification enclave ID (PCEID)
+    pub fn pce_id(&self) -> u16 {
+        self.pce_id
+    }
+}
+
+impl<'a> TryFrom<&CertificationData<'a>> for PlatformId<'a> {
+    type Error = Quote3Error;
+
+    /// The platform identifiers of `certification_data`.
+    ///
+    /// # Errors
+    /// * [`Quote3Error::CertificationDataType`] if `certification_data` is not
+    ///   one of the PPID forms.
+    /// * [`Quote3Error::InputLength`] if the data is not the size of the PPID,
+    ///   CPUSVN, PCESVN, and PCEID.
+    fn try_from(certification_data: &CertificationData<'a>) -> Result<Self> {
+        let (bytes, encryption, ppid_size) = match certification_data {
+            CertificationData::Ppid(Ppid(bytes)) => (*bytes, PpidEncryption::None, PPID_SIZE),
+            CertificationData::PpidEncryptedRsa2048(PpidEncryptedRsa2048(bytes)) => {
+                (*bytes, PpidEncryption::Rsa2048, PPID_RSA2048_SIZE)
+            }
+            CertificationData::PpidEncryptedRsa3072(PpidEncryptedRsa3072(bytes)) => {
+                (*bytes, PpidEncryption::Rsa3072, PPID_RSA3072_SIZE)
+            }
+            CertificationData::Pck(_) => return Err(Quote3Error::CertificationDataType(Pck::KIND)),
+            CertificationData::PckCertificateChain(_) => {
+                return Err(Quote3Error::CertificationDataType(
+                    PckCertificateChain::KIND,
+                ))
+            }
+            CertificationData::EcdsaSignatureAuxData(_) => {
+                return Err(Quote3Error::CertificationDataType(
+                    EcdsaSignatureAuxData::KIND,
+                ))
+            }
+            CertificationData::PlatformManifest(_) => {
+                return Err(Quote3Error::CertificationDataType(PlatformManifest::KIND))
+            }
+        };
+
+        let required = ppid_size + PLATFORM_TCB_SIZE;
+        let actual = bytes.len();
+        if actual != required {
+            return Err(Quote3Error::InputLength { required, actual });
+        }
+
+        let (ppid, bytes) = bytes.split_at(ppid_size);
+        let (cpu_svn, bytes) = bytes.split_at(CpuSvn::SIZE);
+        let mut cpu_svn_bytes = [0u8; CpuSvn::SIZE];
+        cpu_svn_bytes.copy_from_slice(cpu_svn);
+        let (bytes, pce_svn) = le_u16(bytes);
+        let (_, pce_id) = le_u16(bytes);
+
+        Ok(Self {
+            ppid,
+            encryption,
+            cpu_svn: CpuSvn::from(cpu_svn_bytes),
+            pce_svn,
+            pce_id,
+        })
+    }
+}
+
 const BEGIN_PEM: &[u8] = b"-----BEGIN ";
 const END_PEM: &[u8] = b"-----END ";
 
@@ -257,6 +399,7 @@ mod test {
     use super::*;
 
     extern crate alloc;
+    use alloc::vec;
     use alloc::vec::Vec;
     use yare::parameterized;
 
@@ -653,4 +796,87 @@ mod test {
         let certs = cert_iter.collect::<Vec<_>>();
         assert_eq!(certs, pem_bytes);
     }
+
+    /// PPID certification data of `ppid_size` bytes of `0x11` followed by a
+    /// CPUSVN of `0x22`, a PCESVN of 0x0304 and a PCEID of 0x0506.
+    fn platform_id_data(ppid_size: usize) -> Vec<u8> {
+        let mut data = vec![0x11; ppid_size];
+        data.extend_from_slice(&[0x22; CpuSvn::SIZE]);
+        data.extend_from_slice(&[0x04, 0x03, 0x06, 0x05]);
+        data
+    }
+
+    #[parameterized(
+        plaintext = { PPID_SIZE, Ppid::KIND, PpidEncryption::None },
+        rsa_2048 = { PPID_RSA2048_SIZE, PpidEncryptedRsa2048::KIND, PpidEncryption::Rsa2048 },
+        rsa_3072 = { PPID_RSA3072_SIZE, PpidEncryptedRsa3072::KIND, PpidEncryption::Rsa3072 },
+    )]
+    fn platform_id(ppid_size: usize, kind: u16, encryption: PpidEncryption) {
+        let data = platform_id_data(ppid_size);
+        let mut bytes = kind.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+        let certification_data = CertificationData::try_from(bytes.as_slice()).unwrap();
+
+        let platform_id = PlatformId::try_from(&certification_data).unwrap();
+
+        assert_eq!(platform_id.ppid(), vec![0x11; ppid_size]);
+        assert_eq!(platform_id.encryption(), encryption);
+        assert_eq!(platform_id.cpu_svn(), &CpuSvn::from([0x22; CpuSvn::SIZE]));
+        assert_eq!(platform_id.pce_svn(), 0x0304);
+        assert_eq!(platform_id.pce_id(), 0x0506);
+    }
+
+    #[parameterized(
+        too_short = { PPID_SIZE + PLATFORM_TCB_SIZE - 1 },
+        too_long = { PPID_SIZE + PLATFORM_TCB_SIZE + 1 },
+        empty = { 0 },
+    )]
+    fn platform_id_wrong_size(size: usize) {
+        let data = vec![0u8; size];
+        let certification_data = CertificationData::Ppid(Ppid(&data));
+
+        assert_eq!(
+            PlatformId::try_from(&certification_data),
+            Err(Quote3Error::InputLength {
+                required: PPID_SIZE + PLATFORM_TCB_SIZE,
+                actual: size
+            })
+        );
+    }
+
+    #[parameterized(
+        pck = { CertificationData::Pck(Pck(&[])), 4 },
+        pck_chain = { CertificationData::PckCertificateChain(PckCertificateChain { data: &[] }), 5 },
+        aux_data = { CertificationData::EcdsaSignatureAuxData(EcdsaSignatureAuxData(&[])), 6 },
+        platform_manifest = { CertificationData::PlatformManifest(PlatformManifest(&[])), 7 },
+    )]
+    fn platform_id_from_other_types(certification_data: CertificationData, kind: u16) {
+        assert_eq!(
+            PlatformId::try_from(&certification_data),
+            Err(Quote3Error::CertificationDataType(kind))
+        );
+    }
+
+    #[parameterized(
+        ppid = { Ppid::KIND },
+        ppid_rsa_2048 = { PpidEncryptedRsa2048::KIND },
+        ppid_rsa_3072 = { PpidEncryptedRsa3072::KIND },
+        pck = { Pck::KIND },
+        pck_cert_chain = { PckCertificateChain::KIND },
+        ecdsa_aux = { EcdsaSignatureAuxData::KIND },
+        platform_manifest = { PlatformManifest::KIND },
+    )]
+    fn certification_data_from_kind(kind: u16) {
+        let certification_data =
+            CertificationData::from_kind(kind, &[1, 2, 3]).expect("Unknown kind");
+
+        assert_eq!(certification_data.kind(), kind);
+        assert_eq!(certification_data.raw_data(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn certification_data_from_unknown_kind() {
+        assert_eq!(CertificationData::from_kind(8, &[1, 2, 3]), None);
+    }
 }
@@ -12,7 +12,9 @@ use alloc::vec::Vec;
 use mc_sgx_dcap_sys_types::sgx_ql_qve_collateral_t;
 use serde::{Deserialize, Serialize};
 use x509_cert::crl::CertificateList;
-use x509_cert::der::Decode;
+use x509_cert::der::{
+    self, Decode, DecodeValue, Encode, EncodeValue, Header, Length, Reader, Sequence, Writer,
+};
 use x509_cert::Certificate;
 
 // Per the comment in `sgx_ql_lib_common.h`
@@ -93,6 +95,30 @@ pub struct Collateral {
 }
 
 impl Collateral {
+    /// Create a new [`Collateral`] from its individual pieces.
+    ///
+    /// The certificate chains are leaf first. The `tcb_info` and `qe_identity`
+    /// are the signed JSON responses of the respective PCS endpoints.
+    pub fn new(
+        root_ca_crl: CertificateList,
+        pck_crl_issuer_chain: Vec<Certificate>,
+        pck_crl: CertificateList,
+        tcb_issuer_chain: Vec<Certificate>,
+        tcb_info: String,
+        qe_identity_issuer_chain: Vec<Certificate>,
+        qe_identity: String,
+    ) -> Self {
+        Self {
+            root_ca_crl,
+            pck_crl_issuer_chain,
+            pck_crl,
+            tcb_issuer_chain,
+            tcb_info,
+            qe_identity_issuer_chain,
+            qe_identity,
+        }
+    }
+
     /// Get the root certificate authority (CA) certificate revocation list
     /// (CRL).
     ///
@@ -153,6 +179,64 @@ impl Collateral {
     }
 }
 
+// The ASN.1 encoding of [`Collateral`] is:
+//
+//      Collateral ::= SEQUENCE {
+//          rootCaCrl              CertificateList,
+//          pckCrlIssuerChain      SEQUENCE OF Certificate,
+//          pckCrl                 CertificateList,
+//          tcbIssuerChain         SEQUENCE OF Certificate,
+//          tcbInfo                UTF8String,
+//          qeIdentityIssuerChain  SEQUENCE OF Certificate,
+//          qeIdentity             UTF8String
+//      }
+//
+// This is not an Intel defined structure, it exists so that the collateral can
+// be carried in places which expect DER, like an X.509 certificate extension.
+impl<'a> DecodeValue<'a> for Collateral {
+    fn decode_value<R: Reader<'a>>(reader: &mut R, header: Header) -> der::Result<Self> {
+        reader.read_nested(header.length, |reader| {
+            Ok(Self {
+                root_ca_crl: reader.decode()?,
+                pck_crl_issuer_chain: reader.decode()?,
+                pck_crl: reader.decode()?,
+                tcb_issuer_chain: reader.decode()?,
+                tcb_info: reader.decode()?,
+                qe_identity_issuer_chain: reader.decode()?,
+                qe_identity: reader.decode()?,
+            })
+        })
+    }
+}
+
+impl EncodeValue for Collateral {
+    fn value_len(&self) -> der::Result<Length> {
+        [
+            self.root_ca_crl.encoded_len()?,
+            self.pck_crl_issuer_chain.encoded_len()?,
+            self.pck_crl.encoded_len()?,
+            self.tcb_issuer_chain.encoded_len()?,
+            self.tcb_info.encoded_len()?,
+            self.qe_identity_issuer_chain.encoded_len()?,
+            self.qe_identity.encoded_len()?,
+        ]
+        .into_iter()
+        .try_fold(Length::ZERO, |total, len| total + len)
+    }
+
+    fn encode_value(&self, writer: &mut impl Writer) -> der::Result<()> {
+        self.root_ca_crl.encode(writer)?;
+        self.pck_crl_issuer_chain.encode(writer)?;
+        self.pck_crl.encode(writer)?;
+        self.tcb_issuer_chain.encode(writer)?;
+        self.tcb_info.encode(writer)?;
+        self.qe_identity_issuer_chain.encode(writer)?;
+        self.qe_identity.encode(writer)
+    }
+}
+
+impl<'a> Sequence<'a> for Collateral {}
+
 impl TryFrom<&sgx_ql_qve_collateral_t> for Collateral {
     type Error = Error;
 
@@ -992,4 +1076,63 @@ mod test {
 
         assert_eq!(collateral, new_collateral);
     }
+
+    // A `Collateral` built from the test data, with placeholder TCB info and
+    // QE identity.
+    fn test_collateral() -> Collateral {
+        let mut root_crl = include_bytes!("../data/tests/root_crl.der").to_vec();
+        root_crl.push(0);
+        let (mut pem_chain, _) = pem_cert_chain();
+        let mut pck_crl = include_bytes!("../data/tests/processor_crl.der").to_vec();
+        pck_crl.push(0);
+        let mut tcb_info = String::from("Hello");
+        let mut qe_identity = String::from("World");
+        let mut sgx_collateral = empty_collateral_with_version(3, 1);
+        sgx_collateral.tee_type = SGX_TEE;
+        sgx_collateral.root_ca_crl = root_crl.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.root_ca_crl_size = root_crl.len() as u32;
+        sgx_collateral.pck_crl_issuer_chain = pem_chain.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.pck_crl_issuer_chain_size = pem_chain.len() as u32;
+        sgx_collateral.pck_crl = pck_crl.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.pck_crl_size = pck_crl.len() as u32;
+        sgx_collateral.tcb_info_issuer_chain = pem_chain.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.tcb_info_issuer_chain_size = pem_chain.len() as u32;
+        sgx_collateral.tcb_info = tcb_info.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.tcb_info_size = tcb_info.len() as u32;
+        sgx_collateral.qe_identity_issuer_chain = pem_chain.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.qe_identity_issuer_chain_size = pem_chain.len() as u32;
+        sgx_collateral.qe_identity = qe_identity.as_mut_ptr() as *mut core::ffi::c_char;
+        sgx_collateral.qe_identity_size = qe_identity.len() as u32;
+
+        Collateral::try_from(&sgx_collateral).expect("Failed to convert collateral")
+    }
+
+    #[test]
+    fn collateral_der_round_trip() {
+        let collateral = test_collateral();
+
+        let der = collateral.to_der().expect("Failed to encode collateral");
+        let new_collateral = Collateral::from_der(&der).expect("Failed to decode collateral");
+
+        assert_eq!(collateral, new_collateral);
+    }
+
+    #[test]
+    fn collateral_der_with_trailing_bytes_fails() {
+        let collateral = test_collateral();
+
+        let mut der = collateral.to_der().expect("Failed to encode collateral");
+        der.push(0);
+
+        assert!(Collateral::from_der(&der).is_err());
+    }
+
+    #[test]
+    fn truncated_collateral_der_fails() {
+        let collateral = test_collateral();
+
+        let der = collateral.to_der().expect("Failed to encode collateral");
+
+        assert!(Collateral::from_der(&der[..der.len() - 1]).is_err());
+    }
 }
@@ -0,0 +1,633 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Validation of the freshness and consistency of a [`Collateral`].
+//!
+//! Unlike appraising a quote, which stops at the first failure, validating
+//! collateral collects every problem along with the validity period of every
+//! part. This allows operators to alert on collateral which is about to go
+//! stale, before verifications start failing.
+
+use crate::x509::{verify, verify_certificate, verifying_key};
+use crate::{AppraisalError, Collateral, FmspcTcbInfo, QeIdentity, TimeError, TimeSource};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
+use x509_cert::crl::CertificateList;
+use x509_cert::der::Encode;
+use x509_cert::Certificate;
+
+/// A part of a [`Collateral`] with its own validity period.
+#[derive(Debug, displaydoc::Display, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CollateralPart {
+    /// root CA CRL
+    RootCaCrl,
+    /// PCK CRL
+    PckCrl,
+    /// TCB info
+    TcbInfo,
+    /// QE identity
+    QeIdentity,
+    /// certificate {0} of the PCK CRL issuer chain
+    PckCrlIssuerCertificate(usize),
+    /// certificate {0} of the TCB issuer chain
+    TcbIssuerCertificate(usize),
+    /// certificate {0} of the QE identity issuer chain
+    QeIdentityIssuerCertificate(usize),
+}
+
+/// The validity period of a [`CollateralPart`].
+///
+/// Times are the duration since the UNIX epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartValidity {
+    part: CollateralPart,
+    not_before: Duration,
+    not_after: Option<Duration>,
+}
+
+impl PartValidity {
+    /// The part of the collateral
+    pub fn part(&self) -> CollateralPart {
+        self.part
+    }
+
+    /// The start of the validity period.
+    ///
+    /// The `thisUpdate` of a CRL, the `issueDate` of the TCB info and QE
+    /// identity, or the `notBefore` of a certificate.
+    pub fn not_before(&self) -> Duration {
+        self.not_before
+    }
+
+    /// The end of the validity period.
+    ///
+    /// The `nextUpdate` of a CRL, TCB info and QE identity, or the `notAfter`
+    /// of a certificate. `None` for a CRL without a `nextUpdate`.
+    pub fn not_after(&self) -> Option<Duration> {
+        self.not_after
+    }
+}
+
+/// A problem with a [`Collateral`] found by [`Collateral::validate()`].
+#[derive(Debug, displaydoc::Display, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CollateralIssue {
+    /// The {0} is not valid yet
+    NotYetValid(CollateralPart),
+    /// The {0} has expired
+    Expired(CollateralPart),
+    /// The {0} has no nextUpdate
+    MissingNextUpdate(CollateralPart),
+    /// The {0} can not be read: {1}
+    Unreadable(CollateralPart, String),
+    /// The issuer chain of the {0} is empty
+    EmptyIssuerChain(CollateralPart),
+    /// The {0} is not signed by the next certificate of its issuer chain
+    BrokenChain(CollateralPart),
+    /// The issuer chain of the {0} does not end in the root CA of the root CA CRL
+    RootMismatch(CollateralPart),
+    /// The TCB evaluation data number of the TCB info, {0}, differs from the QE identity, {1}
+    TcbEvaluationDataNumberMismatch(u32, u32),
+}
+
+/// The result of [`Collateral::validate()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollateralReport {
+    validities: Vec<PartValidity>,
+    issues: Vec<CollateralIssue>,
+    tcb_evaluation_data_number: Option<u32>,
+}
+
+impl CollateralReport {
+    /// Is the collateral free of issues
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The issues found with the collateral.
+    pub fn issues(&self) -> &[CollateralIssue] {
+        &self.issues
+    }
+
+    /// The validity periods of the parts of the collateral which could be
+    /// read.
+    pub fn validities(&self) -> &[PartValidity] {
+        &self.validities
+    }
+
+    /// The part of the collateral which expires first.
+    ///
+    /// Returns `None` if no part has an expiry.
+    pub fn earliest_expiry(&self) -> Option<&PartValidity> {
+        self.validities
+            .iter()
+            .filter(|validity| validity.not_after.is_some())
+            .min_by_key(|validity| validity.not_after)
+    }
+
+    /// The TCB evaluation data number of the TCB info.
+    ///
+    /// Returns `None` if the TCB info can not be read.
+    pub fn tcb_evaluation_data_number(&self) -> Option<u32> {
+        self.tcb_evaluation_data_number
+    }
+}
+
+impl Collateral {
+    /// Validate the freshness and consistency of the collateral at the time
+    /// from `time`.
+    ///
+    /// The following are checked:
+    /// - the `thisUpdate` and `nextUpdate` of each CRL
+    /// - the `issueDate` and `nextUpdate` of the TCB info and QE identity
+    /// - the validity period of each certificate of the issuer chains
+    /// - that each issuer chain is signed in order and ends in the root CA
+    ///   which signed the root CA CRL
+    /// - that the TCB info and QE identity have the same TCB evaluation data
+    ///   number
+    ///
+    /// Whether the root CA is trusted is *not* checked, that is left to the
+    /// appraisal of a quote with a trust anchor.
+    ///
+    /// # Arguments
+    /// * `time` - The time to validate at, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// A [`TimeError`] if the time can't be read from `time`.
+    pub fn validate<S: TimeSource>(&self, time: S) -> Result<CollateralReport, TimeError> {
+        let now = time.now()?;
+        let mut validator = Validator::default();
+        let root = self.pck_crl_issuer_chain().last();
+
+        validator.crl(CollateralPart::RootCaCrl, self.root_ca_crl(), root);
+        validator.chain(
+            CollateralPart::PckCrl,
+            self.pck_crl_issuer_chain(),
+            root,
+            CollateralPart::PckCrlIssuerCertificate,
+        );
+        validator.crl(
+            CollateralPart::PckCrl,
+            self.pck_crl(),
+            self.pck_crl_issuer_chain().first(),
+        );
+
+        validator.chain(
+            CollateralPart::TcbInfo,
+            self.tcb_issuer_chain(),
+            root,
+            CollateralPart::TcbIssuerCertificate,
+        );
+        let tcb_info = validator.signed_json(
+            CollateralPart::TcbInfo,
+            self.tcb_info(),
+            self.tcb_issuer_chain().first(),
+            FmspcTcbInfo::from_signed_json,
+        );
+        if let Some(tcb_info) = &tcb_info {
+            validator.period(
+                CollateralPart::TcbInfo,
+                tcb_info.issue_date().unix_duration(),
+                Some(tcb_info.next_update().unix_duration()),
+            );
+        }
+
+        validator.chain(
+            CollateralPart::QeIdentity,
+            self.qe_identity_issuer_chain(),
+            root,
+            CollateralPart::QeIdentityIssuerCertificate,
+        );
+        let qe_identity = validator.signed_json(
+            CollateralPart::QeIdentity,
+            self.qe_identity(),
+            self.qe_identity_issuer_chain().first(),
+            QeIdentity::from_signed_json,
+        );
+        if let Some(qe_identity) = &qe_identity {
+            validator.period(
+                CollateralPart::QeIdentity,
+                qe_identity.issue_date().unix_duration(),
+                Some(qe_identity.next_update().unix_duration()),
+            );
+        }
+
+        if let (Some(tcb_info), Some(qe_identity)) = (&tcb_info, &qe_identity) {
+            let tcb_number = tcb_info.tcb_evaluation_data_number();
+            let qe_number = qe_identity.tcb_evaluation_data_number();
+            if tcb_number != qe_number {
+                validator
+                    .issues
+                    .push(CollateralIssue::TcbEvaluationDataNumberMismatch(
+                        tcb_number, qe_number,
+                    ));
+            }
+        }
+
+        Ok(validator.report(now, tcb_info.map(|info| info.tcb_evaluation_data_number())))
+    }
+}
+
+/// Accumulates the validity periods and issues of the collateral parts.
+#[derive(Default)]
+struct Validator {
+    validities: Vec<PartValidity>,
+    issues: Vec<CollateralIssue>,
+}
+
+impl Validator {
+    fn period(&mut self, part: CollateralPart, not_before: Duration, not_after: Option<Duration>) {
+        self.validities.push(PartValidity {
+            part,
+            not_before,
+            not_after,
+        });
+    }
+
+    /// Check `crl` is signed by `issuer` and record its validity period.
+    fn crl(&mut self, part: CollateralPart, crl: &CertificateList, issuer: Option<&Certificate>) {
+        let tbs = &crl.tbs_cert_list;
+        let signed = issuer.map_or(false, |issuer| {
+            tbs.issuer == issuer.tbs_certificate.subject && crl_signed_by(crl, issuer)
+        });
+        if !signed {
+            self.issues.push(CollateralIssue::BrokenChain(part));
+        }
+
+        let next_update = tbs.next_update.map(|time| time.to_unix_duration());
+        if next_update.is_none() {
+            self.issues.push(CollateralIssue::MissingNextUpdate(part));
+        }
+        self.period(part, tbs.this_update.to_unix_duration(), next_update);
+    }
+
+    /// Check `chain`, leaf first, is signed in order and ends in `root`.
+    ///
+    /// The validity period of each certificate is recorded as
+    /// `certificate_part(index)`.
+    fn chain(
+        &mut self,
+        part: CollateralPart,
+        chain: &[Certificate],
+        root: Option<&Certificate>,
+        certificate_part: fn(usize) -> CollateralPart,
+    ) {
+        let Some(last) = chain.last() else {
+            self.issues.push(CollateralIssue::EmptyIssuerChain(part));
+            return;
+        };
+
+        for (index, certificate) in chain.iter().enumerate() {
+            let validity = &certificate.tbs_certificate.validity;
+            self.period(
+                certificate_part(index),
+                validity.not_before.to_unix_duration(),
+                Some(validity.not_after.to_unix_duration()),
+            );
+
+            // The root is self-signed, its trust comes from the trust anchor
+            // of the appraisal.
+            let issuer = chain.get(index + 1).unwrap_or(certificate);
+            if !certificate_signed_by(certificate, issuer) {
+                self.issues
+                    .push(CollateralIssue::BrokenChain(certificate_part(index)));
+            }
+        }
+
+        if Some(last) != root {
+            self.issues.push(CollateralIssue::RootMismatch(part));
+        }
+    }
+
+    /// Parse the signed JSON `json` with `parse`, using the leaf of the
+    /// issuer chain as the signer.
+    fn signed_json<T>(
+        &mut self,
+        part: CollateralPart,
+        json: &str,
+        signer: Option<&Certificate>,
+        parse: fn(&str, &Certificate) -> Result<T, AppraisalError>,
+    ) -> Option<T> {
+        // An empty issuer chain has already been reported.
+        let signer = signer?;
+        match parse(json, signer) {
+            Ok(value) => Some(value),
+            Err(AppraisalError::JsonSignature) => {
+                self.issues.push(CollateralIssue::BrokenChain(part));
+                None
+            }
+            Err(err) => {
+                self.issues
+                    .push(CollateralIssue::Unreadable(part, err.to_string()));
+                None
+            }
+        }
+    }
+
+    /// Check the recorded validity periods at `now` and create the report.
+    fn report(
+        mut self,
+        now: Duration,
+        tcb_evaluation_data_number: Option<u32>,
+    ) -> CollateralReport {
+        for validity in &self.validities {
+            if now < validity.not_before {
+                self.issues
+                    .push(CollateralIssue::NotYetValid(validity.part));
+            } else if validity
+                .not_after
+                .map_or(false, |not_after| now > not_after)
+            {
+                self.issues.push(CollateralIssue::Expired(validity.part));
+            }
+        }
+        CollateralReport {
+            validities: self.validities,
+            issues: self.issues,
+            tcb_evaluation_data_number,
+        }
+    }
+}
+
+fn certificate_signed_by(certificate: &Certificate, issuer: &Certificate) -> bool {
+    certificate.tbs_certificate.issuer == issuer.tbs_certificate.subject
+        && verifying_key(issuer)
+            .and_then(|key| verify_certificate(certificate, &key))
+            .is_ok()
+}
+
+fn crl_signed_by(crl: &CertificateList, issuer: &Certificate) -> bool {
+    let (Ok(key), Ok(tbs)) = (verifying_key(issuer), crl.tbs_cert_list.to_der()) else {
+        return false;
+    };
+    verify(&tbs, &crl.signature_algorithm, &crl.signature, &key).is_ok()
+}
+
+#[cfg(all(test, feature = "ra-tls"))]
+mod test {
+    use super::*;
+    use crate::test_utils::{TestCa, TCB_EVALUATION_DATA_NUMBER};
+    use alloc::vec;
+    use x509_cert::der::{DateTime, Decode};
+    use yare::parameterized;
+
+    // 2024-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_717_200_000);
+
+    fn date(year: u16, month: u8, day: u8) -> DateTime {
+        DateTime::new(year, month, day, 0, 0, 0).expect("Invalid date")
+    }
+
+    /// Collateral from the test CA valid from 2024-01-01 until 2024-07-01.
+    fn collateral(ca: &TestCa) -> Collateral {
+        ca.collateral_builder()
+            .validity(date(2024, 1, 1), date(2024, 7, 1))
+            .build()
+    }
+
+    /// The collateral of `hw_quote.dat`, issued by the Intel SGX Root CA.
+    fn intel_collateral() -> Collateral {
+        let collateral_der = include_bytes!("../data/tests/collateral.der");
+        Collateral::from_der(collateral_der).expect("Failed to decode collateral")
+    }
+
+    #[test]
+    fn valid_collateral() {
+        let ca = TestCa::default();
+        let report = collateral(&ca)
+            .validate(NOW)
+            .expect("Failed to get the time");
+
+        assert_eq!(report.issues(), &[]);
+        assert!(report.is_valid());
+        assert_eq!(
+            report.tcb_evaluation_data_number(),
+            Some(TCB_EVALUATION_DATA_NUMBER)
+        );
+        // 2 CRLs, 6 certificates, TCB info, and QE identity
+        assert_eq!(report.validities().len(), 10);
+    }
+
+    #[test]
+    fn validate_with_time_before_floor_fails() {
+        let ca = TestCa::default();
+        let floor = NOW + Duration::from_secs(1);
+        let time = crate::MonotonicFloor::new(NOW).initial_floor(floor);
+
+        assert_eq!(
+            collateral(&ca).validate(&time),
+            Err(TimeError::Backwards { floor, now: NOW })
+        );
+    }
+
+    #[test]
+    fn earliest_expiry() {
+        let ca = TestCa::default();
+        let report = collateral(&ca)
+            .validate(NOW)
+            .expect("Failed to get the time");
+
+        let earliest = report.earliest_expiry().expect("Missing expiry");
+        assert_eq!(earliest.part(), CollateralPart::RootCaCrl);
+        assert_eq!(earliest.not_before(), date(2024, 1, 1).unix_duration());
+        assert_eq!(earliest.not_after(), Some(date(2024, 7, 1).unix_duration()));
+    }
+
+    #[parameterized(
+        before_issue = { date(2023, 12, 31), CollateralIssue::NotYetValid },
+        after_next_update = { date(2024, 7, 2), CollateralIssue::Expired },
+    )]
+    fn outside_validity(now: DateTime, issue: fn(CollateralPart) -> CollateralIssue) {
+        let ca = TestCa::default();
+        let report = collateral(&ca)
+            .validate(now.unix_duration())
+            .expect("Failed to get the time");
+
+        assert!(!report.is_valid());
+        let parts = [
+            CollateralPart::RootCaCrl,
+            CollateralPart::PckCrl,
+            CollateralPart::TcbInfo,
+            CollateralPart::QeIdentity,
+        ];
+        for part in parts {
+            assert!(report.issues().contains(&issue(part)), "{part}");
+        }
+    }
+
+    #[test]
+    fn expired_certificates() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+
+        // The test CA certificates are valid through 2049-12-31
+        let report = collateral
+            .validate(date(2050, 1, 1).unix_duration())
+            .expect("Failed to get the time");
+
+        let issues = report.issues();
+        assert!(issues.contains(&CollateralIssue::Expired(
+            CollateralPart::TcbIssuerCertificate(0)
+        )));
+        assert!(issues.contains(&CollateralIssue::Expired(
+            CollateralPart::PckCrlIssuerCertificate(1)
+        )));
+    }
+
+    #[test]
+    fn mismatched_tcb_evaluation_data_numbers() {
+        let ca = TestCa::default();
+        let tcb = ca
+            .collateral_builder()
+            .tcb_evaluation_data_number(16)
+            .build();
+        let qe = ca.collateral();
+        let collateral = Collateral::new(
+            tcb.root_ca_crl().clone(),
+            tcb.pck_crl_issuer_chain().to_vec(),
+            tcb.pck_crl().clone(),
+            tcb.tcb_issuer_chain().to_vec(),
+            tcb.tcb_info().into(),
+            qe.qe_identity_issuer_chain().to_vec(),
+            qe.qe_identity().into(),
+        );
+
+        let report = collateral.validate(NOW).expect("Failed to get the time");
+
+        assert_eq!(
+            report.issues(),
+            &[CollateralIssue::TcbEvaluationDataNumberMismatch(
+                16,
+                TCB_EVALUATION_DATA_NUMBER
+            )]
+        );
+        assert_eq!(report.tcb_evaluation_data_number(), Some(16));
+    }
+
+    #[test]
+    fn tcb_issuer_chain_from_another_root() {
+        let ca = TestCa::default();
+        let test = collateral(&ca);
+        let intel = intel_collateral();
+        let collateral = Collateral::new(
+            test.root_ca_crl().clone(),
+            test.pck_crl_issuer_chain().to_vec(),
+            test.pck_crl().clone(),
+            intel.pck_crl_issuer_chain().to_vec(),
+            test.tcb_info().into(),
+            test.qe_identity_issuer_chain().to_vec(),
+            test.qe_identity().into(),
+        );
+
+        let report = collateral.validate(NOW).expect("Failed to get the time");
+
+        assert_eq!(
+            report.issues(),
+            &[
+                CollateralIssue::RootMismatch(CollateralPart::TcbInfo),
+                CollateralIssue::BrokenChain(CollateralPart::TcbInfo),
+            ]
+        );
+        assert_eq!(report.tcb_evaluation_data_number(), None);
+    }
+
+    #[test]
+    fn out_of_order_issuer_chain() {
+        let ca = TestCa::default();
+        let test = collateral(&ca);
+        let collateral = Collateral::new(
+            test.root_ca_crl().clone(),
+            test.pck_crl_issuer_chain().to_vec(),
+            test.pck_crl().clone(),
+            test.tcb_issuer_chain().to_vec(),
+            test.tcb_info().into(),
+            vec![
+                ca.tcb_signing_certificate().clone(),
+                ca.intermediate_certificate().clone(),
+                ca.root_certificate().clone(),
+            ],
+            test.qe_identity().into(),
+        );
+
+        let report = collateral.validate(NOW).expect("Failed to get the time");
+
+        assert_eq!(
+            report.issues(),
+            &[CollateralIssue::BrokenChain(
+                CollateralPart::QeIdentityIssuerCertificate(0)
+            )]
+        );
+    }
+
+    #[test]
+    fn empty_issuer_chains() {
+        let ca = TestCa::default();
+        let test = collateral(&ca);
+        let collateral = Collateral::new(
+            test.root_ca_crl().clone(),
+            vec![],
+            test.pck_crl().clone(),
+            test.tcb_issuer_chain().to_vec(),
+            test.tcb_info().into(),
+            vec![],
+            test.qe_identity().into(),
+        );
+
+        let report = collateral.validate(NOW).expect("Failed to get the time");
+
+        // Without the PCK CRL issuer chain there is no root
+        assert_eq!(
+            report.issues(),
+            &[
+                CollateralIssue::BrokenChain(CollateralPart::RootCaCrl),
+                CollateralIssue::EmptyIssuerChain(CollateralPart::PckCrl),
+                CollateralIssue::BrokenChain(CollateralPart::PckCrl),
+                CollateralIssue::RootMismatch(CollateralPart::TcbInfo),
+                CollateralIssue::EmptyIssuerChain(CollateralPart::QeIdentity),
+            ]
+        );
+    }
+
+    #[test]
+    fn crls_swapped() {
+        let ca = TestCa::default();
+        let test = collateral(&ca);
+        let collateral = Collateral::new(
+            test.pck_crl().clone(),
+            test.pck_crl_issuer_chain().to_vec(),
+            test.root_ca_crl().clone(),
+            test.tcb_issuer_chain().to_vec(),
+            test.tcb_info().into(),
+            test.qe_identity_issuer_chain().to_vec(),
+            test.qe_identity().into(),
+        );
+
+        let report = collateral.validate(NOW).expect("Failed to get the time");
+
+        assert_eq!(
+            report.issues(),
+            &[
+                CollateralIssue::BrokenChain(CollateralPart::RootCaCrl),
+                CollateralIssue::BrokenChain(CollateralPart::PckCrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn unreadable_json() {
+        let intel = intel_collateral();
+
+        let report = intel.validate(NOW).expect("Failed to get the time");
+
+        assert!(report.issues().iter().any(|issue| matches!(
+            issue,
+            CollateralIssue::Unreadable(CollateralPart::TcbInfo, _)
+        )));
+        assert!(report.issues().iter().any(|issue| matches!(
+            issue,
+            CollateralIssue::Unreadable(CollateralPart::QeIdentity, _)
+        )));
+    }
+}
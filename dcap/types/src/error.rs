@@ -4,7 +4,7 @@
 
 use displaydoc::Display;
 use mc_sgx_dcap_sys_types::quote3_error_t;
-use mc_sgx_util::{ResultFrom, ResultInto};
+use mc_sgx_util::{Classify, ErrorClass, ResultFrom, ResultInto};
 use serde::{Deserialize, Serialize};
 
 /// Errors interacting with a Quote3
@@ -23,6 +23,15 @@ pub enum Quote3Error {
     CertificationDataType(u16),
     /// Error verifying the signature
     SignatureVerification,
+    /// Invalid SGX type in the platform manifest: {0}, should be 0 - 2
+    SgxType(u8),
+    /** The platform manifest has {0} structures after the platform info,
+     * should be a pairing receipt and key blob per package and a signature */
+    PlatformManifestStructures(usize),
+    /// The QE report is from a different quoting enclave than the quote
+    QeReportIdentity,
+    /// The QE report does not bind the quote to the nonce
+    QeReportNonce,
 }
 
 impl Quote3Error {
@@ -445,6 +454,26 @@ impl From<QlError> for quote3_error_t {
 impl ResultFrom<quote3_error_t> for QlError {}
 impl ResultInto<QlError> for quote3_error_t {}
 
+impl Classify for QlError {
+    /// Resource exhaustion, lost enclaves, and failures reaching the AESM
+    /// service or the PCCS are transient, everything else is permanent.
+    fn class(&self) -> ErrorClass {
+        match self {
+            QlError::OutOfMemory
+            | QlError::OutOfEpc
+            | QlError::EnclaveLost
+            | QlError::Network
+            | QlError::UnableToGetCollateral
+            | QlError::ServiceUnavailable
+            | QlError::NetworkFailure
+            | QlError::ServiceTimeout
+            | QlError::Busy
+            | QlError::InternalServerError => ErrorClass::Transient,
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     extern crate std;
@@ -547,6 +576,21 @@ mod test {
         )
     }
 
+    #[parameterized(
+    out_of_epc = { QlError::OutOfEpc, ErrorClass::Transient },
+    enclave_lost = { QlError::EnclaveLost, ErrorClass::Transient },
+    busy = { QlError::Busy, ErrorClass::Transient },
+    network = { QlError::Network, ErrorClass::Transient },
+    service_timeout = { QlError::ServiceTimeout, ErrorClass::Transient },
+    invalid_report = { QlError::InvalidReport, ErrorClass::Permanent },
+    unsupported_att_key_id = { QlError::UnsupportedAttestationKeyId, ErrorClass::Permanent },
+    platform_unknown = { QlError::PlatformUnknown, ErrorClass::Permanent },
+    no_platform_cert_data = { QlError::NoPlatformCertData, ErrorClass::Permanent },
+    )]
+    fn ql_error_class(error: QlError, expected: ErrorClass) {
+        assert_eq!(error.class(), expected);
+    }
+
     #[test]
     fn success_is_not_an_error() {
         assert!(QlError::try_from(quote3_error_t::SGX_QL_SUCCESS).is_err())
@@ -0,0 +1,264 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Archived attestation evidence, and auditing it after the fact.
+//!
+//! When a quote is accepted, storing it with the collateral it was appraised
+//! with, and the time of the appraisal, allows answering later whether it was
+//! valid when it was accepted. Collateral changes over time, a TCB recovery
+//! lowers the TCB status of platforms and CRLs revoke PCK certificates, so an
+//! [`Audit`] also appraises the quote with the current collateral.
+
+use crate::{
+    Appraisal, AppraisalError, Collateral, IdentityPolicy, Quote3, QuoteAppraiser, TimeSource,
+};
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// A quote, the collateral it was appraised with, and the time of the
+/// appraisal.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Evidence {
+    quote: Quote3<Vec<u8>>,
+    collateral: Collateral,
+    accepted_at: Duration,
+}
+
+impl Evidence {
+    /// Archive `quote` accepted with `collateral`.
+    ///
+    /// # Arguments
+    /// * `quote` - The accepted quote.
+    /// * `collateral` - The collateral `quote` was appraised with.
+    /// * `accepted_at` - The time of the appraisal, as the duration since the
+    ///   UNIX epoch.
+    pub fn new(quote: Quote3<Vec<u8>>, collateral: Collateral, accepted_at: Duration) -> Self {
+        Self {
+            quote,
+            collateral,
+            accepted_at,
+        }
+    }
+
+    /// The accepted quote
+    pub fn quote(&self) -> &Quote3<Vec<u8>> {
+        &self.quote
+    }
+
+    /// The collateral the quote was appraised with
+    pub fn collateral(&self) -> &Collateral {
+        &self.collateral
+    }
+
+    /// The time the quote was accepted, as the duration since the UNIX epoch.
+    pub fn accepted_at(&self) -> Duration {
+        self.accepted_at
+    }
+
+    /// Appraise the quote with the archived collateral, at the time it was
+    /// accepted.
+    ///
+    /// # Errors
+    /// An [`AppraisalError`] describing the first check that failed.
+    pub fn appraise(&self, appraiser: &QuoteAppraiser) -> Result<Appraisal, AppraisalError> {
+        appraiser.appraise(&self.quote, &self.collateral, self.accepted_at)
+    }
+
+    /// Audit the evidence, appraising the quote both at the time it was
+    /// accepted and with the current collateral.
+    ///
+    /// # Arguments
+    /// * `appraiser` - The appraiser with the trust anchor for the quote.
+    /// * `current_collateral` - The current collateral for the platform of the
+    ///   quote.
+    /// * `time` - The current time, as the duration since the UNIX epoch or
+    ///   from another [`TimeSource`].
+    pub fn audit<S: TimeSource>(
+        &self,
+        appraiser: &QuoteAppraiser,
+        current_collateral: &Collateral,
+        time: S,
+    ) -> Audit {
+        Audit {
+            historical: self.appraise(appraiser),
+            current: appraiser.appraise(&self.quote, current_collateral, time),
+        }
+    }
+}
+
+/// The result of auditing [`Evidence`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Audit {
+    historical: Result<Appraisal, AppraisalError>,
+    current: Result<Appraisal, AppraisalError>,
+}
+
+impl Audit {
+    /// The appraisal with the archived collateral, at the time the quote was
+    /// accepted.
+    pub fn historical(&self) -> Result<&Appraisal, &AppraisalError> {
+        self.historical.as_ref()
+    }
+
+    /// The appraisal with the current collateral, at the current time.
+    pub fn current(&self) -> Result<&Appraisal, &AppraisalError> {
+        self.current.as_ref()
+    }
+
+    /// Check that the quote satisfied `policy` when it was accepted.
+    ///
+    /// # Errors
+    /// An [`AppraisalError`] describing the first check that failed.
+    pub fn passed(&self, policy: &IdentityPolicy) -> Result<(), AppraisalError> {
+        Self::check(&self.historical, policy)
+    }
+
+    /// Check that the quote still satisfies `policy` with the current
+    /// collateral.
+    ///
+    /// # Errors
+    /// An [`AppraisalError`] describing the first check that failed, for
+    /// instance [`AppraisalError::CertificateRevoked`] when the PCK
+    /// certificate has since been revoked or [`AppraisalError::TcbStatus`]
+    /// when a TCB recovery has lowered the TCB status of the platform.
+    pub fn still_passes(&self, policy: &IdentityPolicy) -> Result<(), AppraisalError> {
+        Self::check(&self.current, policy)
+    }
+
+    fn check(
+        appraisal: &Result<Appraisal, AppraisalError>,
+        policy: &IdentityPolicy,
+    ) -> Result<(), AppraisalError> {
+        let appraisal = appraisal.as_ref().map_err(Clone::clone)?;
+        policy.check(appraisal)
+    }
+}
+
+#[cfg(all(test, feature = "ra-tls"))]
+mod test {
+    use super::*;
+    use crate::test_utils::TestCa;
+    use crate::TcbStatus;
+    use assert_matches::assert_matches;
+    use mc_sgx_core_types::ReportBody;
+    use x509_cert::der::DateTime;
+
+    fn date(year: u16, month: u8, day: u8) -> Duration {
+        DateTime::new(year, month, day, 0, 0, 0)
+            .expect("Invalid date")
+            .unix_duration()
+    }
+
+    /// Evidence accepted on 2024-01-15 with collateral valid for January 2024
+    fn evidence(ca: &TestCa) -> Evidence {
+        let collateral = ca
+            .collateral_builder()
+            .validity(
+                DateTime::new(2024, 1, 1, 0, 0, 0).expect("Invalid date"),
+                DateTime::new(2024, 2, 1, 0, 0, 0).expect("Invalid date"),
+            )
+            .build();
+        Evidence::new(
+            ca.quote(&ReportBody::default()),
+            collateral,
+            date(2024, 1, 15),
+        )
+    }
+
+    fn policy() -> IdentityPolicy {
+        IdentityPolicy::mr_enclave(ReportBody::default().mr_enclave())
+    }
+
+    #[test]
+    fn evidence_round_trips_through_serde() {
+        let ca = TestCa::default();
+        let evidence = evidence(&ca);
+
+        let bytes = serde_cbor::to_vec(&evidence).expect("Failed to serialize evidence");
+        let decoded: Evidence =
+            serde_cbor::from_slice(&bytes).expect("Failed to deserialize evidence");
+
+        assert_eq!(decoded, evidence);
+    }
+
+    #[test]
+    fn archived_evidence_valid_when_accepted() {
+        let ca = TestCa::default();
+        let evidence = evidence(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        // The archived collateral has long expired
+        assert_eq!(
+            appraiser.appraise(evidence.quote(), evidence.collateral(), date(2024, 6, 1)),
+            Err(AppraisalError::CrlValidity)
+        );
+        assert_matches!(evidence.appraise(&appraiser), Ok(_));
+    }
+
+    #[test]
+    fn audit_still_passes_with_current_collateral() {
+        let ca = TestCa::default();
+        let evidence = evidence(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        let audit = evidence.audit(&appraiser, &ca.collateral(), date(2024, 6, 1));
+
+        assert_eq!(audit.passed(&policy()), Ok(()));
+        assert_eq!(audit.still_passes(&policy()), Ok(()));
+    }
+
+    #[test]
+    fn audit_after_pck_revoked() {
+        let ca = TestCa::default();
+        let evidence = evidence(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let current = ca.collateral_builder().revoke_pck().build();
+
+        let audit = evidence.audit(&appraiser, &current, date(2024, 6, 1));
+
+        assert_eq!(audit.passed(&policy()), Ok(()));
+        assert_eq!(audit.current(), Err(&AppraisalError::CertificateRevoked));
+        assert_eq!(
+            audit.still_passes(&policy()),
+            Err(AppraisalError::CertificateRevoked)
+        );
+    }
+
+    #[test]
+    fn audit_after_tcb_recovery() {
+        let ca = TestCa::default();
+        let evidence = evidence(&ca);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        let current = ca
+            .collateral_builder()
+            .tcb_status(TcbStatus::OutOfDate)
+            .tcb_evaluation_data_number(18)
+            .build();
+
+        let audit = evidence.audit(&appraiser, &current, date(2024, 6, 1));
+
+        assert_eq!(audit.passed(&policy()), Ok(()));
+        assert_eq!(
+            audit.current().map(Appraisal::tcb_status),
+            Ok(TcbStatus::OutOfDate)
+        );
+        assert_eq!(
+            audit.still_passes(&policy()),
+            Err(AppraisalError::TcbStatus(TcbStatus::OutOfDate))
+        );
+    }
+
+    #[test]
+    fn audit_of_evidence_invalid_when_accepted() {
+        let ca = TestCa::default();
+        let mut evidence = evidence(&ca);
+        evidence.accepted_at = date(2024, 3, 1);
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+
+        let audit = evidence.audit(&appraiser, &ca.collateral(), date(2024, 6, 1));
+
+        assert_eq!(audit.historical(), Err(&AppraisalError::CrlValidity));
+        assert_eq!(audit.passed(&policy()), Err(AppraisalError::CrlValidity));
+        assert_eq!(audit.still_passes(&policy()), Ok(()));
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! The TCB info for a platform model, identified by its FMSPC.
+//!
+//! This is the JSON TCB info provided by the [`Collateral`](crate::Collateral),
+//! described at
+//! <https://api.portal.trustedservices.intel.com/documentation#pcs-tcb-info-model-v3>.
+//! It lists the TCB levels of the platform model and the status of each, which
+//! is compared against the [`TcbInfo`] of a PCK certificate.
+
+use crate::appraisal::Error;
+use crate::json::{date_time, hex_bytes, json_error, verify_signed_json};
+use crate::{TcbInfo, COMPONENT_SVN_COUNT, FMSPC_SIZE};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use x509_cert::der::DateTime;
+use x509_cert::Certificate;
+
+const SGX_ID: &str = "SGX";
+const TCB_INFO_VERSION: u32 = 3;
+const PCE_ID_SIZE: usize = 2;
+
+/// The status of a TCB level.
+///
+/// Values are from
+/// <https://api.portal.trustedservices.intel.com/documentation#pcs-tcb-info-model-v3>
+#[derive(
+    Debug,
+    displaydoc::Display,
+    Clone,
+    Copy,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub enum TcbStatus {
+    /// Up to date
+    UpToDate,
+    /// Up to date, software hardening is needed
+    #[serde(rename = "SWHardeningNeeded")]
+    SwHardeningNeeded,
+    /// Up to date, platform configuration is needed
+    ConfigurationNeeded,
+    /// Up to date, platform configuration and software hardening are needed
+    #[serde(rename = "ConfigurationAndSWHardeningNeeded")]
+    ConfigurationAndSwHardeningNeeded,
+    /// Out of date
+    OutOfDate,
+    /// Out of date, platform configuration is needed
+    OutOfDateConfigurationNeeded,
+    /// Revoked
+    Revoked,
+}
+
+/// A TCB level of an [`FmspcTcbInfo`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TcbLevel {
+    svns: [u32; COMPONENT_SVN_COUNT],
+    pce_svn: u32,
+    tcb_date: DateTime,
+    tcb_status: TcbStatus,
+    advisory_ids: Vec<String>,
+}
+
+impl TcbLevel {
+    /// The minimum component SVN values of this level.
+    pub fn svns(&self) -> &[u32; COMPONENT_SVN_COUNT] {
+        &self.svns
+    }
+
+    /// The minimum PCE SVN value of this level.
+    pub fn pce_svn(&self) -> u32 {
+        self.pce_svn
+    }
+
+    /// The date of this level.
+    pub fn tcb_date(&self) -> DateTime {
+        self.tcb_date
+    }
+
+    /// The status of platforms at this level.
+    pub fn tcb_status(&self) -> TcbStatus {
+        self.tcb_status
+    }
+
+    /// The Intel security advisories which apply to platforms at this level.
+    pub fn advisory_ids(&self) -> &[String] {
+        &self.advisory_ids
+    }
+
+    /// Is `tcb_info` at, or above, this TCB level.
+    ///
+    /// Every component SVN, and the PCE SVN, must be greater than or equal to
+    /// those of this level.
+    pub fn is_satisfied_by(&self, tcb_info: &TcbInfo) -> bool {
+        tcb_info
+            .svns()
+            .iter()
+            .zip(self.svns.iter())
+            .all(|(platform, level)| platform >= level)
+            && *tcb_info.pce_svn() >= self.pce_svn
+    }
+}
+
+/// The TCB info for the platforms of an FMSPC.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FmspcTcbInfo {
+    issue_date: DateTime,
+    next_update: DateTime,
+    fmspc: [u8; FMSPC_SIZE],
+    pce_id: [u8; PCE_ID_SIZE],
+    tcb_type: u32,
+    tcb_evaluation_data_number: u32,
+    tcb_levels: Vec<TcbLevel>,
+}
+
+impl FmspcTcbInfo {
+    /// Parse the signed TCB info `json` verifying its signature.
+    ///
+    /// # Arguments
+    /// * `json` - The signed TCB info JSON, as provided by
+    ///   [`Collateral::tcb_info()`](crate::Collateral::tcb_info).
+    /// * `signer` - The TCB signing certificate, the leaf of
+    ///   [`Collateral::tcb_issuer_chain()`](crate::Collateral::tcb_issuer_chain).
+    ///   The caller is responsible for verifying the certificate.
+    ///
+    /// # Errors
+    /// * [`Error::Json`] if `json` is not valid TCB info JSON.
+    /// * [`Error::JsonSignature`] if the signature is not valid.
+    /// * [`Error::UnsupportedTcbInfo`] if the TCB info is not version 3 SGX
+    ///   TCB info.
+    pub fn from_signed_json(json: &str, signer: &Certificate) -> Result<Self, Error> {
+        let body = verify_signed_json(json, signer)?;
+        let raw: RawTcbInfo = serde_json::from_str(body).map_err(json_error)?;
+        Self::try_from(raw)
+    }
+
+    /// The date the TCB info was issued.
+    pub fn issue_date(&self) -> DateTime {
+        self.issue_date
+    }
+
+    /// The date by which the next TCB info will be issued.
+    pub fn next_update(&self) -> DateTime {
+        self.next_update
+    }
+
+    /// The FMSPC of the platforms this TCB info applies to.
+    pub fn fmspc(&self) -> &[u8; FMSPC_SIZE] {
+        &self.fmspc
+    }
+
+    /// The PCE ID of the platforms this TCB info applies to.
+    pub fn pce_id(&self) -> &[u8; PCE_ID_SIZE] {
+        &self.pce_id
+    }
+
+    /// The TCB type, how the TCB components are compared.
+    pub fn tcb_type(&self) -> u32 {
+        self.tcb_type
+    }
+
+    /// The TCB evaluation data number, incremented each TCB recovery.
+    pub fn tcb_evaluation_data_number(&self) -> u32 {
+        self.tcb_evaluation_data_number
+    }
+
+    /// The TCB levels, from highest to lowest.
+    pub fn tcb_levels(&self) -> &[TcbLevel] {
+        &self.tcb_levels
+    }
+
+    /// The highest TCB level satisfied by `tcb_info`.
+    ///
+    /// Returns `None` when `tcb_info` is below every TCB level, or is for a
+    /// different FMSPC.
+    pub fn tcb_level(&self, tcb_info: &TcbInfo) -> Option<&TcbLevel> {
+        if tcb_info.fmspc() != &self.fmspc {
+            return None;
+        }
+        self.tcb_levels
+            .iter()
+            .find(|level| level.is_satisfied_by(tcb_info))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTcbInfo {
+    id: String,
+    version: u32,
+    issue_date: String,
+    next_update: String,
+    fmspc: String,
+    pce_id: String,
+    tcb_type: u32,
+    tcb_evaluation_data_number: u32,
+    tcb_levels: Vec<RawTcbLevel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTcbLevel {
+    tcb: RawTcb,
+    tcb_date: String,
+    tcb_status: TcbStatus,
+    #[serde(default, rename = "advisoryIDs")]
+    advisory_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawTcb {
+    sgxtcbcomponents: Vec<RawTcbComponent>,
+    pcesvn: u32,
+}
+
+#[derive(Deserialize)]
+struct RawTcbComponent {
+    svn: u32,
+}
+
+impl TryFrom<RawTcbInfo> for FmspcTcbInfo {
+    type Error = Error;
+
+    fn try_from(raw: RawTcbInfo) -> Result<Self, Self::Error> {
+        if raw.id != SGX_ID || raw.version != TCB_INFO_VERSION {
+            return Err(Error::UnsupportedTcbInfo);
+        }
+        let tcb_levels = raw
+            .tcb_levels
+            .into_iter()
+            .map(TcbLevel::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            issue_date: date_time(&raw.issue_date)?,
+            next_update: date_time(&raw.next_update)?,
+            fmspc: hex_bytes(&raw.fmspc)?,
+            pce_id: hex_bytes(&raw.pce_id)?,
+            tcb_type: raw.tcb_type,
+            tcb_evaluation_data_number: raw.tcb_evaluation_data_number,
+            tcb_levels,
+        })
+    }
+}
+
+impl TryFrom<RawTcbLevel> for TcbLevel {
+    type Error = Error;
+
+    fn try_from(raw: RawTcbLevel) -> Result<Self, Self::Error> {
+        let components = raw.tcb.sgxtcbcomponents;
+        let svns = components
+            .iter()
+            .map(|component| component.svn)
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| Error::TcbComponentCount(components.len()))?;
+        Ok(Self {
+            svns,
+            pce_svn: raw.tcb.pcesvn,
+            tcb_date: date_time(&raw.tcb_date)?,
+            tcb_status: raw.tcb_status,
+            advisory_ids: raw.advisory_ids,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "ra-tls"))]
+mod test {
+    use super::*;
+    use crate::test_utils::{TestCa, FMSPC, PCE_SVN, TCB_COMPONENT_SVNS};
+    use assert_matches::assert_matches;
+    use yare::parameterized;
+
+    fn tcb_info() -> FmspcTcbInfo {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        FmspcTcbInfo::from_signed_json(collateral.tcb_info(), &collateral.tcb_issuer_chain()[0])
+            .expect("Failed to parse TCB info")
+    }
+
+    #[test]
+    fn parse_signed_tcb_info() {
+        let tcb_info = tcb_info();
+
+        assert_eq!(tcb_info.fmspc(), &FMSPC);
+        assert_eq!(tcb_info.pce_id(), &[0, 0]);
+        assert_eq!(tcb_info.tcb_levels().len(), 2);
+        assert_eq!(
+            tcb_info.issue_date(),
+            DateTime::new(2024, 1, 1, 0, 0, 0).expect("Invalid date")
+        );
+    }
+
+    #[test]
+    fn tcb_info_signed_by_wrong_key_fails() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+
+        assert_eq!(
+            FmspcTcbInfo::from_signed_json(collateral.tcb_info(), ca.root_certificate()),
+            Err(Error::JsonSignature)
+        );
+    }
+
+    #[test]
+    fn modified_tcb_info_fails() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        let json = collateral
+            .tcb_info()
+            .replacen("\"UpToDate\"", "\"OutOfDate\"", 1);
+
+        assert_eq!(
+            FmspcTcbInfo::from_signed_json(&json, &collateral.tcb_issuer_chain()[0]),
+            Err(Error::JsonSignature)
+        );
+    }
+
+    #[test]
+    fn not_json_fails() {
+        let ca = TestCa::default();
+
+        assert_matches!(
+            FmspcTcbInfo::from_signed_json("not json", ca.root_certificate()),
+            Err(Error::Json(_))
+        );
+    }
+
+    #[parameterized(
+        at_level = { TCB_COMPONENT_SVNS, PCE_SVN as u32, Some(TcbStatus::UpToDate) },
+        above_level = { [20; COMPONENT_SVN_COUNT], 20, Some(TcbStatus::UpToDate) },
+        one_component_below = { [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 15], PCE_SVN as u32, Some(TcbStatus::OutOfDate) },
+        pce_svn_below = { TCB_COMPONENT_SVNS, PCE_SVN as u32 - 1, Some(TcbStatus::OutOfDate) },
+        below_all_levels = { [0; COMPONENT_SVN_COUNT], 0, None },
+    )]
+    fn tcb_level_for_platform(
+        svns: [u32; COMPONENT_SVN_COUNT],
+        pce_svn: u32,
+        expected: Option<TcbStatus>,
+    ) {
+        let tcb_info = tcb_info();
+        let platform = TcbInfo::new(svns, pce_svn, FMSPC);
+
+        let status = tcb_info.tcb_level(&platform).map(TcbLevel::tcb_status);
+
+        assert_eq!(status, expected);
+    }
+
+    #[test]
+    fn tcb_level_for_different_fmspc() {
+        let tcb_info = tcb_info();
+        let platform = TcbInfo::new(TCB_COMPONENT_SVNS, PCE_SVN as u32, [1; FMSPC_SIZE]);
+
+        assert_eq!(tcb_info.tcb_level(&platform), None);
+    }
+
+    #[parameterized(
+        up_to_date = { "\"UpToDate\"", TcbStatus::UpToDate },
+        sw_hardening = { "\"SWHardeningNeeded\"", TcbStatus::SwHardeningNeeded },
+        configuration = { "\"ConfigurationNeeded\"", TcbStatus::ConfigurationNeeded },
+        configuration_and_sw_hardening = { "\"ConfigurationAndSWHardeningNeeded\"", TcbStatus::ConfigurationAndSwHardeningNeeded },
+        out_of_date = { "\"OutOfDate\"", TcbStatus::OutOfDate },
+        out_of_date_configuration = { "\"OutOfDateConfigurationNeeded\"", TcbStatus::OutOfDateConfigurationNeeded },
+        revoked = { "\"Revoked\"", TcbStatus::Revoked },
+    )]
+    fn tcb_status_from_json(json: &str, expected: TcbStatus) {
+        let status: TcbStatus = serde_json::from_str(json).expect("Failed to parse status");
+        assert_eq!(status, expected);
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Helpers for the signed JSON collateral, TCB info and QE identity.
+//!
+//! Both are of the form
+//!
+//! ```json
+//! {"tcbInfo":{...},"signature":"..."}
+//! ```
+//!
+//! where the signature is the hex encoded ECDSA P-256 signature, `r || s`, of
+//! the exact bytes of the body, `{...}`.
+
+use crate::appraisal::Error;
+use crate::x509::verifying_key;
+use alloc::string::{String, ToString};
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::Signature;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use x509_cert::der::DateTime;
+use x509_cert::Certificate;
+
+#[derive(Deserialize)]
+struct SignedJson<'a> {
+    #[serde(borrow, alias = "tcbInfo", alias = "enclaveIdentity")]
+    body: &'a RawValue,
+    signature: String,
+}
+
+/// Verify the signature of the signed `json` with the key of `signer`.
+///
+/// Returns the body of the signed JSON.
+///
+/// # Errors
+/// * [`Error::Json`] if `json` is not a signed JSON object.
+/// * [`Error::JsonSignature`] if the signature is not valid.
+pub(crate) fn verify_signed_json<'a>(
+    json: &'a str,
+    signer: &Certificate,
+) -> Result<&'a str, Error> {
+    let signed: SignedJson = serde_json::from_str(json).map_err(json_error)?;
+    let mut signature = [0u8; 64];
+    hex::decode_to_slice(&signed.signature, &mut signature).map_err(|_| Error::JsonSignature)?;
+    let signature = Signature::from_slice(&signature).map_err(|_| Error::JsonSignature)?;
+    let key = verifying_key(signer).map_err(|_| Error::UnsupportedKey)?;
+    let body = signed.body.get();
+    key.verify(body.as_bytes(), &signature)
+        .map_err(|_| Error::JsonSignature)?;
+    Ok(body)
+}
+
+pub(crate) fn json_error(err: serde_json::Error) -> Error {
+    Error::Json(err.to_string())
+}
+
+/// Decode `hex` into exactly `N` bytes.
+pub(crate) fn hex_bytes<const N: usize>(hex: &str) -> Result<[u8; N], Error> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(hex, &mut bytes).map_err(|_| Error::Hex(hex.into()))?;
+    Ok(bytes)
+}
+
+/// Parse an RFC 3339 UTC date, `YYYY-MM-DDTHH:MM:SSZ`, as used by the
+/// collateral JSON.
+///
+/// Fractional seconds are accepted and discarded.
+pub(crate) fn date_time(date: &str) -> Result<DateTime, Error> {
+    let error = || Error::Date(date.into());
+    let bytes = date.as_bytes();
+    let (bytes, fraction) = match bytes {
+        [date_time @ .., b'Z'] if date_time.len() >= 19 => date_time.split_at(19),
+        _ => return Err(error()),
+    };
+    if !(fraction.is_empty()
+        || (fraction.len() > 1
+            && fraction[0] == b'.'
+            && fraction[1..].iter().all(u8::is_ascii_digit)))
+    {
+        return Err(error());
+    }
+
+    let number = |range: core::ops::Range<usize>| {
+        let digits = &bytes[range];
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return Err(error());
+        }
+        Ok(digits
+            .iter()
+            .fold(0u16, |total, digit| total * 10 + u16::from(digit - b'0')))
+    };
+    let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+    if separators
+        .iter()
+        .any(|(index, separator)| bytes[*index] != *separator)
+    {
+        return Err(error());
+    }
+
+    // The digit counts guarantee these fit in a `u8`
+    DateTime::new(
+        number(0..4)?,
+        number(5..7)? as u8,
+        number(8..10)? as u8,
+        number(11..13)? as u8,
+        number(14..16)? as u8,
+        number(17..19)? as u8,
+    )
+    .map_err(|_| error())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yare::parameterized;
+
+    #[parameterized(
+        intel = { "2024-03-13T11:48:07Z", (2024, 3, 13, 11, 48, 7) },
+        fractional = { "2023-08-09T00:00:00.123Z", (2023, 8, 9, 0, 0, 0) },
+        end_of_year = { "2049-12-31T23:59:59Z", (2049, 12, 31, 23, 59, 59) },
+    )]
+    fn valid_date_time(date: &str, expected: (u16, u8, u8, u8, u8, u8)) {
+        let (year, month, day, hour, minutes, seconds) = expected;
+        let expected =
+            DateTime::new(year, month, day, hour, minutes, seconds).expect("Invalid date");
+        assert_eq!(date_time(date), Ok(expected));
+    }
+
+    #[parameterized(
+        empty = { "" },
+        no_zone = { "2024-03-13T11:48:07" },
+        offset = { "2024-03-13T11:48:07+01:00" },
+        bad_separator = { "2024-03-13 11:48:07Z" },
+        bad_digit = { "2024-0a-13T11:48:07Z" },
+        bad_month = { "2024-13-13T11:48:07Z" },
+        empty_fraction = { "2024-03-13T11:48:07.Z" },
+    )]
+    fn invalid_date_time(date: &str) {
+        assert_eq!(date_time(date), Err(Error::Date(date.into())));
+    }
+}
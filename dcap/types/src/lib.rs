@@ -6,50 +6,110 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 #[cfg(feature = "tcb")]
 mod tcb;
 
+#[cfg(feature = "appraisal")]
+mod appraisal;
+
 mod certification_data;
 
 #[cfg(feature = "alloc")]
 mod collateral;
+#[cfg(feature = "appraisal")]
+mod collateral_report;
 
 mod error;
+#[cfg(feature = "appraisal")]
+mod evidence;
+#[cfg(feature = "appraisal")]
+mod fmspc_tcb_info;
+#[cfg(feature = "appraisal")]
+mod json;
+mod log_level;
+#[cfg(feature = "alloc")]
+mod pck_cert_id;
+mod platform_manifest;
+#[cfg(feature = "appraisal")]
+mod qe_identity;
 mod quote3;
 mod quoting_enclave;
+#[cfg(feature = "ra-tls")]
+pub mod ra_tls;
 mod request_policy;
+#[cfg(any(all(test, feature = "ra-tls"), feature = "software-ca"))]
+pub mod software_ca;
+#[cfg(feature = "appraisal")]
+mod tcb_recovery;
+mod time;
+#[cfg(feature = "appraisal")]
+mod x509;
 
+#[cfg(any(all(test, feature = "ra-tls"), feature = "test-utils"))]
+pub mod test_utils;
+
+#[cfg(target_has_atomic = "64")]
+pub use crate::time::MonotonicFloor;
+#[cfg(feature = "std")]
+pub use crate::time::SystemClock;
 pub use crate::{
     certification_data::{
-        CertificationData, EcdsaSignatureAuxData, Pck, PckCertificateChain, PlatformManifest, Ppid,
-        PpidEncryptedRsa2048, PpidEncryptedRsa3072,
+        CertificationData, EcdsaSignatureAuxData, Pck, PckCertificateChain, PlatformId,
+        PlatformManifest, Ppid, PpidEncryptedRsa2048, PpidEncryptedRsa3072, PpidEncryption,
     },
     error::{QlError, Quote3Error},
-    quote3::{Quote3, SignatureData},
-    quoting_enclave::ReportInfo,
+    log_level::LogLevel,
+    platform_manifest::{
+        Package, PackageIter, ParsedPlatformManifest, PlatformInfo, SgxType,
+        PLATFORM_INSTANCE_ID_SIZE,
+    },
+    quote3::{Quote3, SignatureData, QE_ID_SIZE},
+    quoting_enclave::{PceInfo, Qe3Id, ReportInfo},
     request_policy::RequestPolicy,
+    time::{TimeError, TimeSource},
 };
 #[cfg(feature = "tcb")]
-pub use crate::{tcb::Error as TcbError, tcb::TcbInfo, tcb::COMPONENT_SVN_COUNT, tcb::FMSPC_SIZE};
+pub use crate::{
+    tcb::CaType, tcb::Error as TcbError, tcb::TcbInfo, tcb::COMPONENT_SVN_COUNT, tcb::FMSPC_SIZE,
+};
 
 #[cfg(feature = "alloc")]
-pub use crate::collateral::{Collateral, Error as CollateralError};
+pub use crate::{
+    collateral::{Collateral, Error as CollateralError},
+    log_level::log_record,
+    pck_cert_id::{PckCertId, QuoteConfig},
+};
+
+#[cfg(feature = "appraisal")]
+pub use crate::{
+    appraisal::{
+        Appraisal, Error as AppraisalError, IdentityPolicy, QuoteAppraiser, VerifiedCollateral,
+        VerifiedPckChain,
+    },
+    collateral_report::{CollateralIssue, CollateralPart, CollateralReport, PartValidity},
+    evidence::{Audit, Evidence},
+    fmspc_tcb_info::{FmspcTcbInfo, TcbLevel, TcbStatus},
+    qe_identity::{QeIdentity, QeTcbLevel},
+    tcb_recovery::{PlatformImpact, TcbRecoveryImpact},
+};
+
+#[cfg(feature = "ra-tls")]
+pub use crate::ra_tls::{
+    Error as RaTlsError, RaTlsCertificate, RaTlsCertificateBuilder, COLLATERAL_EXTENSION_OID,
+    QUOTE_EXTENSION_OID,
+};
 
 // TODO:
 //
-// "_sgx_ql_qe3_id_t",
-// "_sgx_ql_config_t",
 // "_sgx_ql_config_version_t",
-// "_sgx_ql_pck_cert_id_t",
 // "_sgx_ql_qve_collateral_param_t",
 // "_sgx_ql_qve_collateral_t",
-// "_sgx_ql_log_level_t",
 // "_sgx_prod_type_t",
-// "sgx_ql_logging_callback_t",
 // "_sgx_pce_error_t",
 // "_sgx_ql_request_policy",
-// "_sgx_pce_info_t",
 // "_sgx_ql_att_key_id_param_t",
 // "_sgx_ql_att_id_list_t",
 // "sgx_ql_attestation_algorithm_id_t",
@@ -0,0 +1,141 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Log levels of the DCAP libraries
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::{Cow, ToOwned};
+#[cfg(feature = "alloc")]
+use core::ffi::CStr;
+use mc_sgx_core_types::FfiError;
+use mc_sgx_dcap_sys_types::sgx_ql_log_level_t;
+
+/// The most verbose messages to receive from the DCAP libraries.
+///
+/// Each level includes the messages of the levels before it.
+#[non_exhaustive]
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq, PartialOrd, Ord, Debug)]
+pub enum LogLevel {
+    /// Errors only
+    #[default]
+    Error,
+    /// Informational messages
+    Info,
+    /// Debugging messages
+    Debug,
+    /// Tracing messages, e.g. function entry and exit
+    Trace,
+}
+
+impl TryFrom<sgx_ql_log_level_t> for LogLevel {
+    type Error = FfiError;
+
+    fn try_from(l: sgx_ql_log_level_t) -> Result<Self, Self::Error> {
+        match l {
+            sgx_ql_log_level_t::SGX_QL_LOG_ERROR => Ok(Self::Error),
+            sgx_ql_log_level_t::SGX_QL_LOG_INFO => Ok(Self::Info),
+            sgx_ql_log_level_t::SGX_QL_LOG_DEBUG => Ok(Self::Debug),
+            sgx_ql_log_level_t::SGX_QL_LOG_TRACE => Ok(Self::Trace),
+            l => Err(FfiError::UnknownEnumValue(l.0.into())),
+        }
+    }
+}
+
+impl From<LogLevel> for sgx_ql_log_level_t {
+    fn from(l: LogLevel) -> sgx_ql_log_level_t {
+        match l {
+            LogLevel::Error => sgx_ql_log_level_t::SGX_QL_LOG_ERROR,
+            LogLevel::Info => sgx_ql_log_level_t::SGX_QL_LOG_INFO,
+            LogLevel::Debug => sgx_ql_log_level_t::SGX_QL_LOG_DEBUG,
+            LogLevel::Trace => sgx_ql_log_level_t::SGX_QL_LOG_TRACE,
+        }
+    }
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(l: LogLevel) -> log::Level {
+        match l {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// The [`log`] level and message for a log message of a DCAP library.
+///
+/// Messages of unknown levels are logged at [`log::Level::Trace`]. The
+/// trailing newline the libraries end their messages with is removed.
+#[cfg(feature = "alloc")]
+pub fn log_record(level: sgx_ql_log_level_t, message: &CStr) -> (log::Level, Cow<str>) {
+    let level = LogLevel::try_from(level).map_or(log::Level::Trace, Into::into);
+    let message = match message.to_string_lossy() {
+        Cow::Borrowed(message) => Cow::Borrowed(message.trim_end()),
+        Cow::Owned(message) => Cow::Owned(message.trim_end().to_owned()),
+    };
+    (level, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yare::parameterized;
+
+    #[parameterized(
+    error = { sgx_ql_log_level_t::SGX_QL_LOG_ERROR, LogLevel::Error },
+    info = { sgx_ql_log_level_t::SGX_QL_LOG_INFO, LogLevel::Info },
+    debug = { sgx_ql_log_level_t::SGX_QL_LOG_DEBUG, LogLevel::Debug },
+    trace = { sgx_ql_log_level_t::SGX_QL_LOG_TRACE, LogLevel::Trace },
+    )]
+    fn from_sgx_to_log_level(sgx_level: sgx_ql_log_level_t, expected: LogLevel) {
+        let level: LogLevel = sgx_level.try_into().unwrap();
+        assert_eq!(level, expected);
+        assert_eq!(sgx_ql_log_level_t::from(level), sgx_level);
+    }
+
+    #[parameterized(
+    error = { LogLevel::Error, log::Level::Error },
+    info = { LogLevel::Info, log::Level::Info },
+    debug = { LogLevel::Debug, log::Level::Debug },
+    trace = { LogLevel::Trace, log::Level::Trace },
+    )]
+    fn to_log_crate_level(level: LogLevel, expected: log::Level) {
+        assert_eq!(log::Level::from(level), expected);
+    }
+
+    #[test]
+    fn sgx_log_level_out_of_bounds_fails() {
+        let result = LogLevel::try_from(sgx_ql_log_level_t(4));
+        assert_eq!(result, Err(FfiError::UnknownEnumValue(4)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[parameterized(
+    error = { sgx_ql_log_level_t::SGX_QL_LOG_ERROR, log::Level::Error },
+    info = { sgx_ql_log_level_t::SGX_QL_LOG_INFO, log::Level::Info },
+    debug = { sgx_ql_log_level_t::SGX_QL_LOG_DEBUG, log::Level::Debug },
+    trace = { sgx_ql_log_level_t::SGX_QL_LOG_TRACE, log::Level::Trace },
+    unknown = { sgx_ql_log_level_t(10), log::Level::Trace },
+    )]
+    fn log_record_level(sgx_level: sgx_ql_log_level_t, expected: log::Level) {
+        let message = CStr::from_bytes_with_nul(b"message\0").unwrap();
+        let (level, _) = log_record(sgx_level, message);
+        assert_eq!(level, expected);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn log_record_trims_trailing_newline() {
+        let message = CStr::from_bytes_with_nul(b"[QPL] Encountered CURL error: (7)\n\0").unwrap();
+        let (_, message) = log_record(sgx_ql_log_level_t::SGX_QL_LOG_ERROR, message);
+        assert_eq!(message, "[QPL] Encountered CURL error: (7)");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn log_record_replaces_invalid_utf8() {
+        let message = CStr::from_bytes_with_nul(b"bad \xff\n\0").unwrap();
+        let (_, message) = log_record(sgx_ql_log_level_t::SGX_QL_LOG_ERROR, message);
+        assert_eq!(message, "bad \u{FFFD}");
+    }
+}
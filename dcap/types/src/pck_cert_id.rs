@@ -0,0 +1,200 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Identification of the PCK certificate of a platform and the certification
+//! data for its quotes.
+
+use crate::{PceInfo, PlatformId, PpidEncryption, Qe3Id, Quote3, Quote3Error};
+use alloc::vec::Vec;
+use mc_sgx_core_types::CpuSvn;
+
+/// The platform identifiers needed to retrieve the Provisioning Certification
+/// Key (PCK) certificate of a platform.
+///
+/// These are also the identifiers needed to register a platform with a
+/// PCCS.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PckCertId {
+    qe_id: Qe3Id,
+    cpu_svn: CpuSvn,
+    pce_info: PceInfo,
+    encryption: PpidEncryption,
+    ppid: Vec<u8>,
+}
+
+impl PckCertId {
+    /// A PCK certificate ID
+    ///
+    /// # Arguments
+    /// * `qe_id` - The ID of the quoting enclave of the platform.
+    /// * `cpu_svn` - The raw CPUSVN of the platform.
+    /// * `pce_info` - The PCESVN and PCEID of the platform.
+    /// * `encryption` - How `ppid` is encrypted.
+    /// * `ppid` - The platform provisioning ID (PPID).
+    pub fn new(
+        qe_id: Qe3Id,
+        cpu_svn: CpuSvn,
+        pce_info: PceInfo,
+        encryption: PpidEncryption,
+        ppid: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            qe_id,
+            cpu_svn,
+            pce_info,
+            encryption,
+            ppid: ppid.into(),
+        }
+    }
+
+    /// The ID of the quoting enclave
+    pub fn qe_id(&self) -> &Qe3Id {
+        &self.qe_id
+    }
+
+    /// The raw CPU security version number (CPUSVN) of the platform
+    pub fn cpu_svn(&self) -> &CpuSvn {
+        &self.cpu_svn
+    }
+
+    /// The PCESVN and PCEID of the platform
+    pub fn pce_info(&self) -> &PceInfo {
+        &self.pce_info
+    }
+
+    /// How the PPID is encrypted
+    pub fn encryption(&self) -> PpidEncryption {
+        self.encryption
+    }
+
+    /// The platform provisioning ID (PPID), encrypted per [`Self::encryption()`]
+    pub fn ppid(&self) -> &[u8] {
+        &self.ppid
+    }
+}
+
+impl<T: AsRef<[u8]>> TryFrom<&Quote3<T>> for PckCertId {
+    type Error = Quote3Error;
+
+    /// The PCK certificate ID of the platform which generated `quote`.
+    ///
+    /// The quoting library generates quotes with the encrypted PPID as the
+    /// certification data when it has no quote provider library (QPL) to
+    /// provide the PCK certificate chain.
+    ///
+    /// # Errors
+    /// A [`Quote3Error`] if the certification data of `quote` is not one of
+    /// the PPID forms, or is malformed.
+    fn try_from(quote: &Quote3<T>) -> Result<Self, Self::Error> {
+        let signature_data = quote.signature_data();
+        let platform = PlatformId::try_from(signature_data.certification_data())?;
+        Ok(Self::new(
+            (*quote.qe_id()).into(),
+            platform.cpu_svn().clone(),
+            PceInfo::new(platform.pce_svn(), platform.pce_id()),
+            platform.encryption(),
+            platform.ppid(),
+        ))
+    }
+}
+
+/// The certification data the quoting enclave uses to certify its
+/// attestation key.
+///
+/// This is provided to the quoting library by a quote provider library (QPL),
+/// for the platform identified by a [`PckCertId`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct QuoteConfig {
+    cert_cpu_svn: CpuSvn,
+    cert_pce_svn: u16,
+    cert_data: Vec<u8>,
+}
+
+impl QuoteConfig {
+    /// A quote configuration
+    ///
+    /// # Arguments
+    /// * `cert_cpu_svn` - The CPUSVN of the TCB level of the PCK certificate.
+    /// * `cert_pce_svn` - The PCESVN of the TCB level of the PCK certificate.
+    /// * `cert_data` - The certification data, the PEM PCK certificate chain.
+    pub fn new(cert_cpu_svn: CpuSvn, cert_pce_svn: u16, cert_data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            cert_cpu_svn,
+            cert_pce_svn,
+            cert_data: cert_data.into(),
+        }
+    }
+
+    /// The CPUSVN of the TCB level of the PCK certificate
+    pub fn cert_cpu_svn(&self) -> &CpuSvn {
+        &self.cert_cpu_svn
+    }
+
+    /// The PCESVN of the TCB level of the PCK certificate
+    pub fn cert_pce_svn(&self) -> u16 {
+        self.cert_pce_svn
+    }
+
+    /// The certification data, the PEM PCK certificate chain
+    pub fn cert_data(&self) -> &[u8] {
+        &self.cert_data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+    use core::mem;
+    use mc_sgx_dcap_sys_types::{sgx_ql_ecdsa_sig_data_t, sgx_quote3_t};
+
+    const PPID_RSA3072_TYPE: u16 = 3;
+
+    /// The hardware quote with its certification data replaced by
+    /// `certification_data` of `kind`.
+    fn hw_quote_with_certification_data(kind: u16, certification_data: &[u8]) -> Vec<u8> {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let signature_data_offset = mem::size_of::<sgx_quote3_t>();
+        let auth_data_offset = signature_data_offset + mem::size_of::<sgx_ql_ecdsa_sig_data_t>();
+        let auth_data_size =
+            u16::from_le_bytes([hw_quote[auth_data_offset], hw_quote[auth_data_offset + 1]]);
+        let certification_data_offset = auth_data_offset + 2 + auth_data_size as usize;
+
+        let mut quote = hw_quote[..certification_data_offset].to_vec();
+        quote.extend_from_slice(&kind.to_le_bytes());
+        quote.extend_from_slice(&(certification_data.len() as u32).to_le_bytes());
+        quote.extend_from_slice(certification_data);
+
+        let signature_data_size = (quote.len() - signature_data_offset) as u32;
+        quote[signature_data_offset - 4..signature_data_offset]
+            .copy_from_slice(&signature_data_size.to_le_bytes());
+        quote
+    }
+
+    #[test]
+    fn pck_cert_id_from_encrypted_ppid_quote() {
+        let mut data = vec![0x11; 384];
+        data.extend_from_slice(&[0x22; CpuSvn::SIZE]);
+        data.extend_from_slice(&[0x0D, 0x00, 0x01, 0x00]);
+        let bytes = hw_quote_with_certification_data(PPID_RSA3072_TYPE, &data);
+        let quote = Quote3::try_from(bytes.as_slice()).expect("Invalid quote");
+
+        let id = PckCertId::try_from(&quote).expect("Not a PPID quote");
+
+        assert_eq!(id.qe_id().id(), quote.qe_id());
+        assert_eq!(id.cpu_svn(), &CpuSvn::from([0x22; CpuSvn::SIZE]));
+        assert_eq!(id.pce_info(), &PceInfo::new(13, 1));
+        assert_eq!(id.encryption(), PpidEncryption::Rsa3072);
+        assert_eq!(id.ppid(), [0x11; 384]);
+    }
+
+    #[test]
+    fn pck_cert_id_from_pck_chain_quote_fails() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Invalid quote");
+
+        assert_eq!(
+            PckCertId::try_from(&quote),
+            Err(Quote3Error::CertificationDataType(5))
+        );
+    }
+}
@@ -0,0 +1,453 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Parsing of the platform manifest of multi-package platforms.
+//!
+//! The platform manifest is the `PLATFORM_MANIFEST` of Intel's SGX
+//! multi-package registration, the structure the Intel PCS "Register
+//! Platform" API takes. The layout below has *not* been checked against
+//! Intel's structure definitions or a manifest captured from a multi-package
+//! platform. The test fixture is synthesized from this layout, so it only
+//! shows the parser matches it.
+//!
+//! The platform manifest is a sequence of structures, each with a header of:
+//!
+//! | Field   | Size | Description                                |
+//! |---------|------|--------------------------------------------|
+//! | GUID    | 16   | Identifies the kind of the structure       |
+//! | Version | 2    | The version of the structure               |
+//! | Size    | 2    | The size of the body following the header  |
+//!
+//! The body of the outer platform manifest structure holds, in order:
+//! 1. The platform info, with the 16 byte platform instance ID and the 1 byte
+//!    SGX type.
+//! 2. For each package, its pairing receipt followed by its key blob.
+//! 3. The signature of the platform manifest.
+//!
+//! All values are little endian.
+
+use crate::{certification_data::PlatformManifest, quote3::le_u16, Quote3Error};
+
+type Result<T> = core::result::Result<T, Quote3Error>;
+
+/// The size of the GUID of a structure header
+const GUID_SIZE: usize = 16;
+
+/// The size of a structure header, the GUID, version and size
+const HEADER_SIZE: usize = GUID_SIZE + 2 + 2;
+
+/// The size of a platform instance ID
+pub const PLATFORM_INSTANCE_ID_SIZE: usize = 16;
+
+/// The minimum size of the platform info body, the platform instance ID and
+/// the SGX type
+const PLATFORM_INFO_SIZE: usize = PLATFORM_INSTANCE_ID_SIZE + 1;
+
+/// A structure of the platform manifest
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct Structure<'a> {
+    version: u16,
+    body: &'a [u8],
+}
+
+impl<'a> Structure<'a> {
+    /// Parse the structure at the start of `bytes`.
+    ///
+    /// Returns the structure and the bytes after it.
+    fn parse(bytes: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let actual = bytes.len();
+        let mut required = HEADER_SIZE;
+        if actual < required {
+            return Err(Quote3Error::InputLength { required, actual });
+        }
+        // The GUID isn't needed, the kind of a structure is by its position
+        let bytes = &bytes[GUID_SIZE..];
+        let (bytes, version) = le_u16(bytes);
+        let (bytes, size) = le_u16(bytes);
+
+        required += size as usize;
+        if actual < required {
+            return Err(Quote3Error::InputLength { required, actual });
+        }
+        let (body, rest) = bytes.split_at(size as usize);
+        Ok((Self { version, body }, rest))
+    }
+
+    /// The size of the structure, header and body
+    fn size(&self) -> usize {
+        HEADER_SIZE + self.body.len()
+    }
+}
+
+/// The kind of SGX of a platform
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SgxType {
+    /// Standard SGX, with integrity protected memory
+    Standard,
+    /// Scalable SGX, without integrity protected memory
+    Scalable,
+    /// Scalable SGX with integrity protected memory
+    ScalableWithIntegrity,
+}
+
+impl TryFrom<u8> for SgxType {
+    type Error = Quote3Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Standard),
+            1 => Ok(Self::Scalable),
+            2 => Ok(Self::ScalableWithIntegrity),
+            value => Err(Quote3Error::SgxType(value)),
+        }
+    }
+}
+
+/// The platform info of a [`ParsedPlatformManifest`]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PlatformInfo<'a> {
+    version: u16,
+    platform_instance_id: &'a [u8; PLATFORM_INSTANCE_ID_SIZE],
+    sgx_type: SgxType,
+}
+
+impl<'a> PlatformInfo<'a> {
+    /// The version of the platform info structure
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// The ID of the platform instance.
+    ///
+    /// This stays the same for the life of the platform, it only changes when
+    /// the platform is reset.
+    pub fn platform_instance_id(&self) -> &'a [u8; PLATFORM_INSTANCE_ID_SIZE] {
+        self.platform_instance_id
+    }
+
+    /// The kind of SGX of the platform
+    pub fn sgx_type(&self) -> SgxType {
+        self.sgx_type
+    }
+}
+
+impl<'a> TryFrom<Structure<'a>> for PlatformInfo<'a> {
+    type Error = Quote3Error;
+
+    fn try_from(structure: Structure<'a>) -> Result<Self> {
+        let body = structure.body;
+        let actual = body.len();
+        if actual < PLATFORM_INFO_SIZE {
+            let required = PLATFORM_INFO_SIZE;
+            return Err(Quote3Error::InputLength { required, actual }.increase_size(HEADER_SIZE));
+        }
+        let (platform_instance_id, body) = body.split_at(PLATFORM_INSTANCE_ID_SIZE);
+        let platform_instance_id = platform_instance_id
+            .try_into()
+            .expect("Size was validated to hold the platform instance ID");
+        Ok(Self {
+            version: structure.version,
+            platform_instance_id,
+            sgx_type: SgxType::try_from(body[0])?,
+        })
+    }
+}
+
+/// The registration data of one package of a multi-package platform
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Package<'a> {
+    pairing_receipt: &'a [u8],
+    key_blob: &'a [u8],
+}
+
+impl<'a> Package<'a> {
+    /// The pairing receipt of the package, binding it to the platform
+    pub fn pairing_receipt(&self) -> &'a [u8] {
+        self.pairing_receipt
+    }
+
+    /// The encrypted platform key blob of the package
+    pub fn key_blob(&self) -> &'a [u8] {
+        self.key_blob
+    }
+}
+
+/// Iterator over the [`Package`]s of a [`ParsedPlatformManifest`]
+#[derive(Debug, Clone)]
+pub struct PackageIter<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for PackageIter<'a> {
+    type Item = Package<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let (pairing_receipt, bytes) =
+            Structure::parse(self.bytes).expect("Packages were validated during parsing");
+        let (key_blob, bytes) =
+            Structure::parse(bytes).expect("Packages were validated during parsing");
+        self.bytes = bytes;
+        Some(Package {
+            pairing_receipt: pairing_receipt.body,
+            key_blob: key_blob.body,
+        })
+    }
+}
+
+/// The structure of a platform manifest.
+///
+/// The platform manifest is created by the BIOS of a multi-package platform
+/// and is used to register the platform, and its packages, with the Intel
+/// registration service.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ParsedPlatformManifest<'a> {
+    version: u16,
+    platform_info: PlatformInfo<'a>,
+    packages: &'a [u8],
+    package_count: usize,
+    signature: &'a [u8],
+}
+
+impl<'a> ParsedPlatformManifest<'a> {
+    /// The version of the platform manifest
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// The platform info
+    pub fn platform_info(&self) -> &PlatformInfo<'a> {
+        &self.platform_info
+    }
+
+    /// The number of packages of the platform
+    pub fn package_count(&self) -> usize {
+        self.package_count
+    }
+
+    /// The packages of the platform
+    pub fn packages(&self) -> PackageIter<'a> {
+        PackageIter {
+            bytes: self.packages,
+        }
+    }
+
+    /// The signature of the platform manifest
+    pub fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ParsedPlatformManifest<'a> {
+    type Error = Quote3Error;
+
+    /// Parse a platform manifest.
+    ///
+    /// # Errors
+    /// * [`Quote3Error::InputLength`] if a structure is larger than the bytes
+    ///   remaining, the sizes are from the start of `bytes`.
+    /// * [`Quote3Error::SgxType`] if the SGX type is unknown.
+    /// * [`Quote3Error::PlatformManifestStructures`] if the structures after
+    ///   the platform info aren't a pair for each package and the signature.
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        let (manifest, _) = Structure::parse(bytes)?;
+
+        let mut offset = HEADER_SIZE;
+        let (info, rest) = Structure::parse(manifest.body).map_err(|e| e.increase_size(offset))?;
+        let platform_info = PlatformInfo::try_from(info).map_err(|e| e.increase_size(offset))?;
+        offset += info.size();
+
+        let packages = rest;
+        let mut structures = 0;
+        let mut remaining = rest;
+        let mut signature = None;
+        while !remaining.is_empty() {
+            let (structure, rest) =
+                Structure::parse(remaining).map_err(|e| e.increase_size(offset))?;
+            offset += structure.size();
+            structures += 1;
+            signature = Some(structure);
+            remaining = rest;
+        }
+
+        let signature = match signature {
+            Some(signature) if structures % 2 == 1 => signature,
+            _ => return Err(Quote3Error::PlatformManifestStructures(structures)),
+        };
+        let packages_size = packages.len() - signature.size();
+
+        Ok(Self {
+            version: manifest.version,
+            platform_info,
+            packages: &packages[..packages_size],
+            package_count: structures / 2,
+            signature: signature.body,
+        })
+    }
+}
+
+impl<'a> TryFrom<&PlatformManifest<'a>> for ParsedPlatformManifest<'a> {
+    type Error = Quote3Error;
+
+    fn try_from(platform_manifest: &PlatformManifest<'a>) -> Result<Self> {
+        Self::try_from(platform_manifest.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate alloc;
+    use alloc::vec::Vec;
+    use yare::parameterized;
+
+    const MANIFEST: &[u8] = include_bytes!("../data/tests/platform_manifest.dat");
+
+    /// A structure of `body`, with a GUID of `guid_byte` repeated
+    fn structure(guid_byte: u8, body: &[u8]) -> Vec<u8> {
+        let mut bytes = [guid_byte; GUID_SIZE].to_vec();
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    /// A manifest of the `structures` following a platform info of
+    /// `sgx_type`
+    fn manifest(sgx_type: u8, structures: &[&[u8]]) -> Vec<u8> {
+        let mut platform_info = [0x1D; PLATFORM_INSTANCE_ID_SIZE].to_vec();
+        platform_info.push(sgx_type);
+        let mut body = structure(1, &platform_info);
+        for s in structures {
+            body.extend_from_slice(&structure(2, s));
+        }
+        structure(0, &body)
+    }
+
+    #[test]
+    fn parse_fixture() {
+        let manifest = ParsedPlatformManifest::try_from(MANIFEST).expect("Invalid manifest");
+
+        assert_eq!(manifest.version(), 1);
+        let platform_info = manifest.platform_info();
+        assert_eq!(platform_info.version(), 1);
+        assert_eq!(platform_info.platform_instance_id(), &[0xA5; 16]);
+        assert_eq!(platform_info.sgx_type(), SgxType::ScalableWithIntegrity);
+        assert_eq!(manifest.package_count(), 2);
+        let packages = manifest.packages().collect::<Vec<_>>();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].pairing_receipt(), [0x11; 32]);
+        assert_eq!(packages[0].key_blob(), [0x12; 64]);
+        assert_eq!(packages[1].pairing_receipt(), [0x21; 32]);
+        assert_eq!(packages[1].key_blob(), [0x22; 64]);
+        assert_eq!(manifest.signature(), [0x5A; 64]);
+    }
+
+    #[test]
+    fn parse_from_certification_data() {
+        let mut bytes = 7u16.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(MANIFEST.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(MANIFEST);
+        let certification_data = crate::CertificationData::try_from(bytes.as_slice()).unwrap();
+        let crate::CertificationData::PlatformManifest(platform_manifest) = certification_data
+        else {
+            panic!("Not a platform manifest");
+        };
+
+        let manifest = ParsedPlatformManifest::try_from(&platform_manifest).unwrap();
+
+        assert_eq!(manifest.package_count(), 2);
+    }
+
+    #[test]
+    fn manifest_without_packages() {
+        let bytes = manifest(0, &[&[3; 8]]);
+
+        let manifest = ParsedPlatformManifest::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(manifest.platform_info().sgx_type(), SgxType::Standard);
+        assert_eq!(manifest.package_count(), 0);
+        assert_eq!(manifest.packages().count(), 0);
+        assert_eq!(manifest.signature(), [3; 8]);
+    }
+
+    #[parameterized(
+        no_signature = { &[], 0 },
+        unpaired_package = { &[&[1], &[2]], 2 },
+        missing_key_blob = { &[&[1], &[2], &[3], &[4]], 4 },
+    )]
+    fn wrong_number_of_structures(structures: &[&[u8]], count: usize) {
+        let bytes = manifest(1, structures);
+
+        assert_eq!(
+            ParsedPlatformManifest::try_from(bytes.as_slice()),
+            Err(Quote3Error::PlatformManifestStructures(count))
+        );
+    }
+
+    #[test]
+    fn unknown_sgx_type() {
+        let bytes = manifest(3, &[&[0; 4]]);
+
+        assert_eq!(
+            ParsedPlatformManifest::try_from(bytes.as_slice()),
+            Err(Quote3Error::SgxType(3))
+        );
+    }
+
+    #[test]
+    fn empty_manifest() {
+        assert_eq!(
+            ParsedPlatformManifest::try_from([].as_slice()),
+            Err(Quote3Error::InputLength {
+                required: HEADER_SIZE,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_manifest() {
+        let actual = MANIFEST.len() - 1;
+
+        assert_eq!(
+            ParsedPlatformManifest::try_from(&MANIFEST[..actual]),
+            Err(Quote3Error::InputLength {
+                required: MANIFEST.len(),
+                actual
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_platform_info() {
+        let bytes = structure(0, &structure(1, &[0x1D; PLATFORM_INFO_SIZE - 1]));
+
+        assert_eq!(
+            ParsedPlatformManifest::try_from(bytes.as_slice()),
+            Err(Quote3Error::InputLength {
+                required: HEADER_SIZE * 2 + PLATFORM_INFO_SIZE,
+                actual: bytes.len()
+            })
+        );
+    }
+
+    #[test]
+    fn structure_larger_than_manifest_body() {
+        // The signature claims 16 bytes, but the manifest only has 4 of them
+        let mut body = structure(1, &[0; PLATFORM_INFO_SIZE]);
+        let signature = structure(2, &[9; 16]);
+        body.extend_from_slice(&signature[..HEADER_SIZE + 4]);
+        let bytes = structure(0, &body);
+
+        assert_eq!(
+            ParsedPlatformManifest::try_from(bytes.as_slice()),
+            Err(Quote3Error::InputLength {
+                required: bytes.len() + 12,
+                actual: bytes.len()
+            })
+        );
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! The identity of the quoting enclave (QE).
+//!
+//! This is the JSON QE identity provided by the [`Collateral`](crate::Collateral),
+//! described at
+//! <https://api.portal.trustedservices.intel.com/documentation#pcs-enclave-identity-model-v2>.
+//! It is compared against the QE report body of a [`Quote3`](crate::Quote3) to
+//! ensure the quote came from a genuine quoting enclave.
+
+use crate::appraisal::Error;
+use crate::json::{date_time, hex_bytes, json_error, verify_signed_json};
+use crate::TcbStatus;
+use alloc::string::String;
+use alloc::vec::Vec;
+use mc_sgx_core_types::{
+    AttributeFlags, Attributes, ExtendedFeatureRequestMask, IsvProductId, IsvSvn,
+    MiscellaneousSelect, MrSigner, ReportBody,
+};
+use serde::Deserialize;
+use x509_cert::der::DateTime;
+use x509_cert::Certificate;
+
+const QE_ID: &str = "QE";
+const QE_IDENTITY_VERSION: u32 = 2;
+
+/// A TCB level of a [`QeIdentity`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QeTcbLevel {
+    isv_svn: IsvSvn,
+    tcb_date: DateTime,
+    tcb_status: TcbStatus,
+    advisory_ids: Vec<String>,
+}
+
+impl QeTcbLevel {
+    /// The minimum ISV SVN of the quoting enclave for this level.
+    pub fn isv_svn(&self) -> IsvSvn {
+        self.isv_svn
+    }
+
+    /// The date of this level.
+    pub fn tcb_date(&self) -> DateTime {
+        self.tcb_date
+    }
+
+    /// The status of quoting enclaves at this level.
+    pub fn tcb_status(&self) -> TcbStatus {
+        self.tcb_status
+    }
+
+    /// The Intel security advisories which apply to quoting enclaves at this
+    /// level.
+    pub fn advisory_ids(&self) -> &[String] {
+        &self.advisory_ids
+    }
+}
+
+/// The identity of the Intel quoting enclave.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QeIdentity {
+    issue_date: DateTime,
+    next_update: DateTime,
+    tcb_evaluation_data_number: u32,
+    miscellaneous_select: MiscellaneousSelect,
+    miscellaneous_select_mask: MiscellaneousSelect,
+    attributes: Attributes,
+    attributes_mask: Attributes,
+    mr_signer: MrSigner,
+    isv_product_id: IsvProductId,
+    tcb_levels: Vec<QeTcbLevel>,
+}
+
+impl QeIdentity {
+    /// Parse the signed QE identity `json` verifying its signature.
+    ///
+    /// # Arguments
+    /// * `json` - The signed QE identity JSON, as provided by
+    ///   [`Collateral::qe_identity()`](crate::Collateral::qe_identity).
+    /// * `signer` - The TCB signing certificate, the leaf of
+    ///   [`Collateral::qe_identity_issuer_chain()`](crate::Collateral::qe_identity_issuer_chain).
+    ///   The caller is responsible for verifying the certificate.
+    ///
+    /// # Errors
+    /// * [`Error::Json`] if `json` is not valid QE identity JSON.
+    /// * [`Error::JsonSignature`] if the signature is not valid.
+    /// * [`Error::UnsupportedQeIdentity`] if the identity is not a version 2
+    ///   QE identity.
+    pub fn from_signed_json(json: &str, signer: &Certificate) -> Result<Self, Error> {
+        let body = verify_signed_json(json, signer)?;
+        let raw: RawQeIdentity = serde_json::from_str(body).map_err(json_error)?;
+        Self::try_from(raw)
+    }
+
+    /// The date the identity was issued.
+    pub fn issue_date(&self) -> DateTime {
+        self.issue_date
+    }
+
+    /// The date by which the next identity will be issued.
+    pub fn next_update(&self) -> DateTime {
+        self.next_update
+    }
+
+    /// The TCB evaluation data number, incremented each TCB recovery.
+    pub fn tcb_evaluation_data_number(&self) -> u32 {
+        self.tcb_evaluation_data_number
+    }
+
+    /// The expected MRSIGNER of the quoting enclave.
+    pub fn mr_signer(&self) -> MrSigner {
+        self.mr_signer
+    }
+
+    /// The expected product ID of the quoting enclave.
+    pub fn isv_product_id(&self) -> IsvProductId {
+        self.isv_product_id
+    }
+
+    /// The TCB levels, from highest to lowest.
+    pub fn tcb_levels(&self) -> &[QeTcbLevel] {
+        &self.tcb_levels
+    }
+
+    /// Does the quoting enclave `report_body` match this identity.
+    ///
+    /// The MRSIGNER and product ID must be equal, and the miscellaneous select
+    /// and attributes must be equal under their respective masks. The ISV SVN
+    /// is evaluated separately with [`QeIdentity::tcb_level()`].
+    pub fn matches(&self, report_body: &ReportBody) -> bool {
+        let misc_mask = self.miscellaneous_select_mask;
+        let misc_matches =
+            report_body.miscellaneous_select() & misc_mask == self.miscellaneous_select & misc_mask;
+
+        let attributes_matches =
+            masked_attributes(&report_body.attributes(), &self.attributes_mask)
+                == masked_attributes(&self.attributes, &self.attributes_mask);
+
+        misc_matches
+            && attributes_matches
+            && report_body.mr_signer() == self.mr_signer
+            && report_body.isv_product_id() == self.isv_product_id
+    }
+
+    /// The highest TCB level satisfied by the quoting enclave's `isv_svn`.
+    ///
+    /// Returns `None` when `isv_svn` is below every TCB level.
+    pub fn tcb_level(&self, isv_svn: IsvSvn) -> Option<&QeTcbLevel> {
+        let isv_svn: u16 = isv_svn.into();
+        self.tcb_levels.iter().find(|level| {
+            let level_svn: u16 = level.isv_svn.into();
+            isv_svn >= level_svn
+        })
+    }
+}
+
+/// The (flags, xfrm) of `attributes` under `mask`.
+fn masked_attributes(attributes: &Attributes, mask: &Attributes) -> (u64, u64) {
+    let attributes = attributes.as_ref();
+    let mask = mask.as_ref();
+    (attributes.flags & mask.flags, attributes.xfrm & mask.xfrm)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawQeIdentity {
+    id: String,
+    version: u32,
+    issue_date: String,
+    next_update: String,
+    tcb_evaluation_data_number: u32,
+    miscselect: String,
+    miscselect_mask: String,
+    attributes: String,
+    attributes_mask: String,
+    mrsigner: String,
+    isvprodid: u16,
+    tcb_levels: Vec<RawQeTcbLevel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawQeTcbLevel {
+    tcb: RawQeTcb,
+    tcb_date: String,
+    tcb_status: TcbStatus,
+    #[serde(default, rename = "advisoryIDs")]
+    advisory_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawQeTcb {
+    isvsvn: u16,
+}
+
+impl TryFrom<RawQeIdentity> for QeIdentity {
+    type Error = Error;
+
+    fn try_from(raw: RawQeIdentity) -> Result<Self, Self::Error> {
+        if raw.id != QE_ID || raw.version != QE_IDENTITY_VERSION {
+            return Err(Error::UnsupportedQeIdentity);
+        }
+        let tcb_levels = raw
+            .tcb_levels
+            .into_iter()
+            .map(QeTcbLevel::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            issue_date: date_time(&raw.issue_date)?,
+            next_update: date_time(&raw.next_update)?,
+            tcb_evaluation_data_number: raw.tcb_evaluation_data_number,
+            miscellaneous_select: miscellaneous_select(&raw.miscselect)?,
+            miscellaneous_select_mask: miscellaneous_select(&raw.miscselect_mask)?,
+            attributes: attributes(&raw.attributes)?,
+            attributes_mask: attributes(&raw.attributes_mask)?,
+            mr_signer: hex_bytes::<{ MrSigner::SIZE }>(&raw.mrsigner)?.into(),
+            isv_product_id: raw.isvprodid.into(),
+            tcb_levels,
+        })
+    }
+}
+
+impl TryFrom<RawQeTcbLevel> for QeTcbLevel {
+    type Error = Error;
+
+    fn try_from(raw: RawQeTcbLevel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            isv_svn: raw.tcb.isvsvn.into(),
+            tcb_date: date_time(&raw.tcb_date)?,
+            tcb_status: raw.tcb_status,
+            advisory_ids: raw.advisory_ids,
+        })
+    }
+}
+
+/// The miscellaneous select is the big endian hex of the 32 bit value.
+fn miscellaneous_select(hex: &str) -> Result<MiscellaneousSelect, Error> {
+    Ok(u32::from_be_bytes(hex_bytes(hex)?).into())
+}
+
+/// The attributes are the hex of the `sgx_attributes_t` bytes, the little
+/// endian flags followed by the little endian xfrm.
+fn attributes(hex: &str) -> Result<Attributes, Error> {
+    let bytes = hex_bytes::<16>(hex)?;
+    let (flags, xfrm) = bytes.split_at(8);
+    let flags = u64::from_le_bytes(flags.try_into().expect("Flags should be 8 bytes"));
+    let xfrm = u64::from_le_bytes(xfrm.try_into().expect("Xfrm should be 8 bytes"));
+    Ok(Attributes::default()
+        .set_flags(AttributeFlags::from_bits_retain(flags))
+        .set_extended_features_mask(ExtendedFeatureRequestMask::from_bits_retain(xfrm)))
+}
+
+#[cfg(all(test, feature = "ra-tls"))]
+mod test {
+    use super::*;
+    use crate::test_utils::{TestCa, QE_SVN};
+    use mc_sgx_core_types::ReportData;
+    use yare::parameterized;
+
+    fn qe_identity() -> QeIdentity {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+        QeIdentity::from_signed_json(
+            collateral.qe_identity(),
+            &collateral.qe_identity_issuer_chain()[0],
+        )
+        .expect("Failed to parse QE identity")
+    }
+
+    fn qe_report_body() -> ReportBody {
+        let quote = TestCa::default().quote_for_report_data(&ReportData::default());
+        let signature_data = quote.signature_data();
+        signature_data.qe_report_body().clone()
+    }
+
+    #[test]
+    fn parse_signed_qe_identity() {
+        let identity = qe_identity();
+
+        assert_eq!(identity.isv_product_id(), IsvProductId::from(1));
+        assert_eq!(identity.tcb_levels().len(), 2);
+    }
+
+    #[test]
+    fn identity_signed_by_wrong_key_fails() {
+        let ca = TestCa::default();
+        let collateral = ca.collateral();
+
+        assert_eq!(
+            QeIdentity::from_signed_json(collateral.qe_identity(), ca.root_certificate()),
+            Err(Error::JsonSignature)
+        );
+    }
+
+    #[test]
+    fn test_ca_quoting_enclave_matches() {
+        assert!(qe_identity().matches(&qe_report_body()));
+    }
+
+    #[test]
+    fn different_mr_signer_does_not_match() {
+        let mut identity = qe_identity();
+        identity.mr_signer = MrSigner::from([0x55; MrSigner::SIZE]);
+
+        assert!(!identity.matches(&qe_report_body()));
+    }
+
+    #[test]
+    fn different_product_id_does_not_match() {
+        let mut identity = qe_identity();
+        identity.isv_product_id = IsvProductId::from(2);
+
+        assert!(!identity.matches(&qe_report_body()));
+    }
+
+    #[test]
+    fn unmasked_attributes_are_ignored() {
+        let mut identity = qe_identity();
+        identity.attributes =
+            attributes("11000000000000000300000000000000").expect("Failed to parse attributes");
+
+        assert!(identity.matches(&qe_report_body()));
+    }
+
+    #[test]
+    fn masked_attributes_must_match() {
+        let mut identity = qe_identity();
+        identity.attributes =
+            attributes("13000000000000000000000000000000").expect("Failed to parse attributes");
+
+        assert!(!identity.matches(&qe_report_body()));
+    }
+
+    #[parameterized(
+        current = { QE_SVN, Some(TcbStatus::UpToDate) },
+        newer = { QE_SVN + 1, Some(TcbStatus::UpToDate) },
+        older = { QE_SVN - 1, Some(TcbStatus::OutOfDate) },
+        too_old = { 0, None },
+    )]
+    fn qe_tcb_level(isv_svn: u16, expected: Option<TcbStatus>) {
+        let identity = qe_identity();
+
+        let status = identity
+            .tcb_level(isv_svn.into())
+            .map(QeTcbLevel::tcb_status);
+
+        assert_eq!(status, expected);
+    }
+
+    #[test]
+    fn miscellaneous_select_is_big_endian() {
+        assert_eq!(
+            miscellaneous_select("00000001"),
+            Ok(MiscellaneousSelect::from(1))
+        );
+    }
+}
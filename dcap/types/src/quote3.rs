@@ -4,7 +4,7 @@
 
 use crate::{
     certification_data::{CertificationData, MIN_CERT_DATA_SIZE},
-    Quote3Error,
+    Quote3Error, ReportInfo,
 };
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
@@ -44,12 +44,25 @@ const SIGNATURE_SIZE: usize = 64;
 // The offset to the report body for the app. From the start of the quote.
 const REPORT_BODY_OFFSET: usize = mem::size_of::<sgx_quote_header_t>();
 
+/// The size of the quoting enclave (QE) ID
+pub const QE_ID_SIZE: usize = 16;
+
+// The size of the user data at the end of the quote header.
+const USER_DATA_SIZE: usize = 20;
+
+// The offset to the QE ID, the start of the user data of the header.
+const QE_ID_OFFSET: usize = REPORT_BODY_OFFSET - USER_DATA_SIZE;
+
 // The offset to the attestation key, from the start of the quote.
 const ATTESTATION_KEY_OFFSET: usize = mem::size_of::<sgx_quote3_t>() + SIGNATURE_SIZE;
 
 // The offset to the QE report body, from the start of the quote.
 const QE_REPORT_BODY_OFFSET: usize = ATTESTATION_KEY_OFFSET + KEY_SIZE;
 
+// The offset to the authentication data, from the start of the quote.
+const AUTHENTICATION_DATA_OFFSET: usize =
+    mem::size_of::<sgx_quote3_t>() + mem::size_of::<sgx_ql_ecdsa_sig_data_t>();
+
 /// The minimum size of a byte array to contain a [`AuthenticationData`]
 /// the 2 bytes for QE authentication data size
 const MIN_AUTH_DATA_SIZE: usize = 2;
@@ -64,11 +77,16 @@ pub const MIN_QUOTE_SIZE: usize = mem::size_of::<sgx_quote3_t>() + MIN_SIGNATURE
 type Result<T> = ::core::result::Result<T, Quote3Error>;
 
 /// Quote version 3
+///
+/// The signature data is decoded once, when the quote is created, so
+/// [`Quote3::signature_data()`] and [`Quote3::verify()`] don't decode the
+/// signatures and the attestation key again.
 #[derive(Clone, Debug)]
 pub struct Quote3<T> {
     // The full raw bytes of the Quote3 data
     raw_bytes: T,
     report_body: ReportBody,
+    signature: DecodedSignatureData,
 }
 
 impl<T: AsRef<[u8]>> Eq for Quote3<T> {}
@@ -194,6 +212,46 @@ impl<T: AsRef<[u8]>> Quote3<T> {
         data.ct_eq(report_data.as_ref()).into()
     }
 
+    /// Verify that `report_info` binds this quote to `nonce`
+    ///
+    /// The report info is returned from quote generation with a nonce. Its QE
+    /// report must come from the quoting enclave that signed this quote and
+    /// have the report data `SHA256(nonce||quote)||32-0x00's`, see
+    /// [`Quote3::verify_nonce()`].
+    ///
+    /// > Note: This does not verify the QE report itself. Only the enclave
+    ///     the QE report targets can do that, with `sgx_verify_report()`.
+    ///
+    /// # Arguments
+    /// * `nonce` - The nonce provided to the quote generation
+    /// * `report_info` - The report info returned from the quote generation
+    ///
+    /// # Errors
+    /// * [`Quote3Error::QeReportIdentity`] if the QE report is from a
+    ///   different quoting enclave than the one in this quote.
+    /// * [`Quote3Error::QeReportNonce`] if `report_info` is not for `nonce`
+    ///   and this quote.
+    pub fn verify_report_info(
+        &self,
+        nonce: &QuoteNonce,
+        report_info: &ReportInfo,
+    ) -> Result<(), Quote3Error> {
+        let qe_report_body = report_info.report().body();
+        let signature_data = self.signature_data();
+        let quote_qe_report_body = signature_data.qe_report_body();
+        if qe_report_body.mr_enclave() != quote_qe_report_body.mr_enclave()
+            || qe_report_body.mr_signer() != quote_qe_report_body.mr_signer()
+        {
+            return Err(Quote3Error::QeReportIdentity);
+        }
+
+        if &report_info.nonce() != nonce || !self.verify_nonce(nonce, &qe_report_body.report_data())
+        {
+            return Err(Quote3Error::QeReportNonce);
+        }
+        Ok(())
+    }
+
     /// Report body of the application enclave
     ///
     /// This is also referred to as the ISV report body.
@@ -201,10 +259,38 @@ impl<T: AsRef<[u8]>> Quote3<T> {
         &self.report_body
     }
 
+    /// The quoting enclave (QE) ID.
+    ///
+    /// The first 16 bytes of the user data of the quote header. The QE ID
+    /// identifies the platform to the PCCS, which uses it to look up the PCK
+    /// certificate for quotes without a PCK certificate chain.
+    pub fn qe_id(&self) -> &[u8; QE_ID_SIZE] {
+        self.raw_bytes.as_ref()[QE_ID_OFFSET..QE_ID_OFFSET + QE_ID_SIZE]
+            .try_into()
+            .expect("Quote size was validated during Quote creation.")
+    }
+
     /// Signature data of the Quote
     pub fn signature_data(&self) -> SignatureData {
-        SignatureData::try_from(&self.raw_bytes.as_ref()[mem::size_of::<sgx_quote3_t>()..])
-            .expect("Signature data was validated during Quote creation.")
+        let signature = &self.signature;
+        let bytes = self.raw_bytes.as_ref();
+        let authentication_data = AuthenticationData {
+            data: &bytes[AUTHENTICATION_DATA_OFFSET + mem::size_of::<u16>()..]
+                [..signature.authentication_data_size],
+        };
+        let certification_data = CertificationData::from_kind(
+            signature.certification_data_type,
+            &bytes[signature.certification_data_offset..][..signature.certification_data_size],
+        )
+        .expect("Certification data was validated during Quote creation.");
+        SignatureData {
+            isv_enclave_signature: signature.isv_enclave_signature,
+            attestation_key: signature.attestation_key,
+            qe_report_body: signature.qe_report_body.clone(),
+            qe_report_signature: signature.qe_report_signature,
+            authentication_data,
+            certification_data,
+        }
     }
 
     /// Try to get a [`Quote3`] from `bytes`
@@ -239,12 +325,14 @@ impl<T: AsRef<[u8]>> Quote3<T> {
         let report_body = ReportBody::try_from(&bytes[REPORT_BODY_OFFSET..])
             .expect("Previous check should guarantee enough size to decode ReportBody");
 
-        let _ = SignatureData::try_from(&bytes[mem::size_of::<sgx_quote3_t>()..])
+        let signature = SignatureData::try_from(&bytes[mem::size_of::<sgx_quote3_t>()..])
             .map_err(|e| e.increase_size(mem::size_of::<sgx_quote3_t>()))?;
+        let signature = DecodedSignatureData::from(&signature);
 
         Ok(Self {
             raw_bytes,
             report_body,
+            signature,
         })
     }
 }
@@ -263,6 +351,7 @@ impl From<Quote3<&[u8]>> for Quote3<Vec<u8>> {
         Self {
             raw_bytes: quote.raw_bytes.to_vec(),
             report_body: quote.report_body,
+            signature: quote.signature,
         }
     }
 }
@@ -422,6 +511,41 @@ impl<'a> SignatureData<'a> {
     }
 }
 
+/// The parts of the [`SignatureData`] which are decoded, rather than
+/// borrowed, from the quote bytes.
+#[derive(Clone, Debug)]
+struct DecodedSignatureData {
+    isv_enclave_signature: Signature,
+    attestation_key: VerifyingKey,
+    qe_report_body: ReportBody,
+    qe_report_signature: Signature,
+    // The length of the authentication data, which follows the QE report
+    // signature.
+    authentication_data_size: usize,
+    // The type, offset from the start of the quote, and length of the data
+    // of the certification data, which follows the authentication data.
+    certification_data_type: u16,
+    certification_data_offset: usize,
+    certification_data_size: usize,
+}
+
+impl From<&SignatureData<'_>> for DecodedSignatureData {
+    fn from(signature_data: &SignatureData) -> Self {
+        Self {
+            isv_enclave_signature: signature_data.isv_enclave_signature,
+            attestation_key: signature_data.attestation_key,
+            qe_report_body: signature_data.qe_report_body.clone(),
+            qe_report_signature: signature_data.qe_report_signature,
+            authentication_data_size: signature_data.authentication_data.data.len(),
+            certification_data_type: signature_data.certification_data.kind(),
+            certification_data_offset: AUTHENTICATION_DATA_OFFSET
+                + signature_data.authentication_data.size()
+                + MIN_CERT_DATA_SIZE,
+            certification_data_size: signature_data.certification_data.raw_data().len(),
+        }
+    }
+}
+
 /// The Quoting enclave authentication data
 ///
 /// Table 8 of
@@ -523,8 +647,9 @@ fn take(count: usize) -> impl Fn(&[u8]) -> (&[u8], &[u8]) {
 mod test {
     use super::*;
     use core::slice;
-    use mc_sgx_core_sys_types::sgx_report_body_t;
+    use mc_sgx_core_sys_types::{sgx_report_body_t, sgx_report_t};
     use mc_sgx_core_types::CpuSvn;
+    use mc_sgx_dcap_sys_types::sgx_ql_qe_report_info_t;
     use yare::parameterized;
     extern crate alloc;
     use alloc::vec::Vec;
@@ -704,6 +829,20 @@ mod test {
         assert_eq!(pems.len(), 3);
     }
 
+    #[test]
+    fn qe_id_from_real_quote_file() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).unwrap();
+
+        assert_eq!(
+            quote.qe_id(),
+            &[
+                0xd1, 0xae, 0x7b, 0x91, 0xa5, 0x82, 0x7a, 0x1d, 0x64, 0x18, 0xd4, 0x46, 0x00, 0xcf,
+                0x1f, 0x53
+            ]
+        );
+    }
+
     #[parameterized(
     version_2 = {2},
     version_4 = {4},
@@ -818,11 +957,86 @@ mod test {
         assert_eq!(quote.verify_nonce(&nonce, &report_data), false);
     }
 
+    /// Report info for `quote` and `nonce`, as returned by the quote
+    /// generation.
+    fn report_info_from_quote_and_nonce(quote: &Quote3<&[u8]>, nonce: &QuoteNonce) -> ReportInfo {
+        let mut qe_report = sgx_report_t {
+            body: quote.signature_data().qe_report_body().clone().into(),
+            ..Default::default()
+        };
+        qe_report.body.report_data = report_data_from_quote_and_nonce(quote, nonce).into();
+        sgx_ql_qe_report_info_t {
+            nonce: nonce.clone().into(),
+            app_enclave_target_info: Default::default(),
+            qe_report,
+        }
+        .into()
+    }
+
+    #[test]
+    fn report_info_for_quote_and_nonce_succeeds() {
+        let mut binding = [3u8; MIN_QUOTE_SIZE];
+        let bytes = quotify_bytes(binding.as_mut_slice());
+        let quote = bytes.as_ref().try_into().unwrap();
+        let nonce = [1u8; QuoteNonce::SIZE].into();
+        let report_info = report_info_from_quote_and_nonce(&quote, &nonce);
+
+        assert_eq!(quote.verify_report_info(&nonce, &report_info), Ok(()));
+    }
+
+    #[test]
+    fn report_info_for_other_nonce_fails() {
+        let mut binding = [3u8; MIN_QUOTE_SIZE];
+        let bytes = quotify_bytes(binding.as_mut_slice());
+        let quote = bytes.as_ref().try_into().unwrap();
+        let nonce = [1u8; QuoteNonce::SIZE].into();
+        let report_info = report_info_from_quote_and_nonce(&quote, &nonce);
+
+        let other_nonce = [2u8; QuoteNonce::SIZE].into();
+        assert_eq!(
+            quote.verify_report_info(&other_nonce, &report_info),
+            Err(Quote3Error::QeReportNonce)
+        );
+    }
+
+    #[test]
+    fn report_info_for_other_quote_fails() {
+        let mut binding = [3u8; MIN_QUOTE_SIZE];
+        let bytes = quotify_bytes(binding.as_mut_slice());
+        let quote = bytes.as_ref().try_into().unwrap();
+        let nonce = [1u8; QuoteNonce::SIZE].into();
+        let report_info = report_info_from_quote_and_nonce(&quote, &nonce);
+
+        let mut other_binding = [8u8; MIN_QUOTE_SIZE];
+        let other_bytes = quotify_bytes(other_binding.as_mut_slice());
+        let other_quote: Quote3<&[u8]> = other_bytes.as_ref().try_into().unwrap();
+        assert_eq!(
+            other_quote.verify_report_info(&nonce, &report_info),
+            Err(Quote3Error::QeReportIdentity)
+        );
+    }
+
+    #[test]
+    fn report_info_with_other_report_data_fails() {
+        let mut binding = [3u8; MIN_QUOTE_SIZE];
+        let bytes = quotify_bytes(binding.as_mut_slice());
+        let quote = bytes.as_ref().try_into().unwrap();
+        let nonce = [1u8; QuoteNonce::SIZE].into();
+        let mut report_info: sgx_ql_qe_report_info_t =
+            report_info_from_quote_and_nonce(&quote, &nonce).into();
+        report_info.qe_report.body.report_data.d[0] ^= 1;
+
+        assert_eq!(
+            quote.verify_report_info(&nonce, &report_info.into()),
+            Err(Quote3Error::QeReportNonce)
+        );
+    }
+
     #[test]
     fn zero_authentication_data() {
         let bytes = [0u8; MIN_AUTH_DATA_SIZE];
         let authentication_data = AuthenticationData::try_from(bytes.as_slice()).unwrap();
-        assert_eq!(authentication_data.data, []);
+        assert_eq!(authentication_data.data, [0u8; 0]);
     }
 
     #[test]
@@ -911,8 +1125,8 @@ mod test {
             signature_data.qe_report_signature,
             Signature::try_from([3u8; 64].as_slice()).unwrap()
         );
-        assert_eq!(signature_data.authentication_data.data, []);
-        assert_eq!(signature_data.certification_data().raw_data(), []);
+        assert_eq!(signature_data.authentication_data.data, [0u8; 0]);
+        assert_eq!(signature_data.certification_data().raw_data(), [0u8; 0]);
     }
 
     #[test]
@@ -947,8 +1161,8 @@ mod test {
             signature_data.qe_report_signature,
             Signature::try_from([4u8; 64].as_slice()).unwrap()
         );
-        assert_eq!(signature_data.authentication_data.data, []);
-        assert_eq!(signature_data.certification_data().raw_data(), []);
+        assert_eq!(signature_data.authentication_data.data, [0u8; 0]);
+        assert_eq!(signature_data.certification_data().raw_data(), [0u8; 0]);
     }
 
     #[test]
@@ -1090,6 +1304,26 @@ mod test {
         assert_eq!(signature_data.certification_data().raw_data(), [23u8; 4]);
     }
 
+    #[test]
+    fn signature_data_of_quote_matches_parsed_signature_data() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+        let parsed = SignatureData::try_from(&hw_quote[mem::size_of::<sgx_quote3_t>()..])
+            .expect("Failed to parse signature data");
+
+        assert_eq!(quote.signature_data(), parsed);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn signature_data_kept_converting_to_vec() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+        let owned = Quote3::<Vec<u8>>::from(quote.clone());
+
+        assert_eq!(owned.signature_data(), quote.signature_data());
+    }
+
     #[test]
     fn verify_quote_signature() {
         let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
@@ -2,8 +2,9 @@
 
 //! Types specific to the quoting enclave
 
-use mc_sgx_core_types::{impl_newtype, QuoteNonce, Report, TargetInfo};
-use mc_sgx_dcap_sys_types::sgx_ql_qe_report_info_t;
+use crate::QE_ID_SIZE;
+use mc_sgx_core_types::{impl_newtype, QuoteNonce, QuotingEnclaveReportInfo, Report, TargetInfo};
+use mc_sgx_dcap_sys_types::{sgx_pce_info_t, sgx_ql_qe3_id_t, sgx_ql_qe_report_info_t};
 
 /// Report info for the Quoting Enclave
 #[repr(transparent)]
@@ -31,10 +32,85 @@ impl ReportInfo {
     }
 }
 
+impl From<QuotingEnclaveReportInfo> for ReportInfo {
+    fn from(info: QuotingEnclaveReportInfo) -> Self {
+        Self(sgx_ql_qe_report_info_t {
+            nonce: info.nonce().into(),
+            app_enclave_target_info: info.app_enclave_target_info().into(),
+            qe_report: info.report().into(),
+        })
+    }
+}
+
+/// The ID of the quoting enclave (QE3).
+///
+/// The ID is unique to the platform and is used by a PCCS to identify the
+/// platform when caching its PCK certificates.
+#[repr(transparent)]
+#[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Qe3Id(sgx_ql_qe3_id_t);
+
+impl_newtype! {
+    Qe3Id, sgx_ql_qe3_id_t;
+}
+
+impl Qe3Id {
+    /// The bytes of the ID
+    pub fn id(&self) -> &[u8; QE_ID_SIZE] {
+        &self.0.id
+    }
+}
+
+impl AsRef<[u8]> for Qe3Id {
+    fn as_ref(&self) -> &[u8] {
+        &self.0.id
+    }
+}
+
+impl From<[u8; QE_ID_SIZE]> for Qe3Id {
+    fn from(id: [u8; QE_ID_SIZE]) -> Self {
+        Self(sgx_ql_qe3_id_t { id })
+    }
+}
+
+/// Identity information of the provisioning certification enclave (PCE)
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PceInfo(sgx_pce_info_t);
+
+impl_newtype! {
+    PceInfo, sgx_pce_info_t;
+}
+
+impl PceInfo {
+    /// The PCE identity of `pce_svn` and `pce_id`
+    pub fn new(pce_svn: u16, pce_id: u16) -> Self {
+        Self(sgx_pce_info_t {
+            pce_isv_svn: pce_svn,
+            pce_id,
+        })
+    }
+
+    /// The PCE security version number (PCESVN)
+    pub fn pce_svn(&self) -> u16 {
+        self.0.pce_isv_svn
+    }
+
+    /// The PCE ID (PCEID).
+    ///
+    /// This changes when a change to the PCE would change the platform
+    /// provisioning ID (PPID) of the platform.
+    pub fn pce_id(&self) -> u16 {
+        self.0.pce_id
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use mc_sgx_core_sys_types::{sgx_quote_nonce_t, sgx_report_t, sgx_target_info_t};
+    use mc_sgx_core_sys_types::{
+        sgx_qe_report_info_t, sgx_quote_nonce_t, sgx_report_t, sgx_target_info_t,
+    };
 
     #[test]
     fn default_report_info() {
@@ -64,4 +140,48 @@ mod test {
         assert_eq!(info.target_info(), target_info.into());
         assert_eq!(info.nonce(), nonce.into());
     }
+
+    #[test]
+    fn from_quoting_enclave_report_info() {
+        let mut report = sgx_report_t::default();
+        report.body.cpu_svn.svn[0] = 4;
+        let mut target_info = sgx_target_info_t::default();
+        target_info.mr_enclave.m[0] = 5;
+        let mut nonce = sgx_quote_nonce_t::default();
+        nonce.rand[0] = 6;
+        let info = QuotingEnclaveReportInfo::from(sgx_qe_report_info_t {
+            qe_report: report,
+            app_enclave_target_info: target_info,
+            nonce,
+        });
+
+        let info = ReportInfo::from(info);
+
+        assert_eq!(info.report(), report.into());
+        assert_eq!(info.target_info(), target_info.into());
+        assert_eq!(info.nonce(), nonce.into());
+    }
+
+    #[test]
+    fn qe3_id_from_bytes() {
+        let bytes = [5u8; QE_ID_SIZE];
+        let id = Qe3Id::from(bytes);
+
+        assert_eq!(id.id(), &bytes);
+        assert_eq!(id.as_ref(), bytes.as_slice());
+        assert_eq!(Qe3Id::from(sgx_ql_qe3_id_t { id: bytes }), id);
+    }
+
+    #[test]
+    fn pce_info_from_pce_info_t() {
+        let info: PceInfo = sgx_pce_info_t {
+            pce_isv_svn: 13,
+            pce_id: 2,
+        }
+        .into();
+
+        assert_eq!(info.pce_svn(), 13);
+        assert_eq!(info.pce_id(), 2);
+        assert_eq!(info, PceInfo::new(13, 2));
+    }
 }
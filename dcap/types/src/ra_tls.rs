@@ -0,0 +1,721 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! RA-TLS (Remote Attestation TLS) certificates.
+//!
+//! An RA-TLS certificate is a self-signed X.509 certificate which binds a TLS
+//! key to an enclave. The enclave places the SHA256 hash of the DER encoded
+//! `SubjectPublicKeyInfo` of the TLS key in the first 32 bytes of its
+//! [`ReportData`], generates a [`Quote3`] from that report, and carries the
+//! quote in a custom certificate extension. The [`Collateral`] needed to
+//! appraise the quote may optionally be carried in a second extension.
+//!
+//! A peer verifies the certificate by checking the self signature, that the
+//! quote's report data matches the certificate's public key, and appraising
+//! the quote with a [`QuoteAppraiser`].
+//!
+//! The quote extension uses the OID of the legacy Gramine RA-TLS quote
+//! extension, [`QUOTE_EXTENSION_OID`]. The collateral extension,
+//! [`COLLATERAL_EXTENSION_OID`], is not standardized and is only understood by
+//! this crate.
+
+use crate::x509::{
+    ecdsa_with_sha256, sign_certificate, subject_public_key_info, time, verify_certificate,
+    verifying_key, SignatureError,
+};
+use crate::{
+    Appraisal, AppraisalError, Collateral, Quote3, Quote3Error, QuoteAppraiser, TimeSource,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use const_oid::ObjectIdentifier;
+use core::str::FromStr;
+use core::time::Duration;
+use mc_sgx_core_types::ReportData;
+use p256::ecdsa::{SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use x509_cert::certificate::{TbsCertificate, Version};
+use x509_cert::der::asn1::OctetString;
+use x509_cert::der::{self, DateTime, Decode, Encode};
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::SubjectPublicKeyInfoOwned;
+use x509_cert::time::Validity;
+use x509_cert::Certificate;
+
+/// The OID of the certificate extension containing the [`Quote3`].
+///
+/// This is the OID used by the legacy Gramine RA-TLS implementation.
+pub const QUOTE_EXTENSION_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1337.6");
+
+/// The OID of the certificate extension containing the [`Collateral`].
+///
+/// The value of the extension is the DER encoding of the [`Collateral`].
+pub const COLLATERAL_EXTENSION_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1337.7");
+
+// The number of bytes of the public key hash to use for the serial number.
+const SERIAL_NUMBER_SIZE: usize = 16;
+
+const DEFAULT_SUBJECT: &str = "CN=RA-TLS";
+
+/// Error working with an [`RaTlsCertificate`]
+#[derive(Debug, displaydoc::Display, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Error encoding or decoding DER {0}
+    Der(der::Error),
+    /// The certificate is missing the quote extension
+    MissingQuote,
+    /// The certificate contains more than one {0} extension
+    DuplicateExtension(ObjectIdentifier),
+    /// Error with the quote {0}
+    Quote(Quote3Error),
+    /// The public key is not an ECDSA P-256 key
+    UnsupportedKey,
+    /// The certificate signature algorithm is not ECDSA with SHA-256
+    UnsupportedSignatureAlgorithm,
+    /// The certificate signature is invalid
+    Signature,
+    /// The quote's report data does not match the certificate's public key
+    KeyBinding,
+    /// Error appraising the quote {0}
+    Appraisal(AppraisalError),
+}
+
+impl From<der::Error> for Error {
+    fn from(err: der::Error) -> Self {
+        Self::Der(err)
+    }
+}
+
+impl From<SignatureError> for Error {
+    fn from(err: SignatureError) -> Self {
+        match err {
+            SignatureError::Der(err) => Self::Der(err),
+            SignatureError::UnsupportedKey => Self::UnsupportedKey,
+            SignatureError::UnsupportedAlgorithm => Self::UnsupportedSignatureAlgorithm,
+            SignatureError::Invalid => Self::Signature,
+        }
+    }
+}
+
+impl From<Quote3Error> for Error {
+    fn from(err: Quote3Error) -> Self {
+        Self::Quote(err)
+    }
+}
+
+impl From<AppraisalError> for Error {
+    fn from(err: AppraisalError) -> Self {
+        Self::Appraisal(err)
+    }
+}
+
+/// Get the [`ReportData`] which binds `key` to an [`RaTlsCertificate`].
+///
+/// The report data is the SHA256 hash of the DER encoded
+/// `SubjectPublicKeyInfo` of `key` followed by 32 bytes of zeros. The report
+/// used to generate the [`Quote3`] for an [`RaTlsCertificate`] must contain
+/// this report data.
+pub fn report_data_for_key(key: &VerifyingKey) -> Result<ReportData, Error> {
+    let spki = subject_public_key_info(key)?;
+    report_data_for_spki(&spki)
+}
+
+/// A self-signed X.509 certificate carrying an enclave [`Quote3`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RaTlsCertificate {
+    certificate: Certificate,
+}
+
+impl RaTlsCertificate {
+    /// Decode an [`RaTlsCertificate`] from DER.
+    ///
+    /// # Errors
+    /// * [`Error::Der`] if `der` is not a valid certificate.
+    /// * [`Error::MissingQuote`] if the certificate has no quote extension.
+    /// * [`Error::Quote`] if the quote extension is not a valid [`Quote3`].
+    pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+        Self::try_from(Certificate::from_der(der)?)
+    }
+
+    /// Encode the certificate as DER.
+    pub fn to_der(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.certificate.to_der()?)
+    }
+
+    /// The underlying X.509 certificate.
+    pub fn certificate(&self) -> &Certificate {
+        &self.certificate
+    }
+
+    /// The [`Quote3`] contained in the certificate.
+    ///
+    /// The quote has not been appraised, see [`RaTlsCertificate::verify()`].
+    pub fn quote(&self) -> Quote3<&[u8]> {
+        let bytes = quote_bytes(&self.certificate)
+            .expect("Quote extension was validated during certificate creation");
+        Quote3::try_from(bytes).expect("Quote was validated during certificate creation")
+    }
+
+    /// The [`Collateral`] contained in the certificate, if any.
+    ///
+    /// # Errors
+    /// * [`Error::DuplicateExtension`] if there is more than one collateral
+    ///   extension.
+    /// * [`Error::Der`] if the collateral extension can not be decoded.
+    pub fn collateral(&self) -> Result<Option<Collateral>, Error> {
+        extension_value(&self.certificate, &COLLATERAL_EXTENSION_OID)?
+            .map(Collateral::from_der)
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// The public key of the certificate.
+    ///
+    /// # Errors
+    /// * [`Error::UnsupportedKey`] if the key is not an ECDSA P-256 key.
+    pub fn public_key(&self) -> Result<VerifyingKey, Error> {
+        Ok(verifying_key(&self.certificate)?)
+    }
+
+    /// Verify the certificate and appraise the [`Quote3`] it contains.
+    ///
+    /// This verifies:
+    /// 1. The self signature of the certificate.
+    /// 2. The quote's report data matches the certificate's public key.
+    /// 3. The quote, with `appraiser` and `collateral`. This verifies the PCK
+    ///    certificate chain leads to the trust anchor of `appraiser` and the
+    ///    signatures of the quote, and determines the TCB status of the
+    ///    platform.
+    ///
+    /// This does *not* verify the identity of the enclave, the returned
+    /// [`Appraisal`] should be checked by the caller, for instance with an
+    /// [`IdentityPolicy`](crate::IdentityPolicy).
+    ///
+    /// # Arguments
+    /// * `appraiser` - The appraiser for the quote.
+    /// * `collateral` - The collateral for the platform of the quote, for
+    ///   instance from [`RaTlsCertificate::collateral()`].
+    /// * `time` - The time of the appraisal, as the duration since the UNIX
+    ///   epoch or from another [`TimeSource`].
+    ///
+    /// # Errors
+    /// * [`Error::UnsupportedSignatureAlgorithm`] if the certificate is not
+    ///   signed with ECDSA P-256 and SHA-256.
+    /// * [`Error::UnsupportedKey`] if the certificate key is not an ECDSA
+    ///   P-256 key.
+    /// * [`Error::Signature`] if the self signature is invalid.
+    /// * [`Error::KeyBinding`] if the quote's report data does not match the
+    ///   certificate's public key.
+    /// * [`Error::Appraisal`] if the quote fails the appraisal.
+    pub fn verify<S: TimeSource>(
+        &self,
+        appraiser: &QuoteAppraiser,
+        collateral: &Collateral,
+        time: S,
+    ) -> Result<Appraisal, Error> {
+        let key = self.verify_signature()?;
+
+        let quote = self.quote();
+        let expected = report_data_for_key(&key)?;
+        ensure_key_binding(&expected, &quote)?;
+
+        Ok(appraiser.appraise(&quote, collateral, time)?)
+    }
+
+    /// Verify the self signature, returning the certificate's public key.
+    fn verify_signature(&self) -> Result<VerifyingKey, Error> {
+        let key = verifying_key(&self.certificate)?;
+        verify_certificate(&self.certificate, &key)?;
+        Ok(key)
+    }
+}
+
+impl TryFrom<Certificate> for RaTlsCertificate {
+    type Error = Error;
+
+    fn try_from(certificate: Certificate) -> Result<Self, Self::Error> {
+        let bytes = quote_bytes(&certificate)?;
+        Quote3::try_from(bytes)?;
+        Ok(Self { certificate })
+    }
+}
+
+impl From<RaTlsCertificate> for Certificate {
+    fn from(certificate: RaTlsCertificate) -> Self {
+        certificate.certificate
+    }
+}
+
+/// Builder for an [`RaTlsCertificate`].
+///
+/// By default the certificate subject is `CN=RA-TLS` and the certificate has
+/// no well-defined expiration, see
+/// <https://www.rfc-editor.org/rfc/rfc5280#section-4.1.2.5>.
+#[derive(Debug, Clone)]
+pub struct RaTlsCertificateBuilder<'a> {
+    signing_key: &'a SigningKey,
+    quote: &'a [u8],
+    collateral: Option<&'a Collateral>,
+    subject: Option<Name>,
+    not_before: Duration,
+    not_after: Option<Duration>,
+}
+
+impl<'a> RaTlsCertificateBuilder<'a> {
+    /// Create a new builder.
+    ///
+    /// # Arguments
+    /// * `signing_key` - The key to sign the certificate with. The public key
+    ///   of the certificate will be the verifying key of `signing_key`.
+    /// * `quote` - The quote of the enclave. The report data of the quote must
+    ///   be the result of [`report_data_for_key()`] for the verifying key of
+    ///   `signing_key`.
+    pub fn new<T: AsRef<[u8]>>(signing_key: &'a SigningKey, quote: &'a Quote3<T>) -> Self {
+        Self {
+            signing_key,
+            quote: quote.as_ref(),
+            collateral: None,
+            subject: None,
+            not_before: Duration::ZERO,
+            not_after: None,
+        }
+    }
+
+    /// Include `collateral` in the certificate.
+    #[must_use]
+    pub fn collateral(mut self, collateral: &'a Collateral) -> Self {
+        self.collateral = Some(collateral);
+        self
+    }
+
+    /// The subject, and issuer, of the certificate.
+    #[must_use]
+    pub fn subject(mut self, subject: Name) -> Self {
+        self.subject = Some(subject);
+        self
+    }
+
+    /// The validity period of the certificate.
+    ///
+    /// The times are the duration since the UNIX epoch.
+    #[must_use]
+    pub fn validity(mut self, not_before: Duration, not_after: Duration) -> Self {
+        self.not_before = not_before;
+        self.not_after = Some(not_after);
+        self
+    }
+
+    /// Build and sign the certificate.
+    ///
+    /// # Errors
+    /// * [`Error::KeyBinding`] if the quote's report data does not match the
+    ///   signing key.
+    /// * [`Error::Der`] if the certificate can not be encoded.
+    pub fn build(self) -> Result<RaTlsCertificate, Error> {
+        let quote = Quote3::try_from(self.quote)?;
+        let spki = subject_public_key_info(self.signing_key.verifying_key())?;
+        let report_data = report_data_for_spki(&spki)?;
+        ensure_key_binding(&report_data, &quote)?;
+
+        let mut extensions = vec![Extension {
+            extn_id: QUOTE_EXTENSION_OID,
+            critical: false,
+            extn_value: OctetString::new(self.quote)?,
+        }];
+        if let Some(collateral) = self.collateral {
+            extensions.push(Extension {
+                extn_id: COLLATERAL_EXTENSION_OID,
+                critical: false,
+                extn_value: OctetString::new(collateral.to_der()?)?,
+            });
+        }
+
+        let subject = match self.subject {
+            Some(subject) => subject,
+            None => Name::from_str(DEFAULT_SUBJECT)?,
+        };
+        let not_after = match self.not_after {
+            Some(not_after) => time(DateTime::from_unix_duration(not_after)?)?,
+            // Per RFC 5280 this is the value to use when there is no
+            // well-defined expiration.
+            None => time(DateTime::new(9999, 12, 31, 23, 59, 59)?)?,
+        };
+        let validity = Validity {
+            not_before: time(DateTime::from_unix_duration(self.not_before)?)?,
+            not_after,
+        };
+
+        let key_hash: &[u8] = report_data.as_ref();
+        let serial_number = SerialNumber::new(&key_hash[..SERIAL_NUMBER_SIZE])?;
+        let tbs_certificate = TbsCertificate {
+            version: Version::V3,
+            serial_number,
+            signature: ecdsa_with_sha256(),
+            issuer: subject.clone(),
+            validity,
+            subject,
+            subject_public_key_info: spki,
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions: Some(extensions),
+        };
+
+        let certificate = sign_certificate(tbs_certificate, self.signing_key)?;
+        Ok(RaTlsCertificate { certificate })
+    }
+}
+
+fn report_data_for_spki(spki: &SubjectPublicKeyInfoOwned) -> Result<ReportData, Error> {
+    let hash = Sha256::digest(spki.to_der()?);
+    let mut report_data = [0u8; ReportData::SIZE];
+    report_data[..hash.len()].copy_from_slice(hash.as_slice());
+    Ok(report_data.into())
+}
+
+/// Ensure the report data of `quote` is the `expected` report data.
+fn ensure_key_binding<T: AsRef<[u8]>>(
+    expected: &ReportData,
+    quote: &Quote3<T>,
+) -> Result<(), Error> {
+    let actual = quote.app_report_body().report_data();
+    let expected: &[u8] = expected.as_ref();
+    match expected.ct_eq(actual.as_ref()).into() {
+        true => Ok(()),
+        false => Err(Error::KeyBinding),
+    }
+}
+
+/// Get the value of the extension with `oid`.
+///
+/// Returns `None` if there is no extension with `oid`.
+///
+/// # Errors
+/// * [`Error::DuplicateExtension`] if there is more than one extension with
+///   `oid`.
+fn extension_value<'a>(
+    certificate: &'a Certificate,
+    oid: &ObjectIdentifier,
+) -> Result<Option<&'a [u8]>, Error> {
+    let mut values = certificate
+        .tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .filter(|extension| &extension.extn_id == oid)
+        .map(|extension| extension.extn_value.as_bytes());
+    let value = values.next();
+    match values.next() {
+        Some(_) => Err(Error::DuplicateExtension(*oid)),
+        None => Ok(value),
+    }
+}
+
+fn quote_bytes(certificate: &Certificate) -> Result<&[u8], Error> {
+    extension_value(certificate, &QUOTE_EXTENSION_OID)?.ok_or(Error::MissingQuote)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TestCa;
+    use crate::x509::EC_PUBLIC_KEY_OID;
+    use crate::TcbStatus;
+    use assert_matches::assert_matches;
+    use x509_cert::time::Time;
+
+    // 2024-06-01
+    const NOW: Duration = Duration::from_secs(1_717_200_000);
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[0x42; 32].into()).expect("Failed to create signing key")
+    }
+
+    /// Verify `certificate` with the trust anchor and collateral of the
+    /// [`TestCa`]
+    fn verify(certificate: &RaTlsCertificate) -> Result<Appraisal, Error> {
+        let ca = TestCa::default();
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        certificate.verify(&appraiser, &ca.collateral(), NOW)
+    }
+
+    fn certificate(key: &SigningKey) -> RaTlsCertificate {
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        let quote = TestCa::default().quote_for_report_data(&report_data);
+        RaTlsCertificateBuilder::new(key, &quote)
+            .build()
+            .expect("Failed to build certificate")
+    }
+
+    #[test]
+    fn report_data_is_hash_of_spki() {
+        let key = signing_key();
+        let spki = subject_public_key_info(key.verifying_key()).expect("Failed to get SPKI");
+        let report_data = report_data_for_key(key.verifying_key()).expect("Failed to hash");
+
+        let hash = Sha256::digest(spki.to_der().expect("Failed to encode SPKI"));
+
+        let bytes: &[u8] = report_data.as_ref();
+        assert_eq!(&bytes[..32], hash.as_slice());
+        assert_eq!(&bytes[32..], &[0u8; 32]);
+    }
+
+    #[test]
+    fn build_and_verify_certificate() {
+        let key = signing_key();
+        let certificate = certificate(&key);
+
+        let der = certificate.to_der().expect("Failed to encode certificate");
+        let decoded = RaTlsCertificate::from_der(&der).expect("Failed to decode certificate");
+
+        assert_eq!(decoded, certificate);
+        assert_eq!(decoded.public_key(), Ok(*key.verifying_key()));
+        assert_eq!(decoded.collateral(), Ok(None));
+        let appraisal = verify(&decoded).expect("Failed to verify certificate");
+        assert_eq!(appraisal.tcb_status(), TcbStatus::UpToDate);
+    }
+
+    #[test]
+    fn default_validity_has_no_expiration() {
+        let certificate = certificate(&signing_key());
+        let validity = certificate.certificate().tbs_certificate.validity;
+
+        assert_eq!(validity.not_before.to_unix_duration(), Duration::ZERO);
+        assert_matches!(validity.not_after, Time::GeneralTime(time) if time.to_date_time().year() == 9999);
+    }
+
+    #[test]
+    fn custom_subject_and_validity() {
+        let key = signing_key();
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        let quote = TestCa::default().quote_for_report_data(&report_data);
+        let subject = Name::from_str("CN=enclave,O=MobileCoin").expect("Failed to parse name");
+        let not_before = Duration::from_secs(1_700_000_000);
+        let not_after = Duration::from_secs(2_600_000_000);
+
+        let certificate = RaTlsCertificateBuilder::new(&key, &quote)
+            .subject(subject.clone())
+            .validity(not_before, not_after)
+            .build()
+            .expect("Failed to build certificate");
+
+        let tbs = &certificate.certificate().tbs_certificate;
+        assert_eq!(tbs.subject, subject);
+        assert_eq!(tbs.issuer, subject);
+        assert_matches!(tbs.validity.not_before, Time::UtcTime(_));
+        assert_eq!(tbs.validity.not_before.to_unix_duration(), not_before);
+        // 2052, past the UTCTime range
+        assert_matches!(tbs.validity.not_after, Time::GeneralTime(_));
+        assert_eq!(tbs.validity.not_after.to_unix_duration(), not_after);
+        assert!(verify(&certificate).is_ok());
+    }
+
+    #[test]
+    fn build_fails_for_quote_of_different_key() {
+        let key = signing_key();
+        let other_key =
+            SigningKey::from_bytes(&[0x43; 32].into()).expect("Failed to create signing key");
+        let report_data =
+            report_data_for_key(other_key.verifying_key()).expect("Failed to get report data");
+        let quote = TestCa::default().quote_for_report_data(&report_data);
+
+        assert_eq!(
+            RaTlsCertificateBuilder::new(&key, &quote).build(),
+            Err(Error::KeyBinding)
+        );
+    }
+
+    #[test]
+    fn build_fails_for_hardware_quote() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+
+        assert_eq!(
+            RaTlsCertificateBuilder::new(&signing_key(), &quote).build(),
+            Err(Error::KeyBinding)
+        );
+    }
+
+    #[test]
+    fn verify_fails_when_key_does_not_match_quote() {
+        let key = signing_key();
+        let mut certificate = certificate(&key).certificate;
+
+        // Re-sign the certificate with a different key, keeping the original
+        // quote.
+        let other_key =
+            SigningKey::from_bytes(&[0x43; 32].into()).expect("Failed to create signing key");
+        certificate.tbs_certificate.subject_public_key_info =
+            subject_public_key_info(other_key.verifying_key()).expect("Failed to get SPKI");
+        let certificate = sign_certificate(certificate.tbs_certificate, &other_key)
+            .expect("Failed to sign certificate");
+        let certificate =
+            RaTlsCertificate::try_from(certificate).expect("Failed to convert certificate");
+
+        assert_eq!(verify(&certificate), Err(Error::KeyBinding));
+    }
+
+    #[test]
+    fn verify_fails_with_bad_self_signature() {
+        let key = signing_key();
+        let mut certificate = certificate(&key).certificate;
+        let other_key =
+            SigningKey::from_bytes(&[0x43; 32].into()).expect("Failed to create signing key");
+        let resigned = sign_certificate(certificate.tbs_certificate.clone(), &other_key)
+            .expect("Failed to sign certificate");
+        certificate.signature = resigned.signature;
+        let certificate =
+            RaTlsCertificate::try_from(certificate).expect("Failed to convert certificate");
+
+        assert_eq!(verify(&certificate), Err(Error::Signature));
+    }
+
+    #[test]
+    fn verify_fails_with_unsupported_signature_algorithm() {
+        let mut certificate = certificate(&signing_key()).certificate;
+        certificate.signature_algorithm.oid = EC_PUBLIC_KEY_OID;
+        let certificate =
+            RaTlsCertificate::try_from(certificate).expect("Failed to convert certificate");
+
+        assert_eq!(
+            verify(&certificate),
+            Err(Error::UnsupportedSignatureAlgorithm)
+        );
+    }
+
+    #[test]
+    fn verify_fails_with_bad_quote_signature() {
+        let key = signing_key();
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        let quote = TestCa::default().quote_for_report_data(&report_data);
+        let mut quote_bytes = quote.as_ref().to_vec();
+        // Corrupt the first byte of the ISV enclave signature, which follows
+        // the 48 byte header, the 384 byte report body, and the 4 byte
+        // signature data length.
+        quote_bytes[436] ^= 0xFF;
+        let quote = Quote3::try_from(quote_bytes).expect("Failed to parse quote");
+
+        let certificate = RaTlsCertificateBuilder::new(&key, &quote)
+            .build()
+            .expect("Failed to build certificate");
+
+        assert_eq!(
+            verify(&certificate),
+            Err(Error::Appraisal(AppraisalError::Quote(
+                Quote3Error::SignatureVerification
+            )))
+        );
+    }
+
+    #[test]
+    fn verify_fails_for_quote_from_untrusted_pck_chain() {
+        let key = signing_key();
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        // The same names as the `TestCa`, but a root key of the forger's own
+        let forged_ca = TestCa::builder()
+            .root(
+                SigningKey::from_bytes(&[0x44; 32].into()).expect("Failed to create signing key"),
+                Name::from_str("CN=Test SGX Root CA,O=MobileCoin").expect("Failed to parse name"),
+            )
+            .build();
+        let quote = forged_ca.quote_for_report_data(&report_data);
+
+        let certificate = RaTlsCertificateBuilder::new(&key, &quote)
+            .build()
+            .expect("Failed to build certificate");
+
+        assert_eq!(
+            verify(&certificate),
+            Err(Error::Appraisal(AppraisalError::UntrustedCertificateChain))
+        );
+    }
+
+    #[test]
+    fn missing_quote_extension() {
+        let mut certificate = certificate(&signing_key()).certificate;
+        certificate.tbs_certificate.extensions = None;
+
+        assert_eq!(
+            RaTlsCertificate::try_from(certificate),
+            Err(Error::MissingQuote)
+        );
+    }
+
+    #[test]
+    fn duplicate_quote_extension() {
+        let mut certificate = certificate(&signing_key()).certificate;
+        let extensions = certificate
+            .tbs_certificate
+            .extensions
+            .as_mut()
+            .expect("Expected extensions");
+        extensions.push(extensions[0].clone());
+
+        assert_eq!(
+            RaTlsCertificate::try_from(certificate),
+            Err(Error::DuplicateExtension(QUOTE_EXTENSION_OID))
+        );
+    }
+
+    #[test]
+    fn invalid_quote_extension() {
+        let mut certificate = certificate(&signing_key()).certificate;
+        let extensions = certificate
+            .tbs_certificate
+            .extensions
+            .as_mut()
+            .expect("Expected extensions");
+        extensions[0].extn_value = OctetString::new([3u8, 0]).expect("Failed to create octets");
+
+        assert_matches!(
+            RaTlsCertificate::try_from(certificate),
+            Err(Error::Quote(Quote3Error::InputLength { .. }))
+        );
+    }
+
+    #[test]
+    fn certificate_with_collateral() {
+        let key = signing_key();
+        let report_data =
+            report_data_for_key(key.verifying_key()).expect("Failed to get report data");
+        let ca = TestCa::default();
+        let quote = ca.quote_for_report_data(&report_data);
+        let collateral = ca.collateral();
+
+        let certificate = RaTlsCertificateBuilder::new(&key, &quote)
+            .collateral(&collateral)
+            .build()
+            .expect("Failed to build certificate");
+
+        assert_eq!(certificate.collateral(), Ok(Some(collateral.clone())));
+        let appraiser = QuoteAppraiser::new(ca.root_certificate().clone());
+        assert!(certificate.verify(&appraiser, &collateral, NOW).is_ok());
+    }
+
+    #[test]
+    fn invalid_collateral_extension() {
+        let mut certificate = certificate(&signing_key()).certificate;
+        let extensions = certificate
+            .tbs_certificate
+            .extensions
+            .as_mut()
+            .expect("Expected extensions");
+        extensions.push(Extension {
+            extn_id: COLLATERAL_EXTENSION_OID,
+            critical: false,
+            extn_value: OctetString::new([0x30, 0x00]).expect("Failed to create octets"),
+        });
+        let certificate =
+            RaTlsCertificate::try_from(certificate).expect("Failed to convert certificate");
+
+        assert_matches!(certificate.collateral(), Err(Error::Der(_)));
+    }
+}
@@ -7,7 +7,7 @@ use mc_sgx_dcap_sys_types::sgx_ql_request_policy_t;
 
 /// Policy used for loading enclaves
 #[non_exhaustive]
-#[derive(Default, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq, Debug)]
 pub enum RequestPolicy {
     /// Quoting Enclave is initialized on first use and reused until process
     /// ends
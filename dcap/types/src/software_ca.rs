@@ -0,0 +1,913 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! A software stand-in for the Intel PCK certificate hierarchy and quoting
+//! enclave.
+//!
+//! Quotes from hardware are signed by keys which chain up to the Intel SGX Root
+//! CA, so nothing else can create new ones. A [`SoftwareCa`] is a certificate
+//! hierarchy shaped like Intel's, and the attestation key of a quoting enclave,
+//! in order to synthesize quotes for arbitrary report bodies. It also issues
+//! the matching [`Collateral`] so the quotes can be appraised against its root
+//! certificate.
+//!
+//! This is *not* for production. The quotes attest to nothing, whoever holds
+//! the keys can create a quote for any report body. It's for running
+//! attestation flows without SGX hardware, like in CI, against a trust anchor
+//! which only those environments accept.
+
+use crate::x509::{ecdsa_with_sha256, sign, sign_certificate, subject_public_key_info, time};
+use crate::{Collateral, Quote3, TcbStatus};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use const_oid::{AssociatedOid, ObjectIdentifier};
+use core::str::FromStr;
+use mc_sgx_core_sys_types::{
+    sgx_attributes_t, sgx_measurement_t, sgx_report_body_t, sgx_target_info_t,
+};
+use mc_sgx_core_types::{ReportBody, ReportData, TargetInfo};
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use sha2::{Digest, Sha256};
+use x509_cert::attr::AttributeTypeAndValue;
+use x509_cert::certificate::{TbsCertificate, Version};
+use x509_cert::crl::{CertificateList, RevokedCert, TbsCertList};
+use x509_cert::der::asn1::{Any, OctetString};
+use x509_cert::der::pem::LineEnding;
+use x509_cert::der::{DateTime, Encode, EncodePem, Tag};
+use x509_cert::ext::pkix::BasicConstraints;
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::time::Validity;
+use x509_cert::Certificate;
+
+// Values from
+// <https://api.trustedservices.intel.com/documents/Intel_SGX_PCK_Certificate_CRL_Spec-1.5.pdf>
+const SGX_OID: &str = "1.2.840.113741.1.13.1";
+const PPID_OID: &str = "1.2.840.113741.1.13.1.1";
+const TCB_OID: &str = "1.2.840.113741.1.13.1.2";
+const PCE_SVN_OID: &str = "1.2.840.113741.1.13.1.2.17";
+const CPU_SVN_OID: &str = "1.2.840.113741.1.13.1.2.18";
+const PCE_ID_OID: &str = "1.2.840.113741.1.13.1.3";
+const FMSPC_OID: &str = "1.2.840.113741.1.13.1.4";
+const SGX_TYPE_OID: &str = "1.2.840.113741.1.13.1.5";
+
+// The Intel quoting enclave vendor ID
+const QE_VENDOR_ID: [u8; 16] = [
+    0x93, 0x9A, 0x72, 0x33, 0xF7, 0x9C, 0x4C, 0xA9, 0x94, 0x0A, 0x0D, 0xB3, 0x95, 0x7F, 0x06, 0x07,
+];
+const QUOTE_VERSION: u16 = 3;
+const ECDSA_P256_ATTESTATION_KEY_TYPE: u16 = 2;
+const PCK_CERTIFICATE_CHAIN_TYPE: u16 = 5;
+const AUTHENTICATION_DATA: [u8; 32] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31,
+];
+
+/// The FMSPC of the [`SoftwareCa`] PCK leaf certificate.
+pub const FMSPC: [u8; 6] = [0x00, 0x90, 0x6E, 0xA1, 0x00, 0x00];
+
+/// The TCB component SVNs of the [`SoftwareCa`] PCK leaf certificate.
+pub const TCB_COMPONENT_SVNS: [u32; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+
+/// The PCE SVN of the [`SoftwareCa`] PCK leaf certificate.
+pub const PCE_SVN: u16 = 13;
+
+/// The QE SVN of quotes from the [`SoftwareCa`].
+pub const QE_SVN: u16 = 8;
+
+/// The product ID of the [`SoftwareCa`] quoting enclave.
+pub const QE_PRODUCT_ID: u16 = 1;
+
+/// The MRSIGNER of the [`SoftwareCa`] quoting enclave.
+pub const QE_MR_SIGNER: [u8; 32] = [0x8C; 32];
+
+// Initialized and 64 bit mode, the attributes of the Intel quoting enclave
+const QE_ATTRIBUTE_FLAGS: u64 = 0x11;
+const QE_ATTRIBUTE_XFRM: u64 = 0xE7;
+
+/// The quoting enclave ID in the header of the [`SoftwareCa`] quotes.
+pub const QE_ID: [u8; 16] = [0x9E; 16];
+
+/// The TCB evaluation data number of the [`SoftwareCa`] collateral by default.
+pub const TCB_EVALUATION_DATA_NUMBER: u32 = 17;
+
+const OUT_OF_DATE_ADVISORY_ID: &str = "INTEL-SA-00001";
+
+/// A software Intel SGX PCK certificate hierarchy and quoting enclave.
+///
+/// The hierarchy is a root CA, an intermediate (processor) CA, a PCK leaf
+/// certificate, and a TCB signing certificate issued by the root CA.
+/// Certificates are valid from 2024-01-01 through 2049-12-31.
+///
+/// The keys and subject names come from a [`SoftwareCaBuilder`]. The
+/// [`Default`] CA uses fixed, publicly known, keys.
+#[derive(Debug, Clone)]
+pub struct SoftwareCa {
+    root_key: SigningKey,
+    intermediate_key: SigningKey,
+    pck_key: SigningKey,
+    attestation_key: SigningKey,
+    tcb_signing_key: SigningKey,
+    root: Certificate,
+    intermediate: Certificate,
+    pck: Certificate,
+    tcb_signing: Certificate,
+}
+
+impl Default for SoftwareCa {
+    fn default() -> Self {
+        SoftwareCaBuilder::default().build()
+    }
+}
+
+/// Builder for a [`SoftwareCa`] with its own keys and subject names.
+///
+/// Keys and names which aren't set are fixed, publicly known, values.
+#[derive(Debug, Clone)]
+pub struct SoftwareCaBuilder {
+    root_key: SigningKey,
+    intermediate_key: SigningKey,
+    pck_key: SigningKey,
+    attestation_key: SigningKey,
+    tcb_signing_key: SigningKey,
+    root_name: Name,
+    intermediate_name: Name,
+    pck_name: Name,
+    tcb_signing_name: Name,
+}
+
+impl Default for SoftwareCaBuilder {
+    fn default() -> Self {
+        Self {
+            root_key: signing_key(1),
+            intermediate_key: signing_key(2),
+            pck_key: signing_key(3),
+            attestation_key: signing_key(4),
+            tcb_signing_key: signing_key(5),
+            root_name: name("CN=Test SGX Root CA,O=MobileCoin"),
+            // The PCK CA uses the Intel common name, as the CA type of a PCK
+            // certificate is determined by its issuer's common name.
+            intermediate_name: name("CN=Intel SGX PCK Processor CA,O=MobileCoin"),
+            pck_name: name("CN=Test SGX PCK Certificate,O=MobileCoin"),
+            tcb_signing_name: name("CN=Test SGX TCB Signing,O=MobileCoin"),
+        }
+    }
+}
+
+impl SoftwareCaBuilder {
+    /// The key and subject name of the self-signed root CA.
+    #[must_use]
+    pub fn root(mut self, key: SigningKey, name: Name) -> Self {
+        self.root_key = key;
+        self.root_name = name;
+        self
+    }
+
+    /// The key and subject name of the intermediate CA.
+    ///
+    /// The common name must be `Intel SGX PCK Processor CA` or
+    /// `Intel SGX PCK Platform CA`, the CA type of the PCK certificate, and
+    /// the collateral it needs, is determined by it.
+    #[must_use]
+    pub fn intermediate(mut self, key: SigningKey, name: Name) -> Self {
+        self.intermediate_key = key;
+        self.intermediate_name = name;
+        self
+    }
+
+    /// The key and subject name of the PCK leaf certificate, which signs the
+    /// QE report of the quotes.
+    #[must_use]
+    pub fn pck(mut self, key: SigningKey, name: Name) -> Self {
+        self.pck_key = key;
+        self.pck_name = name;
+        self
+    }
+
+    /// The attestation key of the quoting enclave, which signs the quotes.
+    #[must_use]
+    pub fn attestation_key(mut self, key: SigningKey) -> Self {
+        self.attestation_key = key;
+        self
+    }
+
+    /// The key and subject name of the TCB signing certificate, which signs
+    /// the TCB info and QE identity of the collateral.
+    #[must_use]
+    pub fn tcb_signing(mut self, key: SigningKey, name: Name) -> Self {
+        self.tcb_signing_key = key;
+        self.tcb_signing_name = name;
+        self
+    }
+
+    /// Issue the certificates of the CA.
+    pub fn build(self) -> SoftwareCa {
+        let Self {
+            root_key,
+            intermediate_key,
+            pck_key,
+            attestation_key,
+            tcb_signing_key,
+            root_name,
+            intermediate_name,
+            pck_name,
+            tcb_signing_name,
+        } = self;
+
+        let root = certificate(
+            1,
+            &root_name,
+            &root_name,
+            &root_key,
+            &root_key,
+            vec![basic_constraints(true)],
+        );
+        let intermediate = certificate(
+            2,
+            &root_name,
+            &intermediate_name,
+            &intermediate_key,
+            &root_key,
+            vec![basic_constraints(true)],
+        );
+        let pck = certificate(
+            3,
+            &intermediate_name,
+            &pck_name,
+            &pck_key,
+            &intermediate_key,
+            vec![basic_constraints(false), sgx_extension()],
+        );
+        let tcb_signing = certificate(
+            4,
+            &root_name,
+            &tcb_signing_name,
+            &tcb_signing_key,
+            &root_key,
+            vec![basic_constraints(false)],
+        );
+
+        SoftwareCa {
+            root_key,
+            intermediate_key,
+            pck_key,
+            attestation_key,
+            tcb_signing_key,
+            root,
+            intermediate,
+            pck,
+            tcb_signing,
+        }
+    }
+}
+
+impl SoftwareCa {
+    /// A builder for a CA with its own keys and subject names.
+    pub fn builder() -> SoftwareCaBuilder {
+        SoftwareCaBuilder::default()
+    }
+
+    /// The self-signed root CA certificate.
+    pub fn root_certificate(&self) -> &Certificate {
+        &self.root
+    }
+
+    /// The intermediate CA certificate, signed by the root CA.
+    pub fn intermediate_certificate(&self) -> &Certificate {
+        &self.intermediate
+    }
+
+    /// The PCK leaf certificate, signed by the intermediate CA.
+    pub fn pck_certificate(&self) -> &Certificate {
+        &self.pck
+    }
+
+    /// The TCB signing certificate, signed by the root CA.
+    ///
+    /// This certificate signs the TCB info and QE identity of the collateral.
+    pub fn tcb_signing_certificate(&self) -> &Certificate {
+        &self.tcb_signing
+    }
+
+    /// The root CA signing key.
+    pub fn root_key(&self) -> &SigningKey {
+        &self.root_key
+    }
+
+    /// The intermediate CA signing key.
+    pub fn intermediate_key(&self) -> &SigningKey {
+        &self.intermediate_key
+    }
+
+    /// The PEM certificate chain, leaf first, as found in the certification
+    /// data of a quote.
+    pub fn pck_certificate_chain_pem(&self) -> String {
+        [&self.pck, &self.intermediate, &self.root]
+            .iter()
+            .map(|cert| {
+                cert.to_pem(LineEnding::LF)
+                    .expect("Failed to encode certificate as PEM")
+            })
+            .collect()
+    }
+
+    /// The collateral for quotes from this CA.
+    ///
+    /// The platform and the quoting enclave are at the
+    /// [`TcbStatus::UpToDate`] TCB level. See [`SoftwareCa::collateral_builder()`]
+    /// for other collateral.
+    pub fn collateral(&self) -> Collateral {
+        self.collateral_builder().build()
+    }
+
+    /// A builder for the collateral of quotes from this CA.
+    pub fn collateral_builder(&self) -> CollateralBuilder<'_> {
+        CollateralBuilder::new(self)
+    }
+
+    /// The target info of the quoting enclave of this CA.
+    ///
+    /// It's derived from the QE report in the quotes of this CA.
+    pub fn quoting_enclave_target_info(&self) -> TargetInfo {
+        let body: sgx_report_body_t = qe_report_body().into();
+        sgx_target_info_t {
+            mr_enclave: body.mr_enclave,
+            attributes: body.attributes,
+            config_svn: body.config_svn,
+            misc_select: body.misc_select,
+            config_id: body.config_id,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    /// Synthesize a quote for `report_data`.
+    ///
+    /// The remaining fields of the application report body are zero.
+    pub fn quote_for_report_data(&self, report_data: &ReportData) -> Quote3<Vec<u8>> {
+        let mut report_body = report_body_to_bytes(&ReportBody::default());
+        report_body[REPORT_DATA_OFFSET..].copy_from_slice(report_data.as_ref());
+        self.quote_for_report_body_bytes(report_body)
+    }
+
+    /// Synthesize a quote for `report_body`.
+    pub fn quote(&self, report_body: &ReportBody) -> Quote3<Vec<u8>> {
+        self.quote_for_report_body_bytes(report_body_to_bytes(report_body))
+    }
+
+    /// Synthesize a quote for `report_body` with certification data of
+    /// `certification_data_type` and `certification_data`.
+    ///
+    /// The QE report is still signed by the PCK key of this CA, so the quote
+    /// can be appraised with [`SoftwareCa::pck_certificate_chain_pem()`] as the
+    /// PCK certificate chain.
+    pub fn quote_with_certification_data(
+        &self,
+        report_body: &ReportBody,
+        certification_data_type: u16,
+        certification_data: &[u8],
+    ) -> Quote3<Vec<u8>> {
+        self.synthesize_quote(
+            report_body_to_bytes(report_body),
+            certification_data_type,
+            certification_data,
+        )
+    }
+
+    fn quote_for_report_body_bytes(&self, report_body: [u8; REPORT_BODY_SIZE]) -> Quote3<Vec<u8>> {
+        let chain = self.pck_certificate_chain_pem();
+        self.synthesize_quote(report_body, PCK_CERTIFICATE_CHAIN_TYPE, chain.as_bytes())
+    }
+
+    fn synthesize_quote(
+        &self,
+        report_body: [u8; REPORT_BODY_SIZE],
+        certification_data_type: u16,
+        certification_data: &[u8],
+    ) -> Quote3<Vec<u8>> {
+        let mut quote = Vec::new();
+        quote.extend_from_slice(&QUOTE_VERSION.to_le_bytes());
+        quote.extend_from_slice(&ECDSA_P256_ATTESTATION_KEY_TYPE.to_le_bytes());
+        // TEE type, 0 for SGX
+        quote.extend_from_slice(&0u32.to_le_bytes());
+        quote.extend_from_slice(&QE_SVN.to_le_bytes());
+        quote.extend_from_slice(&PCE_SVN.to_le_bytes());
+        quote.extend_from_slice(&QE_VENDOR_ID);
+        quote.extend_from_slice(&QE_ID);
+        quote.extend_from_slice(&[0u8; 4]);
+        quote.extend_from_slice(&report_body);
+
+        let isv_signature: Signature = self.attestation_key.sign(&quote);
+        let attestation_key = self.attestation_key.verifying_key().to_encoded_point(false);
+        // Skip the leading SEC1 tag byte, the quote only has the coordinates
+        let attestation_key = &attestation_key.as_bytes()[1..];
+
+        let mut hasher = Sha256::new();
+        hasher.update(attestation_key);
+        hasher.update(AUTHENTICATION_DATA);
+        let mut qe_report_body = report_body_to_bytes(&qe_report_body());
+        qe_report_body[REPORT_DATA_OFFSET..REPORT_DATA_OFFSET + 32]
+            .copy_from_slice(hasher.finalize().as_slice());
+        let qe_report_signature: Signature = self.pck_key.sign(&qe_report_body);
+
+        let mut signature_data = Vec::new();
+        signature_data.extend_from_slice(&isv_signature.to_bytes());
+        signature_data.extend_from_slice(attestation_key);
+        signature_data.extend_from_slice(&qe_report_body);
+        signature_data.extend_from_slice(&qe_report_signature.to_bytes());
+        signature_data.extend_from_slice(&(AUTHENTICATION_DATA.len() as u16).to_le_bytes());
+        signature_data.extend_from_slice(&AUTHENTICATION_DATA);
+        signature_data.extend_from_slice(&certification_data_type.to_le_bytes());
+        signature_data.extend_from_slice(&(certification_data.len() as u32).to_le_bytes());
+        signature_data.extend_from_slice(certification_data);
+
+        quote.extend_from_slice(&(signature_data.len() as u32).to_le_bytes());
+        quote.extend_from_slice(&signature_data);
+
+        Quote3::try_from(quote).expect("Failed to synthesize quote")
+    }
+}
+
+/// Builder for the [`Collateral`] of a [`SoftwareCa`].
+///
+/// The TCB info has two TCB levels. The first is the TCB of the PCK
+/// certificate, [`TCB_COMPONENT_SVNS`] and [`PCE_SVN`], with the status from
+/// [`CollateralBuilder::tcb_status()`]. The second has all SVNs of 1 and is
+/// [`TcbStatus::OutOfDate`]. The QE identity is similar with the first level
+/// being [`QE_SVN`].
+#[derive(Debug, Clone)]
+pub struct CollateralBuilder<'a> {
+    ca: &'a SoftwareCa,
+    tcb_status: TcbStatus,
+    qe_tcb_status: TcbStatus,
+    advisory_ids: Vec<String>,
+    tcb_evaluation_data_number: u32,
+    issue_date: DateTime,
+    next_update: DateTime,
+    revoke_pck: bool,
+}
+
+impl<'a> CollateralBuilder<'a> {
+    fn new(ca: &'a SoftwareCa) -> Self {
+        Self {
+            ca,
+            tcb_status: TcbStatus::UpToDate,
+            qe_tcb_status: TcbStatus::UpToDate,
+            advisory_ids: Vec::new(),
+            tcb_evaluation_data_number: TCB_EVALUATION_DATA_NUMBER,
+            issue_date: DateTime::new(2024, 1, 1, 0, 0, 0).expect("Invalid date"),
+            next_update: DateTime::new(2049, 12, 31, 23, 59, 59).expect("Invalid date"),
+            revoke_pck: false,
+        }
+    }
+
+    /// The status of the platform's TCB level.
+    #[must_use]
+    pub fn tcb_status(mut self, status: TcbStatus) -> Self {
+        self.tcb_status = status;
+        self
+    }
+
+    /// The status of the quoting enclave's TCB level.
+    #[must_use]
+    pub fn qe_tcb_status(mut self, status: TcbStatus) -> Self {
+        self.qe_tcb_status = status;
+        self
+    }
+
+    /// The advisory IDs of the platform's TCB level.
+    #[must_use]
+    pub fn advisory_ids(mut self, advisory_ids: &[&str]) -> Self {
+        self.advisory_ids = advisory_ids.iter().map(|id| id.to_string()).collect();
+        self
+    }
+
+    /// The TCB evaluation data number of the TCB info and QE identity.
+    #[must_use]
+    pub fn tcb_evaluation_data_number(mut self, number: u32) -> Self {
+        self.tcb_evaluation_data_number = number;
+        self
+    }
+
+    /// The issue date and next update of the CRLs, TCB info, and QE identity.
+    #[must_use]
+    pub fn validity(mut self, issue_date: DateTime, next_update: DateTime) -> Self {
+        self.issue_date = issue_date;
+        self.next_update = next_update;
+        self
+    }
+
+    /// Revoke the PCK leaf certificate in the PCK CRL.
+    #[must_use]
+    pub fn revoke_pck(mut self) -> Self {
+        self.revoke_pck = true;
+        self
+    }
+
+    /// Build and sign the collateral.
+    pub fn build(self) -> Collateral {
+        let ca = self.ca;
+        let root_ca_crl = self.crl(&ca.root, &ca.root_key, None);
+        let revoked = self.revoke_pck.then_some(&ca.pck);
+        let pck_crl = self.crl(&ca.intermediate, &ca.intermediate_key, revoked);
+        let tcb_issuer_chain = vec![ca.tcb_signing.clone(), ca.root.clone()];
+        Collateral::new(
+            root_ca_crl,
+            vec![ca.intermediate.clone(), ca.root.clone()],
+            pck_crl,
+            tcb_issuer_chain.clone(),
+            self.tcb_info(),
+            tcb_issuer_chain,
+            self.qe_identity(),
+        )
+    }
+
+    fn crl(
+        &self,
+        issuer: &Certificate,
+        issuer_key: &SigningKey,
+        revoked: Option<&Certificate>,
+    ) -> CertificateList {
+        let revoked_certificates = revoked.map(|certificate| {
+            vec![RevokedCert {
+                serial_number: certificate.tbs_certificate.serial_number.clone(),
+                revocation_date: time(self.issue_date).expect("Invalid time"),
+                crl_entry_extensions: None,
+            }]
+        });
+        let tbs_cert_list = TbsCertList {
+            version: Version::V2,
+            signature: ecdsa_with_sha256(),
+            issuer: issuer.tbs_certificate.subject.clone(),
+            this_update: time(self.issue_date).expect("Invalid time"),
+            next_update: Some(time(self.next_update).expect("Invalid time")),
+            revoked_certificates,
+            crl_extensions: None,
+        };
+        let signature = sign(
+            &tbs_cert_list.to_der().expect("Failed to encode CRL"),
+            issuer_key,
+        )
+        .expect("Failed to sign CRL");
+        CertificateList {
+            tbs_cert_list,
+            signature_algorithm: ecdsa_with_sha256(),
+            signature,
+        }
+    }
+
+    fn tcb_info(&self) -> String {
+        let level = |svns: &[u32], pce_svn: u16, status: TcbStatus, advisory_ids: &[String]| {
+            let components = svns
+                .iter()
+                .map(|svn| format!("{{\"svn\":{svn}}}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!(
+                "{{\"tcb\":{{\"sgxtcbcomponents\":[{components}],\"pcesvn\":{pce_svn}}},\"tcbDate\":\"{}\",\"tcbStatus\":{},\"advisoryIDs\":{}}}",
+                self.issue_date,
+                json(&status),
+                json(&advisory_ids),
+            )
+        };
+        let levels = [
+            level(
+                &TCB_COMPONENT_SVNS,
+                PCE_SVN,
+                self.tcb_status,
+                &self.advisory_ids,
+            ),
+            level(
+                &[1; 16],
+                1,
+                TcbStatus::OutOfDate,
+                &[OUT_OF_DATE_ADVISORY_ID.to_string()],
+            ),
+        ];
+        let body = format!(
+            "{{\"id\":\"SGX\",\"version\":3,\"issueDate\":\"{}\",\"nextUpdate\":\"{}\",\"fmspc\":\"{}\",\"pceId\":\"0000\",\"tcbType\":0,\"tcbEvaluationDataNumber\":{},\"tcbLevels\":[{}]}}",
+            self.issue_date,
+            self.next_update,
+            hex::encode(FMSPC),
+            self.tcb_evaluation_data_number,
+            levels.join(","),
+        );
+        self.sign_json("tcbInfo", &body)
+    }
+
+    fn qe_identity(&self) -> String {
+        let level = |isv_svn: u16, status: TcbStatus, advisory_ids: &[&str]| {
+            format!(
+                "{{\"tcb\":{{\"isvsvn\":{isv_svn}}},\"tcbDate\":\"{}\",\"tcbStatus\":{},\"advisoryIDs\":{}}}",
+                self.issue_date,
+                json(&status),
+                json(&advisory_ids),
+            )
+        };
+        let levels = [
+            level(QE_SVN, self.qe_tcb_status, &[]),
+            level(1, TcbStatus::OutOfDate, &[OUT_OF_DATE_ADVISORY_ID]),
+        ];
+        let mut attributes = [0u8; 16];
+        attributes[..8].copy_from_slice(&QE_ATTRIBUTE_FLAGS.to_le_bytes());
+        let body = format!(
+            "{{\"id\":\"QE\",\"version\":2,\"issueDate\":\"{}\",\"nextUpdate\":\"{}\",\"tcbEvaluationDataNumber\":{},\"miscselect\":\"00000000\",\"miscselectMask\":\"FFFFFFFF\",\"attributes\":\"{}\",\"attributesMask\":\"FBFFFFFFFFFFFFFF0000000000000000\",\"mrsigner\":\"{}\",\"isvprodid\":{QE_PRODUCT_ID},\"tcbLevels\":[{}]}}",
+            self.issue_date,
+            self.next_update,
+            self.tcb_evaluation_data_number,
+            hex::encode_upper(attributes),
+            hex::encode_upper(QE_MR_SIGNER),
+            levels.join(","),
+        );
+        self.sign_json("enclaveIdentity", &body)
+    }
+
+    fn sign_json(&self, name: &str, body: &str) -> String {
+        let signature: Signature = self.ca.tcb_signing_key.sign(body.as_bytes());
+        format!(
+            "{{\"{name}\":{body},\"signature\":\"{}\"}}",
+            hex::encode(signature.to_bytes())
+        )
+    }
+}
+
+fn json<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).expect("Failed to encode JSON")
+}
+
+/// The report body of the [`SoftwareCa`] quoting enclave.
+fn qe_report_body() -> ReportBody {
+    let mut body: sgx_report_body_t = ReportBody::default().into();
+    body.attributes = sgx_attributes_t {
+        flags: QE_ATTRIBUTE_FLAGS,
+        xfrm: QE_ATTRIBUTE_XFRM,
+    };
+    body.mr_signer = sgx_measurement_t { m: QE_MR_SIGNER };
+    body.isv_prod_id = QE_PRODUCT_ID;
+    body.isv_svn = QE_SVN;
+    body.into()
+}
+
+const REPORT_BODY_SIZE: usize = 384;
+const REPORT_DATA_OFFSET: usize = REPORT_BODY_SIZE - ReportData::SIZE;
+
+/// Convert `report_body` to the bytes of an `sgx_report_body_t`.
+pub fn report_body_to_bytes(report_body: &ReportBody) -> [u8; REPORT_BODY_SIZE] {
+    let body: sgx_report_body_t = report_body.clone().into();
+    let mut bytes = Vec::with_capacity(REPORT_BODY_SIZE);
+    bytes.extend_from_slice(&body.cpu_svn.svn);
+    bytes.extend_from_slice(&body.misc_select.to_le_bytes());
+    bytes.extend_from_slice(&body.reserved1);
+    bytes.extend_from_slice(&body.isv_ext_prod_id);
+    bytes.extend_from_slice(&body.attributes.flags.to_le_bytes());
+    bytes.extend_from_slice(&body.attributes.xfrm.to_le_bytes());
+    bytes.extend_from_slice(&body.mr_enclave.m);
+    bytes.extend_from_slice(&body.reserved2);
+    bytes.extend_from_slice(&body.mr_signer.m);
+    bytes.extend_from_slice(&body.reserved3);
+    bytes.extend_from_slice(&body.config_id);
+    bytes.extend_from_slice(&body.isv_prod_id.to_le_bytes());
+    bytes.extend_from_slice(&body.isv_svn.to_le_bytes());
+    bytes.extend_from_slice(&body.config_svn.to_le_bytes());
+    bytes.extend_from_slice(&body.reserved4);
+    bytes.extend_from_slice(&body.isv_family_id);
+    bytes.extend_from_slice(&body.report_data.d);
+    bytes
+        .try_into()
+        .expect("Report body should be exactly `REPORT_BODY_SIZE` bytes")
+}
+
+fn signing_key(byte: u8) -> SigningKey {
+    SigningKey::from_bytes(&[byte; 32].into()).expect("Failed to create signing key")
+}
+
+fn name(name: &str) -> Name {
+    Name::from_str(name).expect("Failed to parse name")
+}
+
+fn certificate(
+    serial: u8,
+    issuer: &Name,
+    subject: &Name,
+    subject_key: &SigningKey,
+    issuer_key: &SigningKey,
+    extensions: Vec<Extension>,
+) -> Certificate {
+    let not_before = DateTime::new(2024, 1, 1, 0, 0, 0).expect("Invalid date");
+    let not_after = DateTime::new(2049, 12, 31, 23, 59, 59).expect("Invalid date");
+    let tbs_certificate = TbsCertificate {
+        version: Version::V3,
+        serial_number: SerialNumber::new(&[serial]).expect("Invalid serial number"),
+        signature: ecdsa_with_sha256(),
+        issuer: issuer.clone(),
+        validity: Validity {
+            not_before: time(not_before).expect("Invalid time"),
+            not_after: time(not_after).expect("Invalid time"),
+        },
+        subject: subject.clone(),
+        subject_public_key_info: subject_public_key_info(subject_key.verifying_key())
+            .expect("Failed to encode public key"),
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(extensions),
+    };
+    sign_certificate(tbs_certificate, issuer_key).expect("Failed to sign certificate")
+}
+
+fn basic_constraints(ca: bool) -> Extension {
+    let constraints = BasicConstraints {
+        ca,
+        path_len_constraint: None,
+    };
+    Extension {
+        extn_id: BasicConstraints::OID,
+        critical: true,
+        extn_value: OctetString::new(
+            constraints
+                .to_der()
+                .expect("Failed to encode basic constraints"),
+        )
+        .expect("Failed to create extension"),
+    }
+}
+
+fn attribute(oid: &str, value: Any) -> AttributeTypeAndValue {
+    AttributeTypeAndValue {
+        oid: ObjectIdentifier::new_unwrap(oid),
+        value,
+    }
+}
+
+fn octets(bytes: &[u8]) -> Any {
+    Any::new(Tag::OctetString, bytes).expect("Failed to encode octets")
+}
+
+/// The SGX extension of the PCK leaf certificate, see table 3 of
+/// <https://api.trustedservices.intel.com/documents/Intel_SGX_PCK_Certificate_CRL_Spec-1.5.pdf>
+fn sgx_extension() -> Extension {
+    let mut tcb = TCB_COMPONENT_SVNS
+        .iter()
+        .enumerate()
+        .map(|(i, svn)| {
+            let oid = alloc::format!("{TCB_OID}.{}", i + 1);
+            attribute(
+                &oid,
+                Any::encode_from(svn).expect("Failed to encode component SVN"),
+            )
+        })
+        .collect::<Vec<_>>();
+    tcb.push(attribute(
+        PCE_SVN_OID,
+        Any::encode_from(&PCE_SVN).expect("Failed to encode PCE SVN"),
+    ));
+    let cpu_svn = TCB_COMPONENT_SVNS.map(|svn| svn as u8);
+    tcb.push(attribute(CPU_SVN_OID, octets(&cpu_svn)));
+
+    let extensions = vec![
+        attribute(PPID_OID, octets(&[0x11; 16])),
+        attribute(
+            TCB_OID,
+            Any::encode_from(&tcb).expect("Failed to encode TCB"),
+        ),
+        attribute(PCE_ID_OID, octets(&[0, 0])),
+        attribute(FMSPC_OID, octets(&FMSPC)),
+        // Enumerated 0 is "Standard"
+        attribute(
+            SGX_TYPE_OID,
+            Any::new(Tag::Enumerated, [0u8]).expect("Failed to encode SGX type"),
+        ),
+    ];
+
+    Extension {
+        extn_id: ObjectIdentifier::new_unwrap(SGX_OID),
+        critical: false,
+        extn_value: OctetString::new(extensions.to_der().expect("Failed to encode extension"))
+            .expect("Failed to create extension"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CertificationData;
+    use x509_cert::der::DecodePem;
+
+    #[test]
+    fn configured_ca_issues_appraisable_quotes() {
+        let ca = SoftwareCa::builder()
+            .root(signing_key(11), name("CN=Staging SGX Root CA,O=Example"))
+            .intermediate(
+                signing_key(12),
+                name("CN=Intel SGX PCK Processor CA,O=Example"),
+            )
+            .pck(
+                signing_key(13),
+                name("CN=Staging SGX PCK Certificate,O=Example"),
+            )
+            .attestation_key(signing_key(14))
+            .tcb_signing(
+                signing_key(15),
+                name("CN=Staging SGX TCB Signing,O=Example"),
+            )
+            .build();
+        let quote = ca.quote(&ReportBody::default());
+        // 2024-06-01T00:00:00Z
+        let now = core::time::Duration::from_secs(1_717_200_000);
+
+        assert_eq!(
+            ca.root_certificate().tbs_certificate.subject,
+            name("CN=Staging SGX Root CA,O=Example")
+        );
+        assert_ne!(
+            ca.root_certificate(),
+            SoftwareCa::default().root_certificate()
+        );
+        let appraiser = crate::QuoteAppraiser::new(ca.root_certificate().clone());
+        assert!(appraiser.appraise(&quote, &ca.collateral(), now).is_ok());
+        let default_appraiser =
+            crate::QuoteAppraiser::new(SoftwareCa::default().root_certificate().clone());
+        assert!(default_appraiser
+            .appraise(&quote, &ca.collateral(), now)
+            .is_err());
+    }
+
+    #[test]
+    fn quote_verifies_with_pck_leaf_key() {
+        let ca = SoftwareCa::default();
+        let report_data = ReportData::from([5u8; ReportData::SIZE]);
+        let quote = ca.quote_for_report_data(&report_data);
+
+        let key = crate::x509::verifying_key(ca.pck_certificate()).expect("Failed to get PCK key");
+
+        assert_eq!(quote.app_report_body().report_data(), report_data);
+        assert!(quote.verify(&key).is_ok());
+    }
+
+    #[test]
+    fn quote_contains_pck_certificate_chain() {
+        let ca = SoftwareCa::default();
+        let quote = ca.quote_for_report_data(&ReportData::default());
+        let signature_data = quote.signature_data();
+        let CertificationData::PckCertificateChain(chain) = signature_data.certification_data()
+        else {
+            panic!("Expected a PCK certificate chain");
+        };
+
+        let chain = chain
+            .into_iter()
+            .map(|pem| Certificate::from_pem(pem).expect("Failed to decode certificate"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chain,
+            [
+                ca.pck_certificate().clone(),
+                ca.intermediate_certificate().clone(),
+                ca.root_certificate().clone()
+            ]
+        );
+    }
+
+    #[test]
+    fn quoting_enclave_target_info_matches_qe_report() {
+        let ca = SoftwareCa::default();
+        let quote = ca.quote_for_report_data(&ReportData::default());
+        let qe_report_body = quote.signature_data().qe_report_body().clone();
+
+        let target_info = ca.quoting_enclave_target_info();
+
+        assert_eq!(target_info.mr_enclave(), qe_report_body.mr_enclave());
+        assert_eq!(target_info.attributes(), qe_report_body.attributes());
+        assert_eq!(
+            target_info.miscellaneous_select(),
+            qe_report_body.miscellaneous_select()
+        );
+    }
+
+    #[cfg(feature = "tcb")]
+    #[test]
+    fn tcb_info_from_quote() {
+        let ca = SoftwareCa::default();
+        let quote = ca.quote_for_report_data(&ReportData::default());
+
+        let tcb_info = crate::TcbInfo::try_from(&quote).expect("Failed to get TCB info");
+
+        assert_eq!(tcb_info.fmspc(), &FMSPC);
+        assert_eq!(tcb_info.svns(), &TCB_COMPONENT_SVNS);
+        assert_eq!(*tcb_info.pce_svn(), PCE_SVN as u32);
+    }
+
+    #[test]
+    fn report_body_round_trips() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+        let report_body = quote.app_report_body();
+
+        let bytes = report_body_to_bytes(report_body);
+
+        assert_eq!(&bytes[..], &hw_quote[48..48 + REPORT_BODY_SIZE]);
+    }
+}
@@ -51,9 +51,15 @@ const TCB_COMPONENT_OIDS: [ObjectIdentifier; COMPONENT_SVN_COUNT] = [
 ];
 const PCE_SVN_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2.17");
 const FMSPC_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.4");
+const COMMON_NAME_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+
+// The common names of the PCK certificate issuers from
+// <https://api.trustedservices.intel.com/documents/Intel_SGX_PCK_Certificate_CRL_Spec-1.5.pdf>
+const PROCESSOR_CA_COMMON_NAME: &str = "Intel SGX PCK Processor CA";
+const PLATFORM_CA_COMMON_NAME: &str = "Intel SGX PCK Platform CA";
 
 /// Error parsing TCB info from PCK leaf certificate
-#[derive(Debug, PartialEq, displaydoc::Display, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, displaydoc::Display, Clone, Serialize, Deserialize)]
 pub enum Error {
     /// Missing the SGX OID extension: {0}
     MissingSgxExtension(String),
@@ -63,6 +69,8 @@ pub enum Error {
     FmspcSize(usize),
     /// Unsupported quote certification data, should be `PckCertificateChain`
     UnsupportedQuoteCertificationData,
+    /// The PCK certificate is not issued by a PCK CA, issuer: {0}
+    UnknownPckIssuer(String),
 }
 
 impl From<x509_cert::der::Error> for Error {
@@ -78,7 +86,7 @@ type SgxExtensions = Vec<AttributeTypeAndValue>;
 
 /// The TCB info provided by the PCK(Provisioning Certification Key) leaf
 /// certificate
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct TcbInfo {
     svns: [u32; COMPONENT_SVN_COUNT],
     pce_svn: u32,
@@ -140,22 +148,85 @@ impl<T: AsRef<[u8]>> TryFrom<&Quote3<T>> for TcbInfo {
     type Error = Error;
 
     fn try_from(quote: &Quote3<T>) -> Result<Self, Self::Error> {
-        let signature_data = quote.signature_data();
-        let certification_data = signature_data.certification_data();
-        let CertificationData::PckCertificateChain(pem_chain) = certification_data else {
-            return Err(Error::UnsupportedQuoteCertificationData);
-        };
-        let chain = pem_chain
-            .into_iter()
-            .map(Certificate::from_pem)
-            .collect::<Result<Vec<_>, _>>()?;
-        let leaf_cert = chain
-            .first()
-            .ok_or(Error::UnsupportedQuoteCertificationData)?;
-        Self::try_from(leaf_cert)
+        let leaf_cert = pck_leaf_certificate(quote)?;
+        Self::try_from(&leaf_cert)
+    }
+}
+
+/// The certificate authority (CA) which issued a PCK certificate.
+///
+/// Intel has a PCK CA for single socket platforms, the processor CA, and one
+/// for multi-package platforms, the platform CA. Each CA has its own PCK CRL.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CaType {
+    /// The Intel SGX PCK Processor CA
+    Processor,
+    /// The Intel SGX PCK Platform CA
+    Platform,
+}
+
+impl CaType {
+    /// The lowercase name of the CA, as used by the `ca` query parameter of
+    /// <https://api.trustedservices.intel.com/sgx/certification/v4/pckcrl?ca={}>
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaType::Processor => "processor",
+            CaType::Platform => "platform",
+        }
+    }
+}
+
+impl TryFrom<&Certificate> for CaType {
+    type Error = Error;
+
+    /// The CA type of the issuer of the PCK leaf certificate `cert`.
+    fn try_from(cert: &Certificate) -> Result<Self, Self::Error> {
+        let issuer = &cert.tbs_certificate.issuer;
+        let common_name = issuer
+            .0
+            .iter()
+            .flat_map(|rdn| rdn.0.iter())
+            .find(|attribute| attribute.oid == COMMON_NAME_OID)
+            .and_then(|attribute| core::str::from_utf8(attribute.value.value()).ok());
+        match common_name {
+            Some(PROCESSOR_CA_COMMON_NAME) => Ok(CaType::Processor),
+            Some(PLATFORM_CA_COMMON_NAME) => Ok(CaType::Platform),
+            _ => Err(Error::UnknownPckIssuer(issuer.to_string())),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> TryFrom<&Quote3<T>> for CaType {
+    type Error = Error;
+
+    fn try_from(quote: &Quote3<T>) -> Result<Self, Self::Error> {
+        let leaf_cert = pck_leaf_certificate(quote)?;
+        Self::try_from(&leaf_cert)
     }
 }
 
+/// Get the PCK leaf certificate from the certification data of the `quote`.
+///
+/// # Errors
+/// * `Error::UnsupportedQuoteCertificationData` if the certification data is
+///   not a PCK certificate chain, or the chain is empty.
+/// * `Error::Der` if a certificate of the chain can not be decoded.
+fn pck_leaf_certificate<T: AsRef<[u8]>>(quote: &Quote3<T>) -> Result<Certificate, Error> {
+    let signature_data = quote.signature_data();
+    let certification_data = signature_data.certification_data();
+    let CertificationData::PckCertificateChain(pem_chain) = certification_data else {
+        return Err(Error::UnsupportedQuoteCertificationData);
+    };
+    let chain = pem_chain
+        .into_iter()
+        .map(Certificate::from_pem)
+        .collect::<Result<Vec<_>, _>>()?;
+    chain
+        .into_iter()
+        .next()
+        .ok_or(Error::UnsupportedQuoteCertificationData)
+}
+
 /// Get the [`SgxExtensions`] from the `cert`.
 ///
 /// # Errors
@@ -234,8 +305,10 @@ mod test {
     use core::mem;
     use core::ops::Range;
     use mc_sgx_dcap_sys_types::{sgx_ql_ecdsa_sig_data_t, sgx_quote3_t};
-    use x509_cert::der::Tag::{BitString, OctetString};
+    use x509_cert::der::asn1::SetOfVec;
+    use x509_cert::der::Tag::{self, BitString, OctetString};
     use x509_cert::der::{Any, Encode};
+    use x509_cert::name::RelativeDistinguishedName;
     use yare::parameterized;
 
     const LEAF_CERT: &[u8] = include_bytes!("../data/tests/leaf_cert.der");
@@ -527,4 +600,57 @@ mod test {
 
         assert_matches!(TcbInfo::try_from(&quote), Err(Error::Der(_)));
     }
+
+    #[test]
+    fn ca_type_from_leaf_cert() {
+        let cert = Certificate::from_der(LEAF_CERT).expect("Failed to decode leaf cert");
+        assert_eq!(CaType::try_from(&cert), Ok(CaType::Processor));
+    }
+
+    #[parameterized(
+        processor = { PROCESSOR_CA_COMMON_NAME, Ok(CaType::Processor) },
+        platform = { PLATFORM_CA_COMMON_NAME, Ok(CaType::Platform) },
+    )]
+    fn ca_type_from_issuer(common_name: &str, expected: Result<CaType, Error>) {
+        let mut cert = Certificate::from_der(LEAF_CERT).expect("Failed to decode leaf cert");
+        let issuer = &mut cert.tbs_certificate.issuer.0;
+        let name = issuer
+            .iter()
+            .flat_map(|rdn| rdn.0.iter())
+            .find(|attribute| attribute.oid == COMMON_NAME_OID)
+            .expect("Missing issuer common name");
+        let mut attribute = name.clone();
+        attribute.value =
+            Any::new(Tag::Utf8String, common_name.as_bytes()).expect("Failed to create value");
+        *issuer = vec![RelativeDistinguishedName::from(
+            SetOfVec::try_from(vec![attribute]).expect("Failed to create RDN"),
+        )];
+
+        assert_eq!(CaType::try_from(&cert), expected);
+    }
+
+    #[test]
+    fn ca_type_from_unknown_issuer() {
+        let mut cert = Certificate::from_der(LEAF_CERT).expect("Failed to decode leaf cert");
+        // The subject of the leaf is "Intel SGX PCK Certificate"
+        cert.tbs_certificate.issuer = cert.tbs_certificate.subject.clone();
+
+        assert_matches!(CaType::try_from(&cert), Err(Error::UnknownPckIssuer(issuer)) if issuer.contains("Intel SGX PCK Certificate"));
+    }
+
+    #[test]
+    fn ca_type_from_quote() {
+        let hw_quote = include_bytes!("../data/tests/hw_quote.dat");
+        let quote = Quote3::try_from(hw_quote.as_ref()).expect("Failed to parse quote");
+
+        assert_eq!(CaType::try_from(&quote), Ok(CaType::Processor));
+    }
+
+    #[parameterized(
+        processor = { CaType::Processor, "processor" },
+        platform = { CaType::Platform, "platform" },
+    )]
+    fn ca_type_as_str(ca: CaType, expected: &str) {
+        assert_eq!(ca.as_str(), expected);
+    }
 }
@@ -0,0 +1,315 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! The impact of a TCB recovery on a fleet of platforms.
+//!
+//! When Intel publishes a TCB recovery, the TCB info of a platform model gains
+//! a new, higher, TCB level and the levels below it become out of date. Given
+//! the TCB info from before and after the recovery, and the [`TcbInfo`] of each
+//! platform's PCK certificate, [`TcbRecoveryImpact::analyze()`] reports which
+//! platforms change status.
+//!
+//! The TCB info of collateral can be verified with
+//! [`QuoteAppraiser::verify_collateral()`](crate::QuoteAppraiser::verify_collateral)
+//! and taken from
+//! [`VerifiedCollateral::tcb_info()`](crate::VerifiedCollateral::tcb_info).
+
+use crate::{FmspcTcbInfo, TcbInfo, TcbStatus};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// The change in TCB status of each platform across a TCB recovery.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TcbRecoveryImpact {
+    platforms: Vec<PlatformImpact>,
+}
+
+impl TcbRecoveryImpact {
+    /// Compare the status of `platforms` in the TCB info `before` and `after`
+    /// a TCB recovery.
+    ///
+    /// The TCB info of a platform is the one with its FMSPC. When there are
+    /// several for the same FMSPC, the one with the highest TCB evaluation
+    /// data number is used.
+    ///
+    /// # Arguments
+    /// * `before` - The TCB info of each platform model before the recovery.
+    /// * `after` - The TCB info of each platform model after the recovery.
+    /// * `platforms` - The TCB info from the PCK certificates of the
+    ///   platforms, as collected from their quotes.
+    pub fn analyze(before: &[FmspcTcbInfo], after: &[FmspcTcbInfo], platforms: &[TcbInfo]) -> Self {
+        let platforms = platforms
+            .iter()
+            .map(|platform| PlatformImpact::new(platform, before, after))
+            .collect();
+        Self { platforms }
+    }
+
+    /// The impact on each platform, in the order the platforms were given.
+    pub fn platforms(&self) -> &[PlatformImpact] {
+        &self.platforms
+    }
+
+    /// The platforms whose TCB status changed.
+    pub fn changed(&self) -> impl Iterator<Item = &PlatformImpact> {
+        self.platforms
+            .iter()
+            .filter(|impact| impact.status_changed())
+    }
+
+    /// The platforms which were [`TcbStatus::UpToDate`] before the recovery
+    /// and aren't after it.
+    pub fn no_longer_up_to_date(&self) -> impl Iterator<Item = &PlatformImpact> {
+        self.platforms
+            .iter()
+            .filter(|impact| impact.no_longer_up_to_date())
+    }
+}
+
+/// The change in TCB status of one platform across a TCB recovery.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlatformImpact {
+    platform: TcbInfo,
+    status_before: Option<TcbStatus>,
+    status_after: Option<TcbStatus>,
+    new_advisory_ids: Vec<String>,
+    tcb_evaluation_data_number: Option<u32>,
+    enforcement_date: Option<Duration>,
+}
+
+impl PlatformImpact {
+    fn new(platform: &TcbInfo, before: &[FmspcTcbInfo], after: &[FmspcTcbInfo]) -> Self {
+        let before_info = latest(before, platform);
+        let after_info = latest(after, platform);
+        let before_level = before_info.and_then(|info| info.tcb_level(platform));
+        let after_level = after_info.and_then(|info| info.tcb_level(platform));
+
+        let advisory_ids_before = before_level
+            .map(|level| level.advisory_ids())
+            .unwrap_or_default();
+        let new_advisory_ids = after_level
+            .map(|level| level.advisory_ids())
+            .unwrap_or_default()
+            .iter()
+            .filter(|id| !advisory_ids_before.contains(*id))
+            .cloned()
+            .collect();
+
+        let enforcement_date = after_info
+            .and_then(|info| info.tcb_levels().first())
+            .map(|level| level.tcb_date().unix_duration());
+
+        Self {
+            platform: platform.clone(),
+            status_before: before_level.map(|level| level.tcb_status()),
+            status_after: after_level.map(|level| level.tcb_status()),
+            new_advisory_ids,
+            tcb_evaluation_data_number: after_info.map(FmspcTcbInfo::tcb_evaluation_data_number),
+            enforcement_date,
+        }
+    }
+
+    /// The TCB info of the platform's PCK certificate
+    pub fn platform(&self) -> &TcbInfo {
+        &self.platform
+    }
+
+    /// The TCB status of the platform before the recovery.
+    ///
+    /// `None` when there is no TCB info for the platform's FMSPC, or the
+    /// platform is below every TCB level.
+    pub fn status_before(&self) -> Option<TcbStatus> {
+        self.status_before
+    }
+
+    /// The TCB status of the platform after the recovery.
+    ///
+    /// `None` when there is no TCB info for the platform's FMSPC, or the
+    /// platform is below every TCB level.
+    pub fn status_after(&self) -> Option<TcbStatus> {
+        self.status_after
+    }
+
+    /// The advisory IDs of the platform's TCB level after the recovery which
+    /// weren't listed before it.
+    pub fn new_advisory_ids(&self) -> &[String] {
+        &self.new_advisory_ids
+    }
+
+    /// The TCB evaluation data number of the TCB info after the recovery.
+    pub fn tcb_evaluation_data_number(&self) -> Option<u32> {
+        self.tcb_evaluation_data_number
+    }
+
+    /// The date the recovery is enforced, as the duration since the UNIX
+    /// epoch.
+    ///
+    /// This is the TCB date of the highest TCB level after the recovery,
+    /// platforms not at that level are no longer up to date as of this date.
+    pub fn enforcement_date(&self) -> Option<Duration> {
+        self.enforcement_date
+    }
+
+    /// Whether the TCB status of the platform changed.
+    pub fn status_changed(&self) -> bool {
+        self.status_before != self.status_after
+    }
+
+    /// Whether the platform was [`TcbStatus::UpToDate`] before the recovery
+    /// and isn't after it.
+    pub fn no_longer_up_to_date(&self) -> bool {
+        self.status_before == Some(TcbStatus::UpToDate)
+            && self.status_after != Some(TcbStatus::UpToDate)
+    }
+}
+
+/// The TCB info for the FMSPC of `platform` with the highest TCB evaluation
+/// data number.
+fn latest<'a>(tcb_infos: &'a [FmspcTcbInfo], platform: &TcbInfo) -> Option<&'a FmspcTcbInfo> {
+    tcb_infos
+        .iter()
+        .filter(|info| info.fmspc() == platform.fmspc())
+        .max_by_key(|info| info.tcb_evaluation_data_number())
+}
+
+#[cfg(all(test, feature = "ra-tls"))]
+mod test {
+    use super::*;
+    use crate::test_utils::{TestCa, FMSPC, PCE_SVN, TCB_COMPONENT_SVNS};
+    use crate::Collateral;
+    use alloc::vec;
+    use x509_cert::der::DateTime;
+
+    fn tcb_info(collateral: &Collateral) -> FmspcTcbInfo {
+        FmspcTcbInfo::from_signed_json(collateral.tcb_info(), &collateral.tcb_issuer_chain()[0])
+            .expect("Failed to parse TCB info")
+    }
+
+    /// The TCB info after a recovery published on 2024-03-01
+    fn recovered(ca: &TestCa) -> FmspcTcbInfo {
+        let collateral = ca
+            .collateral_builder()
+            .tcb_status(TcbStatus::OutOfDate)
+            .advisory_ids(&["INTEL-SA-00001", "INTEL-SA-00002"])
+            .tcb_evaluation_data_number(18)
+            .validity(
+                DateTime::new(2024, 3, 1, 0, 0, 0).expect("Invalid date"),
+                DateTime::new(2049, 12, 31, 23, 59, 59).expect("Invalid date"),
+            )
+            .build();
+        tcb_info(&collateral)
+    }
+
+    fn platform() -> TcbInfo {
+        TcbInfo::new(TCB_COMPONENT_SVNS, PCE_SVN as u32, FMSPC)
+    }
+
+    #[test]
+    fn up_to_date_platform_becomes_out_of_date() {
+        let ca = TestCa::default();
+        let before = tcb_info(
+            &ca.collateral_builder()
+                .advisory_ids(&["INTEL-SA-00001"])
+                .build(),
+        );
+        let after = recovered(&ca);
+
+        let impact = TcbRecoveryImpact::analyze(&[before], &[after], &[platform()]);
+
+        let platform = &impact.platforms()[0];
+        assert_eq!(platform.platform(), &self::platform());
+        assert_eq!(platform.status_before(), Some(TcbStatus::UpToDate));
+        assert_eq!(platform.status_after(), Some(TcbStatus::OutOfDate));
+        assert_eq!(platform.new_advisory_ids(), ["INTEL-SA-00002"]);
+        assert_eq!(platform.tcb_evaluation_data_number(), Some(18));
+        assert_eq!(
+            platform.enforcement_date(),
+            Some(
+                DateTime::new(2024, 3, 1, 0, 0, 0)
+                    .expect("Invalid date")
+                    .unix_duration()
+            )
+        );
+        assert!(platform.status_changed());
+        assert!(platform.no_longer_up_to_date());
+        assert_eq!(impact.no_longer_up_to_date().count(), 1);
+    }
+
+    #[test]
+    fn platform_unchanged_without_recovery() {
+        let ca = TestCa::default();
+        let before = tcb_info(&ca.collateral());
+
+        let impact = TcbRecoveryImpact::analyze(&[before.clone()], &[before], &[platform()]);
+
+        let platform = &impact.platforms()[0];
+        assert_eq!(platform.status_before(), Some(TcbStatus::UpToDate));
+        assert_eq!(platform.status_after(), Some(TcbStatus::UpToDate));
+        assert!(platform.new_advisory_ids().is_empty());
+        assert!(!platform.status_changed());
+        assert_eq!(impact.changed().count(), 0);
+    }
+
+    #[test]
+    fn platform_already_out_of_date() {
+        let ca = TestCa::default();
+        let before = tcb_info(&ca.collateral());
+        let after = recovered(&ca);
+        let platform = TcbInfo::new([1; 16], 1, FMSPC);
+
+        let impact = TcbRecoveryImpact::analyze(&[before], &[after], &[platform]);
+
+        let platform = &impact.platforms()[0];
+        assert_eq!(platform.status_before(), Some(TcbStatus::OutOfDate));
+        assert_eq!(platform.status_after(), Some(TcbStatus::OutOfDate));
+        assert!(platform.new_advisory_ids().is_empty());
+        assert!(!platform.no_longer_up_to_date());
+    }
+
+    #[test]
+    fn platform_with_unknown_fmspc() {
+        let ca = TestCa::default();
+        let before = tcb_info(&ca.collateral());
+        let after = recovered(&ca);
+        let platform = TcbInfo::new(TCB_COMPONENT_SVNS, PCE_SVN as u32, [0xFF; 6]);
+
+        let impact = TcbRecoveryImpact::analyze(&[before], &[after], &[platform]);
+
+        let platform = &impact.platforms()[0];
+        assert_eq!(platform.status_before(), None);
+        assert_eq!(platform.status_after(), None);
+        assert_eq!(platform.tcb_evaluation_data_number(), None);
+        assert_eq!(platform.enforcement_date(), None);
+        assert!(!platform.status_changed());
+    }
+
+    #[test]
+    fn latest_tcb_info_for_fmspc_is_used() {
+        let ca = TestCa::default();
+        let before = tcb_info(&ca.collateral());
+        let after = recovered(&ca);
+
+        let impact = TcbRecoveryImpact::analyze(&[before.clone()], &[after, before], &[platform()]);
+
+        assert_eq!(
+            impact.platforms()[0].status_after(),
+            Some(TcbStatus::OutOfDate)
+        );
+    }
+
+    #[test]
+    fn impact_round_trips_through_serde() {
+        let ca = TestCa::default();
+        let before = tcb_info(&ca.collateral());
+        let after = recovered(&ca);
+        let platforms = vec![platform(), TcbInfo::new([1; 16], 1, FMSPC)];
+        let impact = TcbRecoveryImpact::analyze(&[before], &[after], &platforms);
+
+        let bytes = serde_cbor::to_vec(&impact).expect("Failed to serialize impact");
+        let decoded: TcbRecoveryImpact =
+            serde_cbor::from_slice(&bytes).expect("Failed to deserialize impact");
+
+        assert_eq!(decoded, impact);
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Module used to assist unit tests that need a [`Quote3`](crate::Quote3)
+//! which can be fully verified.
+//!
+//! The [`TestCa`] is the [`SoftwareCa`] with its default, deterministic, keys.
+//! They must *never* be used outside of tests.
+
+pub use crate::software_ca::{
+    report_body_to_bytes, CollateralBuilder, SoftwareCa, SoftwareCaBuilder, FMSPC, PCE_SVN, QE_ID,
+    QE_MR_SIGNER, QE_PRODUCT_ID, QE_SVN, TCB_COMPONENT_SVNS, TCB_EVALUATION_DATA_NUMBER,
+};
+
+/// A software Intel SGX PCK certificate hierarchy and quoting enclave for
+/// tests, see [`SoftwareCa`].
+pub type TestCa = SoftwareCa;
@@ -0,0 +1,225 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Sources of the time that collateral and certificates are checked against.
+//!
+//! Inside an enclave there is no trustworthy clock, the time comes from the
+//! untrusted host. A [`TimeSource`] lets the verification code be given the
+//! time from wherever the caller trusts it.
+
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+/// Errors getting the time from a [`TimeSource`]
+#[derive(Clone, Copy, Debug, displaydoc::Display, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum TimeError {
+    /// The time is before the UNIX epoch
+    BeforeUnixEpoch,
+    /// The time is too far in the future to represent
+    OutOfRange,
+    /// The time went backwards, from {floor:?} to {now:?} since the UNIX epoch
+    #[allow(missing_docs)]
+    Backwards { floor: Duration, now: Duration },
+}
+
+/// A source of the current time, as the duration since the UNIX epoch.
+///
+/// A [`Duration`] is a time source which always returns itself, for a
+/// timestamp supplied by the caller.
+pub trait TimeSource {
+    /// The current time, as the duration since the UNIX epoch.
+    ///
+    /// # Errors
+    /// A [`TimeError`] if the time can't be determined or can't be trusted.
+    fn now(&self) -> Result<Duration, TimeError>;
+}
+
+impl TimeSource for Duration {
+    fn now(&self) -> Result<Duration, TimeError> {
+        Ok(*self)
+    }
+}
+
+impl<S: TimeSource + ?Sized> TimeSource for &S {
+    fn now(&self) -> Result<Duration, TimeError> {
+        (**self).now()
+    }
+}
+
+/// The system clock.
+///
+/// Not to be relied on inside an enclave, where the system clock is
+/// controlled by the host.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl TimeSource for SystemClock {
+    fn now(&self) -> Result<Duration, TimeError> {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|_| TimeError::BeforeUnixEpoch)
+    }
+}
+
+/// A [`TimeSource`] which refuses to go backwards.
+///
+/// Each time from the wrapped source must be no earlier than every time
+/// returned before it, and no earlier than the initial floor. Times which are
+/// earlier fail with [`TimeError::Backwards`], so a host can't roll the clock
+/// back to make expired collateral or revoked certificates acceptable again.
+///
+/// An enclave can seed the floor with a time it already trusts, for example
+/// one it sealed on a previous run.
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug)]
+pub struct MonotonicFloor<S> {
+    source: S,
+    // The floor in nanoseconds since the UNIX epoch.
+    floor: AtomicU64,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl<S: TimeSource> MonotonicFloor<S> {
+    /// Refuse to let the times from `source` go backwards.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            floor: AtomicU64::new(0),
+        }
+    }
+
+    /// Refuse times earlier than `floor`, as the duration since the UNIX
+    /// epoch.
+    ///
+    /// Floors too far in the future to represent are clamped to the latest
+    /// representable time, about the year 2554.
+    #[must_use]
+    pub fn initial_floor(self, floor: Duration) -> Self {
+        let floor = u64::try_from(floor.as_nanos()).unwrap_or(u64::MAX);
+        self.floor.fetch_max(floor, Ordering::AcqRel);
+        self
+    }
+
+    /// The earliest time which will be accepted, as the duration since the
+    /// UNIX epoch.
+    pub fn floor(&self) -> Duration {
+        Duration::from_nanos(self.floor.load(Ordering::Acquire))
+    }
+
+    /// The wrapped source
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl<S: TimeSource> TimeSource for MonotonicFloor<S> {
+    fn now(&self) -> Result<Duration, TimeError> {
+        let now = self.source.now()?;
+        let nanos = u64::try_from(now.as_nanos()).map_err(|_| TimeError::OutOfRange)?;
+        let floor = self.floor.fetch_max(nanos, Ordering::AcqRel);
+        if nanos < floor {
+            return Err(TimeError::Backwards {
+                floor: Duration::from_nanos(floor),
+                now,
+            });
+        }
+        Ok(now)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::cell::Cell;
+
+    /// Returns the times in `times`, one per call, repeating the last.
+    struct Sequence<'a> {
+        times: &'a [u64],
+        next: Cell<usize>,
+    }
+
+    impl<'a> Sequence<'a> {
+        fn new(times: &'a [u64]) -> Self {
+            Self {
+                times,
+                next: Cell::new(0),
+            }
+        }
+    }
+
+    impl TimeSource for Sequence<'_> {
+        fn now(&self) -> Result<Duration, TimeError> {
+            let next = self.next.get();
+            self.next.set(next + 1);
+            let seconds = self.times[next.min(self.times.len() - 1)];
+            Ok(Duration::from_secs(seconds))
+        }
+    }
+
+    #[test]
+    fn duration_is_its_own_time() {
+        let time = Duration::from_secs(1_717_200_000);
+
+        assert_eq!(time.now(), Ok(time));
+        assert_eq!((&time).now(), Ok(time));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_clock_is_after_2024() {
+        let now = SystemClock.now().expect("Failed to read the system clock");
+
+        assert!(now > Duration::from_secs(1_704_067_200));
+    }
+
+    #[test]
+    fn monotonic_floor_allows_time_to_advance() {
+        let time = MonotonicFloor::new(Sequence::new(&[10, 10, 20]));
+
+        assert_eq!(time.now(), Ok(Duration::from_secs(10)));
+        assert_eq!(time.now(), Ok(Duration::from_secs(10)));
+        assert_eq!(time.now(), Ok(Duration::from_secs(20)));
+        assert_eq!(time.floor(), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn monotonic_floor_refuses_to_go_backwards() {
+        let time = MonotonicFloor::new(Sequence::new(&[20, 10, 30]));
+
+        assert_eq!(time.now(), Ok(Duration::from_secs(20)));
+        assert_eq!(
+            time.now(),
+            Err(TimeError::Backwards {
+                floor: Duration::from_secs(20),
+                now: Duration::from_secs(10),
+            })
+        );
+        assert_eq!(time.now(), Ok(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn monotonic_floor_refuses_times_before_initial_floor() {
+        let time =
+            MonotonicFloor::new(Duration::from_secs(10)).initial_floor(Duration::from_secs(15));
+
+        assert_eq!(
+            time.now(),
+            Err(TimeError::Backwards {
+                floor: Duration::from_secs(15),
+                now: Duration::from_secs(10),
+            })
+        );
+        assert_eq!(time.floor(), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn monotonic_floor_out_of_range() {
+        let time = MonotonicFloor::new(Duration::MAX);
+
+        assert_eq!(time.now(), Err(TimeError::OutOfRange));
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! X.509 helpers for the ECDSA P-256 keys used by SGX.
+//!
+//! Every key and signature in the Intel PCK certificate hierarchy, and in an
+//! RA-TLS certificate, is ECDSA P-256 with SHA-256. These helpers only support
+//! that combination.
+
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{DerSignature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use x509_cert::der::asn1::BitString;
+use x509_cert::der::{self, Encode};
+use x509_cert::spki::{AlgorithmIdentifierOwned, ObjectIdentifier};
+use x509_cert::Certificate;
+#[cfg(feature = "ra-tls")]
+use {
+    p256::ecdsa::{signature::Signer, SigningKey},
+    x509_cert::certificate::TbsCertificate,
+    x509_cert::der::asn1::{Any, GeneralizedTime, UtcTime},
+    x509_cert::der::DateTime,
+    x509_cert::spki::SubjectPublicKeyInfoOwned,
+    x509_cert::time::Time,
+};
+
+// From <https://www.rfc-editor.org/rfc/rfc5758#section-3.2>
+pub(crate) const ECDSA_WITH_SHA256_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+
+// From <https://www.rfc-editor.org/rfc/rfc5480#section-2.1.1>
+#[cfg(feature = "ra-tls")]
+pub(crate) const EC_PUBLIC_KEY_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+#[cfg(feature = "ra-tls")]
+const SECP256R1_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+
+/// Error verifying a signature over X.509 data
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum SignatureError {
+    /// Error encoding or decoding DER
+    Der(der::Error),
+    /// The public key is not an ECDSA P-256 key
+    UnsupportedKey,
+    /// The signature algorithm is not ECDSA with SHA-256
+    UnsupportedAlgorithm,
+    /// The signature is invalid
+    Invalid,
+}
+
+impl From<der::Error> for SignatureError {
+    fn from(err: der::Error) -> Self {
+        Self::Der(err)
+    }
+}
+
+/// Sign `tbs_certificate` with `key`.
+///
+/// The `signature` algorithm of `tbs_certificate` is expected to be
+/// ECDSA with SHA-256.
+#[cfg(feature = "ra-tls")]
+pub(crate) fn sign_certificate(
+    tbs_certificate: TbsCertificate,
+    key: &SigningKey,
+) -> der::Result<Certificate> {
+    let signature = sign(&tbs_certificate.to_der()?, key)?;
+    Ok(Certificate {
+        tbs_certificate,
+        signature_algorithm: ecdsa_with_sha256(),
+        signature,
+    })
+}
+
+/// Sign `message` with `key`, as the DER encoded signature of an X.509
+/// structure.
+#[cfg(feature = "ra-tls")]
+pub(crate) fn sign(message: &[u8], key: &SigningKey) -> der::Result<BitString> {
+    let signature: DerSignature = key.sign(message);
+    BitString::from_bytes(signature.as_bytes())
+}
+
+/// Verify that `signature` is the signature of `message` by `key`.
+///
+/// `algorithm` is the signature algorithm as listed in the signed X.509
+/// structure.
+pub(crate) fn verify(
+    message: &[u8],
+    algorithm: &AlgorithmIdentifierOwned,
+    signature: &BitString,
+    key: &VerifyingKey,
+) -> Result<(), SignatureError> {
+    if algorithm.oid != ECDSA_WITH_SHA256_OID {
+        return Err(SignatureError::UnsupportedAlgorithm);
+    }
+    let signature_bytes = signature.as_bytes().ok_or(SignatureError::Invalid)?;
+    let signature = DerSignature::try_from(signature_bytes).map_err(|_| SignatureError::Invalid)?;
+    key.verify(message, &signature)
+        .map_err(|_| SignatureError::Invalid)
+}
+
+/// Verify that `certificate` was signed by `key`.
+pub(crate) fn verify_certificate(
+    certificate: &Certificate,
+    key: &VerifyingKey,
+) -> Result<(), SignatureError> {
+    if certificate.signature_algorithm != certificate.tbs_certificate.signature {
+        return Err(SignatureError::UnsupportedAlgorithm);
+    }
+    verify(
+        &certificate.tbs_certificate.to_der()?,
+        &certificate.signature_algorithm,
+        &certificate.signature,
+        key,
+    )
+}
+
+/// The `SubjectPublicKeyInfo` for an ECDSA P-256 `key`.
+#[cfg(feature = "ra-tls")]
+pub(crate) fn subject_public_key_info(
+    key: &VerifyingKey,
+) -> der::Result<SubjectPublicKeyInfoOwned> {
+    let point = key.to_encoded_point(false);
+    Ok(SubjectPublicKeyInfoOwned {
+        algorithm: AlgorithmIdentifierOwned {
+            oid: EC_PUBLIC_KEY_OID,
+            parameters: Some(Any::encode_from(&SECP256R1_OID)?),
+        },
+        subject_public_key: BitString::from_bytes(point.as_bytes())?,
+    })
+}
+
+/// The [`AlgorithmIdentifierOwned`] for ECDSA with SHA-256.
+#[cfg(feature = "ra-tls")]
+pub(crate) fn ecdsa_with_sha256() -> AlgorithmIdentifierOwned {
+    // Per RFC 5758 the parameters must be absent
+    AlgorithmIdentifierOwned {
+        oid: ECDSA_WITH_SHA256_OID,
+        parameters: None,
+    }
+}
+
+/// Convert `date_time` into a certificate [`Time`].
+///
+/// Per <https://www.rfc-editor.org/rfc/rfc5280#section-4.1.2.5> dates through
+/// 2049 are encoded as `UTCTime` and dates in 2050 or later are encoded as
+/// `GeneralizedTime`.
+#[cfg(feature = "ra-tls")]
+pub(crate) fn time(date_time: DateTime) -> der::Result<Time> {
+    let time = match date_time.year() {
+        year if year < 2050 => Time::UtcTime(UtcTime::from_date_time(date_time)?),
+        _ => Time::GeneralTime(GeneralizedTime::from_date_time(date_time)),
+    };
+    Ok(time)
+}
+
+/// The ECDSA P-256 public key of `certificate`.
+pub(crate) fn verifying_key(certificate: &Certificate) -> Result<VerifyingKey, SignatureError> {
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()?;
+    VerifyingKey::from_public_key_der(&spki).map_err(|_| SignatureError::UnsupportedKey)
+}
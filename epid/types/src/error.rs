@@ -0,0 +1,51 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Errors verifying and parsing IAS verification reports.
+
+use alloc::string::String;
+use mc_sgx_core_types::FfiError;
+use x509_cert::der;
+
+/// Error verifying or parsing an IAS verification report
+#[derive(Debug, displaydoc::Display, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Error encoding or decoding DER {0}
+    Der(der::Error),
+    /// The {0} is not valid base64
+    Base64(&'static str),
+    /// The signing certificate header is not a URL encoded PEM certificate chain
+    CertificateHeader,
+    /// A public key is not an RSA key
+    UnsupportedKey,
+    /// A signature algorithm is not RSA with SHA-256
+    UnsupportedSignatureAlgorithm,
+    /// The signing certificate chain is empty
+    EmptyCertificateChain,
+    /// The signing certificate chain does not end in the trust anchor
+    UntrustedCertificateChain,
+    /// A certificate signature is invalid
+    CertificateSignature,
+    /// A certificate is signed by a certificate which is not a certificate authority
+    NotCertificateAuthority,
+    /// A certificate is not valid at the time of verification
+    CertificateValidity,
+    /// The signature of the report is invalid
+    ReportSignature,
+    /// Error parsing the report JSON {0}
+    Json(String),
+    /// Unknown isvEnclaveQuoteStatus: {0}
+    UnknownQuoteStatus(String),
+    /// Invalid timestamp in the report: {0}
+    Timestamp(String),
+    /// The isvEnclaveQuoteBody is not valid: {0}
+    QuoteBody(FfiError),
+    /// The platformInfoBlob is not valid
+    PlatformInfoBlob,
+}
+
+impl From<der::Error> for Error {
+    fn from(err: der::Error) -> Self {
+        Self::Der(err)
+    }
+}
@@ -0,0 +1,22 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+#![doc = include_str!("../README.md")]
+#![no_std]
+#![deny(missing_docs, missing_debug_implementations, unsafe_code)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+mod error;
+#[cfg(feature = "alloc")]
+mod verification_report;
+#[cfg(feature = "alloc")]
+mod verifier;
+
+#[cfg(feature = "alloc")]
+pub use crate::{
+    error::Error,
+    verification_report::{QuoteStatus, VerificationReport},
+    verifier::{ReportVerifier, SignedVerificationReport},
+};
@@ -0,0 +1,478 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! The attestation verification report from the Intel Attestation Service
+//! (IAS).
+//!
+//! The report is the JSON body of the response to
+//! `POST /attestation/v4/report`, as documented in
+//! <https://api.trustedservices.intel.com/documents/sgx-attestation-api-spec.pdf>.
+
+use crate::Error;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use base64ct::{Base64, Encoding};
+use mc_sgx_core_types::{FfiError, PlatformInfo, Quote, QUOTE_MIN_SIZE};
+use serde::Deserialize;
+use x509_cert::der::DateTime;
+
+/// The size of the quote in `isvEnclaveQuoteBody`.
+///
+/// IAS only provides the quote up to the report body, without the
+/// `signature_len` or the signature.
+const QUOTE_BODY_SIZE: usize = QUOTE_MIN_SIZE - 4;
+
+/// The TLV header of `platformInfoBlob`; type 21, version 2, and the size of
+/// the platform info in big endian.
+const PLATFORM_INFO_TLV_HEADER: [u8; 4] = [21, 2, 0, 101];
+
+/// The status of the quote as determined by IAS, `isvEnclaveQuoteStatus`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QuoteStatus {
+    /// The EPID signature of the quote was verified and the platform is up to
+    /// date
+    Ok,
+    /// The EPID signature of the quote is invalid
+    SignatureInvalid,
+    /// The EPID group has been revoked
+    GroupRevoked,
+    /// The EPID private key used to sign the quote has been revoked by
+    /// signature
+    SignatureRevoked,
+    /// The EPID private key used to sign the quote has been directly revoked
+    KeyRevoked,
+    /// The signature revocation list version in the quote does not match the
+    /// most recent version
+    SigrlVersionMismatch,
+    /// The TCB level of the platform is outdated
+    GroupOutOfDate,
+    /// Additional configuration of the platform may be needed
+    ConfigurationNeeded,
+    /// Software hardening may be needed for the enclave
+    SwHardeningNeeded,
+    /// Additional configuration of the platform and software hardening may be
+    /// needed
+    ConfigurationAndSwHardeningNeeded,
+}
+
+impl TryFrom<&str> for QuoteStatus {
+    type Error = Error;
+
+    fn try_from(status: &str) -> Result<Self, Error> {
+        match status {
+            "OK" => Ok(Self::Ok),
+            "SIGNATURE_INVALID" => Ok(Self::SignatureInvalid),
+            "GROUP_REVOKED" => Ok(Self::GroupRevoked),
+            "SIGNATURE_REVOKED" => Ok(Self::SignatureRevoked),
+            "KEY_REVOKED" => Ok(Self::KeyRevoked),
+            "SIGRL_VERSION_MISMATCH" => Ok(Self::SigrlVersionMismatch),
+            "GROUP_OUT_OF_DATE" => Ok(Self::GroupOutOfDate),
+            "CONFIGURATION_NEEDED" => Ok(Self::ConfigurationNeeded),
+            "SW_HARDENING_NEEDED" => Ok(Self::SwHardeningNeeded),
+            "CONFIGURATION_AND_SW_HARDENING_NEEDED" => Ok(Self::ConfigurationAndSwHardeningNeeded),
+            _ => Err(Error::UnknownQuoteStatus(status.into())),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportJson {
+    id: String,
+    timestamp: String,
+    version: u32,
+    isv_enclave_quote_status: String,
+    isv_enclave_quote_body: String,
+    revocation_reason: Option<u32>,
+    platform_info_blob: Option<String>,
+    nonce: Option<String>,
+    epid_pseudonym: Option<String>,
+    #[serde(rename = "advisoryURL")]
+    advisory_url: Option<String>,
+    #[serde(rename = "advisoryIDs", default)]
+    advisory_ids: Vec<String>,
+}
+
+/// An attestation verification report from IAS.
+///
+/// A report is only available from a verified
+/// [`SignedVerificationReport`](crate::SignedVerificationReport), see
+/// [`ReportVerifier`](crate::ReportVerifier).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationReport {
+    id: String,
+    timestamp: DateTime,
+    version: u32,
+    quote_status: QuoteStatus,
+    // The quote body with a zero `signature_len` so it can be parsed as a
+    // `Quote`
+    quote: Vec<u8>,
+    revocation_reason: Option<u32>,
+    platform_info: Option<PlatformInfo>,
+    nonce: Option<String>,
+    epid_pseudonym: Option<Vec<u8>>,
+    advisory_url: Option<String>,
+    advisory_ids: Vec<String>,
+}
+
+impl VerificationReport {
+    /// Parse the report `json`.
+    ///
+    /// The signature of the report is *not* verified.
+    pub(crate) fn from_json(json: &str) -> Result<Self, Error> {
+        let report: ReportJson =
+            serde_json::from_str(json).map_err(|err| Error::Json(err.to_string()))?;
+
+        let mut quote = Base64::decode_vec(&report.isv_enclave_quote_body)
+            .map_err(|_| Error::Base64("isvEnclaveQuoteBody"))?;
+        if quote.len() != QUOTE_BODY_SIZE {
+            return Err(Error::QuoteBody(FfiError::InvalidInputLength));
+        }
+        quote.extend_from_slice(&[0; 4]);
+        Quote::try_from(quote.as_slice()).map_err(Error::QuoteBody)?;
+
+        let platform_info = report
+            .platform_info_blob
+            .as_deref()
+            .map(platform_info)
+            .transpose()?;
+        let epid_pseudonym = report
+            .epid_pseudonym
+            .as_deref()
+            .map(|pseudonym| {
+                Base64::decode_vec(pseudonym).map_err(|_| Error::Base64("epidPseudonym"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            id: report.id,
+            timestamp: timestamp(&report.timestamp)?,
+            version: report.version,
+            quote_status: report.isv_enclave_quote_status.as_str().try_into()?,
+            quote,
+            revocation_reason: report.revocation_reason,
+            platform_info,
+            nonce: report.nonce,
+            epid_pseudonym,
+            advisory_url: report.advisory_url,
+            advisory_ids: report.advisory_ids,
+        })
+    }
+
+    /// The ID of the report
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The time the report was created, UTC
+    pub fn timestamp(&self) -> DateTime {
+        self.timestamp
+    }
+
+    /// The version of the attestation API the report is from
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The status of the quote, `isvEnclaveQuoteStatus`
+    pub fn quote_status(&self) -> QuoteStatus {
+        self.quote_status
+    }
+
+    /// The quote that was verified, `isvEnclaveQuoteBody`.
+    ///
+    /// IAS only provides the body of the quote so the signature of the
+    /// returned quote is empty.
+    pub fn quote(&self) -> Quote<'_> {
+        Quote::try_from(self.quote.as_slice())
+            .expect("The quote body is validated when the report is parsed")
+    }
+
+    /// The reason the EPID group was revoked, as an RFC 5280 `CRLReason`.
+    ///
+    /// Only present for [`QuoteStatus::GroupRevoked`].
+    pub fn revocation_reason(&self) -> Option<u32> {
+        self.revocation_reason
+    }
+
+    /// The platform info, `platformInfoBlob`.
+    ///
+    /// Present when the platform needs attention, for instance with
+    /// [`QuoteStatus::GroupOutOfDate`]. It can be provided to
+    /// `sgx_report_attestation_status()` to learn what needs updating.
+    pub fn platform_info(&self) -> Option<&PlatformInfo> {
+        self.platform_info.as_ref()
+    }
+
+    /// The nonce provided in the attestation evidence payload
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_deref()
+    }
+
+    /// The EPID pseudonym of the platform.
+    ///
+    /// Only present for quotes with a
+    /// [`QuoteSignatureKind::Linkable`](mc_sgx_core_types::QuoteSignatureKind::Linkable)
+    /// signature.
+    pub fn epid_pseudonym(&self) -> Option<&[u8]> {
+        self.epid_pseudonym.as_deref()
+    }
+
+    /// The URL with details of the [`Self::advisory_ids()`]
+    pub fn advisory_url(&self) -> Option<&str> {
+        self.advisory_url.as_deref()
+    }
+
+    /// The IDs of the Intel security advisories affecting the platform
+    pub fn advisory_ids(&self) -> &[String] {
+        &self.advisory_ids
+    }
+}
+
+/// Decode the hex `blob` of TLV encoded platform info.
+fn platform_info(blob: &str) -> Result<PlatformInfo, Error> {
+    let bytes = hex::decode(blob).map_err(|_| Error::PlatformInfoBlob)?;
+    let info = match bytes.strip_prefix(&PLATFORM_INFO_TLV_HEADER) {
+        Some(info) if info.len() == usize::from(PLATFORM_INFO_TLV_HEADER[3]) => info,
+        _ => return Err(Error::PlatformInfoBlob),
+    };
+    PlatformInfo::try_from(info).map_err(|_| Error::PlatformInfoBlob)
+}
+
+/// Parse the report `timestamp`, `YYYY-MM-DDTHH:MM:SS.ffffff`.
+///
+/// The timestamp is UTC without a zone designator. Fractional seconds are
+/// accepted and discarded.
+fn timestamp(timestamp: &str) -> Result<DateTime, Error> {
+    let error = || Error::Timestamp(timestamp.into());
+    let bytes = timestamp.as_bytes();
+    if bytes.len() < 19 {
+        return Err(error());
+    }
+    let (bytes, fraction) = bytes.split_at(19);
+    if !(fraction.is_empty()
+        || (fraction.len() > 1
+            && fraction[0] == b'.'
+            && fraction[1..].iter().all(u8::is_ascii_digit)))
+    {
+        return Err(error());
+    }
+
+    let number = |range: core::ops::Range<usize>| {
+        let digits = &bytes[range];
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return Err(error());
+        }
+        Ok(digits
+            .iter()
+            .fold(0u16, |total, digit| total * 10 + u16::from(digit - b'0')))
+    };
+    let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+    if separators
+        .iter()
+        .any(|(index, separator)| bytes[*index] != *separator)
+    {
+        return Err(error());
+    }
+
+    // The digit counts guarantee these fit in a `u8`
+    DateTime::new(
+        number(0..4)?,
+        number(5..7)? as u8,
+        number(8..10)? as u8,
+        number(11..13)? as u8,
+        number(14..16)? as u8,
+        number(17..19)? as u8,
+    )
+    .map_err(|_| error())
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use assert_matches::assert_matches;
+    use mc_sgx_core_types::{BaseQuote, QuoteSignatureKind};
+    use std::format;
+    use yare::parameterized;
+
+    const OK_REPORT: &str = include_str!("../data/tests/ok_report.json");
+    const GROUP_OUT_OF_DATE_REPORT: &str =
+        include_str!("../data/tests/group_out_of_date_report.json");
+
+    fn ok_quote() -> Vec<u8> {
+        let report = VerificationReport::from_json(OK_REPORT).expect("Failed to parse report");
+        report.quote.clone()
+    }
+
+    /// The `isvEnclaveQuoteBody` for `quote`.
+    fn quote_body(quote: &[u8]) -> String {
+        Base64::encode_string(&quote[..QUOTE_BODY_SIZE])
+    }
+
+    fn report_with_body(body: &str) -> String {
+        format!(
+            r#"{{"id":"1","timestamp":"2026-10-18T15:03:12.345678","version":4,"isvEnclaveQuoteStatus":"OK","isvEnclaveQuoteBody":"{body}"}}"#
+        )
+    }
+
+    #[test]
+    fn ok_report() {
+        let report = VerificationReport::from_json(OK_REPORT).expect("Failed to parse report");
+        assert_eq!(report.id(), "165171271757108173876306223827987629752");
+        assert_eq!(
+            report.timestamp(),
+            DateTime::new(2026, 10, 18, 15, 3, 12).expect("Invalid date")
+        );
+        assert_eq!(report.version(), 4);
+        assert_eq!(report.quote_status(), QuoteStatus::Ok);
+        assert_eq!(report.revocation_reason(), None);
+        assert_eq!(report.platform_info(), None);
+        assert_eq!(report.nonce(), Some("0123456789abcdef"));
+        assert_eq!(
+            report.epid_pseudonym(),
+            Some((0..128).collect::<Vec<u8>>().as_slice())
+        );
+        assert_eq!(report.advisory_url(), None);
+        assert!(report.advisory_ids().is_empty());
+
+        let quote = report.quote();
+        assert_eq!(quote.version(), 2.into());
+        assert_eq!(quote.signature_type(), QuoteSignatureKind::Linkable);
+        assert_eq!(quote.quoting_enclave_svn(), 11.into());
+        assert_eq!(quote.provisioning_certification_enclave_svn(), 10.into());
+        assert_eq!(quote.signature_len(), 0);
+
+        let report_body = quote.report_body();
+        assert_eq!(report_body.mr_enclave(), [0xAE; 32].into());
+        assert_eq!(report_body.mr_signer(), [0x51; 32].into());
+        assert_eq!(report_body.isv_product_id(), 3.into());
+        assert_eq!(report_body.isv_svn(), 5.into());
+    }
+
+    #[test]
+    fn group_out_of_date_report() {
+        let report = VerificationReport::from_json(GROUP_OUT_OF_DATE_REPORT)
+            .expect("Failed to parse report");
+        assert_eq!(report.quote_status(), QuoteStatus::GroupOutOfDate);
+        assert_eq!(
+            report.quote().signature_type(),
+            QuoteSignatureKind::UnLinkable
+        );
+        assert_eq!(report.nonce(), None);
+        assert_eq!(report.epid_pseudonym(), None);
+        assert_eq!(
+            report.advisory_url(),
+            Some("https://security-center.intel.com")
+        );
+        assert_eq!(report.advisory_ids(), ["INTEL-SA-00334", "INTEL-SA-00615"]);
+
+        let platform_info = report.platform_info().expect("Missing platform info");
+        let bytes: &[u8] = platform_info.as_ref();
+        assert_eq!(&bytes[..4], &[0x04, 0x00, 0x01, 0x00]);
+    }
+
+    #[parameterized(
+        ok = { "OK", QuoteStatus::Ok },
+        signature_invalid = { "SIGNATURE_INVALID", QuoteStatus::SignatureInvalid },
+        group_revoked = { "GROUP_REVOKED", QuoteStatus::GroupRevoked },
+        signature_revoked = { "SIGNATURE_REVOKED", QuoteStatus::SignatureRevoked },
+        key_revoked = { "KEY_REVOKED", QuoteStatus::KeyRevoked },
+        sigrl_version_mismatch = { "SIGRL_VERSION_MISMATCH", QuoteStatus::SigrlVersionMismatch },
+        group_out_of_date = { "GROUP_OUT_OF_DATE", QuoteStatus::GroupOutOfDate },
+        configuration_needed = { "CONFIGURATION_NEEDED", QuoteStatus::ConfigurationNeeded },
+        sw_hardening_needed = { "SW_HARDENING_NEEDED", QuoteStatus::SwHardeningNeeded },
+        configuration_and_sw_hardening_needed = { "CONFIGURATION_AND_SW_HARDENING_NEEDED", QuoteStatus::ConfigurationAndSwHardeningNeeded },
+    )]
+    fn quote_status_from_str(status: &str, expected: QuoteStatus) {
+        assert_eq!(QuoteStatus::try_from(status), Ok(expected));
+    }
+
+    #[test]
+    fn unknown_quote_status() {
+        assert_eq!(
+            QuoteStatus::try_from("Ok"),
+            Err(Error::UnknownQuoteStatus("Ok".into()))
+        );
+    }
+
+    #[test]
+    fn quote_body_too_short() {
+        let quote = ok_quote();
+        let json = report_with_body(&Base64::encode_string(&quote[..QUOTE_BODY_SIZE - 1]));
+        assert_eq!(
+            VerificationReport::from_json(&json),
+            Err(Error::QuoteBody(FfiError::InvalidInputLength))
+        );
+    }
+
+    #[test]
+    fn quote_body_with_signature_len() {
+        let quote = ok_quote();
+        let json = report_with_body(&Base64::encode_string(&quote));
+        assert_eq!(
+            VerificationReport::from_json(&json),
+            Err(Error::QuoteBody(FfiError::InvalidInputLength))
+        );
+    }
+
+    #[test]
+    fn quote_body_with_unknown_signature_type() {
+        let mut quote = ok_quote();
+        quote[2] = 5;
+        let json = report_with_body(&quote_body(&quote));
+        assert_eq!(
+            VerificationReport::from_json(&json),
+            Err(Error::QuoteBody(FfiError::UnknownEnumValue(5)))
+        );
+    }
+
+    #[test]
+    fn quote_body_not_base64() {
+        let json = report_with_body("not*base64");
+        assert_eq!(
+            VerificationReport::from_json(&json),
+            Err(Error::Base64("isvEnclaveQuoteBody"))
+        );
+    }
+
+    #[test]
+    fn missing_quote_status() {
+        let json = r#"{"id":"1","timestamp":"2026-10-18T15:03:12","version":4}"#;
+        assert_matches!(VerificationReport::from_json(json), Err(Error::Json(_)));
+    }
+
+    #[parameterized(
+        not_hex = { "15020065ZZ" },
+        wrong_type = { &format!("16020065{}", "00".repeat(101)) },
+        too_short = { &format!("15020065{}", "00".repeat(100)) },
+        too_long = { &format!("15020065{}", "00".repeat(102)) },
+    )]
+    fn invalid_platform_info_blob(blob: &str) {
+        assert_eq!(platform_info(blob), Err(Error::PlatformInfoBlob));
+    }
+
+    #[parameterized(
+        fractional = { "2026-10-18T15:03:12.345678", (2026, 10, 18, 15, 3, 12) },
+        whole_seconds = { "2019-06-05T19:02:23", (2019, 6, 5, 19, 2, 23) },
+    )]
+    fn valid_timestamp(date: &str, expected: (u16, u8, u8, u8, u8, u8)) {
+        let (year, month, day, hour, minutes, seconds) = expected;
+        let expected =
+            DateTime::new(year, month, day, hour, minutes, seconds).expect("Invalid date");
+        assert_eq!(timestamp(date), Ok(expected));
+    }
+
+    #[parameterized(
+        empty = { "" },
+        zone = { "2026-10-18T15:03:12Z" },
+        bad_separator = { "2026-10-18 15:03:12" },
+        bad_digit = { "2026-1a-18T15:03:12" },
+        bad_month = { "2026-13-18T15:03:12" },
+        empty_fraction = { "2026-10-18T15:03:12." },
+    )]
+    fn invalid_timestamp(date: &str) {
+        assert_eq!(timestamp(date), Err(Error::Timestamp(date.into())));
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+
+//! Offline verification of IAS attestation verification reports.
+//!
+//! IAS signs the exact bytes of the report JSON with RSA PKCS#1 v1.5 and
+//! SHA-256. The signature is provided in the `X-IASReport-Signature` header
+//! and the certificate chain of the signing key, leaf first, in the
+//! `X-IASReport-Signing-Certificate` header.
+
+use crate::{Error, VerificationReport};
+use alloc::string::String;
+use alloc::vec::Vec;
+use base64ct::{Base64, Encoding};
+use core::time::Duration;
+use percent_encoding::percent_decode_str;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::Sha256;
+use x509_cert::der::Encode;
+use x509_cert::ext::pkix::BasicConstraints;
+use x509_cert::spki::ObjectIdentifier;
+use x509_cert::Certificate;
+
+// From <https://www.rfc-editor.org/rfc/rfc4055#section-5>
+const SHA256_WITH_RSA_ENCRYPTION_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// An attestation verification report as received from IAS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedVerificationReport {
+    body: String,
+    signature: Vec<u8>,
+    signing_chain: Vec<Certificate>,
+}
+
+impl SignedVerificationReport {
+    /// Create a signed report from the response of
+    /// `POST /attestation/v4/report`.
+    ///
+    /// # Arguments
+    /// * `body` - The JSON body of the response, exactly as received.
+    /// * `signature` - The value of the `X-IASReport-Signature` header.
+    /// * `signing_certificates` - The value of the
+    ///   `X-IASReport-Signing-Certificate` header.
+    ///
+    /// # Errors
+    /// * [`Error::Base64`] if `signature` is not base64.
+    /// * [`Error::CertificateHeader`] if `signing_certificates` is not a URL
+    ///   encoded PEM certificate chain.
+    pub fn new(
+        body: impl Into<String>,
+        signature: &str,
+        signing_certificates: &str,
+    ) -> Result<Self, Error> {
+        let signature =
+            Base64::decode_vec(signature).map_err(|_| Error::Base64("X-IASReport-Signature"))?;
+        let pem = percent_decode_str(signing_certificates)
+            .decode_utf8()
+            .map_err(|_| Error::CertificateHeader)?;
+        // `load_pem_chain()` panics on empty input
+        if pem.trim().is_empty() {
+            return Err(Error::CertificateHeader);
+        }
+        let signing_chain =
+            Certificate::load_pem_chain(pem.as_bytes()).map_err(|_| Error::CertificateHeader)?;
+        Ok(Self {
+            body: body.into(),
+            signature,
+            signing_chain,
+        })
+    }
+
+    /// The JSON body of the report
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// The signature of the body
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The certificate chain of the key that signed the report, leaf first
+    pub fn signing_chain(&self) -> &[Certificate] {
+        &self.signing_chain
+    }
+}
+
+/// Verifies [`SignedVerificationReport`]s without contacting IAS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportVerifier {
+    trust_anchor: Certificate,
+}
+
+impl ReportVerifier {
+    /// Create a new verifier.
+    ///
+    /// # Arguments
+    /// * `trust_anchor` - The root CA certificate that the signing
+    ///   certificate chain must lead to. For IAS this is the Intel SGX
+    ///   Attestation Report Signing CA, available at
+    ///   <https://certificates.trustedservices.intel.com/Intel_SGX_Attestation_RootCA.pem>.
+    pub fn new(trust_anchor: Certificate) -> Self {
+        Self { trust_anchor }
+    }
+
+    /// The trust anchor of the verifier.
+    pub fn trust_anchor(&self) -> &Certificate {
+        &self.trust_anchor
+    }
+
+    /// Verify `report` and parse its body.
+    ///
+    /// Only the authenticity of the report is verified. The
+    /// [`VerificationReport::quote_status()`], the nonce, and the enclave
+    /// identity in the quote are for the caller to check.
+    ///
+    /// # Arguments
+    /// * `report` - The report to verify.
+    /// * `now` - The time of the verification, as the duration since the
+    ///   UNIX epoch.
+    ///
+    /// # Errors
+    /// An [`Error`] describing the first check that failed.
+    pub fn verify(
+        &self,
+        report: &SignedVerificationReport,
+        now: Duration,
+    ) -> Result<VerificationReport, Error> {
+        let chain = report.signing_chain();
+        self.verify_chain(chain, now)?;
+        let key = verifying_key(&chain[0])?;
+        let signature =
+            Signature::try_from(report.signature()).map_err(|_| Error::ReportSignature)?;
+        key.verify(report.body().as_bytes(), &signature)
+            .map_err(|_| Error::ReportSignature)?;
+
+        VerificationReport::from_json(report.body())
+    }
+
+    /// Verify `chain`, leaf first, leads to the trust anchor.
+    fn verify_chain(&self, chain: &[Certificate], now: Duration) -> Result<(), Error> {
+        let (anchor, certificates) = chain.split_last().ok_or(Error::EmptyCertificateChain)?;
+        if anchor != &self.trust_anchor {
+            return Err(Error::UntrustedCertificateChain);
+        }
+        ensure_certificate_validity(anchor, now)?;
+
+        for (certificate, issuer) in certificates.iter().zip(chain.iter().skip(1)) {
+            if certificate.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+                return Err(Error::UntrustedCertificateChain);
+            }
+            ensure_certificate_authority(issuer)?;
+            verify_certificate(certificate, &verifying_key(issuer)?)?;
+            ensure_certificate_validity(certificate, now)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The RSA public key of `certificate`.
+fn verifying_key(certificate: &Certificate) -> Result<VerifyingKey<Sha256>, Error> {
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()?;
+    let key = RsaPublicKey::from_public_key_der(&spki).map_err(|_| Error::UnsupportedKey)?;
+    Ok(VerifyingKey::new(key))
+}
+
+/// Verify that `certificate` was signed by `key`.
+fn verify_certificate(certificate: &Certificate, key: &VerifyingKey<Sha256>) -> Result<(), Error> {
+    let algorithm = &certificate.signature_algorithm;
+    if algorithm != &certificate.tbs_certificate.signature
+        || algorithm.oid != SHA256_WITH_RSA_ENCRYPTION_OID
+    {
+        return Err(Error::UnsupportedSignatureAlgorithm);
+    }
+    let signature = certificate
+        .signature
+        .as_bytes()
+        .and_then(|bytes| Signature::try_from(bytes).ok())
+        .ok_or(Error::CertificateSignature)?;
+    key.verify(&certificate.tbs_certificate.to_der()?, &signature)
+        .map_err(|_| Error::CertificateSignature)
+}
+
+fn ensure_certificate_validity(certificate: &Certificate, now: Duration) -> Result<(), Error> {
+    let validity = &certificate.tbs_certificate.validity;
+    let not_before = validity.not_before.to_unix_duration();
+    let not_after = validity.not_after.to_unix_duration();
+    if now < not_before || now > not_after {
+        return Err(Error::CertificateValidity);
+    }
+    Ok(())
+}
+
+fn ensure_certificate_authority(certificate: &Certificate) -> Result<(), Error> {
+    let (_, constraints) = certificate
+        .tbs_certificate
+        .get::<BasicConstraints>()?
+        .ok_or(Error::NotCertificateAuthority)?;
+    match constraints.ca {
+        true => Ok(()),
+        false => Err(Error::NotCertificateAuthority),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::QuoteStatus;
+    use mc_sgx_core_types::BaseQuote;
+    use x509_cert::der::DecodePem;
+    use yare::parameterized;
+
+    const OK_REPORT: &str = include_str!("../data/tests/ok_report.json");
+    const OK_SIGNATURE: &str = include_str!("../data/tests/ok_report.sig");
+    const GROUP_OUT_OF_DATE_REPORT: &str =
+        include_str!("../data/tests/group_out_of_date_report.json");
+    const GROUP_OUT_OF_DATE_SIGNATURE: &str =
+        include_str!("../data/tests/group_out_of_date_report.sig");
+    const SIGNING_CHAIN: &str = include_str!("../data/tests/signing_chain.txt");
+    const ROOT_CA: &str = include_str!("../data/tests/root_ca.pem");
+
+    // 2027-01-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_798_761_600);
+
+    fn verifier() -> ReportVerifier {
+        ReportVerifier::new(Certificate::from_pem(ROOT_CA).expect("Invalid root CA"))
+    }
+
+    fn ok_report() -> SignedVerificationReport {
+        SignedVerificationReport::new(OK_REPORT, OK_SIGNATURE, SIGNING_CHAIN)
+            .expect("Failed to create signed report")
+    }
+
+    #[test]
+    fn signed_report_from_headers() {
+        let report = ok_report();
+        assert_eq!(report.body(), OK_REPORT);
+        assert_eq!(report.signature().len(), 256);
+        let chain = report.signing_chain();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[1], *verifier().trust_anchor());
+    }
+
+    #[parameterized(
+        ok = { OK_REPORT, OK_SIGNATURE, QuoteStatus::Ok },
+        group_out_of_date = { GROUP_OUT_OF_DATE_REPORT, GROUP_OUT_OF_DATE_SIGNATURE, QuoteStatus::GroupOutOfDate },
+    )]
+    fn verify_report(body: &str, signature: &str, status: QuoteStatus) {
+        let report = SignedVerificationReport::new(body, signature, SIGNING_CHAIN)
+            .expect("Failed to create signed report");
+        let report = verifier()
+            .verify(&report, NOW)
+            .expect("Failed to verify report");
+        assert_eq!(report.quote_status(), status);
+        assert_eq!(report.quote().report_body().mr_enclave(), [0xAE; 32].into());
+    }
+
+    #[test]
+    fn signature_not_base64() {
+        assert_eq!(
+            SignedVerificationReport::new(OK_REPORT, "not*base64", SIGNING_CHAIN),
+            Err(Error::Base64("X-IASReport-Signature"))
+        );
+    }
+
+    #[parameterized(
+        not_url_encoded = { "%ZZ" },
+        invalid_utf8 = { "%FF%FE" },
+        not_pem = { "certificate" },
+        empty = { "" },
+        decoded_pem_without_end = { "-----BEGIN%20CERTIFICATE-----%0AMIIE" },
+    )]
+    fn invalid_certificate_header(header: &str) {
+        assert_eq!(
+            SignedVerificationReport::new(OK_REPORT, OK_SIGNATURE, header),
+            Err(Error::CertificateHeader)
+        );
+    }
+
+    #[test]
+    fn modified_body_fails() {
+        let body = OK_REPORT.replace(r#""OK""#, r#""KEY_REVOKED""#);
+        let report = SignedVerificationReport::new(body, OK_SIGNATURE, SIGNING_CHAIN)
+            .expect("Failed to create signed report");
+        assert_eq!(verifier().verify(&report, NOW), Err(Error::ReportSignature));
+    }
+
+    #[test]
+    fn signature_of_other_report_fails() {
+        let report =
+            SignedVerificationReport::new(OK_REPORT, GROUP_OUT_OF_DATE_SIGNATURE, SIGNING_CHAIN)
+                .expect("Failed to create signed report");
+        assert_eq!(verifier().verify(&report, NOW), Err(Error::ReportSignature));
+    }
+
+    #[test]
+    fn untrusted_chain_fails() {
+        let report = ok_report();
+        let leaf = report.signing_chain()[0].clone();
+        let verifier = ReportVerifier::new(leaf);
+        assert_eq!(
+            verifier.verify(&report, NOW),
+            Err(Error::UntrustedCertificateChain)
+        );
+    }
+
+    #[test]
+    fn chain_without_leaf_fails() {
+        let mut report = ok_report();
+        report.signing_chain.remove(0);
+        assert_eq!(verifier().verify(&report, NOW), Err(Error::ReportSignature));
+    }
+
+    #[test]
+    fn empty_chain_fails() {
+        let mut report = ok_report();
+        report.signing_chain.clear();
+        assert_eq!(
+            verifier().verify(&report, NOW),
+            Err(Error::EmptyCertificateChain)
+        );
+    }
+
+    #[test]
+    fn leaf_as_issuer_fails() {
+        let mut report = ok_report();
+        let leaf = report.signing_chain[0].clone();
+        report.signing_chain.insert(0, leaf);
+        assert_eq!(
+            verifier().verify(&report, NOW),
+            Err(Error::UntrustedCertificateChain)
+        );
+    }
+
+    #[test]
+    fn modified_leaf_fails() {
+        let mut report = ok_report();
+        report.signing_chain[0].tbs_certificate.serial_number = report.signing_chain[1]
+            .tbs_certificate
+            .serial_number
+            .clone();
+        assert_eq!(
+            verifier().verify(&report, NOW),
+            Err(Error::CertificateSignature)
+        );
+    }
+
+    #[parameterized(
+        // 2023-12-31T23:59:59Z
+        before_validity = { Duration::from_secs(1_704_067_199) },
+        // 2034-01-01T00:00:01Z
+        leaf_expired = { Duration::from_secs(2_019_686_401) },
+    )]
+    fn verify_outside_validity_fails(now: Duration) {
+        assert_eq!(
+            verifier().verify(&ok_report(), now),
+            Err(Error::CertificateValidity)
+        );
+    }
+}
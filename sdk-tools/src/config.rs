@@ -0,0 +1,850 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Typed enclave configuration file (Enclave.config.xml).
+
+use displaydoc::Display;
+use std::{collections::BTreeSet, fmt::Write, fs, io::Error as IoError, path::Path};
+
+/// The size of an enclave page, sizes in the configuration must be a multiple
+/// of it.
+pub const PAGE_SIZE: u64 = 0x1000;
+
+// The `sgx_sign` defaults for the elements which other elements are checked
+// against.
+const DEFAULT_STACK_MAX_SIZE: u64 = 0x40000;
+const DEFAULT_STACK_MIN_SIZE: u64 = 0x2000;
+const DEFAULT_HEAP_MAX_SIZE: u64 = 0x100000;
+const DEFAULT_HEAP_MIN_SIZE: u64 = 0x1000;
+const DEFAULT_TCS_NUM: u32 = 1;
+const DEFAULT_TCS_MIN_POOL: u32 = 1;
+
+const ROOT: &str = "EnclaveConfiguration";
+
+/// Errors which can occur when reading, validating or writing an
+/// [`EnclaveConfig`].
+#[derive(Debug, Display)]
+#[non_exhaustive]
+pub enum Error {
+    /// There was an error writing the configuration file: {0}
+    Io(IoError),
+    /// The configuration is not well formed XML: {0}
+    Xml(String),
+    /// Unknown element `{0}`
+    UnknownElement(String),
+    /// The element `{0}` is given more than once
+    DuplicateElement(String),
+    /// The value `{value}` of `{element}` is invalid
+    #[allow(missing_docs)]
+    InvalidValue { element: String, value: String },
+    /// `{0}`, {1:#x}, is not a multiple of the page size, 0x1000
+    NotPageAligned(&'static str, u64),
+    /// `{smaller}`, {smaller_value:#x}, is greater than `{larger}`, {larger_value:#x}
+    #[allow(missing_docs)]
+    OutOfOrder {
+        smaller: &'static str,
+        smaller_value: u64,
+        larger: &'static str,
+        larger_value: u64,
+    },
+    /// `TCSNum` must be at least 1
+    NoTcs,
+    /// `ISVEXTPRODID` and `ISVFAMILYID` need `EnableKSS`
+    KssNotEnabled,
+}
+
+impl From<IoError> for Error {
+    fn from(src: IoError) -> Error {
+        Error::Io(src)
+    }
+}
+
+/// The binding of thread control structures (TCS) to untrusted threads.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TcsPolicy {
+    /// A TCS is bound to the untrusted thread that first uses it
+    Bound,
+    /// A TCS is released after each ECALL, `sgx_sign`'s default
+    Unbound,
+}
+
+/// The enclave configuration file, `Enclave.config.xml`, used by
+/// [`SgxSign`](crate::SgxSign).
+///
+/// Elements which aren't set are left out of the file, and get the
+/// `sgx_sign` defaults. See the Enclave Configuration File section of the
+/// [Intel SGX Developer Reference](https://download.01.org/intel-sgx/sgx-linux/2.18/docs/Intel_SGX_Developer_Reference_Linux_2.18_Open_Source.pdf)
+/// for the meaning of each element.
+///
+/// ```
+/// use mc_sgx_sdk_tools::EnclaveConfig;
+///
+/// let xml = EnclaveConfig::new()
+///     .isv_svn(2)
+///     .heap_max_size(0x200000)
+///     .tcs_num(8)
+///     .disable_debug(true)
+///     .to_xml()
+///     .expect("Invalid enclave configuration");
+///
+/// assert!(xml.contains("<HeapMaxSize>0x200000</HeapMaxSize>"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EnclaveConfig {
+    prod_id: Option<u16>,
+    isv_svn: Option<u16>,
+    stack_max_size: Option<u64>,
+    stack_min_size: Option<u64>,
+    heap_max_size: Option<u64>,
+    heap_init_size: Option<u64>,
+    heap_min_size: Option<u64>,
+    tcs_num: Option<u32>,
+    tcs_max_num: Option<u32>,
+    tcs_min_pool: Option<u32>,
+    tcs_policy: Option<TcsPolicy>,
+    reserved_mem_max_size: Option<u64>,
+    reserved_mem_init_size: Option<u64>,
+    reserved_mem_min_size: Option<u64>,
+    reserved_mem_executable: Option<bool>,
+    user_region_size: Option<u64>,
+    disable_debug: Option<bool>,
+    misc_select: Option<u32>,
+    misc_mask: Option<u32>,
+    enable_kss: Option<bool>,
+    isv_ext_prod_id_high: Option<u64>,
+    isv_ext_prod_id_low: Option<u64>,
+    isv_family_id_high: Option<u64>,
+    isv_family_id_low: Option<u64>,
+}
+
+impl EnclaveConfig {
+    /// Create a configuration with every element left to the `sgx_sign`
+    /// default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The product ID of the enclave, `ProdID`.
+    #[must_use]
+    pub fn prod_id(mut self, prod_id: u16) -> Self {
+        self.prod_id = Some(prod_id);
+        self
+    }
+
+    /// The security version number of the enclave, `ISVSVN`.
+    #[must_use]
+    pub fn isv_svn(mut self, isv_svn: u16) -> Self {
+        self.isv_svn = Some(isv_svn);
+        self
+    }
+
+    /// The maximum stack size of each thread, `StackMaxSize`.
+    #[must_use]
+    pub fn stack_max_size(mut self, size: u64) -> Self {
+        self.stack_max_size = Some(size);
+        self
+    }
+
+    /// The stack size committed to each thread when it's created,
+    /// `StackMinSize`.
+    #[must_use]
+    pub fn stack_min_size(mut self, size: u64) -> Self {
+        self.stack_min_size = Some(size);
+        self
+    }
+
+    /// The maximum heap size, `HeapMaxSize`.
+    #[must_use]
+    pub fn heap_max_size(mut self, size: u64) -> Self {
+        self.heap_max_size = Some(size);
+        self
+    }
+
+    /// The heap size committed when the enclave is loaded, `HeapInitSize`.
+    #[must_use]
+    pub fn heap_init_size(mut self, size: u64) -> Self {
+        self.heap_init_size = Some(size);
+        self
+    }
+
+    /// The heap size which is always committed, `HeapMinSize`.
+    #[must_use]
+    pub fn heap_min_size(mut self, size: u64) -> Self {
+        self.heap_min_size = Some(size);
+        self
+    }
+
+    /// The number of TCS created when the enclave is loaded, `TCSNum`.
+    #[must_use]
+    pub fn tcs_num(mut self, count: u32) -> Self {
+        self.tcs_num = Some(count);
+        self
+    }
+
+    /// The maximum number of TCS, `TCSMaxNum`.
+    #[must_use]
+    pub fn tcs_max_num(mut self, count: u32) -> Self {
+        self.tcs_max_num = Some(count);
+        self
+    }
+
+    /// The number of TCS which are always available, `TCSMinPool`.
+    #[must_use]
+    pub fn tcs_min_pool(mut self, count: u32) -> Self {
+        self.tcs_min_pool = Some(count);
+        self
+    }
+
+    /// The binding of TCS to untrusted threads, `TCSPolicy`.
+    #[must_use]
+    pub fn tcs_policy(mut self, policy: TcsPolicy) -> Self {
+        self.tcs_policy = Some(policy);
+        self
+    }
+
+    /// The maximum size of the reserved memory, `ReservedMemMaxSize`.
+    #[must_use]
+    pub fn reserved_mem_max_size(mut self, size: u64) -> Self {
+        self.reserved_mem_max_size = Some(size);
+        self
+    }
+
+    /// The reserved memory committed when the enclave is loaded,
+    /// `ReservedMemInitSize`.
+    #[must_use]
+    pub fn reserved_mem_init_size(mut self, size: u64) -> Self {
+        self.reserved_mem_init_size = Some(size);
+        self
+    }
+
+    /// The reserved memory which is always committed, `ReservedMemMinSize`.
+    #[must_use]
+    pub fn reserved_mem_min_size(mut self, size: u64) -> Self {
+        self.reserved_mem_min_size = Some(size);
+        self
+    }
+
+    /// Whether the reserved memory is executable, `ReservedMemExecutable`.
+    #[must_use]
+    pub fn reserved_mem_executable(mut self, executable: bool) -> Self {
+        self.reserved_mem_executable = Some(executable);
+        self
+    }
+
+    /// The size of the user region which can be allocated at runtime,
+    /// `UserRegionSize`.
+    #[must_use]
+    pub fn user_region_size(mut self, size: u64) -> Self {
+        self.user_region_size = Some(size);
+        self
+    }
+
+    /// Whether debugging the enclave is disallowed, `DisableDebug`.
+    #[must_use]
+    pub fn disable_debug(mut self, disable: bool) -> Self {
+        self.disable_debug = Some(disable);
+        self
+    }
+
+    /// The desired extended SSA frame features, `MiscSelect`.
+    #[must_use]
+    pub fn misc_select(mut self, misc_select: u32) -> Self {
+        self.misc_select = Some(misc_select);
+        self
+    }
+
+    /// The bits of `MiscSelect` to enforce, `MiscMask`.
+    #[must_use]
+    pub fn misc_mask(mut self, misc_mask: u32) -> Self {
+        self.misc_mask = Some(misc_mask);
+        self
+    }
+
+    /// Whether the key separation and sharing (KSS) fields are used,
+    /// `EnableKSS`.
+    #[must_use]
+    pub fn enable_kss(mut self, enable: bool) -> Self {
+        self.enable_kss = Some(enable);
+        self
+    }
+
+    /// The extended product ID, `ISVEXTPRODID_H` and `ISVEXTPRODID_L`. Needs
+    /// [`EnclaveConfig::enable_kss()`].
+    ///
+    /// # Arguments
+    /// * `high` - The high 64 bits of the extended product ID.
+    /// * `low` - The low 64 bits of the extended product ID.
+    #[must_use]
+    pub fn isv_ext_prod_id(mut self, high: u64, low: u64) -> Self {
+        self.isv_ext_prod_id_high = Some(high);
+        self.isv_ext_prod_id_low = Some(low);
+        self
+    }
+
+    /// The product family ID, `ISVFAMILYID_H` and `ISVFAMILYID_L`. Needs
+    /// [`EnclaveConfig::enable_kss()`].
+    ///
+    /// # Arguments
+    /// * `high` - The high 64 bits of the family ID.
+    /// * `low` - The low 64 bits of the family ID.
+    #[must_use]
+    pub fn isv_family_id(mut self, high: u64, low: u64) -> Self {
+        self.isv_family_id_high = Some(high);
+        self.isv_family_id_low = Some(low);
+        self
+    }
+
+    /// Check the constraints `sgx_sign` places on the configuration.
+    ///
+    /// Elements which aren't set are checked with their `sgx_sign` default.
+    ///
+    /// # Errors
+    /// * [`Error::NotPageAligned`] if a size isn't a multiple of [`PAGE_SIZE`].
+    /// * [`Error::OutOfOrder`] if a minimum is greater than an initial size,
+    ///   or either is greater than a maximum.
+    /// * [`Error::NoTcs`] if `TCSNum` is 0.
+    /// * [`Error::KssNotEnabled`] if the KSS IDs are set without `EnableKSS`.
+    pub fn validate(&self) -> Result<(), Error> {
+        let sizes = [
+            ("StackMaxSize", self.stack_max_size),
+            ("StackMinSize", self.stack_min_size),
+            ("HeapMaxSize", self.heap_max_size),
+            ("HeapInitSize", self.heap_init_size),
+            ("HeapMinSize", self.heap_min_size),
+            ("ReservedMemMaxSize", self.reserved_mem_max_size),
+            ("ReservedMemInitSize", self.reserved_mem_init_size),
+            ("ReservedMemMinSize", self.reserved_mem_min_size),
+            ("UserRegionSize", self.user_region_size),
+        ];
+        for (element, size) in sizes {
+            match size {
+                Some(size) if size % PAGE_SIZE != 0 => {
+                    return Err(Error::NotPageAligned(element, size))
+                }
+                _ => {}
+            }
+        }
+
+        let stack_max = self.stack_max_size.unwrap_or(DEFAULT_STACK_MAX_SIZE);
+        let stack_min = self.stack_min_size.unwrap_or(DEFAULT_STACK_MIN_SIZE);
+        ensure_order(("StackMinSize", stack_min), ("StackMaxSize", stack_max))?;
+
+        let heap_max = self.heap_max_size.unwrap_or(DEFAULT_HEAP_MAX_SIZE);
+        let heap_init = self.heap_init_size.unwrap_or(heap_max);
+        let heap_min = self.heap_min_size.unwrap_or(DEFAULT_HEAP_MIN_SIZE);
+        ensure_order(("HeapInitSize", heap_init), ("HeapMaxSize", heap_max))?;
+        ensure_order(("HeapMinSize", heap_min), ("HeapInitSize", heap_init))?;
+
+        let reserved_max = self.reserved_mem_max_size.unwrap_or(0);
+        let reserved_init = self.reserved_mem_init_size.unwrap_or(reserved_max);
+        let reserved_min = self.reserved_mem_min_size.unwrap_or(0);
+        ensure_order(
+            ("ReservedMemInitSize", reserved_init),
+            ("ReservedMemMaxSize", reserved_max),
+        )?;
+        ensure_order(
+            ("ReservedMemMinSize", reserved_min),
+            ("ReservedMemInitSize", reserved_init),
+        )?;
+
+        let tcs_num = self.tcs_num.unwrap_or(DEFAULT_TCS_NUM);
+        if tcs_num == 0 {
+            return Err(Error::NoTcs);
+        }
+        let tcs_max = self.tcs_max_num.unwrap_or(tcs_num);
+        let tcs_min_pool = self.tcs_min_pool.unwrap_or(DEFAULT_TCS_MIN_POOL);
+        ensure_order(("TCSNum", tcs_num.into()), ("TCSMaxNum", tcs_max.into()))?;
+        ensure_order(
+            ("TCSMinPool", tcs_min_pool.into()),
+            ("TCSNum", tcs_num.into()),
+        )?;
+
+        let kss_ids = [
+            self.isv_ext_prod_id_high,
+            self.isv_ext_prod_id_low,
+            self.isv_family_id_high,
+            self.isv_family_id_low,
+        ];
+        if self.enable_kss != Some(true) && kss_ids.iter().any(Option::is_some) {
+            return Err(Error::KssNotEnabled);
+        }
+
+        Ok(())
+    }
+
+    /// The configuration as an `Enclave.config.xml` document.
+    ///
+    /// # Errors
+    /// Any of the errors from [`EnclaveConfig::validate()`].
+    pub fn to_xml(&self) -> Result<String, Error> {
+        self.validate()?;
+
+        let mut xml = format!("<{ROOT}>\n");
+        let mut element = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                writeln!(xml, "  <{name}>{value}</{name}>").expect("Writing to a String");
+            }
+        };
+        let decimal = |value: Option<u32>| value.map(|value| value.to_string());
+        let hex = |value: Option<u64>| value.map(|value| format!("{value:#x}"));
+        let flag = |value: Option<bool>| value.map(|value| u8::from(value).to_string());
+
+        element("ProdID", decimal(self.prod_id.map(Into::into)));
+        element("ISVSVN", decimal(self.isv_svn.map(Into::into)));
+        element("StackMaxSize", hex(self.stack_max_size));
+        element("StackMinSize", hex(self.stack_min_size));
+        element("HeapMaxSize", hex(self.heap_max_size));
+        element("HeapInitSize", hex(self.heap_init_size));
+        element("HeapMinSize", hex(self.heap_min_size));
+        element("TCSNum", decimal(self.tcs_num));
+        element("TCSMaxNum", decimal(self.tcs_max_num));
+        element("TCSMinPool", decimal(self.tcs_min_pool));
+        element(
+            "TCSPolicy",
+            flag(self.tcs_policy.map(|policy| policy == TcsPolicy::Unbound)),
+        );
+        element("ReservedMemMaxSize", hex(self.reserved_mem_max_size));
+        element("ReservedMemInitSize", hex(self.reserved_mem_init_size));
+        element("ReservedMemMinSize", hex(self.reserved_mem_min_size));
+        element("ReservedMemExecutable", flag(self.reserved_mem_executable));
+        element("UserRegionSize", hex(self.user_region_size));
+        element("DisableDebug", flag(self.disable_debug));
+        element("MiscSelect", hex(self.misc_select.map(Into::into)));
+        element("MiscMask", hex(self.misc_mask.map(Into::into)));
+        element("EnableKSS", flag(self.enable_kss));
+        element("ISVEXTPRODID_H", hex(self.isv_ext_prod_id_high));
+        element("ISVEXTPRODID_L", hex(self.isv_ext_prod_id_low));
+        element("ISVFAMILYID_H", hex(self.isv_family_id_high));
+        element("ISVFAMILYID_L", hex(self.isv_family_id_low));
+
+        writeln!(xml, "</{ROOT}>").expect("Writing to a String");
+        Ok(xml)
+    }
+
+    /// Write the configuration to `path`, for passing to
+    /// [`SgxSign`](crate::SgxSign) as the `config_file`.
+    ///
+    /// # Errors
+    /// Any of the errors from [`EnclaveConfig::validate()`], or
+    /// [`Error::Io`] if the file can't be written.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, self.to_xml()?)?;
+        Ok(())
+    }
+
+    /// Parse and validate an `Enclave.config.xml` document.
+    ///
+    /// Numbers may be decimal or hexadecimal with a `0x` prefix, as accepted
+    /// by `sgx_sign`.
+    ///
+    /// # Errors
+    /// * [`Error::Xml`] if the document isn't well formed.
+    /// * [`Error::UnknownElement`] if an element isn't a configuration
+    ///   element.
+    /// * [`Error::DuplicateElement`] if an element is given more than once.
+    /// * [`Error::InvalidValue`] if the value of an element can't be parsed.
+    /// * Any of the errors from [`EnclaveConfig::validate()`].
+    pub fn from_xml(xml: &str) -> Result<Self, Error> {
+        let mut parser = Parser { rest: xml };
+        let mut config = Self::default();
+        let mut seen = BTreeSet::new();
+
+        parser.skip_misc();
+        if parser.open_tag()? != ROOT {
+            return Err(Error::Xml(format!("the root element must be `{ROOT}`")));
+        }
+        loop {
+            parser.skip_misc();
+            if parser.rest.starts_with("</") {
+                break;
+            }
+            let name = parser.open_tag()?;
+            let value = parser.text();
+            parser.close_tag(name)?;
+            if !seen.insert(name) {
+                return Err(Error::DuplicateElement(name.to_string()));
+            }
+            config.set(name, value.trim())?;
+        }
+        parser.close_tag(ROOT)?;
+        parser.skip_misc();
+        if !parser.rest.is_empty() {
+            return Err(Error::Xml(format!("content after `</{ROOT}>`")));
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, element: &str, value: &str) -> Result<(), Error> {
+        match element {
+            "ProdID" => self.prod_id = Some(parse_number(element, value)?),
+            "ISVSVN" => self.isv_svn = Some(parse_number(element, value)?),
+            "StackMaxSize" => self.stack_max_size = Some(parse_number(element, value)?),
+            "StackMinSize" => self.stack_min_size = Some(parse_number(element, value)?),
+            "HeapMaxSize" => self.heap_max_size = Some(parse_number(element, value)?),
+            "HeapInitSize" => self.heap_init_size = Some(parse_number(element, value)?),
+            "HeapMinSize" => self.heap_min_size = Some(parse_number(element, value)?),
+            "TCSNum" => self.tcs_num = Some(parse_number(element, value)?),
+            "TCSMaxNum" => self.tcs_max_num = Some(parse_number(element, value)?),
+            "TCSMinPool" => self.tcs_min_pool = Some(parse_number(element, value)?),
+            "TCSPolicy" => {
+                self.tcs_policy = Some(if parse_flag(element, value)? {
+                    TcsPolicy::Unbound
+                } else {
+                    TcsPolicy::Bound
+                })
+            }
+            "ReservedMemMaxSize" => {
+                self.reserved_mem_max_size = Some(parse_number(element, value)?)
+            }
+            "ReservedMemInitSize" => {
+                self.reserved_mem_init_size = Some(parse_number(element, value)?)
+            }
+            "ReservedMemMinSize" => {
+                self.reserved_mem_min_size = Some(parse_number(element, value)?)
+            }
+            "ReservedMemExecutable" => {
+                self.reserved_mem_executable = Some(parse_flag(element, value)?)
+            }
+            "UserRegionSize" => self.user_region_size = Some(parse_number(element, value)?),
+            "DisableDebug" => self.disable_debug = Some(parse_flag(element, value)?),
+            "MiscSelect" => self.misc_select = Some(parse_number(element, value)?),
+            "MiscMask" => self.misc_mask = Some(parse_number(element, value)?),
+            "EnableKSS" => self.enable_kss = Some(parse_flag(element, value)?),
+            "ISVEXTPRODID_H" => self.isv_ext_prod_id_high = Some(parse_number(element, value)?),
+            "ISVEXTPRODID_L" => self.isv_ext_prod_id_low = Some(parse_number(element, value)?),
+            "ISVFAMILYID_H" => self.isv_family_id_high = Some(parse_number(element, value)?),
+            "ISVFAMILYID_L" => self.isv_family_id_low = Some(parse_number(element, value)?),
+            _ => return Err(Error::UnknownElement(element.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Parse a decimal, or `0x` prefixed hexadecimal, number which fits in `T`.
+fn parse_number<T: TryFrom<u64>>(element: &str, value: &str) -> Result<T, Error> {
+    let number = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    };
+    number
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| Error::InvalidValue {
+            element: element.to_string(),
+            value: value.to_string(),
+        })
+}
+
+/// Parse a flag, `0` or `1`.
+fn parse_flag(element: &str, value: &str) -> Result<bool, Error> {
+    match parse_number::<u8>(element, value)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(Error::InvalidValue {
+            element: element.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Ensure the `smaller` element is no greater than the `larger` one.
+fn ensure_order(smaller: (&'static str, u64), larger: (&'static str, u64)) -> Result<(), Error> {
+    if smaller.1 > larger.1 {
+        return Err(Error::OutOfOrder {
+            smaller: smaller.0,
+            smaller_value: smaller.1,
+            larger: larger.0,
+            larger_value: larger.1,
+        });
+    }
+    Ok(())
+}
+
+/// A parser for the flat XML of the enclave configuration, elements with text
+/// content inside the root element.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    /// Skip whitespace, comments and the XML declaration.
+    fn skip_misc(&mut self) {
+        loop {
+            self.rest = self.rest.trim_start();
+            let end = if self.rest.starts_with("<!--") {
+                self.rest.find("-->").map(|end| end + 3)
+            } else if self.rest.starts_with("<?") {
+                self.rest.find("?>").map(|end| end + 2)
+            } else {
+                return;
+            };
+            match end {
+                Some(end) => self.rest = &self.rest[end..],
+                // Leave the unterminated markup for the tag parsing to reject.
+                None => return,
+            }
+        }
+    }
+
+    fn open_tag(&mut self) -> Result<&'a str, Error> {
+        let name = self.tag("<")?;
+        if name.starts_with('/') {
+            return Err(Error::Xml(format!("unexpected `<{name}>`")));
+        }
+        Ok(name)
+    }
+
+    fn close_tag(&mut self, name: &str) -> Result<(), Error> {
+        let found = self.tag("</")?;
+        if found != name {
+            return Err(Error::Xml(format!(
+                "expected `</{name}>`, found `</{found}>`"
+            )));
+        }
+        Ok(())
+    }
+
+    fn tag(&mut self, start: &str) -> Result<&'a str, Error> {
+        let rest = self
+            .rest
+            .strip_prefix(start)
+            .ok_or_else(|| Error::Xml(format!("expected `{start}`")))?;
+        let end = rest
+            .find('>')
+            .ok_or_else(|| Error::Xml("unterminated tag".to_string()))?;
+        let name = rest[..end].trim();
+        let bare = name.strip_prefix('/').unwrap_or(name);
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        if bare.is_empty() || !bare.chars().all(valid) {
+            return Err(Error::Xml(format!("unsupported tag `{start}{name}>`")));
+        }
+        self.rest = &rest[end + 1..];
+        Ok(name)
+    }
+
+    /// The text up to the next tag.
+    fn text(&mut self) -> &'a str {
+        let end = self.rest.find('<').unwrap_or(self.rest.len());
+        let (text, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use yare::parameterized;
+
+    /// The configuration from the SGX SDK sample enclave
+    static SAMPLE_CONFIG: &str = r#"<!-- Please refer to User's Guide for the explanation of each field -->
+<EnclaveConfiguration>
+  <ProdID>0</ProdID>
+  <ISVSVN>0</ISVSVN>
+  <StackMaxSize>0x40000</StackMaxSize>
+  <HeapMaxSize>0x100000</HeapMaxSize>
+  <TCSNum>10</TCSNum>
+  <TCSPolicy>1</TCSPolicy>
+  <DisableDebug>0</DisableDebug>
+  <MiscSelect>0</MiscSelect>
+  <MiscMask>0xFFFFFFFF</MiscMask>
+</EnclaveConfiguration>
+"#;
+
+    fn full_config() -> EnclaveConfig {
+        EnclaveConfig::new()
+            .prod_id(1)
+            .isv_svn(2)
+            .stack_max_size(0x40000)
+            .stack_min_size(0x2000)
+            .heap_max_size(0x200000)
+            .heap_init_size(0x100000)
+            .heap_min_size(0x1000)
+            .tcs_num(8)
+            .tcs_max_num(16)
+            .tcs_min_pool(2)
+            .tcs_policy(TcsPolicy::Bound)
+            .reserved_mem_max_size(0x100000)
+            .reserved_mem_init_size(0x10000)
+            .reserved_mem_min_size(0x1000)
+            .reserved_mem_executable(true)
+            .user_region_size(0x10000)
+            .disable_debug(true)
+            .misc_select(0)
+            .misc_mask(0xFFFFFFFF)
+            .enable_kss(true)
+            .isv_ext_prod_id(0x1122, 0x3344)
+            .isv_family_id(0x5566, 0x7788)
+    }
+
+    #[test]
+    fn parse_sample_config() {
+        let config = EnclaveConfig::from_xml(SAMPLE_CONFIG).expect("Failed to parse config");
+
+        let expected = EnclaveConfig::new()
+            .prod_id(0)
+            .isv_svn(0)
+            .stack_max_size(0x40000)
+            .heap_max_size(0x100000)
+            .tcs_num(10)
+            .tcs_policy(TcsPolicy::Unbound)
+            .disable_debug(false)
+            .misc_select(0)
+            .misc_mask(0xFFFFFFFF);
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn unset_elements_are_left_out() {
+        let xml = EnclaveConfig::new()
+            .heap_max_size(0x200000)
+            .to_xml()
+            .expect("Invalid config");
+
+        assert_eq!(
+            xml,
+            "<EnclaveConfiguration>\n  <HeapMaxSize>0x200000</HeapMaxSize>\n</EnclaveConfiguration>\n"
+        );
+    }
+
+    #[test]
+    fn full_config_round_trips() {
+        let config = full_config();
+        let xml = config.to_xml().expect("Invalid config");
+
+        assert!(xml.contains("<TCSPolicy>0</TCSPolicy>"));
+        assert!(xml.contains("<ISVEXTPRODID_H>0x1122</ISVEXTPRODID_H>"));
+        assert_eq!(
+            EnclaveConfig::from_xml(&xml).expect("Failed to parse config"),
+            config
+        );
+    }
+
+    #[test]
+    fn write_config_file() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("Enclave.config.xml");
+
+        full_config().write(&path).expect("Failed to write config");
+
+        let xml = fs::read_to_string(&path).expect("Failed to read config");
+        assert_eq!(
+            EnclaveConfig::from_xml(&xml).expect("Failed to parse config"),
+            full_config()
+        );
+    }
+
+    #[test]
+    fn invalid_config_is_not_written() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("Enclave.config.xml");
+
+        let result = EnclaveConfig::new().heap_max_size(0x1234).write(&path);
+
+        assert_matches!(result, Err(Error::NotPageAligned("HeapMaxSize", 0x1234)));
+        assert!(!path.exists());
+    }
+
+    #[parameterized(
+        stack_max = {EnclaveConfig::new().stack_max_size(0x40001), "StackMaxSize"},
+        heap_init = {EnclaveConfig::new().heap_init_size(0x800), "HeapInitSize"},
+        reserved_min = {EnclaveConfig::new().reserved_mem_min_size(0x10), "ReservedMemMinSize"},
+        user_region = {EnclaveConfig::new().user_region_size(0x1001), "UserRegionSize"},
+    )]
+    fn size_not_page_aligned(config: EnclaveConfig, element: &str) {
+        assert_matches!(config.validate(), Err(Error::NotPageAligned(name, _)) if name == element);
+    }
+
+    #[parameterized(
+        stack_min_over_max = {EnclaveConfig::new().stack_min_size(0x80000), "StackMinSize", "StackMaxSize"},
+        stack_max_under_default_min = {EnclaveConfig::new().stack_max_size(0x1000), "StackMinSize", "StackMaxSize"},
+        heap_init_over_max = {EnclaveConfig::new().heap_max_size(0x10000).heap_init_size(0x20000), "HeapInitSize", "HeapMaxSize"},
+        heap_init_over_default_max = {EnclaveConfig::new().heap_init_size(0x200000), "HeapInitSize", "HeapMaxSize"},
+        heap_min_over_init = {EnclaveConfig::new().heap_init_size(0x2000).heap_min_size(0x3000), "HeapMinSize", "HeapInitSize"},
+        reserved_init_over_max = {EnclaveConfig::new().reserved_mem_max_size(0x1000).reserved_mem_init_size(0x2000), "ReservedMemInitSize", "ReservedMemMaxSize"},
+        reserved_min_over_init = {EnclaveConfig::new().reserved_mem_max_size(0x4000).reserved_mem_init_size(0x2000).reserved_mem_min_size(0x3000), "ReservedMemMinSize", "ReservedMemInitSize"},
+        tcs_num_over_max = {EnclaveConfig::new().tcs_num(4).tcs_max_num(2), "TCSNum", "TCSMaxNum"},
+        tcs_min_pool_over_num = {EnclaveConfig::new().tcs_num(2).tcs_min_pool(3), "TCSMinPool", "TCSNum"},
+    )]
+    fn out_of_order(config: EnclaveConfig, smaller: &str, larger: &str) {
+        assert_matches!(
+            config.validate(),
+            Err(Error::OutOfOrder { smaller: s, larger: l, .. }) if s == smaller && l == larger
+        );
+    }
+
+    #[test]
+    fn heap_init_defaults_to_max() {
+        let config = EnclaveConfig::new()
+            .heap_max_size(0x400000)
+            .heap_min_size(0x200000);
+
+        assert_matches!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn no_tcs() {
+        assert_matches!(
+            EnclaveConfig::new().tcs_num(0).validate(),
+            Err(Error::NoTcs)
+        );
+    }
+
+    #[test]
+    fn kss_ids_need_kss_enabled() {
+        let config = EnclaveConfig::new().isv_family_id(0, 1);
+
+        assert_matches!(config.validate(), Err(Error::KssNotEnabled));
+        assert_matches!(config.enable_kss(true).validate(), Ok(()));
+    }
+
+    #[parameterized(
+        not_a_number = {"<EnclaveConfiguration><TCSNum>ten</TCSNum></EnclaveConfiguration>"},
+        bad_hex = {"<EnclaveConfiguration><HeapMaxSize>0xZZ</HeapMaxSize></EnclaveConfiguration>"},
+        too_large = {"<EnclaveConfiguration><ISVSVN>0x10000</ISVSVN></EnclaveConfiguration>"},
+        not_a_flag = {"<EnclaveConfiguration><DisableDebug>2</DisableDebug></EnclaveConfiguration>"},
+    )]
+    fn invalid_value(xml: &str) {
+        assert_matches!(
+            EnclaveConfig::from_xml(xml),
+            Err(Error::InvalidValue { .. })
+        );
+    }
+
+    #[test]
+    fn unknown_element() {
+        let xml = "<EnclaveConfiguration><HeapMaxSise>0x1000</HeapMaxSise></EnclaveConfiguration>";
+
+        assert_matches!(
+            EnclaveConfig::from_xml(xml),
+            Err(Error::UnknownElement(name)) if name == "HeapMaxSise"
+        );
+    }
+
+    #[test]
+    fn duplicate_element() {
+        let xml =
+            "<EnclaveConfiguration><TCSNum>1</TCSNum><TCSNum>2</TCSNum></EnclaveConfiguration>";
+
+        assert_matches!(
+            EnclaveConfig::from_xml(xml),
+            Err(Error::DuplicateElement(name)) if name == "TCSNum"
+        );
+    }
+
+    #[parameterized(
+        wrong_root = {"<Config><TCSNum>1</TCSNum></Config>"},
+        mismatched_close = {"<EnclaveConfiguration><TCSNum>1</TCSMaxNum></EnclaveConfiguration>"},
+        unclosed_root = {"<EnclaveConfiguration><TCSNum>1</TCSNum>"},
+        nested = {"<EnclaveConfiguration><TCSNum><A>1</A></TCSNum></EnclaveConfiguration>"},
+        trailing = {"<EnclaveConfiguration></EnclaveConfiguration><TCSNum>1</TCSNum>"},
+        attributes = {"<EnclaveConfiguration version=\"1\"></EnclaveConfiguration>"},
+    )]
+    fn malformed_xml(xml: &str) {
+        assert_matches!(EnclaveConfig::from_xml(xml), Err(Error::Xml(_)));
+    }
+}
@@ -2,8 +2,10 @@
 
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs, missing_debug_implementations, unsafe_code)]
+mod config;
 mod edger8r;
 mod sign;
 
+pub use crate::config::{EnclaveConfig, Error as ConfigError, TcsPolicy, PAGE_SIZE};
 pub use crate::edger8r::Edger8r;
 pub use crate::sign::SgxSign;
@@ -6,9 +6,13 @@
 
 extern crate alloc;
 
+#[cfg(feature = "dcap")]
+mod quote;
 mod report;
 mod seal;
 
+#[cfg(feature = "dcap")]
+pub use crate::quote::{QeReportError, VerifyQeReport};
 pub use crate::{
     report::Report,
     seal::{SealedBuilder, Unseal},
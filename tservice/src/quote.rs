@@ -0,0 +1,63 @@
+// Copyright (c) 2023-2024 The MobileCoin Foundation
+//! Verification of the quoting enclave (QE) report returned with a quote
+
+use crate::Report;
+use mc_sgx_core_types::QuoteNonce;
+use mc_sgx_dcap_types::{Quote3, Quote3Error, ReportInfo};
+
+/// Errors verifying the QE report of a quote
+#[derive(Clone, Debug, displaydoc::Display, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum QeReportError {
+    /// Error verifying the QE report {0}
+    Sgx(mc_sgx_core_types::Error),
+    /// The QE report does not match the quote {0}
+    Quote3(Quote3Error),
+}
+
+impl From<mc_sgx_core_types::Error> for QeReportError {
+    fn from(src: mc_sgx_core_types::Error) -> Self {
+        QeReportError::Sgx(src)
+    }
+}
+
+impl From<Quote3Error> for QeReportError {
+    fn from(src: Quote3Error) -> Self {
+        QeReportError::Quote3(src)
+    }
+}
+
+/// Verification of the QE report returned with a quote generated with a
+/// nonce
+pub trait VerifyQeReport {
+    /// Verify the QE report of `report_info` binds this quote to `nonce`.
+    ///
+    /// This must be called in the enclave the QE report targets, which is the
+    /// enclave that provided `nonce` to the quote generation.
+    ///
+    /// # Arguments
+    /// * `nonce` - The nonce this enclave provided to the quote generation
+    /// * `report_info` - The report info returned with the quote
+    ///
+    /// # Errors
+    /// * [`QeReportError::Sgx`] if the QE report fails `sgx_verify_report()`.
+    /// * [`QeReportError::Quote3`] if the QE report is not from the quoting
+    ///   enclave of this quote, or is not for `nonce` and this quote.
+    fn verify_qe_report(
+        &self,
+        nonce: &QuoteNonce,
+        report_info: &ReportInfo,
+    ) -> Result<(), QeReportError>;
+}
+
+impl<T: AsRef<[u8]>> VerifyQeReport for Quote3<T> {
+    fn verify_qe_report(
+        &self,
+        nonce: &QuoteNonce,
+        report_info: &ReportInfo,
+    ) -> Result<(), QeReportError> {
+        report_info.report().verify()?;
+        self.verify_report_info(nonce, report_info)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Running blocking SGX library calls from async code.
+
+use crate::{Classify, ErrorClass};
+use core::time::Duration;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Errors running a call on a [`BlockingPool`]
+#[derive(Clone, Debug, displaydoc::Display, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum BlockingError {
+    /// The call didn't finish within {0:?}
+    Timeout(Duration),
+    /// The call panicked or the runtime shut down
+    TaskFailed,
+}
+
+impl Classify for BlockingError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            Self::Timeout(_) => ErrorClass::Transient,
+            Self::TaskFailed => ErrorClass::Permanent,
+        }
+    }
+}
+
+/// Runs blocking calls on the tokio blocking thread pool, limiting how many
+/// run at once.
+///
+/// Clones share the same limit, a pool is meant to be shared by all the
+/// callers of one library.
+///
+/// Calls can be cancelled, by dropping their future, or time out, while
+/// waiting for their turn. Once a call has started it runs to completion, FFI
+/// calls can't be interrupted. It continues to count against the limit until
+/// it completes.
+#[derive(Debug, Clone)]
+pub struct BlockingPool {
+    permits: Arc<Semaphore>,
+    timeout: Option<Duration>,
+}
+
+impl BlockingPool {
+    /// A pool running at most `max_concurrency` calls at once.
+    ///
+    /// Values less than 1 are treated as 1.
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrency.max(1))),
+            timeout: None,
+        }
+    }
+
+    /// Fail calls which take longer than `timeout`, including the time spent
+    /// waiting for their turn.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Run `call` on the blocking thread pool.
+    ///
+    /// # Errors
+    /// * [`BlockingError::Timeout`] if `call` doesn't finish within the
+    ///   timeout.
+    /// * [`BlockingError::TaskFailed`] if `call` panics.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        call: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, BlockingError> {
+        let permits = self.permits.clone();
+        let task = async move {
+            let permit = permits
+                .acquire_owned()
+                .await
+                .map_err(|_| BlockingError::TaskFailed)?;
+            tokio::task::spawn_blocking(move || {
+                let result = call();
+                drop(permit);
+                result
+            })
+            .await
+            .map_err(|_| BlockingError::TaskFailed)
+        };
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, task)
+                .await
+                .map_err(|_| BlockingError::Timeout(timeout))?,
+            None => task.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::vec::Vec;
+
+    #[tokio::test]
+    async fn run_returns_result() {
+        let pool = BlockingPool::new(1);
+
+        assert_eq!(pool.run(|| 1 + 2).await, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn run_limits_concurrency() {
+        let pool = BlockingPool::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+
+        let calls = (0..6)
+            .map(|_| {
+                let pool = pool.clone();
+                let running = running.clone();
+                let most = most.clone();
+                tokio::spawn(async move {
+                    pool.run(move || {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        most.fetch_max(now, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                    })
+                    .await
+                })
+            })
+            .collect::<Vec<_>>();
+        for call in calls {
+            call.await.unwrap().unwrap();
+        }
+
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn run_times_out() {
+        let timeout = Duration::from_millis(10);
+        let pool = BlockingPool::new(1).timeout(timeout);
+
+        let result = pool.run(|| thread::sleep(Duration::from_millis(200))).await;
+
+        assert_eq!(result, Err(BlockingError::Timeout(timeout)));
+    }
+
+    #[tokio::test]
+    async fn panic_fails_task() {
+        let pool = BlockingPool::new(1);
+
+        let result = pool.run(|| panic!("FFI call failed")).await;
+
+        assert_eq!(result, Err::<(), _>(BlockingError::TaskFailed));
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) 2022-2024 The MobileCoin Foundation
 #![deny(missing_docs, missing_debug_implementations, unsafe_code)]
-//! Contains format utilities for formatting Hex representations of integers and byte arrays.
+//! Contains format utilities for formatting Hex representations of integers and byte arrays,
+//! and for trimming text.
 
 use core::fmt::Formatter;
 
@@ -18,3 +19,42 @@ pub fn fmt_hex(src: &[u8], f: &mut Formatter) -> core::fmt::Result {
 
     Ok(())
 }
+
+/// Trim the leading and trailing ASCII whitespace of `bytes`.
+///
+/// Equivalent to `<[u8]>::trim_ascii()`, which needs a newer Rust than the
+/// minimum supported version.
+pub const fn trim_ascii_whitespace(bytes: &[u8]) -> &[u8] {
+    let mut bytes = bytes;
+    while let [first, rest @ ..] = bytes {
+        if first.is_ascii_whitespace() {
+            bytes = rest;
+        } else {
+            break;
+        }
+    }
+    while let [rest @ .., last] = bytes {
+        if last.is_ascii_whitespace() {
+            bytes = rest;
+        } else {
+            break;
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trim_ascii_whitespace_trims_both_ends() {
+        assert_eq!(trim_ascii_whitespace(b" \r\n\tsome text\r\n"), b"some text");
+    }
+
+    #[test]
+    fn trim_ascii_whitespace_of_only_whitespace_is_empty() {
+        assert_eq!(trim_ascii_whitespace(b"\n \t\r\n"), b"");
+        assert_eq!(trim_ascii_whitespace(b""), b"");
+    }
+}
@@ -4,8 +4,18 @@
 #![no_std]
 #![deny(missing_docs, missing_debug_implementations, unsafe_code)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "tokio")]
+mod blocking;
 mod format;
-pub use format::fmt_hex;
+mod retry;
+
+#[cfg(feature = "tokio")]
+pub use blocking::{BlockingError, BlockingPool};
+pub use format::{fmt_hex, trim_ascii_whitespace};
+pub use retry::{Classify, ErrorClass, RetryPolicy};
 
 /// A trait to add to an error type which can be constructed from an underlying
 /// "status" type which contains both success and failure codes.
@@ -0,0 +1,240 @@
+// Copyright (c) 2024 The MobileCoin Foundation
+
+//! Classification of errors as transient or permanent, and retrying of
+//! operations failing with transient errors.
+
+use core::time::Duration;
+
+/// Whether an error is worth retrying
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum ErrorClass {
+    /// The condition is expected to clear on its own, retrying the same
+    /// operation may succeed. For example a busy service, a network failure,
+    /// or running out of EPC.
+    Transient,
+    /// Retrying the same operation will fail the same way until the inputs,
+    /// the platform, or the configuration change.
+    Permanent,
+}
+
+/// An error which can be classified as [`ErrorClass::Transient`] or
+/// [`ErrorClass::Permanent`].
+pub trait Classify {
+    /// The class of this error
+    fn class(&self) -> ErrorClass;
+
+    /// Is this error [`ErrorClass::Transient`]
+    fn is_transient(&self) -> bool {
+        self.class() == ErrorClass::Transient
+    }
+}
+
+/// How often, and how long to wait before, retrying an operation which failed
+/// with a transient error.
+///
+/// The delay grows exponentially, from [`RetryPolicy::initial_delay()`] by
+/// [`RetryPolicy::multiplier()`] for each retry, up to
+/// [`RetryPolicy::max_delay()`].
+///
+/// The default makes 3 attempts, waiting 100 milliseconds then 200
+/// milliseconds.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::time::Duration;
+/// use mc_sgx_util::{Classify, ErrorClass, RetryPolicy};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Busy;
+///
+/// impl Classify for Busy {
+///     fn class(&self) -> ErrorClass {
+///         ErrorClass::Transient
+///     }
+/// }
+///
+/// let policy = RetryPolicy::default().max_attempts(4);
+/// let mut calls = 0;
+/// let mut slept = Duration::ZERO;
+/// let result = policy.retry_with_sleep(
+///     || {
+///         calls += 1;
+///         if calls < 3 { Err(Busy) } else { Ok(calls) }
+///     },
+///     |delay| slept += delay,
+/// );
+///
+/// assert_eq!(result, Ok(3));
+/// assert_eq!(slept, Duration::from_millis(300));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which makes only one attempt
+    pub fn no_retry() -> Self {
+        Self::default().max_attempts(1)
+    }
+
+    /// Set the total number of attempts, including the first one.
+    ///
+    /// Values less than 1 are treated as 1.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry
+    #[must_use]
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the longest delay between retries
+    #[must_use]
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the factor the delay grows by for each retry, 1 for a constant
+    /// delay.
+    #[must_use]
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// The delay before `retry`, where the first retry is 1
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(retry.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+
+    /// Run `operation` until it succeeds, fails with a permanent error, or
+    /// runs out of attempts, calling `sleep` with the delay before each retry.
+    ///
+    /// Returns the result of the last attempt.
+    pub fn retry_with_sleep<T, E: Classify>(
+        &self,
+        mut operation: impl FnMut() -> Result<T, E>,
+        mut sleep: impl FnMut(Duration),
+    ) -> Result<T, E> {
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Err(err) if err.is_transient() && attempt < self.max_attempts => {
+                    sleep(self.delay(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run `operation` until it succeeds, fails with a permanent error, or
+    /// runs out of attempts, sleeping the current thread before each retry.
+    ///
+    /// Returns the result of the last attempt.
+    #[cfg(feature = "std")]
+    pub fn retry<T, E: Classify>(&self, operation: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+        self.retry_with_sleep(operation, std::thread::sleep)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct TestError(ErrorClass);
+
+    impl Classify for TestError {
+        fn class(&self) -> ErrorClass {
+            self.0
+        }
+    }
+
+    #[test]
+    fn delay_grows_to_max() {
+        let policy = RetryPolicy::default()
+            .initial_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(5));
+
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(4), Duration::from_secs(5));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn permanent_error_is_not_retried() {
+        let mut calls = 0;
+
+        let result: Result<(), _> = RetryPolicy::default().retry_with_sleep(
+            || {
+                calls += 1;
+                Err(TestError(ErrorClass::Permanent))
+            },
+            |_| {},
+        );
+
+        assert_eq!(result, Err(TestError(ErrorClass::Permanent)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn transient_error_stops_at_max_attempts() {
+        let mut calls = 0;
+        let mut sleeps = 0;
+
+        let result: Result<(), _> = RetryPolicy::default().max_attempts(5).retry_with_sleep(
+            || {
+                calls += 1;
+                Err(TestError(ErrorClass::Transient))
+            },
+            |_| sleeps += 1,
+        );
+
+        assert_eq!(result, Err(TestError(ErrorClass::Transient)));
+        assert_eq!(calls, 5);
+        assert_eq!(sleeps, 4);
+    }
+
+    #[test]
+    fn no_retry_makes_one_attempt() {
+        let mut calls = 0;
+
+        let _: Result<(), _> = RetryPolicy::no_retry().retry_with_sleep(
+            || {
+                calls += 1;
+                Err(TestError(ErrorClass::Transient))
+            },
+            |_| {},
+        );
+
+        assert_eq!(calls, 1);
+    }
+}